@@ -16,7 +16,7 @@
 
 use crate::encoding::Elligator2;
 use snarkos_curves::edwards_bls12::*;
-use snarkos_models::curves::Zero;
+use snarkos_models::curves::{ProjectiveCurve, Zero};
 use snarkos_utilities::rand::UniformRand;
 
 use rand::SeedableRng;
@@ -46,3 +46,18 @@ fn test_elligator2_zero() {
     let decode = Elligator2::<EdwardsParameters, EdwardsProjective>::decode(&EdwardsAffine::zero(), false);
     assert!(decode.is_err());
 }
+
+#[test]
+fn test_elligator2_decode_outside_image() {
+    let rng = &mut XorShiftRng::seed_from_u64(1231275789u64);
+
+    // Roughly half of all group elements are not the encoding of any field element.
+    let rejected = (0..100)
+        .filter(|_| {
+            let element = EdwardsProjective::rand(rng).into_affine();
+            Elligator2::<EdwardsParameters, EdwardsProjective>::decode(&element, false).is_err()
+        })
+        .count();
+
+    assert!(rejected > 0);
+}
@@ -19,6 +19,8 @@ use snarkos_errors::algorithms::MerkleError;
 use snarkos_models::algorithms::{MerkleParameters, CRH};
 use snarkos_utilities::ToBytes;
 
+use std::collections::HashMap;
+
 pub struct MerkleTree<P: MerkleParameters> {
     /// The computed root of the full Merkle tree.
     root: Option<<P::H as CRH>::Output>,
@@ -26,8 +28,8 @@ pub struct MerkleTree<P: MerkleParameters> {
     /// The internal hashes, from root to hashed leaves, of the full Merkle tree.
     tree: Vec<<P::H as CRH>::Output>,
 
-    /// The hash of each non-empty leaf in the Merkle tree.
-    hashed_leaves: Vec<<P::H as CRH>::Output>,
+    /// The number of non-empty leaves in the Merkle tree.
+    num_leaves: usize,
 
     /// For each level after a full tree has been built from the leaves,
     /// keeps both the roots the siblings that are used to get to the desired depth.
@@ -108,16 +110,47 @@ impl<P: MerkleParameters> MerkleTree<P> {
 
         end_timer!(new_time);
 
-        let hashed_leaves = tree[last_level_index..].to_vec();
         Ok(MerkleTree {
             tree,
             padding_tree,
-            hashed_leaves,
+            num_leaves: leaves.len(),
             parameters,
             root: Some(root_hash),
         })
     }
 
+    /// Returns a Merkle tree with the given number of leaves from its non-empty nodes,
+    /// keyed by their height above the leaves and their position from the left.
+    pub fn from_nodes(
+        parameters: P,
+        num_leaves: usize,
+        nodes: &HashMap<(usize, usize), <P::H as CRH>::Output>,
+    ) -> Result<Self, MerkleError> {
+        let mut merkle_tree = MerkleTree {
+            tree: vec![parameters.hash_empty()?],
+            padding_tree: vec![],
+            num_leaves: 0,
+            parameters,
+            root: None,
+        };
+        merkle_tree.resize(num_leaves)?;
+        merkle_tree.num_leaves = num_leaves;
+
+        let tree_depth = merkle_tree.depth();
+        for height in 0..=tree_depth {
+            for position in 0..non_empty_width(num_leaves, height) {
+                match nodes.get(&(height, position)) {
+                    Some(node) => merkle_tree.tree[tree_index(tree_depth, height, position)] = node.clone(),
+                    None => return Err(MerkleError::MissingNode(height, position)),
+                }
+            }
+        }
+
+        merkle_tree.update_root()?;
+
+        Ok(merkle_tree)
+    }
+
     #[inline]
     pub fn root(&self) -> <P::H as CRH>::Output {
         self.root.clone().unwrap()
@@ -125,7 +158,178 @@ impl<P: MerkleParameters> MerkleTree<P> {
 
     #[inline]
     pub fn hashed_leaves(&self) -> Vec<<P::H as CRH>::Output> {
-        self.hashed_leaves.clone()
+        self.tree[tree_index(self.depth(), 0, 0)..].to_vec()
+    }
+
+    #[inline]
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Returns the node at the given height above the leaves and position from the left,
+    /// or `None` if the node only covers empty leaves.
+    pub fn node(&self, height: usize, position: usize) -> Option<<P::H as CRH>::Output> {
+        let tree_depth = self.depth();
+
+        if height > tree_depth || position >= non_empty_width(self.num_leaves, height) {
+            return None;
+        }
+
+        Some(self.tree[tree_index(tree_depth, height, position)].clone())
+    }
+
+    /// Returns the height and position of every node that covers at least one non-empty leaf.
+    pub fn non_empty_nodes(&self) -> Vec<(usize, usize)> {
+        match self.num_leaves {
+            0 => vec![],
+            num_leaves => changed_nodes(0, num_leaves, self.depth()),
+        }
+    }
+
+    /// Appends the given leaves to the tree, rehashing only the nodes above them.
+    /// Returns the height and position of every node that changed.
+    pub fn push_leaves<L: ToBytes>(&mut self, leaves: &[L]) -> Result<Vec<(usize, usize)>, MerkleError> {
+        if leaves.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let old_num_leaves = self.num_leaves;
+        let new_num_leaves = old_num_leaves + leaves.len();
+
+        self.resize(new_num_leaves)?;
+        let tree_depth = self.depth();
+
+        let hash_input_size_in_bytes = (P::H::INPUT_SIZE_BITS / 8) * 2;
+        let mut buffer = vec![0u8; hash_input_size_in_bytes];
+        for (i, leaf) in leaves.iter().enumerate() {
+            self.tree[tree_index(tree_depth, 0, old_num_leaves + i)] = self.parameters.hash_leaf(leaf, &mut buffer)?;
+        }
+        self.num_leaves = new_num_leaves;
+
+        self.rehash(old_num_leaves, new_num_leaves)?;
+        self.update_root()?;
+
+        Ok(changed_nodes(old_num_leaves, new_num_leaves, tree_depth))
+    }
+
+    /// Removes every leaf from the given index onwards, rehashing only the nodes above them.
+    /// Returns the height and position of every node that changed.
+    pub fn truncate_leaves(&mut self, num_leaves: usize) -> Result<Vec<(usize, usize)>, MerkleError> {
+        if num_leaves >= self.num_leaves {
+            return Ok(vec![]);
+        }
+
+        let old_num_leaves = self.num_leaves;
+        let changed_nodes = changed_nodes(num_leaves, old_num_leaves, self.depth());
+
+        self.resize(num_leaves)?;
+        let tree_depth = self.depth();
+
+        let empty_hash = self.parameters.hash_empty()?;
+        for i in num_leaves..old_num_leaves.min(1 << tree_depth) {
+            self.tree[tree_index(tree_depth, 0, i)] = empty_hash.clone();
+        }
+        self.num_leaves = num_leaves;
+
+        self.rehash(num_leaves, old_num_leaves)?;
+        self.update_root()?;
+
+        Ok(changed_nodes)
+    }
+
+    /// Returns the depth of the tree, without padding.
+    #[inline]
+    fn depth(&self) -> usize {
+        tree_depth(self.tree.len())
+    }
+
+    /// Resizes the tree to fit the given number of leaves, keeping the nodes that still fit
+    /// and filling the new nodes with the hashes of empty subtrees.
+    fn resize(&mut self, num_leaves: usize) -> Result<(), MerkleError> {
+        let old_depth = self.depth();
+        let new_depth = tree_depth(2 * num_leaves.next_power_of_two() - 1);
+
+        if new_depth == old_depth {
+            return Ok(());
+        }
+        if new_depth > Self::DEPTH as usize {
+            return Err(MerkleError::InvalidTreeDepth(new_depth, Self::DEPTH as usize));
+        }
+
+        // Compute the hash of an empty subtree at each height.
+        let hash_input_size_in_bytes = (P::H::INPUT_SIZE_BITS / 8) * 2;
+        let mut buffer = vec![0u8; hash_input_size_in_bytes];
+        let mut empty_hashes = vec![self.parameters.hash_empty()?];
+        for height in 1..=new_depth {
+            let empty_hash = &empty_hashes[height - 1];
+            empty_hashes.push(self.parameters.hash_inner_node(empty_hash, empty_hash, &mut buffer)?);
+        }
+
+        let mut tree = Vec::with_capacity(2 * (1 << new_depth) - 1);
+        for level in 0..=new_depth {
+            let height = new_depth - level;
+            let width = 1 << level;
+
+            if height <= old_depth {
+                let start_index = tree_index(old_depth, height, 0);
+                let old_width = 1 << (old_depth - height);
+                tree.extend_from_slice(&self.tree[start_index..start_index + old_width.min(width)]);
+            }
+            while tree.len() < tree_index(new_depth, height, width) {
+                tree.push(empty_hashes[height].clone());
+            }
+        }
+        self.tree = tree;
+
+        Ok(())
+    }
+
+    /// Rehashes the nodes above the leaves in the given range of indices.
+    fn rehash(&mut self, start: usize, end: usize) -> Result<(), MerkleError> {
+        let tree_depth = self.depth();
+
+        let hash_input_size_in_bytes = (P::H::INPUT_SIZE_BITS / 8) * 2;
+        let mut buffer = vec![0u8; hash_input_size_in_bytes];
+        for height in 1..=tree_depth {
+            let last_position = ((end - 1) >> height).min((1 << (tree_depth - height)) - 1);
+            for position in (start >> height)..=last_position {
+                let left_index = tree_index(tree_depth, height - 1, 2 * position);
+                let right_index = tree_index(tree_depth, height - 1, 2 * position + 1);
+
+                self.tree[tree_index(tree_depth, height, position)] =
+                    self.parameters
+                        .hash_inner_node(&self.tree[left_index], &self.tree[right_index], &mut buffer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recomputes the padding and the root above the top of the tree.
+    fn update_root(&mut self) -> Result<(), MerkleError> {
+        let hash_input_size_in_bytes = (P::H::INPUT_SIZE_BITS / 8) * 2;
+        let mut buffer = vec![0u8; hash_input_size_in_bytes];
+        let empty_hash = self.parameters.hash_empty()?;
+
+        let mut current_depth = self.depth();
+        let mut padding_tree = vec![];
+        let mut current_hash = self.tree[0].clone();
+        while current_depth < Self::DEPTH as usize {
+            current_hash = self
+                .parameters
+                .hash_inner_node(&current_hash, &empty_hash, &mut buffer)?;
+
+            // do not pad at the top-level of the tree
+            if current_depth < Self::DEPTH as usize - 1 {
+                padding_tree.push((current_hash.clone(), empty_hash.clone()));
+            }
+            current_depth += 1;
+        }
+
+        self.padding_tree = padding_tree;
+        self.root = Some(current_hash);
+
+        Ok(())
     }
 
     pub fn generate_proof<L: ToBytes>(&self, index: usize, leaf: &L) -> Result<MerklePath<P>, MerkleError> {
@@ -189,7 +393,7 @@ impl<P: MerkleParameters> Default for MerkleTree<P> {
         MerkleTree {
             tree: vec![],
             padding_tree: vec![],
-            hashed_leaves: vec![],
+            num_leaves: 0,
             root: None,
             parameters: P::default(),
         }
@@ -249,6 +453,34 @@ fn parent(index: usize) -> Option<usize> {
     if index > 0 { Some((index - 1) >> 1) } else { None }
 }
 
+/// Returns the index in the tree of the node at the given height and position, given the depth of the tree.
+#[inline]
+fn tree_index(tree_depth: usize, height: usize, position: usize) -> usize {
+    (1 << (tree_depth - height)) - 1 + position
+}
+
+/// Returns the number of nodes at the given height that cover at least one non-empty leaf.
+#[inline]
+fn non_empty_width(num_leaves: usize, height: usize) -> usize {
+    if num_leaves == 0 {
+        0
+    } else {
+        ((num_leaves - 1) >> height) + 1
+    }
+}
+
+/// Returns the height and position of every node, up to the given depth,
+/// that covers a leaf in the given range of indices.
+fn changed_nodes(start: usize, end: usize, tree_depth: usize) -> Vec<(usize, usize)> {
+    let mut nodes = vec![];
+    for height in 0..=tree_depth {
+        for position in (start >> height)..=((end - 1) >> height) {
+            nodes.push((height, position));
+        }
+    }
+    nodes
+}
+
 #[inline]
 fn convert_index_to_last_level(index: usize, tree_depth: usize) -> usize {
     index + (1 << tree_depth) - 1
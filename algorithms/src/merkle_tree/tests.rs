@@ -22,6 +22,8 @@ use crate::{
 use snarkos_models::algorithms::{crh::CRH, merkle_tree::LoadableMerkleParameters};
 use snarkos_utilities::{to_bytes, ToBytes};
 
+use std::collections::HashMap;
+
 /// Generates a valid Merkle tree and verifies the Merkle path witness for each leaf.
 fn generate_merkle_tree<P: LoadableMerkleParameters, L: ToBytes + Clone + Eq>(
     leaves: &[L],
@@ -164,6 +166,64 @@ fn run_padded_merkle_tree_matches_hashing_test<P: LoadableMerkleParameters>() {
     assert_eq!(merkle_tree_root, expected_root);
 }
 
+fn run_incremental_merkle_tree_test<P: LoadableMerkleParameters>() {
+    let parameters = &P::default();
+
+    let mut leaves = vec![];
+    for i in 0..9u8 {
+        leaves.push([i, i, i, i, i, i, i, i]);
+    }
+
+    // Append the leaves in batches that grow the depth of the tree
+
+    let mut merkle_tree = MerkleTree::<P>::new(parameters.clone(), &leaves[..0]).unwrap();
+    for &(start, end) in &[(0, 1), (1, 2), (2, 5), (5, 9)] {
+        let changed_nodes = merkle_tree.push_leaves(&leaves[start..end]).unwrap();
+        assert!(changed_nodes.contains(&(0, end - 1)));
+
+        let expected_tree = generate_merkle_tree(&leaves[..end], parameters);
+        assert_eq!(expected_tree.root(), merkle_tree.root());
+        assert_eq!(expected_tree.hashed_leaves(), merkle_tree.hashed_leaves());
+
+        for (i, leaf) in leaves[..end].iter().enumerate() {
+            let proof = merkle_tree.generate_proof(i, leaf).unwrap();
+            assert!(proof.verify(&merkle_tree.root(), leaf).unwrap());
+        }
+    }
+
+    // Remove the leaves in batches that shrink the depth of the tree
+
+    for &num_leaves in &[7, 4, 1, 0] {
+        merkle_tree.truncate_leaves(num_leaves).unwrap();
+
+        let expected_tree = MerkleTree::<P>::new(parameters.clone(), &leaves[..num_leaves]).unwrap();
+        assert_eq!(expected_tree.root(), merkle_tree.root());
+        assert_eq!(expected_tree.hashed_leaves(), merkle_tree.hashed_leaves());
+    }
+
+    // Rebuild the tree from its non-empty nodes
+
+    merkle_tree.push_leaves(&leaves).unwrap();
+
+    let mut nodes = HashMap::new();
+    for height in 0..=4 {
+        for position in 0..16 {
+            if let Some(node) = merkle_tree.node(height, position) {
+                nodes.insert((height, position), node);
+            }
+        }
+    }
+    assert_eq!(nodes.len(), 9 + 5 + 3 + 2 + 1);
+    assert_eq!(nodes.len(), merkle_tree.non_empty_nodes().len());
+
+    let rebuilt_tree = MerkleTree::<P>::from_nodes(parameters.clone(), leaves.len(), &nodes).unwrap();
+    assert_eq!(merkle_tree.root(), rebuilt_tree.root());
+    assert_eq!(merkle_tree.hashed_leaves(), rebuilt_tree.hashed_leaves());
+
+    nodes.remove(&(1, 4));
+    assert!(MerkleTree::<P>::from_nodes(parameters.clone(), leaves.len(), &nodes).is_err());
+}
+
 mod pedersen_crh_on_affine {
     use super::*;
     use snarkos_curves::edwards_bls12::EdwardsAffine as Edwards;
@@ -187,6 +247,12 @@ mod pedersen_crh_on_affine {
         run_good_root_test::<MTParameters>();
     }
 
+    #[test]
+    fn incremental_merkle_tree_test() {
+        define_merkle_tree_parameters!(MTParameters, PedersenCRH<Edwards, Size>, 32);
+        run_incremental_merkle_tree_test::<MTParameters>();
+    }
+
     #[should_panic]
     #[test]
     fn bad_root_test() {
@@ -230,6 +296,12 @@ mod pedersen_crh_on_projective {
         run_good_root_test::<MTParameters>();
     }
 
+    #[test]
+    fn incremental_merkle_tree_test() {
+        define_merkle_tree_parameters!(MTParameters, PedersenCRH<Edwards, Size>, 32);
+        run_incremental_merkle_tree_test::<MTParameters>();
+    }
+
     #[should_panic]
     #[test]
     fn bad_root_test() {
@@ -277,6 +349,12 @@ mod pedersen_compressed_crh_on_projective {
         run_good_root_test::<MTParameters>();
     }
 
+    #[test]
+    fn incremental_merkle_tree_test() {
+        define_merkle_tree_parameters!(MTParameters, PedersenCompressedCRH<Edwards, Size>, 32);
+        run_incremental_merkle_tree_test::<MTParameters>();
+    }
+
     #[should_panic]
     #[test]
     fn bad_root_test() {
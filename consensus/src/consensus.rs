@@ -14,20 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{difficulty::bitcoin_retarget, memory_pool::MemoryPool, MerkleTreeLedger};
+use crate::{
+    events::{ConsensusEvent, ConsensusEvents},
+    genesis::DEV_DIFFICULTY_TARGET,
+    memory_pool::MemoryPool,
+    network_time::NetworkTime,
+    profile::{RetargetAlgorithm, RewardSchedule},
+    upgrades::ConsensusUpgrade,
+    MerkleTreeLedger,
+};
 use snarkos_curves::bls12_377::Bls12_377;
 use snarkos_dpc::base_dpc::{
     instantiated::*,
     parameters::PublicParameters,
-    program::NoopProgram,
+    program::ProgramRegistry,
     record::DPCRecord,
     record_payload::RecordPayload,
-    BaseDPCComponents,
 };
 use snarkos_errors::consensus::ConsensusError;
 use snarkos_models::{
     algorithms::{CRH, SNARK},
-    dpc::{DPCComponents, DPCScheme, Program},
+    dpc::{DPCComponents, DPCScheme},
     objects::{AccountScheme, LedgerScheme},
 };
 use snarkos_objects::{
@@ -45,14 +52,16 @@ use snarkos_objects::{
 };
 use snarkos_posw::{txids_to_roots, Marlin, PoswMarlin};
 use snarkos_profiler::{end_timer, start_timer};
-use snarkos_storage::BlockPath;
+use snarkos_storage::{BlockPath, KeyValueStore, Ledger};
 use snarkos_utilities::{to_bytes, FromBytes, ToBytes};
 
-use chrono::Utc;
 use rand::Rng;
 
 pub const TWO_HOURS_UNIX: i64 = 7200;
 
+/// The number of blocks whose median timestamp the timestamp of a new block may not precede.
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
 /// Parameters for a proof of work blockchain.
 #[derive(Clone, Debug)]
 pub struct ConsensusParameters {
@@ -68,63 +77,138 @@ pub struct ConsensusParameters {
     /// Network
     pub network: Network,
 
+    /// The difficulty retarget algorithm
+    pub retarget_algorithm: RetargetAlgorithm,
+
+    /// The block reward schedule
+    pub reward_schedule: RewardSchedule,
+
+    /// The changes of the consensus rules activated at later block heights
+    pub upgrades: Vec<ConsensusUpgrade>,
+
     /// The Proof of Succinct Work verifier (read-only mode, no proving key loaded)
     pub verifier: PoswMarlin,
 
     /// The authorized inner SNARK IDs
     pub authorized_inner_snark_ids: Vec<Vec<u8>>,
-}
 
-/// Calculate a block reward that halves every 4 years * 365 days * 24 hours * 100 blocks/hr = 3,504,000 blocks.
-pub fn get_block_reward(block_num: u32) -> AleoAmount {
-    let expected_blocks_per_hour: u32 = 100;
-    let num_years = 4;
-    let block_segments = num_years * 365 * 24 * expected_blocks_per_hour;
+    /// Notifies subscribers of the blocks committed to and reverted from the canon chain
+    pub events: ConsensusEvents<Tx>,
 
-    let aleo_denonimation = AleoAmount::COIN;
-    let initial_reward = 150i64 * aleo_denonimation;
+    /// The local time adjusted by the clocks of the connected peers
+    pub network_time: NetworkTime,
 
-    // The block reward halves at most 2 times - minimum is 37.5 ALEO after 8 years.
-    let num_halves = u32::min(block_num / block_segments, 2);
-    let reward = initial_reward / (2_u64.pow(num_halves)) as i64;
+    /// Mine every block at the trivial `DEV_DIFFICULTY_TARGET` of a local development chain
+    pub dev_mode: bool,
+}
 
-    AleoAmount::from_bytes(reward)
+/// Calculate a block reward following the Aleo reward schedule, see `RewardSchedule::ALEO`.
+pub fn get_block_reward(block_num: u32) -> AleoAmount {
+    RewardSchedule::ALEO.block_reward(block_num)
 }
 
 impl ConsensusParameters {
-    /// Calculate the difficulty for the next block based off how long it took to mine the last one.
-    pub fn get_block_difficulty(&self, prev_header: &BlockHeader, block_timestamp: i64) -> u64 {
-        bitcoin_retarget(
+    /// Calculate the difficulty for the next block based off how long it took to mine the last blocks.
+    /// `ancestor_headers` are the parent of the next block and its ancestors, newest first,
+    /// see `ancestor_span` and `Ledger::get_ancestor_headers`.
+    /// The retarget algorithm is the one active at the height of the next block.
+    /// Blocks of a development chain are always mined at the trivial `DEV_DIFFICULTY_TARGET`.
+    pub fn get_block_difficulty(
+        &self,
+        ancestor_headers: &[BlockHeader],
+        block_timestamp: i64,
+        block_height: u32,
+    ) -> u64 {
+        if self.dev_mode {
+            return DEV_DIFFICULTY_TARGET;
+        }
+
+        let ancestor_timestamps: Vec<i64> = ancestor_headers.iter().map(|header| header.time).collect();
+
+        self.retarget_algorithm_at(block_height).retarget(
             block_timestamp,
-            prev_header.time,
+            &ancestor_timestamps,
             self.target_block_time,
-            prev_header.difficulty_target,
+            ancestor_headers[0].difficulty_target,
         )
     }
 
+    /// Returns the number of ancestor headers, starting with the parent, that a block at the given height is
+    /// verified against. These are the `MEDIAN_TIME_SPAN` headers of the median time past and the headers
+    /// the difficulty is retargeted over.
+    pub fn ancestor_span(&self, block_height: u32) -> usize {
+        usize::max(MEDIAN_TIME_SPAN, self.retarget_algorithm_at(block_height).window())
+    }
+
+    /// Returns the reward of the block at the given height, following the reward schedule active at that height.
+    pub fn get_block_reward(&self, block_num: u32) -> AleoAmount {
+        self.reward_schedule_at(block_num).block_reward(block_num)
+    }
+
     pub fn is_genesis(block_header: &BlockHeader) -> bool {
         block_header.previous_block_hash == BlockHeaderHash([0u8; 32])
     }
 
+    /// Returns the median timestamp of the first `MEDIAN_TIME_SPAN` of the given headers,
+    /// which are the parent of a new block and its ancestors. See `Ledger::get_ancestor_headers`.
+    pub fn median_time_past(headers: &[BlockHeader]) -> i64 {
+        let mut timestamps: Vec<i64> = headers
+            .iter()
+            .take(MEDIAN_TIME_SPAN)
+            .map(|header| header.time)
+            .collect();
+        timestamps.sort_unstable();
+
+        timestamps.get(timestamps.len() / 2).cloned().unwrap_or(0)
+    }
+
     /// Verify all fields in a block header.
     /// 1. The parent hash points to the tip of the chain.
     /// 2. Transactions hash to merkle root.
-    /// 3. The timestamp is less than 2 hours into the future.
-    /// 4. The timestamp is greater than parent timestamp.
+    /// 3. The timestamp is less than 2 hours into the network-adjusted future.
+    /// 4. The timestamp is not less than the median timestamp of the parent and its ancestors.
     /// 5. The header is greater than or equal to target difficulty.
     /// 6. The nonce is within the limit.
+    /// 7. The proof of succinct work is valid.
+    /// The rules are those active at the given height of the block.
+    /// `ancestor_headers` are the parent and its ancestors, newest first, see `ancestor_span`.
     pub fn verify_header(
         &self,
         header: &BlockHeader,
         parent_header: &BlockHeader,
         merkle_root_hash: &MerkleRootHash,
         pedersen_merkle_root_hash: &PedersenMerkleRootHash,
+        block_height: u32,
+        ancestor_headers: &[BlockHeader],
+    ) -> Result<(), ConsensusError> {
+        self.verify_header_without_proof(
+            header,
+            parent_header,
+            merkle_root_hash,
+            pedersen_merkle_root_hash,
+            block_height,
+            ancestor_headers,
+        )?;
+
+        self.verify_header_proof(header)
+    }
+
+    /// Verify all fields in a block header like `verify_header`, except its proof of succinct work.
+    pub fn verify_header_without_proof(
+        &self,
+        header: &BlockHeader,
+        parent_header: &BlockHeader,
+        merkle_root_hash: &MerkleRootHash,
+        pedersen_merkle_root_hash: &PedersenMerkleRootHash,
+        block_height: u32,
+        ancestor_headers: &[BlockHeader],
     ) -> Result<(), ConsensusError> {
         let hash_result = header.to_difficulty_hash();
 
-        let now = Utc::now().timestamp();
+        let now = self.network_time.now();
         let future_timelimit: i64 = now + TWO_HOURS_UNIX;
-        let expected_difficulty = self.get_block_difficulty(parent_header, header.time);
+        let median_time_past = Self::median_time_past(ancestor_headers);
+        let expected_difficulty = self.get_block_difficulty(ancestor_headers, header.time, block_height);
 
         if parent_header.get_hash() != header.previous_block_hash {
             return Err(ConsensusError::NoParent(
@@ -139,8 +223,8 @@ impl ConsensusParameters {
             ));
         } else if header.time > future_timelimit {
             return Err(ConsensusError::FuturisticTimestamp(future_timelimit, header.time));
-        } else if header.time < parent_header.time {
-            return Err(ConsensusError::TimestampInvalid(header.time, parent_header.time));
+        } else if header.time < median_time_past {
+            return Err(ConsensusError::TimestampInvalid(header.time, median_time_past));
         } else if hash_result > header.difficulty_target {
             return Err(ConsensusError::PowInvalid(header.difficulty_target, hash_result));
         } else if header.nonce >= self.max_nonce {
@@ -152,7 +236,11 @@ impl ConsensusParameters {
             ));
         }
 
-        // Verify the proof
+        Ok(())
+    }
+
+    /// Verify the proof of succinct work of a block header.
+    pub fn verify_header_proof(&self, header: &BlockHeader) -> Result<(), ConsensusError> {
         let proof = <Marlin<Bls12_377> as SNARK>::Proof::read(&header.proof.0[..])?;
         let verification_timer = start_timer!(|| "POSW verify");
         self.verifier
@@ -162,15 +250,42 @@ impl ConsensusParameters {
         Ok(())
     }
 
-    /// Check if the transaction is valid.
-    pub fn verify_transaction(
+    /// Verify the header of a block that extends a stored block which need not be the canon chain tip,
+    /// against its parent and ancestors like `verify_header`.
+    /// Side chain blocks are verified before their work is counted, so a side chain cannot appear heavier
+    /// with a forged difficulty target.
+    pub fn verify_side_chain_header(
         &self,
-        parameters: &<InstantiatedDPC as DPCScheme<MerkleTreeLedger>>::Parameters,
+        storage: &MerkleTreeLedger,
+        block: &Block<Tx>,
+        block_height: u32,
+    ) -> Result<(), ConsensusError> {
+        let transaction_ids = block.transactions.to_transaction_ids()?;
+        let (merkle_root, pedersen_merkle_root, _) = txids_to_roots(&transaction_ids);
+
+        let parent_header = storage.get_block_header(&block.header.previous_block_hash)?;
+        let ancestor_headers =
+            storage.get_ancestor_headers(&block.header.previous_block_hash, self.ancestor_span(block_height))?;
+
+        self.verify_header(
+            &block.header,
+            &parent_header,
+            &merkle_root,
+            &pedersen_merkle_root,
+            block_height,
+            &ancestor_headers,
+        )
+    }
+
+    /// Check if the transaction is valid in the next block of the ledger.
+    pub fn verify_transaction<S: KeyValueStore>(
+        &self,
+        parameters: &PublicParameters<Components>,
         transaction: &Tx,
-        ledger: &MerkleTreeLedger,
+        ledger: &Ledger<Tx, CommitmentMerkleParameters, S>,
     ) -> Result<bool, ConsensusError> {
         if !self
-            .authorized_inner_snark_ids
+            .authorized_inner_snark_ids_at(ledger.len() as u32)
             .contains(&to_bytes![transaction.inner_snark_id]?)
         {
             return Ok(false);
@@ -179,15 +294,17 @@ impl ConsensusParameters {
         Ok(InstantiatedDPC::verify(parameters, transaction, ledger)?)
     }
 
-    /// Check if the transactions are valid.
-    pub fn verify_transactions(
+    /// Check if the transactions are valid in the next block of the ledger.
+    pub fn verify_transactions<S: KeyValueStore>(
         &self,
-        parameters: &<InstantiatedDPC as DPCScheme<MerkleTreeLedger>>::Parameters,
+        parameters: &PublicParameters<Components>,
         transactions: &Vec<Tx>,
-        ledger: &MerkleTreeLedger,
+        ledger: &Ledger<Tx, CommitmentMerkleParameters, S>,
     ) -> Result<bool, ConsensusError> {
+        let authorized_inner_snark_ids = self.authorized_inner_snark_ids_at(ledger.len() as u32);
+
         for tx in transactions {
-            if !self.authorized_inner_snark_ids.contains(&to_bytes![tx.inner_snark_id]?) {
+            if !authorized_inner_snark_ids.contains(&to_bytes![tx.inner_snark_id]?) {
                 return Ok(false);
             }
         }
@@ -196,12 +313,35 @@ impl ConsensusParameters {
     }
 
     /// Check if the block is valid.
-    /// Verify transactions and transaction fees.
-    pub fn verify_block(
+    /// Verify the block header, transactions and transaction fees.
+    pub fn verify_block<S: KeyValueStore>(
         &self,
-        parameters: &<InstantiatedDPC as DPCScheme<MerkleTreeLedger>>::Parameters,
+        parameters: &PublicParameters<Components>,
         block: &Block<Tx>,
-        ledger: &MerkleTreeLedger,
+        ledger: &Ledger<Tx, CommitmentMerkleParameters, S>,
+    ) -> Result<bool, ConsensusError> {
+        if !self.verify_block_without_proofs(block, ledger)? {
+            return Ok(false);
+        }
+
+        // Verify the proof of succinct work
+        if !Self::is_genesis(&block.header) {
+            if let Err(err) = self.verify_header_proof(&block.header) {
+                println!("header failed to verify: {:?}", err);
+                return Ok(false);
+            }
+        }
+
+        // Check that all the transction proofs verify
+        Ok(self.verify_transactions(parameters, &block.transactions.0, ledger)?)
+    }
+
+    /// Check if the block is valid, without verifying its proof of succinct work or its transaction proofs.
+    /// Verify the block header fields and transaction fees.
+    pub fn verify_block_without_proofs<S: KeyValueStore>(
+        &self,
+        block: &Block<Tx>,
+        ledger: &Ledger<Tx, CommitmentMerkleParameters, S>,
     ) -> Result<bool, ConsensusError> {
         let transaction_ids: Vec<Vec<u8>> = block.transactions.to_transaction_ids()?;
         let (merkle_root, pedersen_merkle_root, _) = txids_to_roots(&transaction_ids);
@@ -209,9 +349,18 @@ impl ConsensusParameters {
         // Verify the block header
         if !Self::is_genesis(&block.header) {
             let parent_block = ledger.get_latest_block()?;
-            if let Err(err) =
-                self.verify_header(&block.header, &parent_block.header, &merkle_root, &pedersen_merkle_root)
-            {
+            let block_height = ledger.len() as u32;
+            let ancestor_headers =
+                ledger.get_ancestor_headers(&parent_block.header.get_hash(), self.ancestor_span(block_height))?;
+
+            if let Err(err) = self.verify_header_without_proof(
+                &block.header,
+                &parent_block.header,
+                &merkle_root,
+                &pedersen_merkle_root,
+                block_height,
+                &ancestor_headers,
+            ) {
                 println!("header failed to verify: {:?}", err);
                 return Ok(false);
             }
@@ -238,7 +387,7 @@ impl ConsensusParameters {
         }
 
         // Check that the block value balances are correct
-        let expected_block_reward = get_block_reward(ledger.len() as u32).0;
+        let expected_block_reward = self.get_block_reward(ledger.len() as u32).0;
         if total_value_balance.0 + expected_block_reward != 0 {
             println!("total_value_balance: {:?}", total_value_balance);
             println!("expected_block_reward: {:?}", expected_block_reward);
@@ -246,8 +395,7 @@ impl ConsensusParameters {
             return Ok(false);
         }
 
-        // Check that all the transction proofs verify
-        Ok(self.verify_transactions(parameters, &block.transactions.0, ledger)?)
+        Ok(true)
     }
 
     /// Return whether or not the given block is valid and insert it.
@@ -278,6 +426,15 @@ impl ConsensusParameters {
             memory_pool.remove_by_hash(&transaction_id)?;
         }
 
+        // 4. Remove expired transactions from the mempool
+        memory_pool.remove_expired(storage.get_latest_block_height())?;
+
+        // 5. Notify subscribers of the new canon block
+        self.events.emit(ConsensusEvent::NewBlock {
+            height: storage.get_latest_block_height(),
+            block: block.clone(),
+        });
+
         Ok(())
     }
 
@@ -315,7 +472,7 @@ impl ConsensusParameters {
 
                     self.process_block(parameters, &storage, memory_pool, block)?;
 
-                    let (_, child_path) = storage.longest_child_path(block.header.get_hash())?;
+                    let (_, child_path) = storage.heaviest_child_path(block.header.get_hash())?;
                     for child_block_hash in child_path {
                         let new_block = storage.get_block(&child_block_hash)?;
                         self.process_block(parameters, &storage, memory_pool, &new_block)?;
@@ -327,14 +484,53 @@ impl ConsensusParameters {
                         side_chain_path.new_block_number
                     );
 
-                    // If the side chain is now longer than the canon chain,
-                    // perform a fork to the side chain.
-                    if side_chain_path.new_block_number > storage.get_latest_block_height() {
-                        debug!("Determined side chain is longer than canon chain");
+                    // Only store a side chain block whose header is valid on top of its parent.
+                    // The heights of the side chain blocks follow the shared block, and the path holds the block.
+                    let block_hash = block.header.get_hash();
+                    let side_chain_heights = (side_chain_path.shared_block_number + 1..).zip(&side_chain_path.path);
+
+                    for (block_height, side_chain_block_hash) in side_chain_heights.clone() {
+                        if *side_chain_block_hash == block_hash {
+                            self.verify_side_chain_header(&storage, block, block_height)?;
+                        }
+                    }
+
+                    // If the side chain now carries more cumulative work than the canon chain,
+                    // perform a fork to the side chain once the headers of its other blocks are verified.
+                    // Orphans are stored before their parent is known, so their headers are only verified here.
+                    let mut is_heavier = storage.is_heavier_side_chain(&side_chain_path)?;
+
+                    if is_heavier {
+                        for (block_height, side_chain_block_hash) in side_chain_heights {
+                            if *side_chain_block_hash == block_hash {
+                                continue;
+                            }
+
+                            let side_chain_block = storage.get_block(side_chain_block_hash)?;
+                            let verification = self.verify_side_chain_header(&storage, &side_chain_block, block_height);
+
+                            if let Err(error) = verification {
+                                warn!(
+                                    "Not forking to a side chain with an invalid block {}: {}",
+                                    side_chain_block_hash, error
+                                );
+                                is_heavier = false;
+                                break;
+                            }
+                        }
+                    }
+
+                    if is_heavier {
+                        debug!("Determined side chain has more work than canon chain");
                         warn!("A valid fork has been detected. Performing a fork to the side chain.");
 
                         // Fork to superior side chain
-                        storage.revert_for_fork(&side_chain_path)?;
+                        let reverted_blocks = storage.revert_for_fork(&side_chain_path)?;
+
+                        self.events.emit(ConsensusEvent::Reorg {
+                            shared_block_height: side_chain_path.shared_block_number,
+                            reverted_blocks: reverted_blocks.clone(),
+                        });
 
                         if !side_chain_path.path.is_empty() {
                             for block_hash in side_chain_path.path {
@@ -346,8 +542,13 @@ impl ConsensusParameters {
                                 }
                             }
                         }
+
+                        // Return the transactions of the reverted blocks to the memory pool
+                        // and revalidate the memory pool against the new canon chain.
+                        memory_pool.insert_reverted_blocks(&storage, &reverted_blocks)?;
+                        memory_pool.cleanse(&storage)?;
                     } else {
-                        // If the sidechain is not longer than the main canon chain, simply store the block
+                        // If the sidechain does not have more work than the main canon chain, simply store the block
                         storage.insert_only(block)?;
                     }
                 }
@@ -370,7 +571,7 @@ impl ConsensusParameters {
         ledger: &MerkleTreeLedger,
         rng: &mut R,
     ) -> Result<(Vec<DPCRecord<Components>>, Tx), ConsensusError> {
-        let mut total_value_balance = get_block_reward(block_num);
+        let mut total_value_balance = self.get_block_reward(block_num);
 
         for transaction in transactions.iter() {
             let tx_value_balance = transaction.value_balance;
@@ -428,8 +629,11 @@ impl ConsensusParameters {
 
         let memo: [u8; 32] = rng.gen();
 
+        let program_registry = ProgramRegistry::new(parameters)?;
+
         self.create_transaction(
             parameters,
+            &program_registry,
             old_records,
             old_account_private_keys,
             new_record_owners,
@@ -448,6 +652,7 @@ impl ConsensusParameters {
     pub fn create_transaction<R: Rng>(
         &self,
         parameters: &<InstantiatedDPC as DPCScheme<MerkleTreeLedger>>::Parameters,
+        program_registry: &ProgramRegistry<Components>,
         old_records: Vec<DPCRecord<Components>>,
         old_account_private_keys: Vec<AccountPrivateKey<Components>>,
         new_record_owners: Vec<AccountAddress<Components>>,
@@ -480,36 +685,16 @@ impl ConsensusParameters {
 
         let local_data = execute_context.into_local_data();
 
-        let noop_program_snark_id = to_bytes![ProgramVerificationKeyCRH::hash(
-            &parameters.system_parameters.program_verification_key_crh,
-            &to_bytes![parameters.noop_program_snark_parameters.verification_key]?
-        )?]?;
-
-        let dpc_program =
-            NoopProgram::<_, <Components as BaseDPCComponents>::NoopProgramSNARK>::new(noop_program_snark_id);
-
         let mut old_death_program_proofs = vec![];
         for i in 0..NUM_INPUT_RECORDS {
-            let private_input = dpc_program.execute(
-                &parameters.noop_program_snark_parameters.proving_key,
-                &parameters.noop_program_snark_parameters.verification_key,
-                &local_data,
-                i as u8,
-                rng,
-            )?;
+            let private_input = program_registry.execute(&local_data, i as u8, rng)?;
 
             old_death_program_proofs.push(private_input);
         }
 
         let mut new_birth_program_proofs = vec![];
         for j in 0..NUM_OUTPUT_RECORDS {
-            let private_input = dpc_program.execute(
-                &parameters.noop_program_snark_parameters.proving_key,
-                &parameters.noop_program_snark_parameters.verification_key,
-                &local_data,
-                (NUM_INPUT_RECORDS + j) as u8,
-                rng,
-            )?;
+            let private_input = program_registry.execute(&local_data, (NUM_INPUT_RECORDS + j) as u8, rng)?;
 
             new_birth_program_proofs.push(private_input);
         }
@@ -531,6 +716,7 @@ impl ConsensusParameters {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
     use rand::{thread_rng, Rng};
     use snarkos_objects::PedersenMerkleRootHash;
     use snarkos_testing::consensus::DATA;
@@ -576,6 +762,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_median_time_past() {
+        let header = |time: i64| BlockHeader {
+            time,
+            ..DATA.block_1.header.clone()
+        };
+
+        assert_eq!(ConsensusParameters::median_time_past(&[]), 0);
+        assert_eq!(ConsensusParameters::median_time_past(&[header(5)]), 5);
+
+        // A single skewed timestamp does not move the median
+        let headers: Vec<BlockHeader> = vec![9, 1, 100_000, 3, 7].into_iter().map(header).collect();
+        assert_eq!(ConsensusParameters::median_time_past(&headers), 7);
+    }
+
     #[test]
     fn verify_header() {
         // mine a PoSW proof
@@ -586,12 +787,19 @@ mod tests {
             max_nonce: std::u32::MAX - 1,
             target_block_time: 2i64, //unix seconds
             network: Network::Mainnet,
+            retarget_algorithm: RetargetAlgorithm::Bitcoin,
+            reward_schedule: RewardSchedule::ALEO,
+            upgrades: vec![],
             verifier: posw,
             authorized_inner_snark_ids: vec![],
+            events: ConsensusEvents::new(),
+            network_time: NetworkTime::new(),
+            dev_mode: false,
         };
 
         let b1 = DATA.block_1.clone();
         let h1 = b1.header.clone();
+        let ancestors = vec![h1.clone()];
 
         let b2 = DATA.block_2.clone();
         let h2 = b2.header.clone();
@@ -600,63 +808,72 @@ mod tests {
 
         // OK
         consensus
-            .verify_header(&h2, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap();
 
         // invalid parent hash
         let mut h2_err = h2.clone();
         h2_err.previous_block_hash = BlockHeaderHash([9; 32]);
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // invalid merkle root hash
         let mut h2_err = h2.clone();
         h2_err.merkle_root_hash = MerkleRootHash([3; 32]);
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // past block
         let mut h2_err = h2.clone();
         h2_err.time = 100;
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
+            .unwrap_err();
+
+        // block before the median time past
+        let late_ancestors = vec![BlockHeader {
+            time: h2.time + 1,
+            ..h1.clone()
+        }];
+        consensus
+            .verify_header(&h2, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &late_ancestors)
             .unwrap_err();
 
         // far in the future block
         let mut h2_err = h2.clone();
         h2_err.time = Utc::now().timestamp() + 7201;
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // invalid difficulty
         let mut h2_err = h2.clone();
         h2_err.difficulty_target = 100; // set the difficulty very very high
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // invalid nonce
         let mut h2_err = h2.clone();
         h2_err.nonce = std::u32::MAX; // over the max nonce
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // invalid pedersen merkle root hash
         let mut h2_err = h2.clone();
         h2_err.pedersen_merkle_root_hash = PedersenMerkleRootHash([9; 32]);
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
 
         // expected difficulty did not match the difficulty target
         let mut h2_err = h2.clone();
-        h2_err.difficulty_target = consensus.get_block_difficulty(&h1, Utc::now().timestamp()) + 1;
+        h2_err.difficulty_target = consensus.get_block_difficulty(&ancestors, Utc::now().timestamp(), 2) + 1;
         consensus
-            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
+            .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root, 2, &ancestors)
             .unwrap_err();
     }
 }
@@ -72,6 +72,59 @@ pub fn bitcoin_retarget(
     x
 }
 
+/// The fixed point precision of the average block time of `ema_retarget`.
+const EMA_PRECISION: u128 = 1 << 32;
+
+/// Exponential moving average difficulty retarget algorithm.
+/// Averages the block times of the last `window` blocks with a smoothing factor of `1 / window`,
+/// starting from the target block time, and scales the parent difficulty by the average block time.
+/// `ancestor_timestamps` are the timestamps of the parent block and its ancestors, newest first,
+/// see `Ledger::get_ancestor_headers`. Fewer than `window` ancestors are averaged with the target block time.
+/// Each block time is limited to a factor of 2 of the target block time.
+/// With a window of 1 block this is the bitcoin retarget algorithm.
+pub fn ema_retarget(
+    block_timestamp: i64,
+    ancestor_timestamps: &[i64],
+    target_block_time: i64,
+    parent_difficulty: u64,
+    window: u32,
+) -> u64 {
+    let window = u32::max(window, 1) as usize;
+
+    // The block times of the new block and its ancestors, newest first
+    let timestamps: Vec<i64> = std::iter::once(block_timestamp)
+        .chain(ancestor_timestamps.iter().cloned())
+        .collect();
+    let block_times = timestamps.windows(2).take(window).map(|pair| {
+        let mut time_elapsed = pair[0] - pair[1];
+
+        // Limit difficulty adjustment by factor of 2
+        if time_elapsed < target_block_time / 2 {
+            time_elapsed = target_block_time / 2
+        } else if time_elapsed > target_block_time * 2 {
+            time_elapsed = target_block_time * 2
+        }
+
+        time_elapsed as u128
+    });
+
+    let target_block_time = target_block_time as u128;
+    let window = window as u128;
+
+    // ema = ema + (block_time - ema) / window, from the oldest block time to the newest
+    let mut ema = target_block_time * EMA_PRECISION;
+    for block_time in block_times.rev() {
+        ema = (ema * (window - 1) + block_time * EMA_PRECISION) / window;
+    }
+
+    // parent_diff * ema / target_block_time
+    let mut x: u128 = parent_difficulty as u128;
+    x *= ema;
+    x /= target_block_time * EMA_PRECISION;
+
+    u128::min(x, u64::max_value() as u128) as u64
+}
+
 /// Ethereum difficulty retarget algorithm.
 pub fn ethereum_retarget(block_timestamp: i64, parent_timestamp: i64, parent_difficulty: u64) -> u64 {
     let parent_diff = parent_difficulty as f64;
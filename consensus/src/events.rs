@@ -0,0 +1,77 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Ledger and memory pool events
+//!
+//! `ConsensusEvents` broadcasts the changes made to the canon chain and the memory pool,
+//! so that subscribers are notified instead of polling the node.
+
+use snarkos_models::objects::Transaction;
+use snarkos_objects::Block;
+
+use tokio::sync::broadcast;
+
+/// The number of events kept for subscribers that fall behind.
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// A change to the canon chain or the memory pool.
+#[derive(Debug, Clone)]
+pub enum ConsensusEvent<T: Transaction> {
+    /// A block was committed to the canon chain at the given height
+    NewBlock { height: u32, block: Block<T> },
+
+    /// Canon blocks after the shared block height were reverted by a fork to a heavier side chain
+    Reorg {
+        shared_block_height: u32,
+        reverted_blocks: Vec<Block<T>>,
+    },
+
+    /// A transaction was added to the memory pool
+    NewTransaction { size: usize, transaction: T },
+}
+
+/// Broadcasts consensus events to every subscriber.
+/// Clones share the same channel, so events emitted through any clone reach all subscribers.
+#[derive(Debug, Clone)]
+pub struct ConsensusEvents<T: Transaction> {
+    sender: broadcast::Sender<ConsensusEvent<T>>,
+}
+
+impl<T: Transaction> ConsensusEvents<T> {
+    /// Create a new event channel with no subscribers.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+
+        Self { sender }
+    }
+
+    /// Returns a receiver of the events emitted after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConsensusEvent<T>> {
+        self.sender.subscribe()
+    }
+
+    /// Send an event to the current subscribers.
+    pub fn emit(&self, event: ConsensusEvent<T>) {
+        // Sending only fails when there are no subscribers to notify
+        let _ = self.sender.send(event);
+    }
+}
+
+impl<T: Transaction> Default for ConsensusEvents<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
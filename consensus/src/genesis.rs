@@ -0,0 +1,103 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{ConsensusParameters, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{instantiated::*, parameters::PublicParameters, record::DPCRecord};
+use snarkos_errors::consensus::ConsensusError;
+use snarkos_models::algorithms::CRH;
+use snarkos_objects::{dpc::DPCTransactions, AccountAddress, Block, BlockHeader, BlockHeaderHash, ProofOfSuccinctWork};
+use snarkos_posw::txids_to_roots;
+use snarkos_utilities::{to_bytes, ToBytes};
+
+use chrono::Utc;
+use rand::Rng;
+use std::path::Path;
+
+/// The difficulty target of the hardcoded genesis block.
+pub const GENESIS_DIFFICULTY_TARGET: u64 = 0x07FF_FFFF_FFFF_FFFF_u64;
+
+/// The difficulty target of every block on a development chain, which any proof meets.
+pub const DEV_DIFFICULTY_TARGET: u64 = u64::MAX;
+
+impl ConsensusParameters {
+    /// Returns a new genesis block and the records it creates.
+    /// The genesis coinbase transaction pays the reward of the first block to the recipient.
+    /// The given ledger must be empty, see `Ledger::open_empty`.
+    pub fn create_genesis_block<R: Rng>(
+        &self,
+        parameters: &PublicParameters<Components>,
+        recipient: AccountAddress<Components>,
+        empty_ledger: &MerkleTreeLedger,
+        rng: &mut R,
+    ) -> Result<(Block<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
+        let program_vk_hash = to_bytes![ProgramVerificationKeyCRH::hash(
+            &parameters.system_parameters.program_verification_key_crh,
+            &to_bytes![parameters.noop_program_snark_parameters.verification_key]?
+        )?]?;
+
+        let new_birth_programs = vec![program_vk_hash.clone(); NUM_OUTPUT_RECORDS];
+        let new_death_programs = vec![program_vk_hash.clone(); NUM_OUTPUT_RECORDS];
+
+        let (records, transaction) = self.create_coinbase_transaction(
+            0,
+            &DPCTransactions::new(),
+            parameters,
+            &program_vk_hash,
+            new_birth_programs,
+            new_death_programs,
+            recipient,
+            empty_ledger,
+            rng,
+        )?;
+
+        let transactions = DPCTransactions(vec![transaction]);
+        let (merkle_root_hash, pedersen_merkle_root_hash, _) = txids_to_roots(&transactions.to_transaction_ids()?);
+
+        let difficulty_target = match self.dev_mode {
+            true => DEV_DIFFICULTY_TARGET,
+            false => GENESIS_DIFFICULTY_TARGET,
+        };
+
+        let header = BlockHeader {
+            previous_block_hash: BlockHeaderHash([0u8; 32]),
+            merkle_root_hash,
+            pedersen_merkle_root_hash,
+            time: Utc::now().timestamp(),
+            difficulty_target,
+            nonce: 0,
+            proof: ProofOfSuccinctWork::default(),
+        };
+
+        Ok((Block { header, transactions }, records))
+    }
+
+    /// Creates a new ledger at the given path, whose genesis block funds the recipient.
+    pub fn create_ledger_with_genesis<R: Rng>(
+        &self,
+        path: &Path,
+        parameters: &PublicParameters<Components>,
+        recipient: AccountAddress<Components>,
+        rng: &mut R,
+    ) -> Result<MerkleTreeLedger, ConsensusError> {
+        let ledger = MerkleTreeLedger::open_empty(path)?;
+        let (genesis_block, records) = self.create_genesis_block(parameters, recipient, &ledger, rng)?;
+
+        ledger.insert_and_commit(&genesis_block)?;
+        ledger.store_records(&records)?;
+
+        Ok(ledger)
+    }
+}
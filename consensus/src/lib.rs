@@ -43,12 +43,27 @@ pub use consensus::*;
 pub mod difficulty;
 pub use difficulty::*;
 
+pub mod events;
+pub use events::*;
+
+pub mod genesis;
+pub use genesis::*;
+
 pub mod miner;
 pub use miner::Miner;
 
 pub mod memory_pool;
 pub use memory_pool::MemoryPool;
 
+pub mod network_time;
+pub use network_time::NetworkTime;
+
+pub mod profile;
+pub use profile::*;
+
+pub mod upgrades;
+pub use upgrades::*;
+
 use snarkos_dpc::base_dpc::instantiated::{CommitmentMerkleParameters, Tx};
 use snarkos_storage::Ledger;
 
@@ -16,14 +16,18 @@
 
 //! Transactions memory pool
 //!
-//! `MemoryPool` keeps a vector of transactions seen by the miner.
+//! `MemoryPool` keeps the transactions seen by the miner, bounded by a byte budget.
+//! Transactions are prioritised by their fee per byte, where the fee is the transaction value balance.
+//! Entries expire after a number of blocks and are revalidated when the canon chain changes.
+//! Every accepted transaction is announced to the subscribers of the memory pool events.
 
+use crate::events::{ConsensusEvent, ConsensusEvents};
 use snarkos_errors::consensus::ConsensusError;
 use snarkos_models::{
     algorithms::LoadableMerkleParameters,
     objects::{LedgerScheme, Transaction},
 };
-use snarkos_objects::{dpc::DPCTransactions, BlockHeader};
+use snarkos_objects::{dpc::DPCTransactions, Block, BlockHeader};
 use snarkos_storage::Ledger;
 use snarkos_utilities::{
     bytes::{FromBytes, ToBytes},
@@ -31,7 +35,7 @@ use snarkos_utilities::{
     to_bytes,
 };
 
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
 /// Stores a transaction and it's size in the memory pool.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -40,57 +44,121 @@ pub struct Entry<T: Transaction> {
     pub transaction: T,
 }
 
+impl<T: Transaction> Entry<T> {
+    /// Returns the fee paid by the transaction.
+    #[inline]
+    pub fn fee(&self) -> i64 {
+        self.transaction.value_balance().into()
+    }
+
+    /// Compares the fee per byte of this entry with the fee per byte of another entry.
+    #[inline]
+    pub fn cmp_fee_rate(&self, other: &Self) -> Ordering {
+        let fee_rate = self.fee() as i128 * other.size as i128;
+        let other_fee_rate = other.fee() as i128 * self.size as i128;
+
+        fee_rate.cmp(&other_fee_rate)
+    }
+}
+
 /// Stores transactions received by the server.
 /// Transaction entries will eventually be fetched by the miner and assembled into blocks.
 #[derive(Debug, Clone)]
 pub struct MemoryPool<T: Transaction> {
     pub total_size: usize,
 
+    // The maximum total size of the stored transactions in bytes
+    pub max_size: usize,
+
+    // The number of blocks after which an entry expires
+    pub expiry: u32,
+
     // Hashmap transaction_id -> Entry
     pub transactions: HashMap<Vec<u8>, Entry<T>>,
+
+    // Hashmap transaction_id -> block height when the entry was inserted
+    pub insertion_heights: HashMap<Vec<u8>, u32>,
+
+    // Notifies subscribers of the transactions added to the memory pool
+    pub events: ConsensusEvents<T>,
 }
 
+/// The default byte budget of the memory pool.
+pub const DEFAULT_MEMORY_POOL_SIZE: usize = 100_000_000;
+
+/// The default number of blocks after which a memory pool entry expires.
+pub const DEFAULT_MEMORY_POOL_EXPIRY: u32 = 8640;
+
 const BLOCK_HEADER_SIZE: usize = BlockHeader::size();
 const COINBASE_TRANSACTION_SIZE: usize = 1490; // TODO Find the value for actual coinbase transaction size
 
 impl<T: Transaction> MemoryPool<T> {
-    /// Initialize a new memory pool with no transactions
+    /// Initialize a new memory pool with no transactions and the default byte budget
     #[inline]
     pub fn new() -> Self {
+        Self::with_max_size(DEFAULT_MEMORY_POOL_SIZE)
+    }
+
+    /// Initialize a new memory pool with no transactions and the given byte budget
+    #[inline]
+    pub fn with_max_size(max_size: usize) -> Self {
         Self {
             total_size: 0,
+            max_size,
+            expiry: DEFAULT_MEMORY_POOL_EXPIRY,
             transactions: HashMap::<Vec<u8>, Entry<T>>::new(),
+            insertion_heights: HashMap::<Vec<u8>, u32>::new(),
+            events: ConsensusEvents::new(),
         }
     }
 
-    /// Load the memory pool from previously stored state in storage
+    /// Load the memory pool from previously stored state in storage.
+    /// Entries keep the block height they were first inserted at, so their expiry carries over restarts.
+    /// Entries stored without an insertion height are treated as inserted at the latest block height.
     #[inline]
-    pub fn from_storage<P: LoadableMerkleParameters>(storage: &Ledger<T, P>) -> Result<Self, ConsensusError> {
-        let mut memory_pool = Self::new();
+    pub fn from_storage<P: LoadableMerkleParameters>(
+        storage: &Ledger<T, P>,
+        max_size: usize,
+    ) -> Result<Self, ConsensusError> {
+        let mut memory_pool = Self::with_max_size(max_size);
 
         if let Ok(serialized_transactions) = storage.get_memory_pool() {
-            if let Ok(transaction_bytes) = DPCTransactions::<T>::read(&serialized_transactions[..]) {
+            let mut reader = &serialized_transactions[..];
+
+            if let Ok(transaction_bytes) = DPCTransactions::<T>::read(&mut reader) {
                 for transaction in transaction_bytes.0 {
+                    let insertion_height = u32::read(&mut reader).ok();
+
                     let size = transaction.size();
                     let entry = Entry { transaction, size };
-                    memory_pool.insert(storage, entry)?;
+
+                    if let (Some(transaction_id), Some(insertion_height)) =
+                        (memory_pool.insert(storage, entry)?, insertion_height)
+                    {
+                        memory_pool.insertion_heights.insert(transaction_id, insertion_height);
+                    }
                 }
             }
         }
 
+        memory_pool.remove_expired(storage.get_latest_block_height())?;
+
         Ok(memory_pool)
     }
 
-    /// Store the memory pool state to the database
+    /// Store the memory pool state to the database.
+    /// The transactions are followed by their insertion heights, in the same order.
     #[inline]
     pub fn store<P: LoadableMerkleParameters>(&self, storage: &Ledger<T, P>) -> Result<(), ConsensusError> {
         let mut transactions = DPCTransactions::<T>::new();
+        let mut insertion_heights = vec![];
 
-        for (_transaction_id, entry) in self.transactions.iter() {
-            transactions.push(entry.transaction.clone())
+        for (transaction_id, entry) in self.transactions.iter() {
+            transactions.push(entry.transaction.clone());
+            insertion_heights.push(self.insertion_heights.get(transaction_id).cloned().unwrap_or(0));
         }
 
-        let serialized_transactions = to_bytes![transactions]?.to_vec();
+        let serialized_transactions = to_bytes![transactions, insertion_heights]?.to_vec();
 
         storage.store_to_memory_pool(serialized_transactions)?;
 
@@ -98,6 +166,7 @@ impl<T: Transaction> MemoryPool<T> {
     }
 
     /// Adds entry to memory pool if valid in the current ledger.
+    /// If the memory pool is full, entries with a lower fee per byte are evicted to make room.
     #[inline]
     pub fn insert<P: LoadableMerkleParameters>(
         &mut self,
@@ -141,25 +210,116 @@ impl<T: Transaction> MemoryPool<T> {
             return Ok(None);
         }
 
+        if !self.make_room(&entry)? {
+            return Ok(None);
+        }
+
         let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
+        self.events.emit(ConsensusEvent::NewTransaction {
+            size: entry.size,
+            transaction: entry.transaction.clone(),
+        });
+
         self.total_size += entry.size;
         self.transactions.insert(transaction_id.clone(), entry);
+        self.insertion_heights
+            .insert(transaction_id.clone(), storage.get_latest_block_height());
 
         Ok(Some(transaction_id))
     }
 
+    /// Returns the transactions of blocks reverted by a fork to the memory pool.
+    /// Coinbase transactions and transactions that are not valid in the current ledger are skipped.
+    pub fn insert_reverted_blocks<P: LoadableMerkleParameters>(
+        &mut self,
+        storage: &Ledger<T, P>,
+        reverted_blocks: &[Block<T>],
+    ) -> Result<(), ConsensusError> {
+        for block in reverted_blocks {
+            for transaction in block.transactions.iter() {
+                let entry = Entry {
+                    size: transaction.size(),
+                    transaction: transaction.clone(),
+                };
+
+                // Coinbase transactions pay out the block reward, so they have a negative fee
+                if entry.fee() < 0 || !storage.transaction_digest_is_valid(&entry.transaction) {
+                    continue;
+                }
+
+                self.insert(storage, entry)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evicts the entries with the lowest fee per byte until the given entry fits in the byte budget.
+    /// Only entries with a lower fee per byte than the given entry are evicted.
+    /// Returns `false` without evicting any entries if the given entry cannot fit.
+    fn make_room(&mut self, entry: &Entry<T>) -> Result<bool, ConsensusError> {
+        if self.total_size + entry.size <= self.max_size {
+            return Ok(true);
+        }
+
+        let mut evictable = self
+            .transactions
+            .iter()
+            .filter(|(_, stored)| stored.cmp_fee_rate(entry) == Ordering::Less)
+            .collect::<Vec<_>>();
+        evictable.sort_by(|(_, a), (_, b)| a.cmp_fee_rate(b));
+
+        let mut total_size = self.total_size;
+        let mut evicted = vec![];
+
+        for (transaction_id, stored) in evictable {
+            if total_size + entry.size <= self.max_size {
+                break;
+            }
+
+            total_size -= stored.size;
+            evicted.push(transaction_id.clone());
+        }
+
+        if total_size + entry.size > self.max_size {
+            return Ok(false);
+        }
+
+        for transaction_id in evicted {
+            self.remove_by_hash(&transaction_id)?;
+        }
+
+        Ok(true)
+    }
+
     /// Cleanse the memory pool of outdated transactions.
+    /// Expired entries and entries whose ledger digest is no longer a canon ledger state are removed.
     #[inline]
     pub fn cleanse<P: LoadableMerkleParameters>(&mut self, storage: &Ledger<T, P>) -> Result<(), ConsensusError> {
-        let mut new_memory_pool = Self::new();
+        self.remove_expired(storage.get_latest_block_height())?;
 
-        for (_, entry) in self.clone().transactions.iter() {
-            new_memory_pool.insert(&storage, entry.clone())?;
+        let mut new_memory_pool = Self::with_max_size(self.max_size);
+        new_memory_pool.expiry = self.expiry;
+
+        for (transaction_id, entry) in self.clone().transactions.iter() {
+            if !storage.transaction_digest_is_valid(&entry.transaction) {
+                continue;
+            }
+
+            // Keep the original insertion height so that the entry still expires on time
+            if new_memory_pool.insert(&storage, entry.clone())?.is_some() {
+                if let Some(insertion_height) = self.insertion_heights.get(transaction_id) {
+                    new_memory_pool
+                        .insertion_heights
+                        .insert(transaction_id.clone(), *insertion_height);
+                }
+            }
         }
 
         self.total_size = new_memory_pool.total_size;
         self.transactions = new_memory_pool.transactions;
+        self.insertion_heights = new_memory_pool.insertion_heights;
 
         Ok(())
     }
@@ -173,6 +333,7 @@ impl<T: Transaction> MemoryPool<T> {
             let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
             self.transactions.remove(&transaction_id);
+            self.insertion_heights.remove(&transaction_id);
 
             return Ok(Some(transaction_id));
         }
@@ -187,6 +348,7 @@ impl<T: Transaction> MemoryPool<T> {
             Some(entry) => {
                 self.total_size -= entry.size;
                 self.transactions.remove(transaction_id);
+                self.insertion_heights.remove(transaction_id);
 
                 Ok(Some(entry.clone()))
             }
@@ -194,6 +356,22 @@ impl<T: Transaction> MemoryPool<T> {
         }
     }
 
+    /// Removes the entries that were inserted at least `expiry` blocks before the given block height.
+    pub fn remove_expired(&mut self, block_height: u32) -> Result<(), ConsensusError> {
+        let expired = self
+            .insertion_heights
+            .iter()
+            .filter(|(_, insertion_height)| block_height.saturating_sub(**insertion_height) >= self.expiry)
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        for transaction_id in expired {
+            self.remove_by_hash(&transaction_id)?;
+        }
+
+        Ok(())
+    }
+
     /// Returns whether or not the memory pool contains the entry.
     #[inline]
     pub fn contains(&self, entry: &Entry<T>) -> bool {
@@ -210,24 +388,37 @@ impl<T: Transaction> MemoryPool<T> {
         storage: &Ledger<T, P>,
         max_size: usize,
     ) -> Result<DPCTransactions<T>, ConsensusError> {
-        let max_size = max_size - (BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
+        let entries = self.transactions.values().cloned().collect();
+
+        Ok(Self::select_candidates(storage, entries, max_size))
+    }
+
+    /// Selects the entries with the highest fee per byte that fit in a block of `max_size` bytes.
+    /// Entries that conflict with the ledger or with an already selected entry are skipped.
+    pub fn select_candidates<P: LoadableMerkleParameters>(
+        storage: &Ledger<T, P>,
+        mut entries: Vec<Entry<T>>,
+        max_size: usize,
+    ) -> DPCTransactions<T> {
+        let max_size = max_size.saturating_sub(BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
 
         let mut block_size = 0;
         let mut transactions = DPCTransactions::new();
 
-        // TODO Change naive transaction selection
-        for (_transaction_id, entry) in self.transactions.clone() {
+        entries.sort_by(|a, b| b.cmp_fee_rate(a));
+
+        for entry in entries {
             if block_size + entry.size <= max_size {
                 if storage.transcation_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
                     continue;
                 }
 
                 block_size += entry.size;
-                transactions.push(entry.transaction.clone());
+                transactions.push(entry.transaction);
             }
         }
 
-        Ok(transactions)
+        transactions
     }
 }
 
@@ -345,6 +536,69 @@ mod tests {
         kill_storage_sync(blockchain);
     }
 
+    /// Returns two entries with different fees per byte, ordered from the lowest to the highest fee per byte.
+    fn entries_by_fee_rate() -> (Entry<Tx>, Entry<Tx>) {
+        let entry_1 = Entry {
+            size: 400,
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+        };
+        let entry_2 = Entry {
+            size: 500,
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        };
+
+        match entry_1.cmp_fee_rate(&entry_2) {
+            Ordering::Less => (entry_1, entry_2),
+            Ordering::Greater => (entry_2, entry_1),
+            Ordering::Equal => panic!("test entries must have different fee rates"),
+        }
+    }
+
+    #[test]
+    fn get_candidates_by_fee_rate() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+
+        let (low, high) = entries_by_fee_rate();
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.insert(&blockchain, low.clone()).unwrap();
+        mem_pool.insert(&blockchain, high.clone()).unwrap();
+
+        // Only one of the entries fits in the block
+        let max_block_size = high.size.max(low.size) + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+
+        let candidates = mem_pool.get_candidates(&blockchain, max_block_size).unwrap();
+
+        assert_eq!(vec![high.transaction], candidates.0);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn evict_lowest_fee_rate() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+
+        let (low, high) = entries_by_fee_rate();
+
+        let mut mem_pool = MemoryPool::with_max_size(600);
+        mem_pool.insert(&blockchain, low.clone()).unwrap();
+
+        // An entry with a higher fee per byte evicts the entry with a lower fee per byte
+        assert!(mem_pool.insert(&blockchain, high.clone()).unwrap().is_some());
+
+        assert!(mem_pool.contains(&high));
+        assert!(!mem_pool.contains(&low));
+        assert_eq!(high.size, mem_pool.total_size);
+
+        // An entry with a lower fee per byte is refused when the memory pool is full
+        assert!(mem_pool.insert(&blockchain, low.clone()).unwrap().is_none());
+
+        assert!(!mem_pool.contains(&low));
+        assert_eq!(high.size, mem_pool.total_size);
+
+        kill_storage_sync(blockchain);
+    }
+
     #[test]
     fn store_memory_pool() {
         let blockchain = Arc::new(FIXTURE_VK.ledger());
@@ -362,13 +616,76 @@ mod tests {
 
         mem_pool.store(&blockchain).unwrap();
 
-        let new_mem_pool = MemoryPool::from_storage(&blockchain).unwrap();
+        let new_mem_pool = MemoryPool::from_storage(&blockchain, DEFAULT_MEMORY_POOL_SIZE).unwrap();
 
         assert_eq!(mem_pool.total_size, new_mem_pool.total_size);
 
         kill_storage_sync(blockchain);
     }
 
+    #[test]
+    fn store_memory_pool_insertion_heights() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let transaction_id = mem_pool
+            .insert(&blockchain, Entry {
+                size: TRANSACTION_2.len(),
+                transaction,
+            })
+            .unwrap()
+            .unwrap();
+
+        mem_pool.store(&blockchain).unwrap();
+
+        // The entry keeps its insertion height after new blocks and a reload
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        blockchain.insert_and_commit(&block_1).unwrap();
+
+        let new_mem_pool = MemoryPool::from_storage(&blockchain, DEFAULT_MEMORY_POOL_SIZE).unwrap();
+
+        assert_eq!(1, new_mem_pool.transactions.len());
+        assert_eq!(Some(&0), new_mem_pool.insertion_heights.get(&transaction_id));
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn insert_announces_new_transaction_once() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+
+        let mut mem_pool = MemoryPool::new();
+        let mut events = mem_pool.events.subscribe();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let size = TRANSACTION_2.len();
+
+        mem_pool
+            .insert(&blockchain, Entry {
+                size,
+                transaction: transaction.clone(),
+            })
+            .unwrap();
+
+        match events.try_recv().unwrap() {
+            ConsensusEvent::NewTransaction {
+                size: event_size,
+                transaction: event_transaction,
+            } => {
+                assert_eq!(size, event_size);
+                assert_eq!(transaction, event_transaction);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        // Duplicate inserts are not announced
+        mem_pool.insert(&blockchain, Entry { size, transaction }).unwrap();
+
+        assert!(events.try_recv().is_err());
+
+        kill_storage_sync(blockchain);
+    }
+
     #[test]
     fn cleanse_memory_pool() {
         let blockchain = Arc::new(FIXTURE_VK.ledger());
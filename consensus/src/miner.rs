@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use crate::{memory_pool::Entry, ConsensusParameters, MemoryPool, MerkleTreeLedger};
 use snarkos_dpc::base_dpc::{instantiated::*, parameters::PublicParameters, record::DPCRecord};
 use snarkos_errors::consensus::ConsensusError;
 use snarkos_models::{
@@ -27,7 +27,6 @@ use snarkos_posw::{txids_to_roots, PoswMarlin};
 use snarkos_storage::Ledger;
 use snarkos_utilities::{bytes::ToBytes, to_bytes};
 
-use chrono::Utc;
 use rand::{thread_rng, Rng};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -108,15 +107,25 @@ impl Miner {
         Ok(records)
     }
 
-    /// Acquires the storage lock and returns the previous block header and verified transactions.
+    /// Acquires the storage lock and returns the headers of the previous block and its ancestors, newest first,
+    /// and the verified transactions.
+    /// The candidate transactions with the highest fee per byte that fit in the maximum block size are selected.
     pub fn establish_block(
         &self,
         parameters: &PublicParameters<Components>,
         storage: &MerkleTreeLedger,
         transactions: &DPCTransactions<Tx>,
-    ) -> Result<(BlockHeader, DPCTransactions<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
+    ) -> Result<(Vec<BlockHeader>, DPCTransactions<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
         let rng = &mut thread_rng();
-        let mut transactions = transactions.clone();
+
+        let entries = transactions
+            .iter()
+            .map(|transaction| Entry {
+                size: transaction.size(),
+                transaction: transaction.clone(),
+            })
+            .collect();
+        let mut transactions = MemoryPool::select_candidates(storage, entries, self.consensus.max_block_size);
         let coinbase_records = self.add_coinbase_transaction(parameters, &storage, &mut transactions, rng)?;
 
         // Verify transactions
@@ -126,23 +135,30 @@ impl Miner {
             storage
         )?);
 
-        let previous_block_header = storage.get_latest_block()?.header;
+        let previous_block_hash = storage.get_latest_block()?.header.get_hash();
+        let block_height = storage.get_latest_block_height() + 1;
+        let ancestor_headers =
+            storage.get_ancestor_headers(&previous_block_hash, self.consensus.ancestor_span(block_height))?;
 
-        Ok((previous_block_header, transactions, coinbase_records))
+        Ok((ancestor_headers, transactions, coinbase_records))
     }
 
-    /// Run proof of work to find block.
+    /// Run proof of work to find the block at the given height.
+    /// `ancestor_headers` are the parent of the block and its ancestors, newest first.
     /// Returns BlockHeader with nonce solution.
     pub fn find_block<T: Transaction>(
         &self,
         transactions: &DPCTransactions<T>,
-        parent_header: &BlockHeader,
+        ancestor_headers: &[BlockHeader],
+        block_height: u32,
     ) -> Result<BlockHeader, ConsensusError> {
         let txids = transactions.to_transaction_ids()?;
         let (merkle_root_hash, pedersen_merkle_root_hash, subroots) = txids_to_roots(&txids);
 
-        let time = Utc::now().timestamp();
-        let difficulty_target = self.consensus.get_block_difficulty(parent_header, time);
+        let time = self.consensus.network_time.now();
+        let difficulty_target = self
+            .consensus
+            .get_block_difficulty(ancestor_headers, time, block_height);
 
         // TODO: Switch this to use a user-provided RNG
         let (nonce, proof) = self.miner.mine(
@@ -153,7 +169,7 @@ impl Miner {
         )?;
 
         Ok(BlockHeader {
-            previous_block_hash: parent_header.get_hash(),
+            previous_block_hash: ancestor_headers[0].get_hash(),
             merkle_root_hash,
             pedersen_merkle_root_hash,
             time,
@@ -176,7 +192,7 @@ impl Miner {
 
         println!("Miner creating block");
 
-        let (previous_block_header, transactions, coinbase_records) =
+        let (ancestor_headers, transactions, coinbase_records) =
             self.establish_block(parameters, storage, &mut candidate_transactions)?;
 
         println!("Miner generated coinbase transaction");
@@ -186,7 +202,7 @@ impl Miner {
             println!("Coinbase record {:?} commitment: {:?}", index, record_commitment);
         }
 
-        let header = self.find_block(&transactions, &previous_block_header)?;
+        let header = self.find_block(&transactions, &ancestor_headers, storage.get_latest_block_height() + 1)?;
 
         println!("Miner found block");
 
@@ -0,0 +1,157 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Network-adjusted time
+//!
+//! Peers report their clock in the `Version` messages of their handshakes. `NetworkTime` adjusts
+//! the local clock by the median offset of the peer clocks, so a node with a skewed clock
+//! validates and mines block timestamps by the time of the network.
+
+use chrono::Utc;
+use parking_lot::RwLock;
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
+
+/// The number of peer clocks required before the local clock is adjusted.
+pub const MIN_TIME_SAMPLES: usize = 5;
+
+/// The maximum number of peer clocks kept.
+pub const MAX_TIME_SAMPLES: usize = 200;
+
+/// The maximum adjustment of the local clock in seconds.
+/// A larger median offset means the local clock is wrong, or the peers are lying, so it is ignored.
+pub const MAX_TIME_ADJUSTMENT: i64 = 70 * 60;
+
+/// The offsets of the peer clocks from the local clock.
+/// Clones share the same offsets, so samples added through any clone adjust the time of all of them.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkTime {
+    offsets: Arc<RwLock<HashMap<IpAddr, i64>>>,
+}
+
+impl NetworkTime {
+    /// Create a network time without any peer clocks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the clock of a peer, given by the timestamp of its `Version` message.
+    /// Each IP address contributes a single offset, so a peer cannot outvote the others by connecting from
+    /// many ports, and new IP addresses are ignored once `MAX_TIME_SAMPLES` are kept.
+    pub fn add_sample(&self, peer: IpAddr, peer_timestamp: i64) {
+        let offset = peer_timestamp - Utc::now().timestamp();
+        let mut offsets = self.offsets.write();
+
+        if offsets.len() < MAX_TIME_SAMPLES || offsets.contains_key(&peer) {
+            offsets.insert(peer, offset);
+        }
+    }
+
+    /// Returns the median offset of the peer clocks from the local clock in seconds.
+    /// The offset is zero until `MIN_TIME_SAMPLES` peer clocks are known, or if it exceeds `MAX_TIME_ADJUSTMENT`.
+    pub fn offset(&self) -> i64 {
+        let mut offsets: Vec<i64> = self.offsets.read().values().cloned().collect();
+        if offsets.len() < MIN_TIME_SAMPLES {
+            return 0;
+        }
+
+        offsets.sort_unstable();
+        let median = offsets[offsets.len() / 2];
+
+        match median.abs() > MAX_TIME_ADJUSTMENT {
+            true => 0,
+            false => median,
+        }
+    }
+
+    /// Returns the network-adjusted unix timestamp.
+    pub fn now(&self) -> i64 {
+        Utc::now().timestamp() + self.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    fn peer(host: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, host])
+    }
+
+    #[test]
+    fn test_median_peer_offset() {
+        let network_time = NetworkTime::new();
+        let now = Utc::now().timestamp();
+
+        // Too few peers to adjust the local clock
+        for host in 0..(MIN_TIME_SAMPLES as u8 - 1) {
+            network_time.add_sample(peer(host), now + 60);
+        }
+        assert_eq!(network_time.offset(), 0);
+
+        // The median offset ignores the outliers
+        network_time.add_sample(peer(100), now + 60);
+        network_time.add_sample(peer(101), now - 10_000);
+        network_time.add_sample(peer(102), now + 10_000);
+
+        let offset = network_time.offset();
+        assert!(offset >= 59 && offset <= 60);
+
+        // A peer contributes a single offset
+        let shared = network_time.clone();
+        for _ in 0..10 {
+            shared.add_sample(peer(101), now - 10_000);
+        }
+        let offset = network_time.offset();
+        assert!(offset >= 59 && offset <= 60);
+    }
+
+    #[test]
+    fn test_excessive_offset_is_ignored() {
+        let network_time = NetworkTime::new();
+        let now = Utc::now().timestamp();
+
+        for host in 0..MIN_TIME_SAMPLES as u8 {
+            network_time.add_sample(peer(host), now + MAX_TIME_ADJUSTMENT + 60);
+        }
+
+        assert_eq!(network_time.offset(), 0);
+    }
+
+    #[test]
+    fn test_one_sample_per_ip() {
+        let network_time = NetworkTime::new();
+        let now = Utc::now().timestamp();
+        let ports = |start: u16, count: u16| (start..start + count).map(|port| SocketAddr::from(([10, 0, 0, 1], port)));
+
+        // A single peer connecting from many ports does not reach the required number of samples
+        for address in ports(4130, MIN_TIME_SAMPLES as u16) {
+            network_time.add_sample(address.ip(), now + 600);
+        }
+        assert_eq!(network_time.offset(), 0);
+
+        // Nor can it outvote the other peers once they are sampled
+        for host in 2..(MIN_TIME_SAMPLES as u8 + 1) {
+            network_time.add_sample(peer(host), now);
+        }
+        for address in ports(5000, 10) {
+            network_time.add_sample(address.ip(), now + 600);
+        }
+
+        let offset = network_time.offset();
+        assert!(offset >= -1 && offset <= 0);
+    }
+}
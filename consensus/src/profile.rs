@@ -0,0 +1,340 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    difficulty::{bitcoin_retarget, ema_retarget, ethereum_retarget},
+    events::ConsensusEvents,
+    network_time::NetworkTime,
+    upgrades::ConsensusUpgrade,
+    ConsensusParameters,
+};
+use snarkos_objects::{AleoAmount, Network};
+use snarkos_posw::PoswMarlin;
+
+/// Hardcoded bootnodes maintained by Aleo.
+/// A node should try and connect to these first after coming online.
+const MAINNET_BOOTNODES: &[&str] = &[]; // "192.168.0.1:4130"
+const TESTNET1_BOOTNODES: &[&str] = &["50.18.83.123:4131"]; // "192.168.0.1:4131"
+
+/// The maximum block size in bytes on the Aleo networks.
+/// A block is relayed in a single network message, whose size limit is derived from this value.
+pub const MAX_BLOCK_SIZE: usize = 2_000_000;
+
+/// The block height from which the Aleo networks retarget the difficulty with an exponential moving average.
+pub const EMA_RETARGET_HEIGHT: u32 = 100_000;
+
+/// The smoothing window in blocks of the exponential moving average difficulty retarget of the Aleo networks.
+pub const EMA_RETARGET_WINDOW: u32 = 30;
+
+/// The difficulty retarget algorithm used to calculate the difficulty of the next block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetargetAlgorithm {
+    /// Scales the parent difficulty by the time elapsed, see `bitcoin_retarget`.
+    Bitcoin,
+    /// Adjusts the parent difficulty in steps of 1/1024, see `ethereum_retarget`.
+    Ethereum,
+    /// Scales the parent difficulty by the exponential moving average of the last `window` block times,
+    /// see `ema_retarget`.
+    Ema { window: u32 },
+}
+
+impl RetargetAlgorithm {
+    /// Returns the difficulty of a block mined at `block_timestamp` on top of its parent.
+    /// `ancestor_timestamps` are the timestamps of the parent and its ancestors, newest first,
+    /// of which the first `window` are used.
+    pub fn retarget(
+        &self,
+        block_timestamp: i64,
+        ancestor_timestamps: &[i64],
+        target_block_time: i64,
+        parent_difficulty: u64,
+    ) -> u64 {
+        let parent_timestamp = ancestor_timestamps[0];
+
+        match self {
+            RetargetAlgorithm::Bitcoin => {
+                bitcoin_retarget(block_timestamp, parent_timestamp, target_block_time, parent_difficulty)
+            }
+            RetargetAlgorithm::Ethereum => ethereum_retarget(block_timestamp, parent_timestamp, parent_difficulty),
+            RetargetAlgorithm::Ema { window } => ema_retarget(
+                block_timestamp,
+                ancestor_timestamps,
+                target_block_time,
+                parent_difficulty,
+                *window,
+            ),
+        }
+    }
+
+    /// Returns the number of ancestor headers, starting with the parent, that the retarget depends on.
+    pub fn window(&self) -> usize {
+        match self {
+            RetargetAlgorithm::Bitcoin | RetargetAlgorithm::Ethereum => 1,
+            RetargetAlgorithm::Ema { window } => u32::max(*window, 1) as usize,
+        }
+    }
+}
+
+/// A block reward that halves every `halving_interval` blocks, at most `max_halvings` times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardSchedule {
+    /// The reward of the genesis block
+    pub initial_reward: AleoAmount,
+
+    /// The number of blocks between two halvings
+    pub halving_interval: u32,
+
+    /// The number of times the reward halves before it stays constant
+    pub max_halvings: u32,
+}
+
+impl RewardSchedule {
+    /// The Aleo reward schedule halves every 4 years * 365 days * 24 hours * 100 blocks/hr = 3,504,000 blocks.
+    /// The block reward halves at most 2 times - minimum is 37.5 ALEO after 8 years.
+    pub const ALEO: RewardSchedule = RewardSchedule {
+        initial_reward: AleoAmount(150 * AleoAmount::COIN),
+        halving_interval: 4 * 365 * 24 * 100,
+        max_halvings: 2,
+    };
+
+    /// Returns the reward of the block at the given height.
+    pub fn block_reward(&self, block_num: u32) -> AleoAmount {
+        let num_halves = u32::min(block_num / self.halving_interval, self.max_halvings);
+        let reward = self.initial_reward.0 / (2_u64.pow(num_halves)) as i64;
+
+        AleoAmount::from_bytes(reward)
+    }
+}
+
+/// The genesis block a network starts from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenesisSource {
+    /// The genesis block of the Aleo testnet, see `snarkos_parameters::GenesisBlock`.
+    Hardcoded,
+    /// A new genesis block funding the miner address, see `ConsensusParameters::create_ledger_with_genesis`.
+    /// The chain is a local development chain, mined at the trivial `DEV_DIFFICULTY_TARGET`.
+    Generated,
+}
+
+/// The consensus rules and bootnodes of a network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsensusProfile {
+    /// The network the profile belongs to
+    pub network: Network,
+
+    /// Maximum block size in bytes
+    pub max_block_size: usize,
+
+    /// The amount of time it should take to find a block
+    pub target_block_time: i64,
+
+    /// The difficulty retarget algorithm
+    pub retarget_algorithm: RetargetAlgorithm,
+
+    /// The block reward schedule
+    pub reward_schedule: RewardSchedule,
+
+    /// The genesis block of the network
+    pub genesis: GenesisSource,
+
+    /// The changes of the consensus rules activated at later block heights
+    pub upgrades: Vec<ConsensusUpgrade>,
+
+    /// The bootnodes a node connects to after coming online
+    pub bootnodes: Vec<String>,
+}
+
+impl ConsensusProfile {
+    /// Returns the profile of the Aleo mainnet.
+    pub fn mainnet() -> Self {
+        Self {
+            network: Network::Mainnet,
+            max_block_size: MAX_BLOCK_SIZE,
+            target_block_time: 10i64,
+            retarget_algorithm: RetargetAlgorithm::Bitcoin,
+            reward_schedule: RewardSchedule::ALEO,
+            genesis: GenesisSource::Hardcoded,
+            upgrades: vec![ConsensusUpgrade {
+                activation_height: EMA_RETARGET_HEIGHT,
+                retarget_algorithm: Some(RetargetAlgorithm::Ema {
+                    window: EMA_RETARGET_WINDOW,
+                }),
+                ..ConsensusUpgrade::default()
+            }],
+            bootnodes: MAINNET_BOOTNODES.iter().map(|node| (*node).to_string()).collect(),
+        }
+    }
+
+    /// Returns the profile of the first Aleo testnet.
+    pub fn testnet1() -> Self {
+        Self {
+            network: Network::Testnet1,
+            bootnodes: TESTNET1_BOOTNODES.iter().map(|node| (*node).to_string()).collect(),
+            ..Self::mainnet()
+        }
+    }
+
+    /// Returns the profile of a custom network, which follows the testnet rules without any bootnodes.
+    pub fn custom(network_id: u8) -> Self {
+        Self {
+            network: Network::Custom(network_id),
+            bootnodes: vec![],
+            ..Self::testnet1()
+        }
+    }
+
+    /// Returns the profile of the given network.
+    pub fn from_network(network: Network) -> Self {
+        match network {
+            Network::Mainnet => Self::mainnet(),
+            Network::Testnet1 => Self::testnet1(),
+            Network::Custom(id) => Self::custom(id),
+        }
+    }
+
+    /// Returns the consensus parameters following this profile.
+    pub fn consensus_parameters(
+        &self,
+        verifier: PoswMarlin,
+        authorized_inner_snark_ids: Vec<Vec<u8>>,
+    ) -> ConsensusParameters {
+        ConsensusParameters {
+            max_block_size: self.max_block_size,
+            max_nonce: u32::max_value(),
+            target_block_time: self.target_block_time,
+            network: self.network,
+            retarget_algorithm: self.retarget_algorithm,
+            reward_schedule: self.reward_schedule,
+            upgrades: self.upgrades.clone(),
+            verifier,
+            authorized_inner_snark_ids,
+            events: ConsensusEvents::new(),
+            network_time: NetworkTime::new(),
+            dev_mode: self.genesis == GenesisSource::Generated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_from_network() {
+        assert_eq!(
+            ConsensusProfile::from_network(Network::Mainnet),
+            ConsensusProfile::mainnet()
+        );
+        assert_eq!(
+            ConsensusProfile::from_network(Network::Testnet1),
+            ConsensusProfile::testnet1()
+        );
+
+        let custom = ConsensusProfile::from_network(Network::Custom(7));
+        assert_eq!(custom.network, Network::Custom(7));
+        assert!(custom.bootnodes.is_empty());
+        assert_eq!(custom.max_block_size, ConsensusProfile::testnet1().max_block_size);
+    }
+
+    #[test]
+    fn test_ema_retarget() {
+        let target_block_time = 10;
+        let parent_difficulty = 1_000_000;
+
+        // A block found on time keeps the difficulty
+        let ema = RetargetAlgorithm::Ema { window: 20 };
+        assert_eq!(
+            ema.retarget(110, &[100], target_block_time, parent_difficulty),
+            parent_difficulty
+        );
+
+        // A slow block lowers the difficulty by a fraction of the bitcoin retarget adjustment
+        let bitcoin_difficulty = RetargetAlgorithm::Bitcoin.retarget(120, &[100], target_block_time, parent_difficulty);
+        assert_eq!(bitcoin_difficulty, 2_000_000);
+        assert_eq!(
+            ema.retarget(120, &[100], target_block_time, parent_difficulty),
+            1_050_000
+        );
+
+        // A fast block raises the difficulty by a fraction of the bitcoin retarget adjustment
+        assert_eq!(ema.retarget(100, &[100], target_block_time, parent_difficulty), 975_000);
+
+        // A window of a single block is the bitcoin retarget
+        let single_block = RetargetAlgorithm::Ema { window: 1 };
+        for block_time in 0..30 {
+            assert_eq!(
+                single_block.retarget(100 + block_time, &[100, 0], target_block_time, parent_difficulty),
+                RetargetAlgorithm::Bitcoin.retarget(100 + block_time, &[100, 0], target_block_time, parent_difficulty)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ema_retarget_sequence() {
+        let target_block_time = 10;
+        let parent_difficulty = 1_000_000;
+
+        // Block times from the oldest: 10, 100 (limited to 20), 20, 10 and 5 for the new block
+        let ancestor_timestamps = [165, 155, 135, 35, 25];
+
+        // The oldest block time is outside the window
+        // ema = 10
+        // ema = 10 + (20 - 10) / 4 = 12.5
+        // ema = 12.5 + (20 - 12.5) / 4 = 14.375
+        // ema = 14.375 + (10 - 14.375) / 4 = 13.28125
+        // ema = 13.28125 + (5 - 13.28125) / 4 = 11.2109375
+        let ema = RetargetAlgorithm::Ema { window: 4 };
+        assert_eq!(ema.window(), 4);
+        assert_eq!(
+            ema.retarget(170, &ancestor_timestamps, target_block_time, parent_difficulty),
+            1_121_093
+        );
+
+        // ema = 10
+        // ema = 10 + (10 - 10) / 2 = 10
+        // ema = 10 + (5 - 10) / 2 = 7.5
+        let ema = RetargetAlgorithm::Ema { window: 2 };
+        assert_eq!(
+            ema.retarget(170, &ancestor_timestamps, target_block_time, parent_difficulty),
+            750_000
+        );
+
+        // Fewer ancestors than the window are averaged with the target block time
+        // ema = 10 + (20 - 10) / 4 = 12.5
+        // ema = 12.5 + (5 - 12.5) / 4 = 10.625
+        let ema = RetargetAlgorithm::Ema { window: 4 };
+        assert_eq!(
+            ema.retarget(140, &[135, 35], target_block_time, parent_difficulty),
+            1_062_500
+        );
+    }
+
+    #[test]
+    fn test_custom_reward_schedule() {
+        let schedule = RewardSchedule {
+            initial_reward: AleoAmount(100),
+            halving_interval: 10,
+            max_halvings: 3,
+        };
+
+        assert_eq!(schedule.block_reward(0), AleoAmount(100));
+        assert_eq!(schedule.block_reward(9), AleoAmount(100));
+        assert_eq!(schedule.block_reward(10), AleoAmount(50));
+        assert_eq!(schedule.block_reward(29), AleoAmount(25));
+        assert_eq!(schedule.block_reward(30), AleoAmount(12));
+        assert_eq!(schedule.block_reward(u32::MAX), AleoAmount(12));
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    profile::{RetargetAlgorithm, RewardSchedule},
+    ConsensusParameters,
+};
+
+/// A change of the consensus rules, followed by every block from the activation height onwards.
+/// The rules an upgrade leaves as `None` stay as they were before the upgrade.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsensusUpgrade {
+    /// The height of the first block following the upgraded rules
+    pub activation_height: u32,
+
+    /// The new difficulty retarget algorithm
+    pub retarget_algorithm: Option<RetargetAlgorithm>,
+
+    /// The new block reward schedule
+    pub reward_schedule: Option<RewardSchedule>,
+
+    /// The new authorized inner SNARK IDs
+    pub authorized_inner_snark_ids: Option<Vec<Vec<u8>>>,
+}
+
+impl ConsensusParameters {
+    /// Returns the rule of the latest upgrade active at the given block height that changes it.
+    fn upgraded_rule<R>(&self, block_height: u32, rule: impl Fn(&ConsensusUpgrade) -> Option<&R>) -> Option<&R> {
+        self.upgrades
+            .iter()
+            .filter(|upgrade| upgrade.activation_height <= block_height)
+            .filter_map(|upgrade| rule(upgrade).map(|value| (upgrade.activation_height, value)))
+            .max_by_key(|(activation_height, _)| *activation_height)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the difficulty retarget algorithm of the block at the given height.
+    pub fn retarget_algorithm_at(&self, block_height: u32) -> RetargetAlgorithm {
+        *self
+            .upgraded_rule(block_height, |upgrade| upgrade.retarget_algorithm.as_ref())
+            .unwrap_or(&self.retarget_algorithm)
+    }
+
+    /// Returns the block reward schedule of the block at the given height.
+    pub fn reward_schedule_at(&self, block_height: u32) -> RewardSchedule {
+        *self
+            .upgraded_rule(block_height, |upgrade| upgrade.reward_schedule.as_ref())
+            .unwrap_or(&self.reward_schedule)
+    }
+
+    /// Returns the inner SNARK IDs authorized in the block at the given height.
+    pub fn authorized_inner_snark_ids_at(&self, block_height: u32) -> &Vec<Vec<u8>> {
+        self.upgraded_rule(block_height, |upgrade| upgrade.authorized_inner_snark_ids.as_ref())
+            .unwrap_or(&self.authorized_inner_snark_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_objects::AleoAmount;
+    use snarkos_testing::consensus::TEST_CONSENSUS;
+
+    #[test]
+    fn test_rules_follow_activation_height() {
+        let mut consensus = TEST_CONSENSUS.clone();
+        consensus.upgrades = vec![
+            ConsensusUpgrade {
+                activation_height: 200,
+                retarget_algorithm: Some(RetargetAlgorithm::Ethereum),
+                authorized_inner_snark_ids: Some(vec![vec![2u8; 32]]),
+                ..ConsensusUpgrade::default()
+            },
+            ConsensusUpgrade {
+                activation_height: 100,
+                retarget_algorithm: Some(RetargetAlgorithm::Ema { window: 10 }),
+                reward_schedule: Some(RewardSchedule {
+                    initial_reward: AleoAmount(100),
+                    halving_interval: 1000,
+                    max_halvings: 1,
+                }),
+                ..ConsensusUpgrade::default()
+            },
+        ];
+
+        // Before the first activation height
+        assert_eq!(consensus.retarget_algorithm_at(99), RetargetAlgorithm::Bitcoin);
+        assert_eq!(consensus.get_block_reward(99), RewardSchedule::ALEO.block_reward(99));
+        assert_eq!(
+            consensus.authorized_inner_snark_ids_at(99),
+            &TEST_CONSENSUS.authorized_inner_snark_ids
+        );
+
+        // From the first activation height
+        assert_eq!(
+            consensus.retarget_algorithm_at(100),
+            RetargetAlgorithm::Ema { window: 10 }
+        );
+        assert_eq!(consensus.get_block_reward(100), AleoAmount(100));
+        assert_eq!(
+            consensus.authorized_inner_snark_ids_at(199),
+            &TEST_CONSENSUS.authorized_inner_snark_ids
+        );
+
+        // The later upgrade only replaces the rules it changes
+        assert_eq!(consensus.retarget_algorithm_at(200), RetargetAlgorithm::Ethereum);
+        assert_eq!(consensus.get_block_reward(1000), AleoAmount(50));
+        assert_eq!(consensus.authorized_inner_snark_ids_at(200), &vec![vec![2u8; 32]]);
+    }
+}
@@ -16,7 +16,12 @@
 
 mod consensus_dpc {
     use snarkos_consensus::{get_block_reward, MemoryPool, Miner};
-    use snarkos_dpc::base_dpc::{instantiated::*, record::DPCRecord, record_payload::RecordPayload};
+    use snarkos_dpc::base_dpc::{
+        instantiated::*,
+        program::ProgramRegistry,
+        record::DPCRecord,
+        record_payload::RecordPayload,
+    };
     use snarkos_models::{
         dpc::{DPCScheme, Program, Record},
         objects::LedgerScheme,
@@ -28,6 +33,7 @@ mod consensus_dpc {
     #[test]
     fn base_dpc_multiple_transactions() {
         let parameters = &FIXTURE.parameters;
+        let program_registry = ProgramRegistry::new(parameters).unwrap();
         let ledger = FIXTURE.ledger();
         let program = FIXTURE.program.clone();
         let [_genesis_address, miner_acc, recipient] = FIXTURE.test_accounts.clone();
@@ -38,9 +44,11 @@ mod consensus_dpc {
 
         println!("Creating block with coinbase transaction");
         let transactions = DPCTransactions::<Tx>::new();
-        let (previous_block_header, transactions, coinbase_records) =
+        let (ancestor_headers, transactions, coinbase_records) =
             miner.establish_block(&parameters, &ledger, &transactions).unwrap();
-        let header = miner.find_block(&transactions, &previous_block_header).unwrap();
+        let header = miner
+            .find_block(&transactions, &ancestor_headers, ledger.len() as u32)
+            .unwrap();
         let block = Block { header, transactions };
 
         assert!(InstantiatedDPC::verify_transactions(&parameters, &block.transactions, &ledger).unwrap());
@@ -86,6 +94,7 @@ mod consensus_dpc {
         let (spend_records, transaction) = consensus
             .create_transaction(
                 &parameters,
+                &program_registry,
                 old_records,
                 old_account_private_keys,
                 new_record_owners,
@@ -112,12 +121,14 @@ mod consensus_dpc {
         println!("Create a new block with the payment transaction");
         let mut transactions = DPCTransactions::new();
         transactions.push(transaction);
-        let (previous_block_header, transactions, new_coinbase_records) =
+        let (ancestor_headers, transactions, new_coinbase_records) =
             miner.establish_block(&parameters, &ledger, &transactions).unwrap();
 
         assert!(InstantiatedDPC::verify_transactions(&parameters, &transactions, &ledger).unwrap());
 
-        let header = miner.find_block(&transactions, &previous_block_header).unwrap();
+        let header = miner
+            .find_block(&transactions, &ancestor_headers, ledger.len() as u32)
+            .unwrap();
         let new_block = Block { header, transactions };
         let new_block_reward = get_block_reward(ledger.len() as u32);
 
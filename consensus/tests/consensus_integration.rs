@@ -28,7 +28,7 @@ mod consensus_integration {
         let miner_address = FIXTURE_VK.test_accounts[0].address.clone();
         let miner = Miner::new(miner_address, consensus.clone());
 
-        let header = miner.find_block(transactions, parent_header).unwrap();
+        let header = miner.find_block(transactions, &[parent_header.clone()], 1).unwrap();
 
         let expected_prev_block_hash = parent_header.get_hash();
         assert_eq!(header.previous_block_hash, expected_prev_block_hash);
@@ -41,7 +41,14 @@ mod consensus_integration {
 
         // ensure that our POSW proof passes
         consensus
-            .verify_header(&header, parent_header, &merkle_root, &pedersen_merkle_root)
+            .verify_header(
+                &header,
+                parent_header,
+                &merkle_root,
+                &pedersen_merkle_root,
+                1,
+                &[parent_header.clone()],
+            )
             .unwrap();
     }
 
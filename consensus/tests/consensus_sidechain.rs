@@ -15,9 +15,18 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 mod consensus_sidechain {
-    use snarkos_consensus::MemoryPool;
-    use snarkos_dpc::base_dpc::instantiated::Tx;
-    use snarkos_objects::Block;
+    use snarkos_consensus::{memory_pool::Entry, ConsensusParameters, MemoryPool, MerkleTreeLedger, Miner};
+    use snarkos_dpc::base_dpc::{
+        instantiated::*,
+        program::ProgramRegistry,
+        record::DPCRecord,
+        record_payload::RecordPayload,
+    };
+    use snarkos_models::{
+        dpc::Program,
+        objects::{LedgerScheme, Transaction},
+    };
+    use snarkos_objects::{dpc::DPCTransactions, Block};
     use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, storage::*};
     use snarkos_utilities::bytes::FromBytes;
 
@@ -103,6 +112,45 @@ mod consensus_sidechain {
         kill_storage_sync(blockchain);
     }
 
+    // Receive two blocks with equal work that reference the same parent, in both orders.
+    // The block received first should remain canonical regardless of which one it is.
+    #[test]
+    fn equal_work_tie() {
+        let parameters = load_verifying_parameters();
+        let consensus = TEST_CONSENSUS.clone();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let alternative_block_1 = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+
+        assert_eq!(block_1.header.to_work(), alternative_block_1.header.to_work());
+
+        for (first, second) in vec![(&block_1, &alternative_block_1), (&alternative_block_1, &block_1)] {
+            let blockchain = Arc::new(FIXTURE_VK.ledger());
+            let mut memory_pool = MemoryPool::new();
+
+            consensus
+                .receive_block(&parameters, &blockchain, &mut memory_pool, first)
+                .unwrap();
+
+            let canon_work = blockchain.get_latest_cumulative_work().unwrap();
+
+            consensus
+                .receive_block(&parameters, &blockchain, &mut memory_pool, second)
+                .unwrap();
+
+            assert_eq!(&blockchain.get_latest_block().unwrap(), first);
+            assert_eq!(blockchain.get_latest_cumulative_work().unwrap(), canon_work);
+
+            // The rejected block is stored with the same cumulative work
+            assert_eq!(
+                blockchain.get_cumulative_work(&second.header.get_hash()).unwrap(),
+                canon_work
+            );
+
+            kill_storage_sync(blockchain);
+        }
+    }
+
     // Receive blocks from a sidechain that overtakes our current canonical chain.
     #[test]
     fn accept() {
@@ -145,6 +193,61 @@ mod consensus_sidechain {
 
         assert_eq!(old_block_height + 1, new_block_height);
 
+        // 3. Ensure the heavier sidechain is now canon.
+
+        assert_eq!(blockchain.get_latest_block().unwrap(), block_2_side);
+        assert!(blockchain.is_canon(&block_1_side.header.get_hash()));
+        assert!(!blockchain.is_canon(&block_1_canon.header.get_hash()));
+
+        kill_storage_sync(blockchain);
+    }
+
+    // Receive sidechain blocks whose work is forged with a low difficulty target.
+    // A forged header must not be stored with a known parent, nor make its side chain heavier as an orphan.
+    #[test]
+    fn forged_difficulty_does_not_fork() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+
+        let mut memory_pool = MemoryPool::new();
+
+        let consensus = TEST_CONSENSUS.clone();
+
+        let block_1_canon = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+
+        let mut forged_block_1_side = block_1_side.clone();
+        forged_block_1_side.header.difficulty_target = 1;
+
+        let mut forged_block_2_side = Block::<Tx>::read(&ALTERNATIVE_BLOCK_2[..]).unwrap();
+        forged_block_2_side.header.difficulty_target = 1;
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_canon)
+            .unwrap();
+
+        // 1. A forged sidechain block with a known parent is rejected before it is stored.
+
+        assert!(
+            consensus
+                .receive_block(&parameters, &blockchain, &mut memory_pool, &forged_block_1_side)
+                .is_err()
+        );
+        assert!(!blockchain.block_hash_exists(&forged_block_1_side.header.get_hash()));
+
+        // 2. A forged orphan does not make its sidechain heavier once its parent is received.
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &forged_block_2_side)
+            .unwrap();
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_side)
+            .unwrap();
+
+        assert_eq!(blockchain.get_latest_block().unwrap(), block_1_canon);
+        assert!(blockchain.is_canon(&block_1_canon.header.get_hash()));
+        assert!(!blockchain.is_canon(&block_1_side.header.get_hash()));
+
         kill_storage_sync(blockchain);
     }
 
@@ -213,4 +316,211 @@ mod consensus_sidechain {
 
         kill_storage_sync(blockchain);
     }
+
+    // Receive blocks from a sidechain that overtakes our current canonical chain while the memory pool holds a
+    // transaction. The reverted canon block's coinbase must not be returned to the memory pool and the pending
+    // transaction is removed once the new canon chain includes it.
+    #[test]
+    fn fork_revalidates_memory_pool() {
+        let blockchain = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+
+        let mut memory_pool = MemoryPool::new();
+
+        let consensus = TEST_CONSENSUS.clone();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        // 1. Receive the canon block and a pending transaction that spends its coinbase records.
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_canon)
+            .unwrap();
+
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let entry = Entry {
+            size: TRANSACTION_2.len(),
+            transaction,
+        };
+
+        memory_pool.insert(&blockchain, entry.clone()).unwrap();
+        assert!(memory_pool.contains(&entry));
+
+        // 2. Receive the heavier sidechain, which reverts the canon block.
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_side)
+            .unwrap();
+
+        assert!(memory_pool.contains(&entry));
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_2_side)
+            .unwrap();
+
+        assert_eq!(blockchain.get_latest_block().unwrap(), block_2_side);
+
+        // 3. Ensure the reverted coinbase was skipped and the included transaction was removed.
+
+        let reverted_coinbase = block_1_canon.transactions.0[0].clone();
+        assert!(reverted_coinbase.value_balance().is_negative());
+
+        assert!(!memory_pool.contains(&Entry {
+            size: reverted_coinbase.size(),
+            transaction: reverted_coinbase,
+        }));
+        assert!(!memory_pool.contains(&entry));
+        assert_eq!(0, memory_pool.total_size);
+
+        kill_storage_sync(blockchain);
+    }
+
+    // Mine a block on top of the ledger with the given transactions and a coinbase paying the miner.
+    fn mine_block(
+        miner: &Miner,
+        ledger: &MerkleTreeLedger,
+        transactions: Vec<Tx>,
+    ) -> (Block<Tx>, Vec<DPCRecord<Components>>) {
+        let (ancestor_headers, transactions, coinbase_records) = miner
+            .establish_block(&FIXTURE.parameters, ledger, &DPCTransactions(transactions))
+            .unwrap();
+        let header = miner
+            .find_block(&transactions, &ancestor_headers, ledger.len() as u32)
+            .unwrap();
+
+        (Block { header, transactions }, coinbase_records)
+    }
+
+    // Receive blocks from a sidechain that overtakes our current canonical chain, reverting a block with a
+    // payment transaction. The payment spends records that also exist on the sidechain, so it must be returned
+    // to the memory pool, while the coinbase of the reverted block is skipped.
+    #[test]
+    fn fork_returns_reverted_transactions_to_memory_pool() {
+        let parameters = &FIXTURE.parameters;
+        let program_registry = ProgramRegistry::new(parameters).unwrap();
+        let program = FIXTURE.program.clone();
+        let [_genesis_address, miner_acc, recipient] = FIXTURE.test_accounts.clone();
+        let mut rng = FIXTURE.rng.clone();
+
+        // Blocks of a development chain are mined at the trivial difficulty, so each block adds the same work.
+        let consensus = ConsensusParameters {
+            dev_mode: true,
+            ..TEST_CONSENSUS.clone()
+        };
+        let miner = Miner::new(miner_acc.address.clone(), consensus.clone());
+
+        let blockchain = Arc::new(FIXTURE.ledger());
+        let side_blockchain = Arc::new(FIXTURE.ledger());
+        let mut memory_pool = MemoryPool::new();
+        let mut side_memory_pool = MemoryPool::new();
+
+        // 1. Both chains share the first block.
+
+        let (block_1, coinbase_records) = mine_block(&miner, &blockchain, vec![]);
+
+        consensus
+            .receive_block(parameters, &blockchain, &mut memory_pool, &block_1)
+            .unwrap();
+        consensus
+            .receive_block(parameters, &side_blockchain, &mut side_memory_pool, &block_1)
+            .unwrap();
+
+        // 2. The canon chain includes a payment spending the coinbase records of the first block.
+
+        let (_, payment) = consensus
+            .create_transaction(
+                parameters,
+                &program_registry,
+                coinbase_records,
+                vec![miner_acc.private_key.clone(); NUM_INPUT_RECORDS],
+                vec![recipient.address.clone(); NUM_OUTPUT_RECORDS],
+                vec![program.into_compact_repr(); NUM_INPUT_RECORDS],
+                vec![program.into_compact_repr(); NUM_OUTPUT_RECORDS],
+                vec![false; NUM_OUTPUT_RECORDS],
+                vec![10; NUM_OUTPUT_RECORDS],
+                vec![RecordPayload::default(); NUM_OUTPUT_RECORDS],
+                [6u8; 32],
+                &blockchain,
+                &mut rng,
+            )
+            .unwrap();
+
+        let (block_2_canon, _) = mine_block(&miner, &blockchain, vec![payment.clone()]);
+
+        consensus
+            .receive_block(parameters, &blockchain, &mut memory_pool, &block_2_canon)
+            .unwrap();
+
+        // 3. The side chain mines two blocks with only their coinbase transactions.
+
+        let mut side_blocks = vec![];
+        for _ in 0..2 {
+            let (side_block, _) = mine_block(&miner, &side_blockchain, vec![]);
+            consensus
+                .receive_block(parameters, &side_blockchain, &mut side_memory_pool, &side_block)
+                .unwrap();
+            side_blocks.push(side_block);
+        }
+
+        // 4. Receive the heavier side chain, which reverts the canon block with the payment.
+
+        for side_block in &side_blocks {
+            consensus
+                .receive_block(parameters, &blockchain, &mut memory_pool, side_block)
+                .unwrap();
+        }
+
+        assert_eq!(&blockchain.get_latest_block().unwrap(), side_blocks.last().unwrap());
+        assert!(!blockchain.is_canon(&block_2_canon.header.get_hash()));
+
+        // 5. Ensure the payment is back in the memory pool and the reverted coinbase was skipped.
+
+        let payment_entry = Entry {
+            size: payment.size(),
+            transaction: payment,
+        };
+        assert!(memory_pool.contains(&payment_entry));
+
+        let reverted_coinbase = block_2_canon.transactions.0.last().unwrap().clone();
+        assert!(reverted_coinbase.value_balance().is_negative());
+        assert!(!memory_pool.contains(&Entry {
+            size: reverted_coinbase.size(),
+            transaction: reverted_coinbase,
+        }));
+        assert_eq!(payment_entry.size, memory_pool.total_size);
+
+        kill_storage_sync(blockchain);
+        kill_storage_sync(side_blockchain);
+    }
+
+    // Memory pool entries expire once the configured number of blocks has been received since their insertion.
+    #[test]
+    fn expire_memory_pool_entries() {
+        let parameters = load_verifying_parameters();
+        let consensus = TEST_CONSENSUS.clone();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let entry = Entry {
+            size: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        };
+
+        for expiry in vec![1, 2] {
+            let blockchain = Arc::new(FIXTURE_VK.ledger());
+            let mut memory_pool = MemoryPool::new();
+            memory_pool.expiry = expiry;
+
+            memory_pool.insert(&blockchain, entry.clone()).unwrap();
+
+            consensus
+                .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1)
+                .unwrap();
+
+            assert_eq!(memory_pool.contains(&entry), expiry > 1);
+
+            kill_storage_sync(blockchain);
+        }
+    }
 }
@@ -0,0 +1,67 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod genesis {
+    use snarkos_consensus::{get_block_reward, ConsensusParameters, MerkleTreeLedger, DEV_DIFFICULTY_TARGET};
+    use snarkos_dpc::base_dpc::{instantiated::Components, record::DPCRecord};
+    use snarkos_models::{dpc::Record, objects::LedgerScheme};
+    use snarkos_testing::{
+        consensus::*,
+        storage::{kill_storage, random_storage_path},
+    };
+    use snarkos_utilities::{bytes::ToBytes, to_bytes};
+
+    #[test]
+    fn dev_genesis_block_funds_recipient() {
+        let parameters = &FIXTURE.parameters;
+        let [_genesis_address, recipient, _] = FIXTURE.test_accounts.clone();
+        let mut rng = FIXTURE.rng.clone();
+
+        let mut consensus = TEST_CONSENSUS.clone();
+        consensus.dev_mode = true;
+
+        let mut path = std::env::temp_dir();
+        path.push(random_storage_path());
+
+        let ledger = consensus
+            .create_ledger_with_genesis(&path, parameters, recipient.address.clone(), &mut rng)
+            .unwrap();
+        assert_eq!(ledger.len(), 1);
+
+        let genesis_block = ledger.get_latest_block().unwrap();
+        assert!(ConsensusParameters::is_genesis(&genesis_block.header));
+        assert_eq!(genesis_block.header.difficulty_target, DEV_DIFFICULTY_TARGET);
+        assert_eq!(genesis_block.transactions.len(), 1);
+
+        // The genesis coinbase record is stored, so the recipient can spend it
+        let commitment = &genesis_block.transactions[0].new_commitments[0];
+        let record: DPCRecord<Components> = ledger.get_record(&to_bytes![commitment].unwrap()).unwrap().unwrap();
+        assert_eq!(record.owner(), &recipient.address);
+        assert_eq!(record.value(), get_block_reward(0).0 as u64);
+
+        // Every following block is mined at the trivial difficulty
+        assert_eq!(
+            consensus.get_block_difficulty(&[genesis_block.header.clone()], genesis_block.header.time + 1, 1),
+            DEV_DIFFICULTY_TARGET
+        );
+
+        // A ledger holding blocks is not replaced
+        drop(ledger);
+        assert!(MerkleTreeLedger::open_empty(&path).is_err());
+
+        kill_storage(MerkleTreeLedger::open_at_path(&path).unwrap());
+    }
+}
@@ -47,14 +47,21 @@ mod miner {
         let (_, miner_address) = keygen(&mut rng);
         let miner = Miner::new(miner_address, consensus.clone());
 
-        let header = miner.find_block(transactions, parent_header).unwrap();
+        let header = miner.find_block(transactions, &[parent_header.clone()], 1).unwrap();
 
         // generate the verifier args
         let (merkle_root, pedersen_merkle_root, _) = txids_to_roots(&transactions.to_transaction_ids().unwrap());
 
         // ensure that our POSW proof passes
         consensus
-            .verify_header(&header, parent_header, &merkle_root, &pedersen_merkle_root)
+            .verify_header(
+                &header,
+                parent_header,
+                &merkle_root,
+                &pedersen_merkle_root,
+                1,
+                &[parent_header.clone()],
+            )
             .unwrap();
     }
 
@@ -19,7 +19,7 @@ use crate::base_dpc::{
     inner_circuit_verifier_input::InnerCircuitVerifierInput,
     outer_circuit::OuterCircuit,
     outer_circuit_verifier_input::OuterCircuitVerifierInput,
-    program::{NoopCircuit, ProgramLocalData},
+    program::{AnyProgramCircuit, AssetCircuit, NoopCircuit, ProgramLocalData},
     transaction::DPCTransaction,
     BaseDPCComponents,
     LocalData as DPCLocalData,
@@ -172,6 +172,7 @@ impl BaseDPCComponents for Components {
     type MerkleParameters = CommitmentMerkleParameters;
     type NoopProgramSNARK = NoopProgramSNARK<Self>;
     type OuterSNARK = OuterSNARK;
+    type ProgramSNARK = ProgramSNARK<Self>;
     type ProgramSNARKGadget = ProgramSNARKGadget;
 }
 
@@ -199,7 +200,9 @@ pub type ProgramVerificationKeyCRH = BoweHopwoodPedersenCompressedCRH<EdwardsSW,
 
 pub type InnerSNARK = Groth16<InnerPairing, InnerCircuit<Components>, InnerCircuitVerifierInput<Components>>;
 pub type OuterSNARK = Groth16<OuterPairing, OuterCircuit<Components>, OuterCircuitVerifierInput<Components>>;
+pub type ProgramSNARK<C> = GM17<InnerPairing, AnyProgramCircuit<C>, ProgramLocalData<C>>;
 pub type NoopProgramSNARK<C> = GM17<InnerPairing, NoopCircuit<C>, ProgramLocalData<C>>;
+pub type AssetProgramSNARK<C> = GM17<InnerPairing, AssetCircuit<C>, ProgramLocalData<C>>;
 pub type PRF = Blake2s;
 
 pub type Tx = DPCTransaction<Components>;
@@ -102,16 +102,22 @@ pub trait BaseDPCComponents: DPCComponents {
         VerifierInput = OuterCircuitVerifierInput<Self>,
     >;
 
-    // TODO (raychu86) remove these from BaseDPCComponents
-    /// SNARK for the Noop "always-accept" that does nothing with its input.
+    /// SNARK that the birth and death program proofs are verified with.
+    /// Each program proves its own circuit, so the circuit of this SNARK is never synthesized.
+    type ProgramSNARK: SNARK<VerifierInput = ProgramLocalData<Self>>;
+
+    /// SNARK Verifier gadget for the program proofs.
+    type ProgramSNARKGadget: SNARKVerifierGadget<Self::ProgramSNARK, Self::OuterField>;
+
+    /// SNARK for the Noop "always-accept" program that does nothing with its input.
+    /// Its proofs and verification keys are those of the program SNARK.
     type NoopProgramSNARK: SNARK<
         Circuit = NoopCircuit<Self>,
         AssignedCircuit = NoopCircuit<Self>,
         VerifierInput = ProgramLocalData<Self>,
+        Proof = <Self::ProgramSNARK as SNARK>::Proof,
+        VerificationParameters = <Self::ProgramSNARK as SNARK>::VerificationParameters,
     >;
-
-    /// SNARK Verifier gadget for the "dummy program" that does nothing with its input.
-    type ProgramSNARKGadget: SNARKVerifierGadget<Self::NoopProgramSNARK, Self::OuterField>;
 }
 
 ///////////////////////////////////////////////////////////////////////////////
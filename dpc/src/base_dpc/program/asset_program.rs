@@ -0,0 +1,270 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::base_dpc::{
+    parameters::SystemParameters,
+    AssetCircuit,
+    BaseDPCComponents,
+    LocalData,
+    PrivateProgramInput,
+    ProgramLocalData,
+    ProgramRegistry,
+    ProvableProgram,
+    ASSET_ID_SIZE,
+};
+use snarkos_errors::dpc::DPCError;
+use snarkos_models::{
+    algorithms::{CommitmentScheme, SNARK},
+    dpc::{Program, Record},
+};
+use snarkos_utilities::{to_bytes, FromBytes, ToBytes};
+
+use rand::Rng;
+use std::{fs, marker::PhantomData, path::Path, sync::Arc};
+
+/// The asset id of the asset program that a node registers.
+pub const NODE_ASSET_ID: [u8; ASSET_ID_SIZE] = [1u8; ASSET_ID_SIZE];
+
+/// Program that accepts the records whose payload starts with its asset id.
+/// Each asset id has its own circuit, and therefore its own program id.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: BaseDPCComponents, S: SNARK"),
+    Debug(bound = "C: BaseDPCComponents, S: SNARK"),
+    PartialEq(bound = "C: BaseDPCComponents, S: SNARK"),
+    Eq(bound = "C: BaseDPCComponents, S: SNARK")
+)]
+pub struct AssetProgram<C: BaseDPCComponents, S: SNARK> {
+    identity: Vec<u8>,
+    asset_id: [u8; ASSET_ID_SIZE],
+    _components: PhantomData<C>,
+    _snark: PhantomData<S>,
+}
+
+impl<C: BaseDPCComponents, S: SNARK> AssetProgram<C, S>
+where
+    S: SNARK<Circuit = AssetCircuit<C>, AssignedCircuit = AssetCircuit<C>, VerifierInput = ProgramLocalData<C>>,
+{
+    pub fn new(identity: Vec<u8>, asset_id: [u8; ASSET_ID_SIZE]) -> Self {
+        Self {
+            identity,
+            asset_id,
+            _components: PhantomData,
+            _snark: PhantomData,
+        }
+    }
+
+    /// Generate the proving and verification parameters of the program for an asset id.
+    pub fn setup<R: Rng>(
+        system_parameters: &SystemParameters<C>,
+        asset_id: [u8; ASSET_ID_SIZE],
+        rng: &mut R,
+    ) -> Result<(S::ProvingParameters, S::VerificationParameters), DPCError> {
+        let (proving_key, prepared_verification_key) = S::setup(AssetCircuit::blank(system_parameters, asset_id), rng)?;
+
+        Ok((proving_key, prepared_verification_key.into()))
+    }
+
+    /// Load the proving key of the program for an asset id from a file,
+    /// or generate the parameters of the program and save the proving key to the file.
+    pub fn load_or_setup<R: Rng>(
+        path: &Path,
+        system_parameters: &SystemParameters<C>,
+        asset_id: [u8; ASSET_ID_SIZE],
+        rng: &mut R,
+    ) -> Result<(S::ProvingParameters, S::VerificationParameters), DPCError> {
+        if path.exists() {
+            let proving_key = S::ProvingParameters::read(&fs::read(path)?[..])?;
+            let verification_key = proving_key.clone().into();
+
+            return Ok((proving_key, verification_key));
+        }
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let (proving_key, verification_key) = Self::setup(system_parameters, asset_id, rng)?;
+        fs::write(path, to_bytes![proving_key]?)?;
+
+        Ok((proving_key, verification_key))
+    }
+
+    /// Register the program for an asset id with its parameters, returning the program id.
+    pub fn register(
+        registry: &mut ProgramRegistry<C>,
+        system_parameters: &SystemParameters<C>,
+        asset_id: [u8; ASSET_ID_SIZE],
+        proving_key: S::ProvingParameters,
+        verification_key: S::VerificationParameters,
+    ) -> Result<Vec<u8>, DPCError>
+    where
+        Self: Send + Sync + 'static,
+        S::ProvingParameters: Send + Sync + 'static,
+        S::VerificationParameters: Send + Sync + 'static,
+    {
+        let verification_key_bytes = to_bytes![verification_key]?;
+        let program_id = ProgramRegistry::program_id(system_parameters, &verification_key_bytes)?;
+
+        let program = ProvableProgram {
+            program: Self::new(program_id, asset_id),
+            proving_key,
+            verification_key,
+        };
+
+        registry.register(system_parameters, verification_key_bytes, Arc::new(program))
+    }
+
+    /// Returns the asset id that the payloads of the records must start with.
+    pub fn asset_id(&self) -> &[u8; ASSET_ID_SIZE] {
+        &self.asset_id
+    }
+
+    /// Returns the circuit of the program for the record at the position of the local data.
+    pub fn circuit(&self, local_data: &LocalData<C>, position: u8) -> Result<AssetCircuit<C>, DPCError> {
+        let records = [&local_data.old_records[..], &local_data.new_records[..]].concat();
+        let record = &records[position as usize];
+
+        // The local data of an old record starts with its serial number
+        let serial_number = if (position as usize) < C::NUM_INPUT_RECORDS {
+            to_bytes![local_data.old_serial_numbers[position as usize]]?
+        } else {
+            vec![0u8; to_bytes![local_data.old_serial_numbers[0]]?.len()]
+        };
+
+        let record_commitment_input = to_bytes![
+            record.owner(),
+            record.is_dummy(),
+            record.value(),
+            record.payload(),
+            record.birth_program_id(),
+            record.death_program_id(),
+            record.serial_number_nonce()
+        ]?;
+
+        Ok(AssetCircuit::new(
+            &local_data.system_parameters,
+            self.asset_id,
+            &local_data.local_data_merkle_tree.root(),
+            position,
+            &local_data.local_data_merkle_tree.leaves(),
+            &local_data.local_data_commitment_randomizers[position as usize],
+            &record_commitment_input,
+            &record.commitment_randomness(),
+            &serial_number,
+            &local_data.memorandum,
+            local_data.network_id,
+        ))
+    }
+
+    /// Returns true if the verification key of the witness is the key of the program, and the proof
+    /// of the witness shows that the record at the position of the local data holds the asset id.
+    pub fn verify(
+        &self,
+        local_data: &LocalData<C>,
+        position: u8,
+        witness: &PrivateProgramInput,
+    ) -> Result<bool, DPCError> {
+        let program_id = ProgramRegistry::program_id(&local_data.system_parameters, &witness.verification_key)?;
+        if program_id != self.identity {
+            return Ok(false);
+        }
+
+        let verification_key = S::VerificationParameters::read(&witness.verification_key[..])?;
+        let proof = S::Proof::read(&witness.proof[..])?;
+
+        let program_pub_input: ProgramLocalData<C> = ProgramLocalData {
+            local_data_commitment_parameters: local_data.system_parameters.local_data_commitment.parameters().clone(),
+            local_data_root: local_data.local_data_merkle_tree.root(),
+            position,
+        };
+
+        Ok(S::verify(&verification_key.into(), &program_pub_input, &proof)?)
+    }
+}
+
+impl<C: BaseDPCComponents, S: SNARK> Program for AssetProgram<C, S>
+where
+    S: SNARK<Circuit = AssetCircuit<C>, AssignedCircuit = AssetCircuit<C>, VerifierInput = ProgramLocalData<C>>,
+{
+    type LocalData = LocalData<C>;
+    type PrivateWitness = PrivateProgramInput;
+    type ProvingParameters = S::ProvingParameters;
+    type PublicInput = (LocalData<C>, u8);
+    type VerificationParameters = S::VerificationParameters;
+
+    fn execute<R: Rng>(
+        &self,
+        proving_key: &Self::ProvingParameters,
+        verification_key: &Self::VerificationParameters,
+        local_data: &Self::LocalData,
+        position: u8,
+        rng: &mut R,
+    ) -> Result<Self::PrivateWitness, DPCError> {
+        let records = [&local_data.old_records[..], &local_data.new_records[..]].concat();
+        assert!((position as usize) < records.len());
+
+        let record = &records[position as usize];
+        if (position as usize) < C::NUM_INPUT_RECORDS {
+            assert_eq!(self.identity, record.death_program_id());
+        } else {
+            assert_eq!(self.identity, record.birth_program_id());
+        }
+
+        if record.payload().to_bytes()[..ASSET_ID_SIZE] != self.asset_id[..] {
+            return Err(DPCError::Message(format!(
+                "the payload of record {} does not hold the asset id {}",
+                position,
+                hex::encode(self.asset_id)
+            )));
+        }
+
+        let circuit = self.circuit(local_data, position)?;
+        let local_data_root = local_data.local_data_merkle_tree.root();
+
+        let proof = S::prove(proving_key, circuit, rng)?;
+
+        {
+            let program_snark_pvk: <S as SNARK>::PreparedVerificationParameters = verification_key.clone().into();
+
+            let program_pub_input: ProgramLocalData<C> = ProgramLocalData {
+                local_data_commitment_parameters: local_data
+                    .system_parameters
+                    .local_data_commitment
+                    .parameters()
+                    .clone(),
+                local_data_root,
+                position,
+            };
+            assert!(S::verify(&program_snark_pvk, &program_pub_input, &proof)?);
+        }
+
+        Ok(Self::PrivateWitness {
+            verification_key: to_bytes![verification_key]?,
+            proof: to_bytes![proof]?,
+        })
+    }
+
+    fn evaluate(&self, primary: &Self::PublicInput, witness: &Self::PrivateWitness) -> bool {
+        let (local_data, position) = primary;
+
+        self.verify(local_data, *position, witness).unwrap_or(false)
+    }
+
+    fn into_compact_repr(&self) -> Vec<u8> {
+        self.identity.clone()
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    base_dpc::{parameters::SystemParameters, BaseDPCComponents},
+    Assignment,
+};
+use snarkos_errors::gadgets::SynthesisError;
+use snarkos_models::{
+    algorithms::{CommitmentScheme, SignatureScheme, CRH},
+    gadgets::{
+        algorithms::{CRHGadget, CommitmentGadget},
+        r1cs::{ConstraintSynthesizer, ConstraintSystem},
+        utilities::{
+            alloc::AllocGadget,
+            boolean::Boolean,
+            eq::{ConditionalEqGadget, EqGadget},
+            uint::UInt8,
+            ToBytesGadget,
+        },
+    },
+};
+use snarkos_utilities::{to_bytes, ToBytes};
+
+/// The number of leading payload bytes that hold the asset id of a record.
+pub const ASSET_ID_SIZE: usize = 16;
+
+/// The offset of the payload in the record commitment input,
+/// after the owner (32 bytes), the dummy flag (1 byte) and the value (8 bytes).
+const PAYLOAD_OFFSET: usize = 41;
+
+/// The size of the record commitment input.
+const RECORD_COMMITMENT_INPUT_SIZE: usize = 201;
+
+/// Program that accepts a record if its payload starts with the asset id of the program.
+///
+/// The record is opened from the local data root: the circuit recomputes the record commitment,
+/// the local data commitment of the record at the position, and the local data root.
+pub struct AssetCircuit<C: BaseDPCComponents> {
+    /// System parameters
+    pub system_parameters: Option<SystemParameters<C>>,
+
+    /// The asset id every accepted record holds
+    pub asset_id: [u8; ASSET_ID_SIZE],
+
+    /// Commitment to the program input.
+    pub local_data_root: Option<<C::LocalDataCRH as CRH>::Output>,
+
+    /// Record position
+    pub position: u8,
+
+    /// The leaves of the local data tree
+    pub local_data_leaves: Option<Vec<<C::LocalDataCommitment as CommitmentScheme>::Output>>,
+
+    /// The randomness of the local data commitment of the record
+    pub local_data_commitment_randomness: Option<<C::LocalDataCommitment as CommitmentScheme>::Randomness>,
+
+    /// The record commitment input
+    pub record_commitment_input: Option<Vec<u8>>,
+
+    /// The record commitment randomness
+    pub record_commitment_randomness: Option<<C::RecordCommitment as CommitmentScheme>::Randomness>,
+
+    /// The serial number of the record, or zero bytes for a new record
+    pub serial_number: Option<Vec<u8>>,
+
+    /// The transaction memo
+    pub memo: Option<[u8; 32]>,
+
+    /// The transaction network id
+    pub network_id: Option<u8>,
+}
+
+impl<C: BaseDPCComponents> AssetCircuit<C> {
+    pub fn blank(system_parameters: &SystemParameters<C>, asset_id: [u8; ASSET_ID_SIZE]) -> Self {
+        let local_data_root = <C::LocalDataCRH as CRH>::Output::default();
+        let local_data_leaves = vec![<C::LocalDataCommitment as CommitmentScheme>::Output::default(); 4];
+        let serial_number = to_bytes![<C::AccountSignature as SignatureScheme>::PublicKey::default()]
+            .expect("failed to serialize the serial number");
+
+        Self {
+            system_parameters: Some(system_parameters.clone()),
+            asset_id,
+            local_data_root: Some(local_data_root),
+            position: 0u8,
+            local_data_leaves: Some(local_data_leaves),
+            local_data_commitment_randomness: Some(Default::default()),
+            record_commitment_input: Some(vec![0u8; RECORD_COMMITMENT_INPUT_SIZE]),
+            record_commitment_randomness: Some(Default::default()),
+            serial_number: Some(serial_number),
+            memo: Some([0u8; 32]),
+            network_id: Some(0),
+        }
+    }
+
+    pub fn new(
+        system_parameters: &SystemParameters<C>,
+        asset_id: [u8; ASSET_ID_SIZE],
+        local_data_root: &<C::LocalDataCRH as CRH>::Output,
+        position: u8,
+        local_data_leaves: &[<C::LocalDataCommitment as CommitmentScheme>::Output],
+        local_data_commitment_randomness: &<C::LocalDataCommitment as CommitmentScheme>::Randomness,
+        record_commitment_input: &[u8],
+        record_commitment_randomness: &<C::RecordCommitment as CommitmentScheme>::Randomness,
+        serial_number: &[u8],
+        memo: &[u8; 32],
+        network_id: u8,
+    ) -> Self {
+        assert_eq!(local_data_leaves.len(), 4);
+        assert_eq!(record_commitment_input.len(), RECORD_COMMITMENT_INPUT_SIZE);
+
+        Self {
+            system_parameters: Some(system_parameters.clone()),
+            asset_id,
+            local_data_root: Some(local_data_root.clone()),
+            position,
+            local_data_leaves: Some(local_data_leaves.to_vec()),
+            local_data_commitment_randomness: Some(local_data_commitment_randomness.clone()),
+            record_commitment_input: Some(record_commitment_input.to_vec()),
+            record_commitment_randomness: Some(record_commitment_randomness.clone()),
+            serial_number: Some(serial_number.to_vec()),
+            memo: Some(*memo),
+            network_id: Some(network_id),
+        }
+    }
+}
+
+impl<C: BaseDPCComponents> ConstraintSynthesizer<C::InnerField> for AssetCircuit<C> {
+    fn generate_constraints<CS: ConstraintSystem<C::InnerField>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        execute_asset_gadget(
+            cs,
+            self.system_parameters.get()?,
+            &self.asset_id,
+            self.local_data_root.get()?,
+            self.position,
+            self.local_data_leaves.get()?,
+            self.local_data_commitment_randomness.get()?,
+            self.record_commitment_input.get()?,
+            self.record_commitment_randomness.get()?,
+            self.serial_number.get()?,
+            self.memo.get()?,
+            *self.network_id.get()?,
+        )
+    }
+}
+
+fn execute_asset_gadget<C: BaseDPCComponents, CS: ConstraintSystem<C::InnerField>>(
+    cs: &mut CS,
+    system_parameters: &SystemParameters<C>,
+    asset_id: &[u8; ASSET_ID_SIZE],
+    local_data_root: &<C::LocalDataCRH as CRH>::Output,
+    position: u8,
+    local_data_leaves: &[<C::LocalDataCommitment as CommitmentScheme>::Output],
+    local_data_commitment_randomness: &<C::LocalDataCommitment as CommitmentScheme>::Randomness,
+    record_commitment_input: &[u8],
+    record_commitment_randomness: &<C::RecordCommitment as CommitmentScheme>::Randomness,
+    serial_number: &[u8],
+    memo: &[u8; 32],
+    network_id: u8,
+) -> Result<(), SynthesisError> {
+    // Declare the program inputs in the order of `ProgramLocalData`
+
+    let position = UInt8::alloc_input_vec(cs.ns(|| "Alloc position"), &[position])?;
+
+    let local_data_commitment_parameters =
+        <C::LocalDataCommitmentGadget as CommitmentGadget<_, _>>::ParametersGadget::alloc_input(
+            &mut cs.ns(|| "Declare local data commitment parameters"),
+            || Ok(system_parameters.local_data_commitment.parameters().clone()),
+        )?;
+
+    let declared_local_data_root = <C::LocalDataCRHGadget as CRHGadget<_, _>>::OutputGadget::alloc_input(
+        cs.ns(|| "Allocate local data root"),
+        || Ok(local_data_root),
+    )?;
+
+    let local_data_crh_parameters = <C::LocalDataCRHGadget as CRHGadget<_, _>>::ParametersGadget::alloc(
+        &mut cs.ns(|| "Declare local data CRH parameters"),
+        || Ok(system_parameters.local_data_crh.parameters().clone()),
+    )?;
+
+    let record_commitment_parameters = <C::RecordCommitmentGadget as CommitmentGadget<_, _>>::ParametersGadget::alloc(
+        &mut cs.ns(|| "Declare record commitment parameters"),
+        || Ok(system_parameters.record_commitment.parameters().clone()),
+    )?;
+
+    // Select the local data leaf of the record from the position
+
+    let position_bits = &position[0].bits;
+    for (i, bit) in position_bits.iter().enumerate().skip(2) {
+        bit.enforce_equal(
+            &mut cs.ns(|| format!("Check that position bit {} is zero", i)),
+            &Boolean::constant(false),
+        )?;
+    }
+
+    let mut is_position = vec![];
+    for i in 0..4 {
+        let low_bit = if i & 1 == 1 {
+            position_bits[0]
+        } else {
+            position_bits[0].not()
+        };
+        let high_bit = if i & 2 == 2 {
+            position_bits[1]
+        } else {
+            position_bits[1].not()
+        };
+
+        is_position.push(Boolean::and(
+            cs.ns(|| format!("Compute is position {}", i)),
+            &low_bit,
+            &high_bit,
+        )?);
+    }
+
+    // Check that the record holds the asset id
+
+    let record_commitment_input =
+        UInt8::alloc_vec(cs.ns(|| "Allocate record commitment input"), record_commitment_input)?;
+
+    for (i, (payload_byte, asset_id_byte)) in record_commitment_input[PAYLOAD_OFFSET..]
+        .iter()
+        .zip(UInt8::constant_vec(asset_id))
+        .enumerate()
+    {
+        payload_byte.enforce_equal(
+            &mut cs.ns(|| format!("Check that payload byte {} holds the asset id", i)),
+            &asset_id_byte,
+        )?;
+    }
+
+    let record_commitment_randomness = <C::RecordCommitmentGadget as CommitmentGadget<_, _>>::RandomnessGadget::alloc(
+        &mut cs.ns(|| "Allocate record commitment randomness"),
+        || Ok(record_commitment_randomness),
+    )?;
+
+    let record_commitment = C::RecordCommitmentGadget::check_commitment_gadget(
+        &mut cs.ns(|| "Compute record commitment"),
+        &record_commitment_parameters,
+        &record_commitment_input,
+        &record_commitment_randomness,
+    )?;
+
+    // Compute the local data commitment of the record as an old record and as a new record
+
+    let record_commitment_bytes = record_commitment.to_bytes(&mut cs.ns(|| "Convert record commitment to bytes"))?;
+    let serial_number = UInt8::alloc_vec(cs.ns(|| "Allocate serial number"), serial_number)?;
+    let memo = UInt8::alloc_vec(cs.ns(|| "Allocate memo"), &memo[..])?;
+    let network_id = UInt8::alloc_vec(cs.ns(|| "Allocate network id"), &[network_id])?;
+
+    let mut new_record_input = vec![];
+    new_record_input.extend_from_slice(&record_commitment_bytes);
+    new_record_input.extend_from_slice(&memo);
+    new_record_input.extend_from_slice(&network_id);
+
+    let mut old_record_input = serial_number;
+    old_record_input.extend_from_slice(&new_record_input);
+
+    let local_data_commitment_randomness =
+        <C::LocalDataCommitmentGadget as CommitmentGadget<_, _>>::RandomnessGadget::alloc(
+            &mut cs.ns(|| "Allocate local data commitment randomness"),
+            || Ok(local_data_commitment_randomness),
+        )?;
+
+    let old_record_leaf = C::LocalDataCommitmentGadget::check_commitment_gadget(
+        &mut cs.ns(|| "Commit to old record local data"),
+        &local_data_commitment_parameters,
+        &old_record_input,
+        &local_data_commitment_randomness,
+    )?;
+
+    let new_record_leaf = C::LocalDataCommitmentGadget::check_commitment_gadget(
+        &mut cs.ns(|| "Commit to new record local data"),
+        &local_data_commitment_parameters,
+        &new_record_input,
+        &local_data_commitment_randomness,
+    )?;
+
+    // Check that the local data commitment of the record is the leaf at the position
+
+    let mut leaves = vec![];
+    let mut leaf_bytes = vec![];
+    for (i, leaf) in local_data_leaves.iter().enumerate() {
+        let leaf = <C::LocalDataCommitmentGadget as CommitmentGadget<_, _>>::OutputGadget::alloc(
+            &mut cs.ns(|| format!("Allocate local data leaf {}", i)),
+            || Ok(leaf),
+        )?;
+        leaf_bytes.push(leaf.to_bytes(&mut cs.ns(|| format!("Convert local data leaf {} to bytes", i)))?);
+        leaves.push(leaf);
+    }
+
+    for (i, (leaf, is_position)) in leaves.iter().zip(&is_position).enumerate() {
+        let record_leaf = if i < 2 { &old_record_leaf } else { &new_record_leaf };
+
+        record_leaf.conditional_enforce_equal(
+            &mut cs.ns(|| format!("Check the record local data at position {}", i)),
+            leaf,
+            is_position,
+        )?;
+    }
+
+    // Check that the leaves hash to the local data root
+
+    let inner1_hash = C::LocalDataCRHGadget::check_evaluation_gadget(
+        cs.ns(|| "Compute local data inner1 hash"),
+        &local_data_crh_parameters,
+        &[&leaf_bytes[0][..], &leaf_bytes[1][..]].concat(),
+    )?;
+
+    let inner2_hash = C::LocalDataCRHGadget::check_evaluation_gadget(
+        cs.ns(|| "Compute local data inner2 hash"),
+        &local_data_crh_parameters,
+        &[&leaf_bytes[2][..], &leaf_bytes[3][..]].concat(),
+    )?;
+
+    let mut inner_hash_bytes = inner1_hash.to_bytes(&mut cs.ns(|| "Convert inner1 hash to bytes"))?;
+    inner_hash_bytes.extend(inner2_hash.to_bytes(&mut cs.ns(|| "Convert inner2 hash to bytes"))?);
+
+    let candidate_local_data_root = C::LocalDataCRHGadget::check_evaluation_gadget(
+        cs.ns(|| "Compute local data root"),
+        &local_data_crh_parameters,
+        &inner_hash_bytes,
+    )?;
+
+    candidate_local_data_root.enforce_equal(
+        &mut cs.ns(|| "Check that local data root is valid"),
+        &declared_local_data_root,
+    )?;
+
+    Ok(())
+}
@@ -14,6 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod asset_program;
+pub use asset_program::*;
+
+pub mod asset_program_circuit;
+pub use asset_program_circuit::*;
+
 pub mod noop_program;
 pub use noop_program::*;
 
@@ -22,3 +28,6 @@ pub use noop_program_circuit::*;
 
 pub mod program;
 pub use program::*;
+
+pub mod program_registry;
+pub use program_registry::*;
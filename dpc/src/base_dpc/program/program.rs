@@ -15,12 +15,15 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::base_dpc::BaseDPCComponents;
-use snarkos_errors::curves::ConstraintFieldError;
+use snarkos_errors::{curves::ConstraintFieldError, gadgets::SynthesisError};
 use snarkos_models::{
     algorithms::{CommitmentScheme, CRH},
     curves::to_field_vec::ToConstraintField,
+    gadgets::r1cs::{ConstraintSynthesizer, ConstraintSystem},
 };
 
+use std::marker::PhantomData;
+
 /// Program verification key and proof
 /// Represented as bytes to be generic for any Program SNARK
 pub struct PrivateProgramInput {
@@ -58,3 +61,13 @@ where
         Ok(v)
     }
 }
+
+/// The circuit of the program SNARK, which stands for the circuit of any program.
+/// Programs prove their own circuits, so this circuit has no constraints to synthesize.
+pub struct AnyProgramCircuit<C: BaseDPCComponents>(PhantomData<C>);
+
+impl<C: BaseDPCComponents> ConstraintSynthesizer<C::InnerField> for AnyProgramCircuit<C> {
+    fn generate_constraints<CS: ConstraintSystem<C::InnerField>>(self, _cs: &mut CS) -> Result<(), SynthesisError> {
+        Err(SynthesisError::AssignmentMissing)
+    }
+}
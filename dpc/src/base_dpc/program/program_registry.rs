@@ -0,0 +1,187 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::base_dpc::{
+    parameters::{NoopProgramSNARKParameters, PublicParameters, SystemParameters},
+    BaseDPCComponents,
+    LocalData,
+    NoopProgram,
+    PrivateProgramInput,
+};
+use snarkos_errors::dpc::DPCError;
+use snarkos_models::{
+    algorithms::CRH,
+    dpc::{Program, Record},
+};
+use snarkos_utilities::{to_bytes, ToBytes};
+
+use rand::RngCore;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Creates the proofs of a program for the records of a transaction.
+pub trait ProgramExecutor<C: BaseDPCComponents> {
+    /// Returns the verification key and the proof that the record at the position
+    /// of the local data satisfies the program.
+    fn execute(
+        &self,
+        local_data: &LocalData<C>,
+        position: u8,
+        rng: &mut dyn RngCore,
+    ) -> Result<PrivateProgramInput, DPCError>;
+}
+
+/// A program with the parameters to prove it.
+pub struct ProvableProgram<P: Program> {
+    pub program: P,
+    pub proving_key: P::ProvingParameters,
+    pub verification_key: P::VerificationParameters,
+}
+
+impl<C: BaseDPCComponents, P> ProgramExecutor<C> for ProvableProgram<P>
+where
+    P: Program<LocalData = LocalData<C>, PrivateWitness = PrivateProgramInput>,
+{
+    fn execute(
+        &self,
+        local_data: &LocalData<C>,
+        position: u8,
+        mut rng: &mut dyn RngCore,
+    ) -> Result<PrivateProgramInput, DPCError> {
+        self.program.execute(
+            &self.proving_key,
+            &self.verification_key,
+            local_data,
+            position,
+            &mut rng,
+        )
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Clone(bound = "C: BaseDPCComponents"))]
+struct RegisteredProgram<C: BaseDPCComponents> {
+    verification_key: Vec<u8>,
+    /// The prover of the program, or `None` for the noop program of the registry
+    executor: Option<Arc<dyn ProgramExecutor<C> + Send + Sync>>,
+}
+
+/// The programs known to a node, indexed by program id.
+/// The id of a program is the hash of its verification key, and the outer circuit
+/// verifies a program proof against the verification key with the id named by the record.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "C: BaseDPCComponents"))]
+pub struct ProgramRegistry<C: BaseDPCComponents> {
+    noop_program: NoopProgram<C, C::NoopProgramSNARK>,
+    noop_program_id: Vec<u8>,
+    noop_parameters: NoopProgramSNARKParameters<C>,
+    programs: BTreeMap<Vec<u8>, RegisteredProgram<C>>,
+}
+
+impl<C: BaseDPCComponents> ProgramRegistry<C> {
+    /// Create a registry holding the noop program of the public parameters.
+    pub fn new(parameters: &PublicParameters<C>) -> Result<Self, DPCError> {
+        let noop_parameters = parameters.noop_program_snark_parameters.clone();
+        let verification_key = to_bytes![noop_parameters.verification_key]?;
+        let noop_program_id = Self::program_id(&parameters.system_parameters, &verification_key)?;
+
+        let mut programs = BTreeMap::new();
+        programs.insert(noop_program_id.clone(), RegisteredProgram {
+            verification_key,
+            executor: None,
+        });
+
+        Ok(Self {
+            noop_program: NoopProgram::new(noop_program_id.clone()),
+            noop_program_id,
+            noop_parameters,
+            programs,
+        })
+    }
+
+    /// Returns the program id of a serialized verification key.
+    pub fn program_id(system_parameters: &SystemParameters<C>, verification_key: &[u8]) -> Result<Vec<u8>, DPCError> {
+        Ok(to_bytes![system_parameters
+            .program_verification_key_crh
+            .hash(verification_key)?]?)
+    }
+
+    /// Register a program by its serialized verification key, returning the program id.
+    pub fn register(
+        &mut self,
+        system_parameters: &SystemParameters<C>,
+        verification_key: Vec<u8>,
+        executor: Arc<dyn ProgramExecutor<C> + Send + Sync>,
+    ) -> Result<Vec<u8>, DPCError> {
+        let program_id = Self::program_id(system_parameters, &verification_key)?;
+
+        self.programs.insert(program_id.clone(), RegisteredProgram {
+            verification_key,
+            executor: Some(executor),
+        });
+
+        Ok(program_id)
+    }
+
+    /// Returns the id of the noop program.
+    pub fn noop_program_id(&self) -> &[u8] {
+        &self.noop_program_id
+    }
+
+    /// Returns true if the program id is registered.
+    pub fn contains(&self, program_id: &[u8]) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
+    /// Returns the ids of the registered programs.
+    pub fn program_ids(&self) -> Vec<Vec<u8>> {
+        self.programs.keys().cloned().collect()
+    }
+
+    /// Returns the serialized verification key of a registered program.
+    pub fn verification_key(&self, program_id: &[u8]) -> Option<&[u8]> {
+        self.programs
+            .get(program_id)
+            .map(|program| &program.verification_key[..])
+    }
+
+    /// Prove the death program of an old record, or the birth program of a new record,
+    /// at the position of the local data.
+    pub fn execute(
+        &self,
+        local_data: &LocalData<C>,
+        position: u8,
+        rng: &mut dyn RngCore,
+    ) -> Result<PrivateProgramInput, DPCError> {
+        let position_index = position as usize;
+        let program_id = if position_index < C::NUM_INPUT_RECORDS {
+            local_data.old_records[position_index].death_program_id()
+        } else {
+            local_data.new_records[position_index - C::NUM_INPUT_RECORDS].birth_program_id()
+        };
+
+        match self.programs.get(program_id).map(|program| &program.executor) {
+            Some(Some(executor)) => executor.execute(local_data, position, rng),
+            Some(None) => self.noop_program.execute(
+                &self.noop_parameters.proving_key,
+                &self.noop_parameters.verification_key,
+                local_data,
+                position,
+                &mut &mut *rng,
+            ),
+            None => Err(DPCError::UnknownProgram(hex::encode(program_id))),
+        }
+    }
+}
@@ -16,7 +16,7 @@
 
 use snarkos_dpc::base_dpc::{
     instantiated::*,
-    program::NoopProgram,
+    program::{AssetProgram, NoopProgram, ProgramRegistry, ASSET_ID_SIZE},
     record::record_encryption::RecordEncryption,
     record_payload::RecordPayload,
     BaseDPCComponents,
@@ -78,7 +78,7 @@ fn base_dpc_integration_test() {
         transactions: DPCTransactions::new(),
     };
 
-    let ledger = initialize_test_blockchain::<Tx, CommitmentMerkleParameters>(ledger_parameters, genesis_block);
+    let ledger = initialize_test_blockchain::<Tx, CommitmentMerkleParameters, MemDb>(ledger_parameters, genesis_block);
 
     let noop_program_id = to_bytes![
         ProgramVerificationKeyCRH::hash(
@@ -259,3 +259,128 @@ fn base_dpc_integration_test() {
 
     kill_storage(ledger);
 }
+
+#[test]
+fn base_dpc_asset_program_test() {
+    let mut rng = XorShiftRng::seed_from_u64(1231275789u64);
+
+    let (ledger_parameters, parameters) = setup_or_load_parameters(false, &mut rng);
+    let [genesis_account, recipient, _] = generate_test_accounts(&parameters, &mut rng);
+
+    let genesis_block = Block {
+        header: BlockHeader {
+            previous_block_hash: BlockHeaderHash([0u8; 32]),
+            merkle_root_hash: MerkleRootHash([0u8; 32]),
+            pedersen_merkle_root_hash: PedersenMerkleRootHash([0u8; 32]),
+            time: 0,
+            difficulty_target: 0x07FF_FFFF_FFFF_FFFF_u64,
+            nonce: 0,
+            proof: ProofOfSuccinctWork::default(),
+        },
+        transactions: DPCTransactions::new(),
+    };
+
+    let ledger = initialize_test_blockchain::<Tx, CommitmentMerkleParameters, MemDb>(ledger_parameters, genesis_block);
+
+    // Register the asset program next to the noop program
+    let asset_id = [7u8; ASSET_ID_SIZE];
+    let (proving_key, verification_key) = AssetProgram::<Components, AssetProgramSNARK<Components>>::setup(
+        &parameters.system_parameters,
+        asset_id,
+        &mut rng,
+    )
+    .unwrap();
+
+    let mut program_registry = ProgramRegistry::new(&parameters).unwrap();
+    let asset_program_id = AssetProgram::<Components, AssetProgramSNARK<Components>>::register(
+        &mut program_registry,
+        &parameters.system_parameters,
+        asset_id,
+        proving_key,
+        verification_key,
+    )
+    .unwrap();
+    let noop_program_id = program_registry.noop_program_id().to_vec();
+    let asset_program =
+        AssetProgram::<Components, AssetProgramSNARK<Components>>::new(asset_program_id.clone(), asset_id);
+
+    let mut asset_payload = [0u8; 32];
+    asset_payload[..ASSET_ID_SIZE].copy_from_slice(&asset_id);
+    let asset_payload = RecordPayload::from_bytes(&asset_payload);
+
+    // The dummy input records hold the asset, and are spent with the asset program
+    let mut old_records = vec![];
+    for i in 0..NUM_INPUT_RECORDS {
+        let old_sn_nonce = SerialNumberNonce::hash(
+            &parameters.system_parameters.serial_number_nonce,
+            &[64u8 + (i as u8); 1],
+        )
+        .unwrap();
+        let old_record = DPC::generate_record(
+            &parameters.system_parameters,
+            &old_sn_nonce,
+            &genesis_account.address,
+            true,
+            0,
+            &asset_payload,
+            &noop_program_id,
+            &asset_program_id,
+            &mut rng,
+        )
+        .unwrap();
+        old_records.push(old_record);
+    }
+
+    // Only the first new record holds the asset, and is created with the asset program
+    let execute_context = <InstantiatedDPC as DPCScheme<L>>::execute_offline(
+        &parameters.system_parameters,
+        &old_records,
+        &vec![genesis_account.private_key.clone(); NUM_INPUT_RECORDS],
+        &vec![recipient.address.clone(); NUM_OUTPUT_RECORDS],
+        &vec![false; NUM_OUTPUT_RECORDS],
+        &vec![10; NUM_OUTPUT_RECORDS],
+        &[asset_payload, RecordPayload::default()],
+        &[asset_program_id.clone(), noop_program_id.clone()],
+        &vec![noop_program_id; NUM_OUTPUT_RECORDS],
+        &[4u8; 32],
+        0,
+        &mut rng,
+    )
+    .unwrap();
+
+    let local_data = execute_context.into_local_data();
+
+    let old_death_program_proofs: Vec<_> = (0..NUM_INPUT_RECORDS)
+        .map(|i| program_registry.execute(&local_data, i as u8, &mut rng).unwrap())
+        .collect();
+    let new_birth_program_proofs: Vec<_> = (0..NUM_OUTPUT_RECORDS)
+        .map(|j| {
+            program_registry
+                .execute(&local_data, (NUM_INPUT_RECORDS + j) as u8, &mut rng)
+                .unwrap()
+        })
+        .collect();
+
+    // The asset program only accepts its own proof for the record holding the asset
+    let asset_position = NUM_INPUT_RECORDS as u8;
+    let public_input = (local_data, asset_position);
+    assert!(asset_program.evaluate(&public_input, &new_birth_program_proofs[0]));
+    assert!(!asset_program.evaluate(&public_input, &new_birth_program_proofs[1]));
+
+    let public_input = (public_input.0, asset_position + 1);
+    assert!(!asset_program.evaluate(&public_input, &new_birth_program_proofs[0]));
+
+    let (_new_records, transaction) = InstantiatedDPC::execute_online(
+        &parameters,
+        execute_context,
+        &old_death_program_proofs,
+        &new_birth_program_proofs,
+        &ledger,
+        &mut rng,
+    )
+    .unwrap();
+
+    assert!(InstantiatedDPC::verify_transactions(&parameters, &vec![transaction], &ledger).unwrap());
+
+    kill_storage(ledger);
+}
@@ -41,6 +41,9 @@ pub enum MerkleError {
 
     #[error("{}", _0)]
     Message(String),
+
+    #[error("Missing node at height {} and position {}", _0, _1)]
+    MissingNode(usize, usize),
 }
 
 impl From<CRHError> for MerkleError {
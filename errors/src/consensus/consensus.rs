@@ -96,7 +96,7 @@ pub enum ConsensusError {
     #[error("{}", _0)]
     StorageError(StorageError),
 
-    #[error("timestamp {:?} is less than parent timestamp {:?}", _0, _1)]
+    #[error("timestamp {:?} is less than the median time past {:?}", _0, _1)]
     TimestampInvalid(i64, i64),
 
     #[error("{}", _0)]
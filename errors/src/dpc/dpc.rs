@@ -76,6 +76,9 @@ pub enum DPCError {
 
     #[error("{}", _0)]
     SNARKError(SNARKError),
+
+    #[error("unknown program id {}", _0)]
+    UnknownProgram(String),
 }
 
 impl From<AccountError> for DPCError {
@@ -30,3 +30,4 @@ pub mod posw;
 pub mod rpc;
 pub mod serialization;
 pub mod storage;
+pub mod wallet;
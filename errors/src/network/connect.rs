@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::network::message::{MessageError, MessageHeaderError};
+use crate::network::{
+    message::{MessageError, MessageHeaderError},
+    NoiseError,
+};
 
 use std::net::SocketAddr;
 
@@ -32,6 +35,9 @@ pub enum ConnectError {
     #[error("{}", _0)]
     MessageError(MessageError),
 
+    #[error("{}", _0)]
+    NoiseError(NoiseError),
+
     #[error("Address {:?} not found", _0)]
     AddressNotFound(SocketAddr),
 }
@@ -48,6 +54,12 @@ impl From<MessageHeaderError> for ConnectError {
     }
 }
 
+impl From<NoiseError> for ConnectError {
+    fn from(error: NoiseError) -> Self {
+        ConnectError::NoiseError(error)
+    }
+}
+
 impl From<std::io::Error> for ConnectError {
     fn from(error: std::io::Error) -> Self {
         ConnectError::Crate("std::io", format!("{:?}", error))
@@ -24,9 +24,18 @@ pub enum MessageHeaderError {
     #[error("{}", _0)]
     Message(String),
 
-    #[error("Invalid message header length {}. Expected length of 16", _0)]
+    #[error("Invalid message header length {}. Expected length of 24", _0)]
     InvalidLength(usize),
 
+    #[error("Invalid network magic {:#x}. Expected {:#x}", _0, _1)]
+    InvalidMagic(u32, u32),
+
+    #[error("Message {} of {} bytes exceeds the maximum size of {} bytes", _0, _1, _2)]
+    MessageTooLarge(String, u32, u32),
+
+    #[error("Invalid message checksum {:#x}. Expected {:#x}", _0, _1)]
+    InvalidChecksum(u32, u32),
+
     #[error("{}", _0)]
     StreamReadError(StreamReadError),
 }
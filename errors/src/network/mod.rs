@@ -17,6 +17,9 @@
 pub mod connect;
 pub use connect::*;
 
+pub mod noise;
+pub use noise::*;
+
 pub mod protocol;
 pub use protocol::*;
 
@@ -0,0 +1,51 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#[derive(Debug, Error)]
+pub enum NoiseError {
+    #[error("{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[error("{}", _0)]
+    Message(String),
+
+    #[error("Invalid noise key length {}. Expected length of {}", _0, _1)]
+    InvalidLength(usize, usize),
+
+    #[error("Invalid public key {}", _0)]
+    InvalidPublicKey(String),
+
+    #[error("Failed to authenticate an encrypted message")]
+    DecryptionFailed,
+
+    #[error("Encrypted frames carry {} bytes for a message of {} bytes", _0, _1)]
+    InvalidFrame(usize, u32),
+
+    #[error("The peer requested an encrypted connection, but encryption is disabled")]
+    EncryptionDisabled,
+
+    #[error("The peer opened a plaintext connection, but encryption is required")]
+    EncryptionRequired,
+
+    #[error("The peer closed the connection during the encrypted handshake, it may not accept encryption")]
+    HandshakeRejected,
+}
+
+impl From<std::io::Error> for NoiseError {
+    fn from(error: std::io::Error) -> Self {
+        NoiseError::Crate("std::io", format!("{:?}", error))
+    }
+}
@@ -28,6 +28,12 @@ pub enum HandshakeError {
     #[error("Peer disconnected {}", _0)]
     PeerDisconnect(SocketAddr),
 
+    #[error("Peer is banned {}", _0)]
+    PeerBanned(SocketAddr),
+
+    #[error("Peer {} uses protocol version {}, the minimum supported version is {}", _0, _1, _2)]
+    IncompatibleVersion(SocketAddr, u64, u64),
+
     #[error("No handshake found for peer: {:?}", _0)]
     HandshakeMissing(SocketAddr),
 
@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    network::{message::MessageError, ConnectError, HandshakeError, PingProtocolError, SendError},
+    network::{message::MessageError, ConnectError, HandshakeError, NoiseError, PingProtocolError, SendError},
     objects::{BlockError, TransactionError},
     storage::StorageError,
 };
@@ -65,6 +65,12 @@ impl From<ConnectError> for ServerError {
     }
 }
 
+impl From<NoiseError> for ServerError {
+    fn from(error: NoiseError) -> Self {
+        ServerError::ConnectError(ConnectError::NoiseError(error))
+    }
+}
+
 impl From<HandshakeError> for ServerError {
     fn from(error: HandshakeError) -> Self {
         ServerError::HandshakeError(error)
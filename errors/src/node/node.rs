@@ -17,9 +17,10 @@
 use crate::{
     algorithms::CRHError,
     consensus::ConsensusError,
-    network::ServerError,
+    dpc::DPCError,
+    network::{NoiseError, ServerError},
     node::CliError,
-    objects::AccountError,
+    objects::{AccountError, BlockError},
     storage::StorageError,
 };
 
@@ -28,6 +29,9 @@ pub enum NodeError {
     #[error("{}", _0)]
     AccountError(AccountError),
 
+    #[error("{}", _0)]
+    BlockError(BlockError),
+
     #[error("{}", _0)]
     CLIError(CliError),
 
@@ -37,6 +41,9 @@ pub enum NodeError {
     #[error("{}", _0)]
     ConsensusError(ConsensusError),
 
+    #[error("{}", _0)]
+    DPCError(DPCError),
+
     #[error("{}: {}", _0, _1)]
     Crate(&'static str, String),
 
@@ -56,6 +63,12 @@ impl From<AccountError> for NodeError {
     }
 }
 
+impl From<BlockError> for NodeError {
+    fn from(error: BlockError) -> Self {
+        NodeError::BlockError(error)
+    }
+}
+
 impl From<CliError> for NodeError {
     fn from(error: CliError) -> Self {
         NodeError::CLIError(error)
@@ -74,12 +87,24 @@ impl From<ConsensusError> for NodeError {
     }
 }
 
+impl From<DPCError> for NodeError {
+    fn from(error: DPCError) -> Self {
+        NodeError::DPCError(error)
+    }
+}
+
 impl From<hex::FromHexError> for NodeError {
     fn from(error: hex::FromHexError) -> Self {
         NodeError::Crate("hex", format!("{:?}", error))
     }
 }
 
+impl From<NoiseError> for NodeError {
+    fn from(error: NoiseError) -> Self {
+        NodeError::ServerError(ServerError::from(error))
+    }
+}
+
 impl From<ServerError> for NodeError {
     fn from(error: ServerError) -> Self {
         NodeError::ServerError(error)
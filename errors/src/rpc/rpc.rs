@@ -20,7 +20,9 @@ use crate::{
     dpc::DPCError,
     network::SendError,
     objects::{AccountError, BlockError, TransactionError},
+    serialization::SerializationError,
     storage::StorageError,
+    wallet::WalletError,
 };
 
 use std::fmt::Debug;
@@ -42,26 +44,67 @@ pub enum RpcError {
     #[error("{}", _0)]
     CRHError(CRHError),
 
+    #[error("blocks can only be generated by a node running a development chain")]
+    DevChainRequired,
+
     #[error("{}", _0)]
     DPCError(DPCError),
 
+    #[error("the input records hold {} but the outputs require {}", _0, _1)]
+    InsufficientInputs(u64, u64),
+
     #[error("invalid block hash: {}", _0)]
     InvalidBlockHash(String),
 
+    #[error("expected a canon block range of at most {} blocks, found {} to {}", _0, _1, _2)]
+    InvalidBlockRange(u32, u32, u32),
+
+    #[error("expected 1 to {} input records, found {}", _0, _1)]
+    InvalidInputCount(usize, usize),
+
     #[error("invalid metadata: {}", _0)]
     InvalidMetadata(String),
 
+    #[error("expected 1 to {} output records, found {}", _0, _1)]
+    InvalidOutputCount(usize, usize),
+
+    #[error("expected a {} byte record payload, found {} bytes", _0, _1)]
+    InvalidPayloadLength(usize, usize),
+
+    #[error("expected a private key for each of the {} input records, found {}", _0, _1)]
+    InvalidPrivateKeyCount(usize, usize),
+
+    #[error(
+        "expected {} to be empty or to hold a value for each of the {} recipients, found {}",
+        _0,
+        _1,
+        _2
+    )]
+    InvalidRecordAttributeCount(&'static str, usize, usize),
+
     #[error("{}", _0)]
     Message(String),
 
     #[error("{}", _0)]
     SendError(SendError),
 
+    #[error("{}", _0)]
+    SerializationError(SerializationError),
+
     #[error("{}", _0)]
     StorageError(StorageError),
 
     #[error("{}", _0)]
     TransactionError(TransactionError),
+
+    #[error("unknown program id {}", _0)]
+    UnknownProgram(String),
+
+    #[error("the transaction values overflow")]
+    ValueOverflow,
+
+    #[error("{}", _0)]
+    WalletError(WalletError),
 }
 
 impl From<AccountError> for RpcError {
@@ -100,6 +143,12 @@ impl From<SendError> for RpcError {
     }
 }
 
+impl From<SerializationError> for RpcError {
+    fn from(error: SerializationError) -> Self {
+        RpcError::SerializationError(error)
+    }
+}
+
 impl From<StorageError> for RpcError {
     fn from(error: StorageError) -> Self {
         RpcError::StorageError(error)
@@ -112,6 +161,12 @@ impl From<TransactionError> for RpcError {
     }
 }
 
+impl From<WalletError> for RpcError {
+    fn from(error: WalletError) -> Self {
+        RpcError::WalletError(error)
+    }
+}
+
 impl From<hex::FromHexError> for RpcError {
     fn from(error: hex::FromHexError) -> Self {
         RpcError::Crate("hex", format!("{:?}", error))
@@ -52,6 +52,9 @@ pub enum StorageError {
     #[error("existing serial number {:?}", _0)]
     ExistingSn(Vec<u8>),
 
+    #[error("the explorer index is not enabled")]
+    ExplorerIndexDisabled,
+
     #[error("Can't decommit the genesis block")]
     InvalidBlockDecommit,
 
@@ -88,6 +91,9 @@ pub enum StorageError {
     #[error("missing child block hashes value for block hash {}", _0)]
     MissingChildBlock(String),
 
+    #[error("missing cumulative work value for block hash {}", _0)]
+    MissingCumulativeWork(String),
+
     #[error("missing current commitment index")]
     MissingCurrentCmIndex,
 
@@ -121,6 +127,9 @@ pub enum StorageError {
     #[error("missing value given key {}", _0)]
     MissingValue(String),
 
+    #[error("database schema version {} is newer than the supported version {}", _0, _1)]
+    UnsupportedSchemaVersion(u32, u32),
+
     #[error("Null Error {:?}", _0)]
     NullError(()),
 
@@ -0,0 +1,123 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    algorithms::CRHError,
+    consensus::ConsensusError,
+    dpc::DPCError,
+    objects::{AccountError, TransactionError},
+    storage::StorageError,
+};
+
+use std::fmt::Debug;
+
+#[derive(Debug, Error)]
+pub enum WalletError {
+    #[error("{}", _0)]
+    AccountError(AccountError),
+
+    #[error("account {} is not in the wallet", _0)]
+    AccountNotFound(String),
+
+    #[error("{}", _0)]
+    ConsensusError(ConsensusError),
+
+    #[error("{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[error("{}", _0)]
+    CRHError(CRHError),
+
+    #[error("{}", _0)]
+    DPCError(DPCError),
+
+    #[error("the account holds {} but {} is required", _0, _1)]
+    InsufficientFunds(u64, u64),
+
+    #[error("invalid wallet password")]
+    InvalidPassword,
+
+    #[error("the stored wallet is {} bytes, expected at least {}", _0, _1)]
+    InvalidLength(usize, usize),
+
+    #[error("{}", _0)]
+    Message(String),
+
+    #[error("{}", _0)]
+    StorageError(StorageError),
+
+    #[error("{} cannot be covered by {} unspent records", _0, _1)]
+    TooManyInputs(u64, usize),
+
+    #[error("{}", _0)]
+    TransactionError(TransactionError),
+    #[error("the transaction values overflow")]
+    ValueOverflow,
+}
+
+impl From<AccountError> for WalletError {
+    fn from(error: AccountError) -> Self {
+        WalletError::AccountError(error)
+    }
+}
+
+impl From<ConsensusError> for WalletError {
+    fn from(error: ConsensusError) -> Self {
+        WalletError::ConsensusError(error)
+    }
+}
+
+impl From<CRHError> for WalletError {
+    fn from(error: CRHError) -> Self {
+        WalletError::CRHError(error)
+    }
+}
+
+impl From<DPCError> for WalletError {
+    fn from(error: DPCError) -> Self {
+        WalletError::DPCError(error)
+    }
+}
+
+impl From<StorageError> for WalletError {
+    fn from(error: StorageError) -> Self {
+        WalletError::StorageError(error)
+    }
+}
+
+impl From<TransactionError> for WalletError {
+    fn from(error: TransactionError) -> Self {
+        WalletError::TransactionError(error)
+    }
+}
+
+impl From<bincode::Error> for WalletError {
+    fn from(error: bincode::Error) -> Self {
+        WalletError::Crate("bincode", format!("{:?}", error))
+    }
+}
+
+impl From<hex::FromHexError> for WalletError {
+    fn from(error: hex::FromHexError) -> Self {
+        WalletError::Crate("hex", format!("{:?}", error))
+    }
+}
+
+impl From<std::io::Error> for WalletError {
+    fn from(error: std::io::Error) -> Self {
+        WalletError::Crate("std::io", format!("{:?}", error))
+    }
+}
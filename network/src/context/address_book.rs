@@ -14,58 +14,88 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::PublicKey;
+
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, net::SocketAddr};
 
-/// Stores the existence of a peer and the date they were last seen.
+/// The identifier of a peer.
+/// Peers that authenticated with their public key keep their identity across addresses,
+/// while peers on plaintext channels are only known by their address.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum PeerId {
+    Key(PublicKey),
+    Address(SocketAddr),
+}
+
+/// Stores the existence of a peer, its current address, and the date they were last seen.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AddressBook {
-    addresses: HashMap<SocketAddr, DateTime<Utc>>,
+    peers: HashMap<PeerId, (SocketAddr, DateTime<Utc>)>,
 }
 
 impl AddressBook {
     /// Construct a new `AddressBook`.
     pub fn new() -> Self {
         Self {
-            addresses: HashMap::default(),
+            peers: HashMap::default(),
         }
     }
 
-    /// Insert or update a new date for an address. Returns true if the new date is stored.
-    pub fn update(&mut self, address: SocketAddr, date: DateTime<Utc>) -> bool {
-        match self.addresses.get(&address) {
-            Some(stored_date) => {
-                if stored_date > &date {
-                    false
-                } else {
-                    self.addresses.insert(address, date);
-                    true
-                }
-            }
-            None => {
-                self.addresses.insert(address, date);
-                true
+    /// Insert or update the address and date of a peer. Returns true if the new date is stored.
+    /// A peer previously stored under another identifier at the same address is replaced.
+    pub fn update(&mut self, id: PeerId, address: SocketAddr, date: DateTime<Utc>) -> bool {
+        if let Some((stored_address, stored_date)) = self.peers.get(&id) {
+            if *stored_address == address && stored_date > &date {
+                return false;
             }
         }
+
+        if let Some(previous_id) = self.get_id(&address) {
+            self.peers.remove(&previous_id);
+        }
+
+        self.peers.insert(id, (address, date));
+        true
     }
 
-    /// Returns true if address is stored in the mapping.
+    /// Returns true if a peer with the address is stored in the mapping.
     pub fn contains(&self, address: &SocketAddr) -> bool {
-        self.addresses.contains_key(address)
+        self.get_id(address).is_some()
     }
 
-    /// Remove an address mapping and return its last seen date.
+    /// Returns true if the peer is stored in the mapping.
+    pub fn contains_id(&self, id: &PeerId) -> bool {
+        self.peers.contains_key(id)
+    }
+
+    /// Returns the identifier of the peer at the address.
+    pub fn get_id(&self, address: &SocketAddr) -> Option<PeerId> {
+        self.peers
+            .iter()
+            .find(|(_id, (peer_address, _date))| peer_address == address)
+            .map(|(id, _peer)| *id)
+    }
+
+    /// Remove the peer at an address and return its last seen date.
     pub fn remove(&mut self, address: &SocketAddr) -> Option<DateTime<Utc>> {
-        self.addresses.remove(address)
+        let id = self.get_id(address)?;
+        self.remove_id(&id).map(|(_address, date)| date)
+    }
+
+    /// Remove a peer and return its address and last seen date.
+    pub fn remove_id(&mut self, id: &PeerId) -> Option<(SocketAddr, DateTime<Utc>)> {
+        self.peers.remove(id)
     }
 
     /// Returns the number of stored peers.
     pub fn length(&self) -> u16 {
-        self.addresses.len() as u16
+        self.peers.len() as u16
     }
 
-    /// Returns copy of addresses
+    /// Returns copy of the peer addresses and the date they were last seen
     pub fn get_addresses(&self) -> HashMap<SocketAddr, DateTime<Utc>> {
-        self.addresses.clone()
+        self.peers.values().cloned().collect()
     }
 }
@@ -0,0 +1,144 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_errors::{
+    consensus::ConsensusError,
+    network::{
+        message::{MessageError, MessageHeaderError},
+        ConnectError,
+        NoiseError,
+        ServerError,
+    },
+};
+
+/// The ban score of a peer that sent an invalid block.
+pub const INVALID_BLOCK_SCORE: u32 = 100;
+
+/// The ban score of a peer that sent a block with a timestamp too far in the future.
+/// The peer may simply have a skewed clock.
+pub const FUTURISTIC_BLOCK_SCORE: u32 = 20;
+
+/// The ban score of a peer that sent a message we could not deserialize.
+pub const MALFORMED_MESSAGE_SCORE: u32 = 20;
+
+/// Assigns a ban score to errors caused by a misbehaving peer.
+/// Errors that may be caused by our own node or the connection score 0.
+pub trait BanScore {
+    fn ban_score(&self) -> u32;
+}
+
+impl BanScore for ConsensusError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            ConsensusError::AlreadySpent(_, _)
+            | ConsensusError::BlockTooLarge(_, _)
+            | ConsensusError::CoinbaseTransactionAlreadyExists()
+            | ConsensusError::ConflictingNetworkId(_, _)
+            | ConsensusError::DifficultyMismatch(_, _)
+            | ConsensusError::InvalidBlock(_)
+            | ConsensusError::InvalidCoinbaseTransaction
+            | ConsensusError::MerkleRoot(_)
+            | ConsensusError::MultipleCoinbaseTransactions(_)
+            | ConsensusError::NonceInvalid(_, _)
+            | ConsensusError::PedersenMerkleRoot(_)
+            | ConsensusError::PowInvalid(_, _)
+            | ConsensusError::PoswError(_)
+            | ConsensusError::TimestampInvalid(_, _)
+            | ConsensusError::TransactionOverspending => INVALID_BLOCK_SCORE,
+            ConsensusError::FuturisticTimestamp(_, _) => FUTURISTIC_BLOCK_SCORE,
+            _ => 0,
+        }
+    }
+}
+
+impl BanScore for ConnectError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            ConnectError::MessageError(error) => error.ban_score(),
+            ConnectError::MessageHeaderError(error) => error.ban_score(),
+            ConnectError::NoiseError(error) => error.ban_score(),
+            _ => 0,
+        }
+    }
+}
+
+impl BanScore for NoiseError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            NoiseError::DecryptionFailed | NoiseError::InvalidFrame(_, _) | NoiseError::InvalidPublicKey(_) => {
+                MALFORMED_MESSAGE_SCORE
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl BanScore for MessageError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            MessageError::Crate("bincode", _) | MessageError::InvalidLength(_, _) => MALFORMED_MESSAGE_SCORE,
+            MessageError::MessageHeaderError(error) => error.ban_score(),
+            _ => 0,
+        }
+    }
+}
+
+impl BanScore for MessageHeaderError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            MessageHeaderError::Crate("bincode", _)
+            | MessageHeaderError::InvalidLength(_)
+            | MessageHeaderError::InvalidMagic(_, _)
+            | MessageHeaderError::MessageTooLarge(_, _, _)
+            | MessageHeaderError::InvalidChecksum(_, _) => MALFORMED_MESSAGE_SCORE,
+            _ => 0,
+        }
+    }
+}
+
+impl BanScore for ServerError {
+    fn ban_score(&self) -> u32 {
+        match self {
+            ServerError::BlockError(_) | ServerError::Crate("bincode", _) => MALFORMED_MESSAGE_SCORE,
+            ServerError::ConnectError(error) => error.ban_score(),
+            ServerError::MessageError(error) => error.ban_score(),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_score() {
+        assert_eq!(INVALID_BLOCK_SCORE, ConsensusError::PowInvalid(1, 2).ban_score());
+        assert_eq!(
+            FUTURISTIC_BLOCK_SCORE,
+            ConsensusError::FuturisticTimestamp(1, 2).ban_score()
+        );
+        assert_eq!(0, ConsensusError::NoParent("a".into(), "b".into()).ban_score());
+
+        let malformed = MessageError::InvalidLength(1, 8);
+        assert_eq!(MALFORMED_MESSAGE_SCORE, malformed.ban_score());
+        assert_eq!(
+            MALFORMED_MESSAGE_SCORE,
+            ServerError::MessageError(malformed).ban_score()
+        );
+        assert_eq!(0, MessageError::Message("closed".into()).ban_score());
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::{DateTime, Duration, Utc};
+use std::time::Instant as StdInstant;
+use tokio::time::Instant;
+
+/// Returns the current time of this node, as measured by the runtime clock.
+/// This is the system time, unless the runtime clock is paused and advanced by hand,
+/// as it is when nodes are run in a simulated network.
+pub fn local_time() -> DateTime<Utc> {
+    let runtime_now = Instant::now().into_std();
+    let system_now = StdInstant::now();
+
+    if runtime_now >= system_now {
+        Utc::now() + Duration::from_std(runtime_now - system_now).unwrap_or_else(|_| Duration::zero())
+    } else {
+        Utc::now() - Duration::from_std(system_now - runtime_now).unwrap_or_else(|_| Duration::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn follows_paused_runtime_clock() {
+        tokio::time::pause();
+
+        let start = local_time();
+        tokio::time::advance(std::time::Duration::from_secs(60)).await;
+        let elapsed = local_time() - start;
+
+        assert!(elapsed >= Duration::seconds(60));
+        assert!(elapsed < Duration::seconds(61));
+    }
+}
@@ -14,9 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Connections, Handshakes, PeerBook, Pings};
+use crate::{Connections, Handshakes, Identity, PeerBook, Pings, TcpTransport, Transport};
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tokio::sync::RwLock;
 
 /// The network context for this node.
@@ -51,6 +51,9 @@ pub struct Context {
 
     /// Ping/pongs with connected peers
     pub pings: RwLock<Pings>,
+
+    /// Listens for and opens connections with peers
+    pub transport: Arc<dyn Transport>,
 }
 
 impl Context {
@@ -62,7 +65,11 @@ impl Context {
         max_peers: u16,
         is_bootnode: bool,
         bootnodes: Vec<String>,
+        ban_duration: u64,
+        identity: Option<Identity>,
     ) -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(TcpTransport);
+
         Self {
             local_address: RwLock::new(local_address),
             memory_pool_interval,
@@ -71,9 +78,21 @@ impl Context {
             is_bootnode,
             bootnodes,
             connections: RwLock::new(Connections::new()),
-            peer_book: RwLock::new(PeerBook::new()),
-            handshakes: RwLock::new(Handshakes::new()),
+            peer_book: RwLock::new(PeerBook::with_ban_duration(ban_duration)),
+            handshakes: RwLock::new(Handshakes::with_transport(identity, transport.clone())),
             pings: RwLock::new(Pings::new()),
+            transport,
+        }
+    }
+
+    /// Returns the network context with peers reached over the given transport instead of TCP.
+    pub fn with_transport(self, transport: Arc<dyn Transport>) -> Self {
+        let identity = self.handshakes.into_inner().identity().cloned();
+
+        Self {
+            handshakes: RwLock::new(Handshakes::with_transport(identity, transport.clone())),
+            transport,
+            ..self
         }
     }
 }
@@ -15,114 +15,170 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    message_types::{GetPeers, Verack, Version},
+    message_types::{Verack, Version},
     Channel,
     Handshake,
     HandshakeState,
+    Identity,
     Message,
+    PeerBook,
+    PeerStream,
+    TcpTransport,
+    Transport,
 };
 use snarkos_errors::network::HandshakeError;
 
-use std::{collections::HashMap, net::SocketAddr};
-use tokio::net::TcpStream;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 /// Stores the address and latest state of peers we are handshaking with.
 #[derive(Clone, Debug)]
 pub struct Handshakes {
     addresses: HashMap<SocketAddr, Handshake>,
+    /// The static identity used to encrypt channels, if encryption is enabled
+    identity: Option<Identity>,
+    /// The transport used to connect to peers
+    transport: Arc<dyn Transport>,
 }
 
 impl Handshakes {
     /// Construct a new store of connected peer `Handshakes`.
     pub fn new() -> Self {
+        Self::with_identity(None)
+    }
+
+    /// Construct a new store of connected peer `Handshakes` that encrypts channels with the given identity.
+    pub fn with_identity(identity: Option<Identity>) -> Self {
+        Self::with_transport(identity, Arc::new(TcpTransport))
+    }
+
+    /// Construct a new store of connected peer `Handshakes` that connects to peers over the given transport.
+    pub fn with_transport(identity: Option<Identity>, transport: Arc<dyn Transport>) -> Self {
         Self {
             addresses: HashMap::default(),
+            identity,
+            transport,
         }
     }
 
+    /// Returns the static identity used to encrypt channels.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.identity.as_ref()
+    }
+
     /// Create a new handshake with a peer and send a handshake request to them.
-    /// If the request is sent successfully, the handshake is stored and returned.
+    /// If the request is sent successfully, the handshake is stored and its channel is returned
+    /// so that the handshake response can be read from the same connection.
     pub async fn send_request(
         &mut self,
         version: u64,
         height: u32,
         address_sender: SocketAddr,
         address_receiver: SocketAddr,
-    ) -> Result<(), HandshakeError> {
-        let handshake = Handshake::send_new(version, height, address_sender, address_receiver).await?;
+    ) -> Result<Arc<Channel>, HandshakeError> {
+        let handshake = Handshake::send_new(
+            version,
+            height,
+            address_sender,
+            address_receiver,
+            self.identity.as_ref(),
+            &*self.transport,
+        )
+        .await?;
+
+        let channel = handshake.channel.clone();
 
         self.addresses.insert(address_receiver, handshake);
         info!("Request handshake with: {:?}", address_receiver);
 
-        Ok(())
+        Ok(channel)
     }
 
     /// Receive the first message upon accepting a peer connection.
-    /// If the message is a Version:
+    /// Handshakes with banned peers or banned peer identities are refused.
+    /// The message must be a Version:
     ///     1. Create a new handshake.
-    ///     2. Send a handshake response.
+    ///     2. Send a handshake response over the accepted connection.
     ///     3. If the response is sent successfully, store the handshake.
     ///     4. Return the handshake, your address as seen by sender, and the version message.
-    /// If the message is a Verack:
-    ///     1. Get the existing handshake.
-    ///     2. Mark the handshake as accepted.
-    ///     3. Send a request for peers.
-    ///     4. Return the accepted handshake and your address as seen by sender.
-    pub async fn receive_any(
+    pub async fn receive_any<S: PeerStream>(
         &mut self,
         version: u64,
         height: u32,
         local_address: SocketAddr,
         peer_address: SocketAddr,
-        reader: TcpStream,
-    ) -> Result<(Handshake, SocketAddr, Option<Version>), HandshakeError> {
-        let channel = Channel::new_read_only(reader)?;
+        peer_book: &PeerBook,
+        stream: S,
+    ) -> Result<(Handshake, SocketAddr, Version), HandshakeError> {
+        let (handshake, receiver, peer_message) = Self::accept_any(
+            self.identity.as_ref(),
+            version,
+            height,
+            local_address,
+            peer_address,
+            peer_book,
+            stream,
+        )
+        .await?;
 
-        // Read the first message or error
-        let (name, bytes) = channel.read().await?;
+        self.insert(handshake.clone());
 
-        if Version::name() == name {
-            let peer_message = Version::deserialize(bytes)?;
+        Ok((handshake, receiver, peer_message))
+    }
 
-            let receiver = peer_message.address_receiver;
+    /// Receive the first message upon accepting a peer connection, like `receive_any`,
+    /// but without storing the handshake. This lets the caller perform the handshake
+    /// without holding the store, and `insert` the handshake once it succeeds.
+    pub async fn accept_any<S: PeerStream>(
+        identity: Option<&Identity>,
+        version: u64,
+        height: u32,
+        local_address: SocketAddr,
+        peer_address: SocketAddr,
+        peer_book: &PeerBook,
+        stream: S,
+    ) -> Result<(Handshake, SocketAddr, Version), HandshakeError> {
+        // Bans are keyed by IP address, so a banned peer cannot return on another port.
+        if peer_book.is_banned(&peer_address) {
+            return Err(HandshakeError::PeerBanned(peer_address));
+        }
 
-            // Peer address and specified port from the version message
-            let peer_address = SocketAddr::new(peer_address.ip(), peer_message.address_sender.port());
+        let channel = Channel::accept(stream, identity).await?;
+        if let Some(peer_key) = channel.peer_key {
+            if peer_book.is_identity_banned(&peer_key) {
+                return Err(HandshakeError::PeerBanned(peer_address));
+            }
+        }
 
-            let handshake = Handshake::receive_new(
-                version,
-                height,
-                channel,
-                peer_message.clone(),
-                local_address,
-                peer_address,
-            )
-            .await?;
+        // Read the first message or error
+        let (name, bytes) = channel.read().await?;
 
-            self.addresses.insert(peer_address, handshake.clone());
+        if Version::name() != name {
+            return Err(HandshakeError::InvalidMessage(name.to_string()));
+        }
 
-            Ok((handshake, receiver, Some(peer_message)))
-        } else if Verack::name() == name {
-            let peer_message = Verack::deserialize(bytes)?;
-            let peer_address = peer_message.address_sender;
-            let receiver = peer_message.address_receiver;
+        let peer_message = Version::deserialize(bytes)?;
 
-            match self.get_mut(&peer_address) {
-                Some(handshake) => {
-                    handshake.accept(peer_message).await?;
-                    handshake.update_reader(channel);
-                    info!("New handshake with: {:?}", peer_address);
+        let receiver = peer_message.address_receiver;
 
-                    // Get our new peer's peer_list
-                    handshake.channel.write(&GetPeers).await?;
+        // Peer address and specified port from the version message
+        let peer_address = SocketAddr::new(peer_address.ip(), peer_message.address_sender.port());
 
-                    Ok((handshake.clone(), receiver, None))
-                }
-                None => Err(HandshakeError::HandshakeMissing(peer_address)),
-            }
-        } else {
-            Err(HandshakeError::InvalidMessage(name.to_string()))
-        }
+        let handshake = Handshake::receive_new(
+            version,
+            height,
+            channel,
+            peer_message.clone(),
+            local_address,
+            peer_address,
+        )
+        .await?;
+
+        Ok((handshake, receiver, peer_message))
+    }
+
+    /// Store a handshake accepted with `accept_any`, keyed by the address of its channel.
+    pub fn insert(&mut self, handshake: Handshake) {
+        self.addresses.insert(handshake.channel.address, handshake);
     }
 
     /// Receive a handshake request from a connected peer.
@@ -178,7 +234,7 @@ impl Handshakes {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Message;
+    use crate::{Message, PROTOCOL_VERSION};
     use snarkos_testing::network::random_socket_address;
 
     use serial_test::serial;
@@ -194,22 +250,17 @@ mod tests {
         let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
 
         tokio::spawn(async move {
-            let mut server_listener = TcpListener::bind(server_address).await.unwrap();
-
             // 2. Server sends server_handshake request
 
             let mut server_handshakes = Handshakes::new();
 
-            server_handshakes
-                .send_request(1u64, 0u32, server_address, peer_address)
+            let channel = server_handshakes
+                .send_request(PROTOCOL_VERSION, 0u32, server_address, peer_address)
                 .await
                 .unwrap();
 
             // 5. Check server handshake state
 
-            let (reader, _socket) = server_listener.accept().await.unwrap();
-            let read_channel = Channel::new_read_only(reader).unwrap();
-
             assert_eq!(
                 HandshakeState::Waiting,
                 server_handshakes.get_state(peer_address).unwrap()
@@ -217,7 +268,7 @@ mod tests {
 
             // 6. Server accepts server_handshake response
 
-            let (_name, bytes) = read_channel.read().await.unwrap();
+            let (_name, bytes) = channel.read().await.unwrap();
             let message = Verack::deserialize(bytes).unwrap();
 
             server_handshakes.accept_response(peer_address, message).await.unwrap();
@@ -229,7 +280,7 @@ mod tests {
 
             // 7. Server receives peer_handshake request
 
-            let (_name, bytes) = read_channel.read().await.unwrap();
+            let (_name, bytes) = channel.read().await.unwrap();
             let message = Version::deserialize(bytes).unwrap();
 
             // 8. Server sends peer_handshake response
@@ -239,13 +290,20 @@ mod tests {
 
         // 3. Peer accepts Server connection
 
-        let (reader, _socket) = peer_listener.accept().await.unwrap();
+        let (stream, _socket) = peer_listener.accept().await.unwrap();
 
         // 4. Peer sends server_handshake response, peer_handshake request
 
         let mut peer_handshakes = Handshakes::new();
         let (peer_hand, _, _) = peer_handshakes
-            .receive_any(1u64, 0u32, peer_address, server_address, reader)
+            .receive_any(
+                PROTOCOL_VERSION,
+                0u32,
+                peer_address,
+                server_address,
+                &PeerBook::new(),
+                stream,
+            )
             .await
             .unwrap();
 
@@ -17,6 +17,12 @@
 pub mod address_book;
 pub use address_book::*;
 
+pub mod ban_score;
+pub use ban_score::*;
+
+pub mod clock;
+pub use clock::*;
+
 pub mod connections;
 pub use connections::*;
 
@@ -14,15 +14,59 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::address_book::AddressBook;
+use crate::{
+    address_book::{AddressBook, PeerId},
+    local_time,
+    PublicKey,
+};
 use snarkos_errors::network::ServerError;
 use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
 use snarkos_storage::Ledger;
 
-use chrono::{DateTime, Utc};
-use std::{collections::HashMap, net::SocketAddr};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
 
-/// Stores connected, disconnected, and known peers.
+/// The ban score at which a misbehaving peer is banned.
+pub const BAN_THRESHOLD: u32 = 100;
+
+/// The number of seconds a misbehaving peer is banned for by default.
+pub const DEFAULT_BAN_DURATION: u64 = 86400;
+
+/// The peers written to storage.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StoredPeers {
+    /// Connected peers and the date they were last seen
+    pub connected: HashMap<SocketAddr, DateTime<Utc>>,
+
+    /// Banned IP addresses and the date their ban expires
+    pub banned: HashMap<IpAddr, DateTime<Utc>>,
+
+    /// Banned peer identities and the date their ban expires
+    pub banned_identities: HashMap<PublicKey, DateTime<Utc>>,
+}
+
+impl StoredPeers {
+    /// Deserialize the stored peers.
+    /// Peer books stored before bans were persisted only contain the connected peers.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, ServerError> {
+        match bincode::deserialize(bytes) {
+            Ok(stored_peers) => Ok(stored_peers),
+            Err(_) => Ok(Self {
+                connected: bincode::deserialize(bytes)?,
+                banned: HashMap::new(),
+                banned_identities: HashMap::new(),
+            }),
+        }
+    }
+}
+
+/// Stores connected, disconnected, known, and banned peers.
+/// Peers that authenticated with their public key are keyed by the key, so a known peer connecting
+/// from a new address replaces its old address.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PeerBook {
     /// Connected peers
@@ -33,14 +77,35 @@ pub struct PeerBook {
 
     /// Gossiped but uncontacted peers
     gossiped: AddressBook,
+
+    /// Banned IP addresses and the date their ban expires
+    banned: HashMap<IpAddr, DateTime<Utc>>,
+
+    /// Ban scores of misbehaving IP addresses
+    ban_scores: HashMap<IpAddr, u32>,
+
+    /// Banned peer identities and the date their ban expires
+    banned_identities: HashMap<PublicKey, DateTime<Utc>>,
+
+    /// Number of seconds a misbehaving peer is banned for
+    ban_duration: u64,
 }
 
 impl PeerBook {
     pub fn new() -> Self {
+        Self::with_ban_duration(DEFAULT_BAN_DURATION)
+    }
+
+    /// Construct a new `PeerBook` that bans misbehaving peers for `ban_duration` seconds.
+    pub fn with_ban_duration(ban_duration: u64) -> Self {
         Self {
             connected: AddressBook::new(),
             disconnected: AddressBook::new(),
             gossiped: AddressBook::new(),
+            banned: HashMap::new(),
+            ban_scores: HashMap::new(),
+            banned_identities: HashMap::new(),
+            ban_duration,
         }
     }
 
@@ -54,6 +119,46 @@ impl PeerBook {
         self.gossiped.get_addresses()
     }
 
+    /// Returns copy of banned IP addresses and the date their ban expires.
+    pub fn get_banned(&self) -> HashMap<IpAddr, DateTime<Utc>> {
+        let now = local_time();
+
+        self.banned
+            .iter()
+            .filter(|(_ip, expiry)| **expiry > now)
+            .map(|(ip, expiry)| (*ip, *expiry))
+            .collect()
+    }
+
+    /// Returns copy of banned peer identities and the date their ban expires.
+    pub fn get_banned_identities(&self) -> HashMap<PublicKey, DateTime<Utc>> {
+        let now = local_time();
+
+        self.banned_identities
+            .iter()
+            .filter(|(_key, expiry)| **expiry > now)
+            .map(|(key, expiry)| (*key, *expiry))
+            .collect()
+    }
+
+    /// Returns the identifier of the peer at the address.
+    /// Peers missing from the peer book are identified by their address.
+    pub fn peer_id(&self, address: &SocketAddr) -> PeerId {
+        self.connected
+            .get_id(address)
+            .or_else(|| self.disconnected.get_id(address))
+            .or_else(|| self.gossiped.get_id(address))
+            .unwrap_or(PeerId::Address(*address))
+    }
+
+    /// Returns the public key a peer authenticated with.
+    pub fn get_identity(&self, address: &SocketAddr) -> Option<PublicKey> {
+        match self.peer_id(address) {
+            PeerId::Key(key) => Some(key),
+            PeerId::Address(_) => None,
+        }
+    }
+
     /// Returns true if address is a connected peer.
     pub fn connected_contains(&self, address: &SocketAddr) -> bool {
         self.connected.contains(address)
@@ -69,25 +174,135 @@ impl PeerBook {
         self.gossiped.contains(address)
     }
 
+    /// Returns true if the IP address of the peer is banned.
+    /// Bans do not depend on the port, which the peer is free to choose.
+    pub fn is_banned(&self, address: &SocketAddr) -> bool {
+        match self.banned.get(&address.ip()) {
+            Some(expiry) => *expiry > local_time(),
+            None => false,
+        }
+    }
+
+    /// Returns true if the peer identity is banned.
+    pub fn is_identity_banned(&self, key: &PublicKey) -> bool {
+        match self.banned_identities.get(key) {
+            Some(expiry) => *expiry > local_time(),
+            None => false,
+        }
+    }
+
+    /// Returns the ban score of the IP address of a peer.
+    pub fn ban_score(&self, address: &SocketAddr) -> u32 {
+        *self.ban_scores.get(&address.ip()).unwrap_or(&0)
+    }
+
+    /// Raise the ban score of the IP address of a misbehaving peer.
+    /// Once the score reaches the `BAN_THRESHOLD` the peer is forgotten and its IP address is banned.
+    /// Returns true if the peer is banned.
+    pub fn misbehave(&mut self, address: SocketAddr, score: u32) -> bool {
+        if score == 0 {
+            return self.is_banned(&address);
+        }
+
+        let ban_score = self.ban_score(&address).saturating_add(score);
+        warn!("Peer {:?} misbehaved, ban score is now {}", address, ban_score);
+
+        if ban_score >= BAN_THRESHOLD {
+            self.ban_peer(address, local_time() + Duration::seconds(self.ban_duration as i64));
+            true
+        } else {
+            self.ban_scores.insert(address.ip(), ban_score);
+            false
+        }
+    }
+
+    /// Ban the IP address of a peer until the given date.
+    /// If the peer authenticated with a public key, the key is banned as well.
+    pub fn ban_peer(&mut self, address: SocketAddr, until: DateTime<Utc>) {
+        if let Some(key) = self.get_identity(&address) {
+            self.ban_identity(key, until);
+        }
+
+        self.ban_ip(address.ip(), until);
+    }
+
+    /// Forget every peer at the IP address and ban it until the given date.
+    pub fn ban_ip(&mut self, ip: IpAddr, until: DateTime<Utc>) {
+        warn!("Banning IP address {} until {}", ip, until);
+
+        let addresses = self
+            .connected
+            .get_addresses()
+            .into_iter()
+            .chain(self.disconnected.get_addresses())
+            .chain(self.gossiped.get_addresses())
+            .map(|(address, _date)| address)
+            .filter(|address| address.ip() == ip);
+
+        for address in addresses.collect::<Vec<_>>() {
+            self.forget_peer(address);
+        }
+
+        self.ban_scores.remove(&ip);
+        self.banned.insert(ip, until);
+    }
+
+    /// Forget the peer with a public key and ban the key until the given date.
+    pub fn ban_identity(&mut self, key: PublicKey, until: DateTime<Utc>) {
+        warn!("Banning peer identity {} until {}", key, until);
+
+        let id = PeerId::Key(key);
+        self.connected.remove_id(&id);
+        self.disconnected.remove_id(&id);
+        self.gossiped.remove_id(&id);
+
+        self.banned_identities.insert(key, until);
+    }
+
+    /// Remove the peers whose ban has expired.
+    pub fn remove_expired_bans(&mut self) {
+        let now = local_time();
+
+        self.banned.retain(|_ip, expiry| *expiry > now);
+        self.banned_identities.retain(|_key, expiry| *expiry > now);
+    }
+
     /// Move a peer from disconnected/gossiped to connected peers.
     pub fn update_connected(&mut self, address: SocketAddr, date: DateTime<Utc>) -> bool {
+        let id = self.peer_id(&address);
+        self.update_connected_peer(id, address, date)
+    }
+
+    /// Move a peer from disconnected/gossiped to connected peers at the given address.
+    /// An authenticated peer known at another address is moved to the new address.
+    pub fn update_connected_peer(&mut self, id: PeerId, address: SocketAddr, date: DateTime<Utc>) -> bool {
+        self.disconnected.remove_id(&id);
         self.disconnected.remove(&address);
+        self.gossiped.remove_id(&id);
         self.gossiped.remove(&address);
-        self.connected.update(address, date)
+        self.connected.update(id, address, date)
     }
 
     /// Move a peer from connected/disconnected to gossiped peers.
+    /// Banned peers are not gossiped.
     pub fn update_gossiped(&mut self, address: SocketAddr, date: DateTime<Utc>) -> bool {
+        if self.is_banned(&address) {
+            return false;
+        }
+
+        let id = self.peer_id(&address);
         self.connected.remove(&address);
         self.disconnected.remove(&address);
-        self.gossiped.update(address, date)
+        self.gossiped.update(id, address, date)
     }
 
     /// Move a peer from connected peers to disconnected peers.
+    /// The peer keeps its identity, so an authenticated peer is recognised when it reconnects.
     pub fn disconnect_peer(&mut self, address: SocketAddr) -> bool {
+        let id = self.peer_id(&address);
         self.connected.remove(&address);
         self.gossiped.remove(&address);
-        self.disconnected.update(address, Utc::now())
+        self.disconnected.update(id, address, local_time())
     }
 
     /// Forget a peer.
@@ -102,11 +317,133 @@ impl PeerBook {
         self.connected.length()
     }
 
-    /// Writes connected peers to storage.
+    /// Writes connected and banned peers to storage.
     pub fn store<T: Transaction, P: LoadableMerkleParameters>(
         &self,
         storage: &Ledger<T, P>,
     ) -> Result<(), ServerError> {
-        Ok(storage.store_to_peer_book(bincode::serialize(&self.get_connected())?)?)
+        let stored_peers = StoredPeers {
+            connected: self.get_connected(),
+            banned: self.get_banned(),
+            banned_identities: self.get_banned_identities(),
+        };
+
+        Ok(storage.store_to_peer_book(bincode::serialize(&stored_peers)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Identity;
+    use snarkos_testing::network::random_socket_address;
+
+    #[test]
+    fn test_ban_peer() {
+        let mut peer_book = PeerBook::new();
+        let address = random_socket_address();
+
+        peer_book.update_connected(address, Utc::now());
+
+        assert!(!peer_book.misbehave(address, BAN_THRESHOLD - 1));
+        assert_eq!(BAN_THRESHOLD - 1, peer_book.ban_score(&address));
+        assert!(peer_book.connected_contains(&address));
+
+        assert!(peer_book.misbehave(address, 1));
+        assert!(peer_book.is_banned(&address));
+        assert!(!peer_book.connected_contains(&address));
+
+        assert!(!peer_book.update_gossiped(address, Utc::now()));
+        assert!(!peer_book.gossiped_contains(&address));
+    }
+
+    #[test]
+    fn test_ban_ip() {
+        let mut peer_book = PeerBook::new();
+        let address: SocketAddr = "10.0.0.1:4130".parse().unwrap();
+        let other_port: SocketAddr = "10.0.0.1:4131".parse().unwrap();
+        let other_ip: SocketAddr = "10.0.0.2:4130".parse().unwrap();
+
+        peer_book.update_connected(other_port, Utc::now());
+        peer_book.update_connected(other_ip, Utc::now());
+
+        // Reconnecting from another port does not reset the ban score
+        assert!(!peer_book.misbehave(address, BAN_THRESHOLD - 1));
+        assert_eq!(BAN_THRESHOLD - 1, peer_book.ban_score(&other_port));
+        assert_eq!(0, peer_book.ban_score(&other_ip));
+
+        // Every port of a banned IP address is banned and forgotten
+        assert!(peer_book.misbehave(other_port, 1));
+        assert!(peer_book.is_banned(&address));
+        assert!(peer_book.is_banned(&other_port));
+        assert!(!peer_book.connected_contains(&other_port));
+
+        assert!(!peer_book.is_banned(&other_ip));
+        assert!(peer_book.connected_contains(&other_ip));
+    }
+
+    #[test]
+    fn test_ban_expires() {
+        let mut peer_book = PeerBook::with_ban_duration(0);
+        let address = random_socket_address();
+
+        assert!(peer_book.misbehave(address, BAN_THRESHOLD));
+        assert!(!peer_book.is_banned(&address));
+
+        peer_book.remove_expired_bans();
+        assert!(peer_book.get_banned().is_empty());
+    }
+
+    #[test]
+    fn test_stored_peers() {
+        let mut peer_book = PeerBook::new();
+        let connected: SocketAddr = "10.0.0.1:4130".parse().unwrap();
+        let banned: SocketAddr = "10.0.0.2:4130".parse().unwrap();
+
+        peer_book.update_connected(connected, Utc::now());
+        peer_book.misbehave(banned, BAN_THRESHOLD);
+
+        let stored_peers = StoredPeers {
+            connected: peer_book.get_connected(),
+            banned: peer_book.get_banned(),
+            banned_identities: peer_book.get_banned_identities(),
+        };
+        let stored_peers = StoredPeers::deserialize(&bincode::serialize(&stored_peers).unwrap()).unwrap();
+
+        assert!(stored_peers.connected.contains_key(&connected));
+        assert!(stored_peers.banned.contains_key(&banned.ip()));
+
+        // Peer books stored before bans were persisted
+        let legacy = StoredPeers::deserialize(&bincode::serialize(&peer_book.get_connected()).unwrap()).unwrap();
+
+        assert!(legacy.connected.contains_key(&connected));
+        assert!(legacy.banned.is_empty());
+    }
+
+    #[test]
+    fn test_ban_identity() {
+        let mut peer_book = PeerBook::new();
+        let address = random_socket_address();
+        let new_address = random_socket_address();
+        let key = Identity::generate().unwrap().public_key();
+
+        peer_book.update_connected_peer(PeerId::Key(key), address, Utc::now());
+        assert_eq!(Some(key), peer_book.get_identity(&address));
+
+        // The same identity connecting from a new address replaces the old address
+        peer_book.update_connected_peer(PeerId::Key(key), new_address, Utc::now());
+        assert!(!peer_book.connected_contains(&address));
+        assert_eq!(Some(key), peer_book.get_identity(&new_address));
+        assert_eq!(1, peer_book.connected_total());
+
+        // A disconnected peer keeps its identity
+        peer_book.disconnect_peer(new_address);
+        assert!(peer_book.disconnected_contains(&new_address));
+        assert_eq!(Some(key), peer_book.get_identity(&new_address));
+
+        assert!(peer_book.misbehave(new_address, BAN_THRESHOLD));
+        assert!(peer_book.is_identity_banned(&key));
+        assert!(peer_book.get_banned_identities().contains_key(&key));
+        assert!(!peer_book.disconnected_contains(&new_address));
     }
 }
@@ -14,88 +14,131 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::message::{
-    read::{read_header, read_message},
-    Message,
-    MessageHeader,
-    MessageName,
+use crate::{
+    message::{
+        noise,
+        read::{read_header, read_message},
+        Identity,
+        Message,
+        MessageHeader,
+        MessageName,
+        NoiseSession,
+        NoiseTransport,
+        PeerStream,
+        PublicKey,
+        TcpTransport,
+        Transport,
+        MESSAGE_HEADER_SIZE,
+        NETWORK_MAGIC,
+        NOISE_MAGIC,
+    },
+    PeerId,
 };
-use snarkos_errors::network::ConnectError;
+use snarkos_errors::network::{message::MessageHeaderError, ConnectError, NoiseError};
 
 use std::{net::SocketAddr, sync::Arc};
-use tokio::{io::AsyncWriteExt, net::TcpStream, sync::Mutex};
+use tokio::{
+    io::{split, AsyncRead, AsyncWriteExt, ReadHalf, WriteHalf},
+    sync::Mutex,
+};
 
 /// A Channel for reading and writing messages to a peer.
-/// Storing two streams allows for simultaneous reading/writing.
-/// Each stream is protected by an Arc + Mutex to allow for channel cloning.
+/// Messages are read and written over a single peer stream, split into halves
+/// that allow for simultaneous reading/writing.
+/// Each half is protected by an Arc + Mutex to allow for channel cloning.
+///
+/// Streams opened with an identity are encrypted with a Noise handshake.
+/// The message header and payload are encrypted together, split into frames of at most 64 KiB.
 #[derive(Clone, Debug)]
 pub struct Channel {
     pub address: SocketAddr,
-    pub reader: Arc<Mutex<TcpStream>>,
-    pub writer: Arc<Mutex<TcpStream>>,
+    pub reader: Arc<Mutex<ReadHalf<Box<dyn PeerStream>>>>,
+    pub writer: Arc<Mutex<WriteHalf<Box<dyn PeerStream>>>>,
+    /// Encrypts and decrypts the messages on the stream if it is encrypted
+    pub transport: Option<Arc<Mutex<NoiseTransport>>>,
+    /// The public key the peer authenticated with on an encrypted stream
+    pub peer_key: Option<PublicKey>,
 }
 
 impl Channel {
-    pub async fn new(
+    /// Returns a new plaintext channel over the given stream.
+    pub fn new<S: PeerStream>(address: SocketAddr, stream: S) -> Self {
+        Self::from_session(address, Box::new(stream), None)
+    }
+
+    /// Returns a new channel connected to the given address over TCP.
+    /// If an identity is given, the stream is encrypted as the handshake initiator.
+    pub async fn connect(address: SocketAddr, identity: Option<&Identity>) -> Result<Self, ConnectError> {
+        Self::connect_with(&TcpTransport, address, identity).await
+    }
+
+    /// Returns a new channel connected to the given address over the given transport.
+    /// If an identity is given, the stream is encrypted as the handshake initiator.
+    pub async fn connect_with(
+        transport: &dyn Transport,
         address: SocketAddr,
-        reader: Arc<Mutex<TcpStream>>,
-        writer: Arc<Mutex<TcpStream>>,
+        identity: Option<&Identity>,
     ) -> Result<Self, ConnectError> {
-        Ok(Self {
-            address,
-            reader,
-            writer,
-        })
-    }
+        let mut stream = transport.connect(address).await?;
 
-    /// Returns a new channel with a writer only stream.
-    pub async fn new_write_only(address: SocketAddr) -> Result<Self, ConnectError> {
-        let stream = Arc::new(Mutex::new(TcpStream::connect(address).await?));
+        let session = match identity {
+            Some(identity) => Some(noise::initiate(&mut stream, identity).await?),
+            None => None,
+        };
 
-        Ok(Self {
-            address,
-            reader: stream.clone(),
-            writer: stream,
-        })
+        Ok(Self::from_session(address, stream, session))
     }
 
-    /// Returns a new channel with a reader only stream.
-    pub fn new_read_only(reader: TcpStream) -> Result<Self, ConnectError> {
-        let address = reader.peer_addr()?;
-        let stream = Arc::new(Mutex::new(reader));
-
-        Ok(Self {
-            address,
-            reader: stream.clone(),
-            writer: stream.clone(),
-        })
+    /// Returns a new channel over a stream accepted from a peer.
+    /// If an identity is given, the peer must start an encrypted handshake and the stream is encrypted as the responder.
+    /// Otherwise the peer must send plaintext messages.
+    pub async fn accept<S: PeerStream>(mut stream: S, identity: Option<&Identity>) -> Result<Self, ConnectError> {
+        let address = stream.peer_addr()?;
+
+        // A handshake begins with the `NOISE_MAGIC` and a plaintext message with the `NETWORK_MAGIC`.
+        let session = match (noise::peek_magic(&mut stream).await?, identity) {
+            (NOISE_MAGIC, Some(identity)) => Some(noise::respond(&mut stream, identity).await?),
+            (NOISE_MAGIC, None) => return Err(NoiseError::EncryptionDisabled.into()),
+            (NETWORK_MAGIC, Some(_)) => return Err(NoiseError::EncryptionRequired.into()),
+            (NETWORK_MAGIC, None) => None,
+            (magic, _) => return Err(MessageHeaderError::InvalidMagic(magic, NETWORK_MAGIC).into()),
+        };
+
+        Ok(Self::from_session(address, Box::new(stream), session))
     }
 
-    /// Returns a new channel with the specified address.
-    pub fn update_address(&self, address: SocketAddr) -> Self {
+    /// Splits the stream into its reader and writer halves, encrypted by the session if there is one.
+    fn from_session(address: SocketAddr, stream: Box<dyn PeerStream>, session: Option<NoiseSession>) -> Self {
+        let (reader, writer) = split(stream);
+
+        let (transport, peer_key) = match session {
+            Some(session) => (Some(Arc::new(Mutex::new(session.transport))), Some(session.remote_key)),
+            None => (None, None),
+        };
+
         Self {
             address,
-            reader: self.reader.clone(),
-            writer: self.writer.clone(),
+            reader: Arc::new(Mutex::new(reader)),
+            writer: Arc::new(Mutex::new(writer)),
+            transport,
+            peer_key,
         }
     }
 
-    /// Returns a new channel with the specified reader stream.
-    pub fn update_reader(&self, reader: Arc<Mutex<TcpStream>>) -> Self {
-        Self {
-            address: self.address,
-            reader,
-            writer: self.writer.clone(),
+    /// Returns the id of the peer, which is its public key on an encrypted stream.
+    pub fn peer_id(&self) -> PeerId {
+        match self.peer_key {
+            Some(peer_key) => PeerId::Key(peer_key),
+            None => PeerId::Address(self.address),
         }
     }
 
-    /// Returns a new channel with the specified address and new writer stream.
-    pub async fn update_writer(&self, address: SocketAddr) -> Result<Self, ConnectError> {
-        Ok(Self {
+    /// Returns a new channel with the specified address.
+    pub fn update_address(&self, address: SocketAddr) -> Self {
+        Self {
             address,
-            reader: self.reader.clone(),
-            writer: Arc::new(Mutex::new(TcpStream::connect(address).await?)),
-        })
+            ..self.clone()
+        }
     }
 
     /// Writes a message header + message.
@@ -103,17 +146,36 @@ impl Channel {
         debug!("Message {:?}, Sent to {:?}", M::name().to_string(), self.address);
 
         let serialized = message.serialize()?;
-        let header = MessageHeader::new(M::name(), serialized.len() as u32);
 
-        self.writer.lock().await.write_all(&header.serialize()?).await?;
-        self.writer.lock().await.write_all(&serialized).await?;
+        // Write the header and payload together so that each message is a single write to the stream
+        let mut bytes = MessageHeader::new(M::name(), &serialized).serialize()?;
+        bytes.extend_from_slice(&serialized);
+
+        // Hold the writer until the message is sent so that messages are written in nonce order
+        let mut writer = self.writer.lock().await;
+
+        if let Some(transport) = &self.transport {
+            bytes = transport.lock().await.encrypt(&bytes)?;
+        }
+
+        writer.write_all(&bytes).await?;
 
         Ok(())
     }
 
     /// Reads a message header + message.
+    /// The message is rejected if it does not match the checksum in the header.
     pub async fn read(&self) -> Result<(MessageName, Vec<u8>), ConnectError> {
-        let header = read_header(&mut *self.reader.lock().await).await?;
+        let mut reader = self.reader.lock().await;
+
+        let (header, bytes) = match &self.transport {
+            Some(transport) => read_encrypted(&mut *reader, transport).await?,
+            None => {
+                let header = read_header(&mut *reader).await?;
+                let bytes = read_message(&mut *reader, header.len as usize).await?;
+                (header, bytes)
+            }
+        };
 
         debug!(
             "Message {:?}, Received from {:?}",
@@ -121,11 +183,42 @@ impl Channel {
             self.address
         );
 
-        Ok((
-            header.name,
-            read_message(&mut *self.reader.lock().await, header.len as usize).await?,
-        ))
+        header.verify_checksum(&bytes)?;
+
+        Ok((header.name, bytes))
+    }
+}
+
+/// Reads and decrypts the frames of a message header + message.
+/// The header is validated before the frames of the payload are read.
+async fn read_encrypted<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    transport: &Mutex<NoiseTransport>,
+) -> Result<(MessageHeader, Vec<u8>), ConnectError> {
+    let mut bytes = vec![];
+    while bytes.len() < MESSAGE_HEADER_SIZE {
+        let frame = noise::read_frame(reader).await?;
+        bytes.extend(transport.lock().await.decrypt(&frame)?);
+    }
+
+    let mut header_bytes = [0u8; MESSAGE_HEADER_SIZE];
+    header_bytes.copy_from_slice(&bytes[..MESSAGE_HEADER_SIZE]);
+
+    let header = MessageHeader::from(header_bytes);
+    header.validate()?;
+
+    let mut payload = bytes.split_off(MESSAGE_HEADER_SIZE);
+    while payload.len() < header.len as usize {
+        let frame = noise::read_frame(reader).await?;
+        payload.extend(transport.lock().await.decrypt(&frame)?);
+    }
+
+    // A message ends with its last frame
+    if payload.len() != header.len as usize {
+        return Err(NoiseError::InvalidFrame(payload.len(), header.len).into());
     }
+
+    Ok((header, payload))
 }
 
 #[cfg(test)]
@@ -148,7 +241,7 @@ mod tests {
 
         // 2. Server connect to peer
 
-        let server_channel = Channel::new_write_only(peer_address).await.unwrap();
+        let server_channel = Channel::connect(peer_address, None).await.unwrap();
 
         // 3. Server write message to peer
 
@@ -164,7 +257,7 @@ mod tests {
         tokio::spawn(async move {
             // 1. Server connects to peer
 
-            let server_channel = Channel::new_write_only(peer_address).await.unwrap();
+            let server_channel = Channel::connect(peer_address, None).await.unwrap();
 
             // 2. Server writes ping message
 
@@ -173,8 +266,8 @@ mod tests {
 
         // 2. Peer accepts server connection
 
-        let (reader, _address) = peer_listener.accept().await.unwrap();
-        let peer_channel = Channel::new_read_only(reader).unwrap();
+        let (stream, _address) = peer_listener.accept().await.unwrap();
+        let peer_channel = Channel::accept(stream, None).await.unwrap();
 
         // 4. Peer reads ping message
 
@@ -186,31 +279,109 @@ mod tests {
 
     #[tokio::test]
     #[serial]
-    async fn test_channel_update() {
-        let server_address = random_socket_address();
+    async fn test_encrypted_read() {
         let peer_address = random_socket_address();
+        let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
+
+        let server_identity = Identity::generate().unwrap();
+        let peer_identity = Identity::generate().unwrap();
+        let server_key = server_identity.public_key();
+
+        tokio::spawn(async move {
+            // 1. Server connects to peer with an encrypted handshake
+
+            let server_channel = Channel::connect(peer_address, Some(&server_identity)).await.unwrap();
+
+            // 2. Server writes ping message
+
+            server_channel.write(&Ping::new()).await.unwrap();
+        });
+
+        // 3. Peer accepts server connection and completes the handshake
+
+        let (stream, _address) = peer_listener.accept().await.unwrap();
+        let peer_channel = Channel::accept(stream, Some(&peer_identity)).await.unwrap();
+
+        assert_eq!(Some(server_key), peer_channel.peer_key);
 
+        // 4. Peer reads and decrypts ping message
+
+        let (name, bytes) = peer_channel.read().await.unwrap();
+
+        assert_eq!(Ping::name(), name);
+        assert!(Ping::deserialize(bytes).is_ok());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_encryption_disabled() {
+        let peer_address = random_socket_address();
         let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
 
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let (ty, ry) = tokio::sync::oneshot::channel();
 
         tokio::spawn(async move {
-            let mut server_listener = TcpListener::bind(server_address).await.unwrap();
+            // 1. Server connects to peer with an encrypted handshake
+
+            let result = Channel::connect(peer_address, Some(&Identity::generate().unwrap())).await;
+
+            // 3. Server learns that the peer rejected the handshake
 
+            match result {
+                Err(ConnectError::NoiseError(NoiseError::HandshakeRejected)) => {}
+                result => panic!("expected a rejected handshake, got {:?}", result.map(|_| ())),
+            }
             tx.send(()).unwrap();
+        });
 
-            // 1. Server connects to peer
+        // 2. Peer without an identity refuses the encrypted handshake
 
-            let mut channel = Channel::new_write_only(peer_address).await.unwrap();
+        let (stream, _address) = peer_listener.accept().await.unwrap();
+        match Channel::accept(stream, None).await {
+            Err(ConnectError::NoiseError(NoiseError::EncryptionDisabled)) => {}
+            result => panic!("expected encryption to be disabled, got {:?}", result.map(|_| ())),
+        }
 
-            // 4. Server accepts peer connection
+        rx.await.unwrap();
+    }
 
-            let (reader, _socket) = server_listener.accept().await.unwrap();
+    #[tokio::test]
+    #[serial]
+    async fn test_encryption_required() {
+        let peer_address = random_socket_address();
+        let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
 
-            channel = channel.update_reader(Arc::new(Mutex::new(reader)));
+        tokio::spawn(async move {
+            // 1. Server connects to peer without encryption and writes ping message
 
-            // 5. Server writes ping
+            let server_channel = Channel::connect(peer_address, None).await.unwrap();
+            server_channel.write(&Ping::new()).await.unwrap();
+        });
+
+        // 2. Peer with an identity refuses the plaintext message
+
+        let (stream, _address) = peer_listener.accept().await.unwrap();
+        match Channel::accept(stream, Some(&Identity::generate().unwrap())).await {
+            Err(ConnectError::NoiseError(NoiseError::EncryptionRequired)) => {}
+            result => panic!("expected encryption to be required, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_duplex() {
+        let peer_address = random_socket_address();
+
+        let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            // 1. Server connects to peer
+
+            let channel = Channel::connect(peer_address, None).await.unwrap();
+
+            // 3. Server writes ping
 
             let server_ping = Ping {
                 nonce: 18446744073709551615u64,
@@ -218,27 +389,22 @@ mod tests {
 
             channel.write(&server_ping).await.unwrap();
 
-            // 6. Server reads pong
+            // 6. Server reads pong from the same connection
 
             let (name, bytes) = channel.read().await.unwrap();
             let peer_pong = Pong::deserialize(bytes).unwrap();
 
             assert_eq!(Pong::name(), name);
             assert_eq!(Pong::new(server_ping), peer_pong);
-            ty.send(()).unwrap();
+            tx.send(()).unwrap();
         });
-        rx.await.unwrap();
 
         // 2. Peer accepts server connection
 
-        let (reader, _address) = peer_listener.accept().await.unwrap();
-        let mut channel = Channel::new_read_only(reader).unwrap();
-
-        // 3. Peer connects to server
-
-        channel = channel.update_writer(server_address).await.unwrap();
+        let (stream, _address) = peer_listener.accept().await.unwrap();
+        let channel = Channel::accept(stream, None).await.unwrap();
 
-        // 6. Peer reads ping message
+        // 4. Peer reads ping message
 
         let (name, bytes) = channel.read().await.unwrap();
         let server_ping = Ping::deserialize(bytes).unwrap();
@@ -251,10 +417,10 @@ mod tests {
             server_ping
         );
 
-        // 7. Peer writes pong message
+        // 5. Peer writes pong message without dialing back
 
         channel.write(&Pong::new(server_ping)).await.unwrap();
 
-        ry.await.unwrap();
+        rx.await.unwrap();
     }
 }
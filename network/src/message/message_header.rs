@@ -15,57 +15,135 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::message::MessageName;
+use snarkos_algorithms::crh::double_sha256;
+use snarkos_consensus::MAX_BLOCK_SIZE;
 use snarkos_errors::network::message::MessageHeaderError;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Cursor;
 
+/// The magic bytes that begin every message sent on the snarkOS network.
+pub const NETWORK_MAGIC: u32 = 0x736E_726B;
+
+/// The size of a serialized message header in bytes.
+pub const MESSAGE_HEADER_SIZE: usize = 24;
+
+/// The number of bytes a message carrying a single block or transaction adds to the serialized data.
+pub const BLOCK_MESSAGE_OVERHEAD: u32 = 8;
+
+/// The maximum payload size of a message carrying a single block or transaction.
+/// Any block within the consensus `MAX_BLOCK_SIZE` fits in a single message.
+pub const MAX_BLOCK_MESSAGE_SIZE: u32 = MAX_BLOCK_SIZE as u32 + BLOCK_MESSAGE_OVERHEAD;
+
+/// The maximum payload size of a message carrying a batch of headers, hashes, transactions, or peers.
+pub const MAX_BATCH_MESSAGE_SIZE: u32 = 4 * 1024 * 1024;
+
+/// The maximum payload size of any other message.
+pub const MAX_CONTROL_MESSAGE_SIZE: u32 = 64 * 1024;
+
+/// Returns the maximum payload size in bytes for a message name.
+pub fn max_message_size(name: &MessageName) -> u32 {
+    if *name == "block" || *name == "syncblock" || *name == "transaction" {
+        MAX_BLOCK_MESSAGE_SIZE
+    } else if *name == "headers" || *name == "memorypool" || *name == "sync" || *name == "peers" {
+        MAX_BATCH_MESSAGE_SIZE
+    } else {
+        MAX_CONTROL_MESSAGE_SIZE
+    }
+}
+
+/// Returns the first four bytes of the double SHA-256 hash of a message payload.
+pub fn checksum(payload: &[u8]) -> u32 {
+    let mut rdr = Cursor::new(double_sha256(payload));
+    rdr.read_u32::<BigEndian>().expect("unable to read u32")
+}
+
 /// A fixed size message corresponding to a variable sized message.
 #[derive(Debug, PartialEq, Eq)]
 pub struct MessageHeader {
+    pub magic: u32,
     pub name: MessageName,
     pub len: u32,
+    pub checksum: u32,
 }
 
 impl MessageHeader {
-    pub fn new(name: MessageName, len: u32) -> Self {
-        MessageHeader { name, len }
+    /// Returns the header for a message payload.
+    pub fn new(name: MessageName, payload: &[u8]) -> Self {
+        MessageHeader {
+            magic: NETWORK_MAGIC,
+            name,
+            len: payload.len() as u32,
+            checksum: checksum(payload),
+        }
+    }
+
+    /// Checks the network magic and the payload size limit of the message name.
+    /// Called before the payload is read so oversized messages are never allocated.
+    pub fn validate(&self) -> Result<(), MessageHeaderError> {
+        if self.magic != NETWORK_MAGIC {
+            return Err(MessageHeaderError::InvalidMagic(self.magic, NETWORK_MAGIC));
+        }
+
+        let max_size = max_message_size(&self.name);
+        if self.len > max_size {
+            return Err(MessageHeaderError::MessageTooLarge(
+                self.name.to_string(),
+                self.len,
+                max_size,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the payload against the checksum in the header.
+    pub fn verify_checksum(&self, payload: &[u8]) -> Result<(), MessageHeaderError> {
+        let expected = checksum(payload);
+        if self.checksum != expected {
+            return Err(MessageHeaderError::InvalidChecksum(self.checksum, expected));
+        }
+
+        Ok(())
     }
 
     pub fn serialize(&self) -> Result<Vec<u8>, MessageHeaderError> {
         let mut result = vec![];
+        result.write_u32::<BigEndian>(self.magic)?;
         result.extend_from_slice(&self.name.as_bytes());
-
-        let mut wtr = vec![];
-        wtr.write_u32::<BigEndian>(self.len)?;
-
-        result.extend_from_slice(&wtr);
+        result.write_u32::<BigEndian>(self.len)?;
+        result.write_u32::<BigEndian>(self.checksum)?;
 
         Ok(result)
     }
 
     pub fn deserialize(vec: Vec<u8>) -> Result<Self, MessageHeaderError> {
-        if vec.len() != 16 {
+        if vec.len() != MESSAGE_HEADER_SIZE {
             return Err(MessageHeaderError::InvalidLength(vec.len()));
         }
 
-        let mut bytes = [0u8; 16];
+        let mut bytes = [0u8; MESSAGE_HEADER_SIZE];
         bytes.copy_from_slice(&vec[..]);
 
         Ok(MessageHeader::from(bytes))
     }
 }
 
-impl From<[u8; 16]> for MessageHeader {
-    fn from(bytes: [u8; 16]) -> Self {
+impl From<[u8; MESSAGE_HEADER_SIZE]> for MessageHeader {
+    fn from(bytes: [u8; MESSAGE_HEADER_SIZE]) -> Self {
         let mut name_bytes = [0u8; 12];
-        name_bytes.copy_from_slice(&bytes[..12]);
+        name_bytes.copy_from_slice(&bytes[4..16]);
 
-        let mut rdr = Cursor::new(bytes[12..].to_vec());
+        let mut rdr = Cursor::new(bytes.to_vec());
+        let magic = rdr.read_u32::<BigEndian>().expect("unable to read u32");
+
+        rdr.set_position(16);
 
         Self {
+            magic,
             name: MessageName::from(name_bytes),
             len: rdr.read_u32::<BigEndian>().expect("unable to read u32"),
+            checksum: rdr.read_u32::<BigEndian>().expect("unable to read u32"),
         }
     }
 }
@@ -73,42 +151,95 @@ impl From<[u8; 16]> for MessageHeader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{message::Message, message_types::Block};
+    use snarkos_consensus::ConsensusProfile;
+    use snarkos_objects::Network;
+
+    const PING_HEADER: [u8; MESSAGE_HEADER_SIZE] = [
+        115, 110, 114, 107, 112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 1,
+    ];
 
     #[test]
     fn serialize_header() {
         let header = MessageHeader {
+            magic: NETWORK_MAGIC,
             name: MessageName::from("ping"),
             len: 4u32,
+            checksum: 1u32,
         };
 
-        assert_eq!(header.serialize().unwrap(), vec![
-            112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
-        ]);
+        assert_eq!(header.serialize().unwrap(), PING_HEADER.to_vec());
     }
 
     #[test]
     fn deserialize_header() {
         let header = MessageHeader {
+            magic: NETWORK_MAGIC,
             name: MessageName::from("ping"),
             len: 4u32,
+            checksum: 1u32,
         };
 
-        assert_eq!(
-            MessageHeader::deserialize(vec![112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]).unwrap(),
-            header
-        )
+        assert_eq!(MessageHeader::deserialize(PING_HEADER.to_vec()).unwrap(), header)
     }
 
     #[test]
     fn header_from_bytes() {
         let header = MessageHeader {
+            magic: NETWORK_MAGIC,
             name: MessageName::from("ping"),
             len: 4u32,
+            checksum: 1u32,
         };
 
-        assert_eq!(
-            header,
-            MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4])
-        );
+        assert_eq!(header, MessageHeader::from(PING_HEADER));
+    }
+
+    #[test]
+    fn validate_header() {
+        let payload = [1u8; 8];
+        let header = MessageHeader::new(MessageName::from("ping"), &payload);
+
+        assert!(header.validate().is_ok());
+        assert!(header.verify_checksum(&payload).is_ok());
+
+        match header.verify_checksum(&[0u8; 8]) {
+            Err(MessageHeaderError::InvalidChecksum(_, _)) => {}
+            result => panic!("expected an invalid checksum, got {:?}", result),
+        }
+
+        let wrong_magic = MessageHeader { magic: 0, ..header };
+        match wrong_magic.validate() {
+            Err(MessageHeaderError::InvalidMagic(0, NETWORK_MAGIC)) => {}
+            result => panic!("expected an invalid magic, got {:?}", result),
+        }
+
+        let too_large = MessageHeader::from({
+            let mut bytes = PING_HEADER;
+            bytes[16..20].copy_from_slice(&(MAX_CONTROL_MESSAGE_SIZE + 1).to_be_bytes());
+            bytes
+        });
+        match too_large.validate() {
+            Err(MessageHeaderError::MessageTooLarge(_, _, MAX_CONTROL_MESSAGE_SIZE)) => {}
+            result => panic!("expected a message that is too large, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn max_block_message_size() {
+        for network in &[Network::Mainnet, Network::Testnet1, Network::Custom(2)] {
+            let max_block_size = ConsensusProfile::from_network(*network).max_block_size;
+
+            // A block of the maximum size fits in a single message
+            let payload = Block::new(vec![0u8; max_block_size]).serialize().unwrap();
+            assert_eq!(max_block_size + BLOCK_MESSAGE_OVERHEAD as usize, payload.len());
+            assert!(MessageHeader::new(Block::name(), &payload).validate().is_ok());
+        }
+
+        let payload = Block::new(vec![0u8; MAX_BLOCK_SIZE + 1]).serialize().unwrap();
+        match MessageHeader::new(Block::name(), &payload).validate() {
+            Err(MessageHeaderError::MessageTooLarge(_, _, MAX_BLOCK_MESSAGE_SIZE)) => {}
+            result => panic!("expected a message that is too large, got {:?}", result),
+        }
     }
 }
@@ -29,5 +29,11 @@ pub use message_header::*;
 pub mod message_name;
 pub use message_name::*;
 
+pub mod noise;
+pub use noise::*;
+
 pub mod read;
 pub use read::*;
+
+pub mod transport;
+pub use transport::*;
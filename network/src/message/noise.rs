@@ -0,0 +1,499 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::message::PeerStream;
+use snarkos_errors::network::NoiseError;
+
+use serde::{Deserialize, Serialize};
+use snow::{params::NoiseParams, Builder, HandshakeState, TransportState};
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::delay_for,
+};
+
+/// The magic bytes that begin an encrypted handshake, in place of the `NETWORK_MAGIC` of a plaintext message.
+pub const NOISE_MAGIC: u32 = 0x6E6F_6973;
+
+/// The interval between peeks at a stream whose magic has only partially arrived.
+const PEEK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The Noise protocol that encrypts peer connections.
+pub const NOISE_PROTOCOL: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// The size of a serialized public or private key in bytes.
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// The size of the authentication tag appended to every encrypted frame.
+pub const TAG_SIZE: usize = 16;
+
+/// The maximum size of a Noise message in bytes, including its authentication tag.
+pub const MAX_NOISE_MESSAGE_SIZE: usize = 65535;
+
+/// The maximum number of plaintext bytes carried by a single encrypted frame.
+pub const MAX_FRAME_PAYLOAD_SIZE: usize = MAX_NOISE_MESSAGE_SIZE - TAG_SIZE;
+
+/// Returns the parameters of the `NOISE_PROTOCOL`.
+fn noise_params() -> NoiseParams {
+    NOISE_PROTOCOL.parse().expect("invalid noise protocol name")
+}
+
+/// Converts an error of the Noise implementation.
+fn noise_error(error: snow::Error) -> NoiseError {
+    match error {
+        snow::Error::Decrypt => NoiseError::DecryptionFailed,
+        error => NoiseError::Crate("snow", format!("{:?}", error)),
+    }
+}
+
+/// The X25519 public key of a node.
+/// A node is identified on the network by the public key of its static identity.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PublicKey(pub [u8; PUBLIC_KEY_SIZE]);
+
+impl PublicKey {
+    /// Returns the public key of the given bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, NoiseError> {
+        if bytes.len() != PUBLIC_KEY_SIZE {
+            return Err(NoiseError::InvalidPublicKey(hex::encode(bytes)));
+        }
+
+        let mut key = [0u8; PUBLIC_KEY_SIZE];
+        key.copy_from_slice(bytes);
+        Ok(Self(key))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
+
+/// The static X25519 key pair of a node.
+/// The identity persists across restarts so peers can recognise the node by its public key.
+#[derive(Clone)]
+pub struct Identity {
+    private_key: [u8; PUBLIC_KEY_SIZE],
+    public_key: PublicKey,
+}
+
+impl Identity {
+    /// Generate a new random identity.
+    pub fn generate() -> Result<Self, NoiseError> {
+        let keypair = Builder::new(noise_params()).generate_keypair().map_err(noise_error)?;
+
+        let mut bytes = keypair.private;
+        bytes.extend_from_slice(&keypair.public);
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the identity of a private key followed by its public key.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, NoiseError> {
+        if bytes.len() != 2 * PUBLIC_KEY_SIZE {
+            return Err(NoiseError::InvalidLength(bytes.len(), 2 * PUBLIC_KEY_SIZE));
+        }
+
+        let mut private_key = [0u8; PUBLIC_KEY_SIZE];
+        private_key.copy_from_slice(&bytes[..PUBLIC_KEY_SIZE]);
+
+        Ok(Self {
+            private_key,
+            public_key: PublicKey::from_slice(&bytes[PUBLIC_KEY_SIZE..])?,
+        })
+    }
+
+    /// Returns the private key followed by the public key.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.private_key.to_vec();
+        bytes.extend_from_slice(&self.public_key.0);
+        bytes
+    }
+
+    /// Returns the identity stored in the key file at the given path, or generates and stores a new one.
+    /// The key file is only readable and writable by its owner.
+    pub fn load_or_generate(path: &Path) -> Result<Self, NoiseError> {
+        if path.exists() {
+            restrict_permissions(path)?;
+            return Self::from_bytes(&fs::read(path)?);
+        }
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let identity = Self::generate()?;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path)?.write_all(&identity.to_bytes())?;
+
+        Ok(identity)
+    }
+
+    /// Returns the public key of the identity.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Identity({})", self.public_key)
+    }
+}
+
+/// Removes the access of other users to a key file.
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    if permissions.mode() & 0o077 != 0 {
+        warn!("Restricting the permissions of the key file {:?} to its owner", path);
+
+        permissions.set_mode(0o600);
+        fs::set_permissions(path, permissions)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Encrypts and decrypts the frames of an established session.
+/// Each frame is a Noise message prefixed by its length as a big endian `u16`.
+pub struct NoiseTransport {
+    state: TransportState,
+}
+
+impl NoiseTransport {
+    /// Encrypt the bytes into as many frames as needed.
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, NoiseError> {
+        let frame_count = plaintext.len() / MAX_FRAME_PAYLOAD_SIZE + 1;
+        let mut frames = Vec::with_capacity(plaintext.len() + frame_count * (2 + TAG_SIZE));
+        let mut buffer = vec![0u8; MAX_NOISE_MESSAGE_SIZE];
+
+        for chunk in plaintext.chunks(MAX_FRAME_PAYLOAD_SIZE) {
+            let length = self.state.write_message(chunk, &mut buffer).map_err(noise_error)?;
+
+            frames.extend_from_slice(&(length as u16).to_be_bytes());
+            frames.extend_from_slice(&buffer[..length]);
+        }
+
+        Ok(frames)
+    }
+
+    /// Decrypt a frame read from the stream.
+    pub fn decrypt(&mut self, frame: &[u8]) -> Result<Vec<u8>, NoiseError> {
+        let mut plaintext = vec![0u8; frame.len()];
+        let length = self.state.read_message(frame, &mut plaintext).map_err(noise_error)?;
+
+        plaintext.truncate(length);
+        Ok(plaintext)
+    }
+}
+
+impl fmt::Debug for NoiseTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NoiseTransport")
+    }
+}
+
+/// The encrypted transport established by a handshake.
+#[derive(Debug)]
+pub struct NoiseSession {
+    /// Encrypts the messages we send and decrypts the messages we receive
+    pub transport: NoiseTransport,
+    /// The static public key of the peer
+    pub remote_key: PublicKey,
+}
+
+/// Returns the length-prefixed frame of a Noise message.
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = (message.len() as u16).to_be_bytes().to_vec();
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Reads a length-prefixed Noise message from the stream.
+pub async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length).await?;
+
+    let mut message = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message).await?;
+
+    Ok(message)
+}
+
+/// Writes the next handshake message, which carries no payload.
+fn write_handshake_message(handshake: &mut HandshakeState) -> Result<Vec<u8>, NoiseError> {
+    let mut message = vec![0u8; MAX_NOISE_MESSAGE_SIZE];
+    let length = handshake.write_message(&[], &mut message).map_err(noise_error)?;
+
+    message.truncate(length);
+    Ok(message)
+}
+
+/// Reads the next handshake message.
+fn read_handshake_message(handshake: &mut HandshakeState, message: &[u8]) -> Result<(), NoiseError> {
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE_SIZE];
+    handshake.read_message(message, &mut payload).map_err(noise_error)?;
+
+    Ok(())
+}
+
+/// Returns the transport session of a completed handshake.
+fn into_session(handshake: HandshakeState) -> Result<NoiseSession, NoiseError> {
+    let remote_key = match handshake.get_remote_static() {
+        Some(remote_key) => PublicKey::from_slice(remote_key)?,
+        None => return Err(NoiseError::Message("incomplete handshake".into())),
+    };
+
+    Ok(NoiseSession {
+        transport: NoiseTransport {
+            state: handshake.into_transport_mode().map_err(noise_error)?,
+        },
+        remote_key,
+    })
+}
+
+/// Performs the initiator side of a `NOISE_PROTOCOL` handshake on a newly connected stream.
+pub async fn initiate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    identity: &Identity,
+) -> Result<NoiseSession, NoiseError> {
+    let mut handshake = Builder::new(noise_params())
+        .local_private_key(&identity.private_key)
+        .build_initiator()
+        .map_err(noise_error)?;
+
+    // -> e
+    let mut message_1 = NOISE_MAGIC.to_be_bytes().to_vec();
+    message_1.extend(frame(&write_handshake_message(&mut handshake)?));
+    stream.write_all(&message_1).await?;
+
+    // <- e, ee, s, es
+    let message_2 = match read_frame(stream).await {
+        Ok(message_2) => message_2,
+        // A peer that does not accept encrypted connections closes the stream
+        Err(error) => match error.kind() {
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset => return Err(NoiseError::HandshakeRejected),
+            _ => return Err(error.into()),
+        },
+    };
+    read_handshake_message(&mut handshake, &message_2)?;
+
+    // -> s, se
+    stream
+        .write_all(&frame(&write_handshake_message(&mut handshake)?))
+        .await?;
+
+    into_session(handshake)
+}
+
+/// Performs the responder side of a `NOISE_PROTOCOL` handshake on an accepted stream.
+pub async fn respond<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    identity: &Identity,
+) -> Result<NoiseSession, NoiseError> {
+    let mut handshake = Builder::new(noise_params())
+        .local_private_key(&identity.private_key)
+        .build_responder()
+        .map_err(noise_error)?;
+
+    let mut magic = [0u8; 4];
+    stream.read_exact(&mut magic).await?;
+    if u32::from_be_bytes(magic) != NOISE_MAGIC {
+        return Err(NoiseError::Message("expected a noise handshake".into()));
+    }
+
+    // -> e
+    read_handshake_message(&mut handshake, &read_frame(stream).await?)?;
+
+    // <- e, ee, s, es
+    stream
+        .write_all(&frame(&write_handshake_message(&mut handshake)?))
+        .await?;
+
+    // -> s, se
+    read_handshake_message(&mut handshake, &read_frame(stream).await?)?;
+
+    into_session(handshake)
+}
+
+/// Returns the magic that begins an accepted stream, which negotiates encryption.
+/// The magic is peeked so that a plaintext message is left on the stream.
+/// A peek may return fewer bytes than were sent, so it is retried until the whole magic has arrived.
+/// Callers bound the wait for a peer that never sends the rest of the magic with a timeout.
+pub async fn peek_magic<S: PeerStream>(stream: &mut S) -> Result<u32, NoiseError> {
+    let mut magic = [0u8; 4];
+
+    loop {
+        match stream.peek(&mut magic).await? {
+            length if length == magic.len() => return Ok(u32::from_be_bytes(magic)),
+            0 => {
+                return Err(NoiseError::Message(format!(
+                    "expected {} bytes negotiating encryption, found the end of the stream",
+                    magic.len()
+                )));
+            }
+            _ => delay_for(PEEK_RETRY_INTERVAL).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn handshake(initiator: &Identity, responder: &Identity) -> (NoiseSession, NoiseSession) {
+        let mut initiator_handshake = Builder::new(noise_params())
+            .local_private_key(&initiator.private_key)
+            .build_initiator()
+            .unwrap();
+        let mut responder_handshake = Builder::new(noise_params())
+            .local_private_key(&responder.private_key)
+            .build_responder()
+            .unwrap();
+
+        let message_1 = write_handshake_message(&mut initiator_handshake).unwrap();
+        read_handshake_message(&mut responder_handshake, &message_1).unwrap();
+        let message_2 = write_handshake_message(&mut responder_handshake).unwrap();
+        read_handshake_message(&mut initiator_handshake, &message_2).unwrap();
+        let message_3 = write_handshake_message(&mut initiator_handshake).unwrap();
+        read_handshake_message(&mut responder_handshake, &message_3).unwrap();
+
+        (
+            into_session(initiator_handshake).unwrap(),
+            into_session(responder_handshake).unwrap(),
+        )
+    }
+
+    /// Splits encrypted bytes into their frames.
+    fn frames(mut bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = vec![];
+        while !bytes.is_empty() {
+            let length = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+            frames.push(bytes[2..2 + length].to_vec());
+            bytes = &bytes[2 + length..];
+        }
+        frames
+    }
+
+    #[test]
+    fn test_handshake() {
+        let initiator = Identity::generate().unwrap();
+        let responder = Identity::generate().unwrap();
+
+        let (mut initiator_session, mut responder_session) = handshake(&initiator, &responder);
+
+        assert_eq!(responder.public_key(), initiator_session.remote_key);
+        assert_eq!(initiator.public_key(), responder_session.remote_key);
+
+        let message = b"ping".to_vec();
+        let ciphertext = initiator_session.transport.encrypt(&message).unwrap();
+        assert_eq!(2 + message.len() + TAG_SIZE, ciphertext.len());
+        assert_eq!(message, responder_session.transport.decrypt(&ciphertext[2..]).unwrap());
+
+        let ciphertext = responder_session.transport.encrypt(&message).unwrap();
+        assert_eq!(message, initiator_session.transport.decrypt(&ciphertext[2..]).unwrap());
+    }
+
+    #[test]
+    fn test_large_message() {
+        let (mut initiator_session, mut responder_session) =
+            handshake(&Identity::generate().unwrap(), &Identity::generate().unwrap());
+
+        let message: Vec<u8> = (0..3 * MAX_FRAME_PAYLOAD_SIZE + 1).map(|i| i as u8).collect();
+        let frames = frames(&initiator_session.transport.encrypt(&message).unwrap());
+        assert_eq!(4, frames.len());
+
+        let mut plaintext = vec![];
+        for frame in frames {
+            plaintext.extend(responder_session.transport.decrypt(&frame).unwrap());
+        }
+        assert_eq!(message, plaintext);
+    }
+
+    #[test]
+    fn test_tampered_message() {
+        let (mut initiator_session, mut responder_session) =
+            handshake(&Identity::generate().unwrap(), &Identity::generate().unwrap());
+
+        let mut ciphertext = initiator_session.transport.encrypt(b"ping").unwrap();
+        ciphertext[2] ^= 1;
+
+        match responder_session.transport.decrypt(&ciphertext[2..]) {
+            Err(NoiseError::DecryptionFailed) => {}
+            result => panic!("expected a decryption failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_identity_file() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("snarkos_identity_{}.key", rand::random::<u64>()));
+
+        let identity = Identity::load_or_generate(&path).unwrap();
+        let loaded = Identity::load_or_generate(&path).unwrap();
+        assert_eq!(identity.public_key(), loaded.public_key());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_peek_split_magic() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            let magic = NOISE_MAGIC.to_be_bytes();
+
+            // The magic arrives in two parts
+            stream.write_all(&magic[..2]).await.unwrap();
+            delay_for(Duration::from_millis(100)).await;
+            stream.write_all(&magic[2..]).await.unwrap();
+        });
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        assert_eq!(peek_magic(&mut stream).await.unwrap(), NOISE_MAGIC);
+    }
+}
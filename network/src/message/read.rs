@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::message::MessageHeader;
+use crate::message::{MessageHeader, MESSAGE_HEADER_SIZE};
 use snarkos_errors::network::message::{MessageError, MessageHeaderError, StreamReadError};
 
 use tokio::{io::AsyncRead, prelude::*};
@@ -29,12 +29,16 @@ pub async fn read_message<T: AsyncRead + Unpin>(mut stream: &mut T, len: usize)
 }
 
 /// Returns a message header read from an input stream.
+/// Headers with the wrong network magic or an oversized payload length are rejected.
 pub async fn read_header<T: AsyncRead + Unpin>(mut stream: &mut T) -> Result<MessageHeader, MessageHeaderError> {
-    let mut buffer = [0u8; 16];
+    let mut buffer = [0u8; MESSAGE_HEADER_SIZE];
 
     stream_read(&mut stream, &mut buffer).await?;
 
-    Ok(MessageHeader::from(buffer))
+    let header = MessageHeader::from(buffer);
+    header.validate()?;
+
+    Ok(header)
 }
 
 /// Reads bytes from an input stream to fill the buffer.
@@ -50,7 +54,7 @@ pub async fn stream_read<'a, T: AsyncRead + Unpin>(
 mod tests {
     use super::*;
     use crate::{
-        message::{message::Message, MessageHeader},
+        message::{message::Message, MessageHeader, MessageName},
         message_types::Ping,
     };
     use snarkos_testing::network::random_socket_address;
@@ -65,27 +69,27 @@ mod tests {
         let mut listener = TcpListener::bind(address).await.unwrap();
 
         tokio::spawn(async move {
-            let header = MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]);
+            let header = MessageHeader::new(MessageName::from("ping"), &[0u8; 4]);
             let mut stream = TcpStream::connect(address).await.unwrap();
             stream.write_all(&header.serialize().unwrap()).await.unwrap();
-            let header = MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8]);
+            let header = MessageHeader::new(MessageName::from("ping"), &[0u8; 8]);
             stream.write_all(&header.serialize().unwrap()).await.unwrap();
         });
 
         let (mut stream, _socket) = listener.accept().await.unwrap();
-        let mut buf = [0u8; 16];
+        let mut buf = [0u8; MESSAGE_HEADER_SIZE];
         stream_read(&mut stream, &mut buf).await.unwrap();
 
         assert_eq!(
-            MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]),
+            MessageHeader::new(MessageName::from("ping"), &[0u8; 4]),
             MessageHeader::from(buf)
         );
 
-        let mut buf = [0u8; 16];
+        let mut buf = [0u8; MESSAGE_HEADER_SIZE];
         stream_read(&mut stream, &mut buf).await.unwrap();
 
         assert_eq!(
-            MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8]),
+            MessageHeader::new(MessageName::from("ping"), &[0u8; 8]),
             MessageHeader::from(buf)
         );
     }
@@ -97,7 +101,7 @@ mod tests {
         let mut listener = TcpListener::bind(address).await.unwrap();
 
         tokio::spawn(async move {
-            let header = MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]);
+            let header = MessageHeader::new(MessageName::from("ping"), &[0u8; 4]);
             let mut stream = TcpStream::connect(address).await.unwrap();
             stream.write_all(&header.serialize().unwrap()).await.unwrap();
         });
@@ -106,10 +110,7 @@ mod tests {
 
         let header = read_header(&mut stream).await.unwrap();
 
-        assert_eq!(
-            MessageHeader::from([112, 105, 110, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]),
-            header
-        );
+        assert_eq!(MessageHeader::new(MessageName::from("ping"), &[0u8; 4]), header);
     }
 
     #[tokio::test]
@@ -132,4 +133,16 @@ mod tests {
 
         assert_eq!(message_copy, actual);
     }
+
+    #[tokio::test]
+    async fn test_read_header_too_large() {
+        let mut header = MessageHeader::new(MessageName::from("ping"), &[0u8; 4]);
+        header.len = u32::MAX;
+        let bytes = header.serialize().unwrap();
+
+        match read_header(&mut &bytes[..]).await {
+            Err(MessageHeaderError::MessageTooLarge(_, len, _)) => assert_eq!(u32::MAX, len),
+            result => panic!("expected a message that is too large, got {:?}", result),
+        }
+    }
 }
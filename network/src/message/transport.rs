@@ -0,0 +1,98 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fmt::Debug, future::Future, io, net::SocketAddr, pin::Pin};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+
+/// A future returned by a transport, boxed so that transports can be used as trait objects.
+pub type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// A reliable, ordered byte stream to a peer.
+pub trait PeerStream: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static {
+    /// Returns the address of the peer at the other end of the stream.
+    fn peer_addr(&self) -> io::Result<SocketAddr>;
+
+    /// Reads bytes into the buffer without removing them from the stream.
+    fn peek<'a>(&'a mut self, buffer: &'a mut [u8]) -> TransportFuture<'a, usize>;
+}
+
+/// Accepts streams opened by peers.
+pub trait Listener: Send {
+    /// Returns the next stream opened by a peer, along with the peer address.
+    fn accept(&mut self) -> TransportFuture<'_, (Box<dyn PeerStream>, SocketAddr)>;
+}
+
+/// Opens streams to peers and listens for streams opened by peers.
+/// Nodes use TCP unless another transport is set in their network context.
+pub trait Transport: Debug + Send + Sync {
+    /// Opens a stream to the peer at the given address.
+    fn connect(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn PeerStream>>;
+
+    /// Listens for streams opened by peers at the given address.
+    fn bind(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn Listener>>;
+}
+
+/// The default transport, which reaches peers over TCP.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TcpTransport;
+
+impl Transport for TcpTransport {
+    fn connect(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn PeerStream>> {
+        Box::pin(async move {
+            let stream = TcpStream::connect(address).await?;
+            Ok(Box::new(stream) as Box<dyn PeerStream>)
+        })
+    }
+
+    fn bind(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn Listener>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(address).await?;
+            Ok(Box::new(listener) as Box<dyn Listener>)
+        })
+    }
+}
+
+impl Listener for TcpListener {
+    fn accept(&mut self) -> TransportFuture<'_, (Box<dyn PeerStream>, SocketAddr)> {
+        Box::pin(async move {
+            let (stream, address) = TcpListener::accept(self).await?;
+            Ok((Box::new(stream) as Box<dyn PeerStream>, address))
+        })
+    }
+}
+
+impl PeerStream for TcpStream {
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        TcpStream::peer_addr(self)
+    }
+
+    fn peek<'a>(&'a mut self, buffer: &'a mut [u8]) -> TransportFuture<'a, usize> {
+        Box::pin(TcpStream::peek(self, buffer))
+    }
+}
+
+impl PeerStream for Box<dyn PeerStream> {
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        (**self).peer_addr()
+    }
+
+    fn peek<'a>(&'a mut self, buffer: &'a mut [u8]) -> TransportFuture<'a, usize> {
+        (**self).peek(buffer)
+    }
+}
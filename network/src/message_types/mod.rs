@@ -24,6 +24,11 @@ pub mod getblock;
 #[doc(inline)]
 pub use getblock::*;
 
+#[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_headers.md"))]
+pub mod getheaders;
+#[doc(inline)]
+pub use getheaders::*;
+
 #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_memory_pool.md"))]
 pub mod getmemorypool;
 #[doc(inline)]
@@ -39,6 +44,11 @@ pub mod getsync;
 #[doc(inline)]
 pub use getsync::*;
 
+#[cfg_attr(nightly, doc(include = "../../documentation/network_messages/headers.md"))]
+pub mod headers;
+#[doc(inline)]
+pub use headers::*;
+
 #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/memory_pool.md"))]
 pub mod memorypool;
 #[doc(inline)]
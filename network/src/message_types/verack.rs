@@ -71,12 +71,12 @@ impl Message for Verack {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::message_types::Version;
+    use crate::{message_types::Version, PROTOCOL_VERSION};
     use snarkos_testing::network::random_socket_address;
 
     #[test]
     fn test_verack() {
-        let version = Version::new(1u64, 1u32, random_socket_address(), random_socket_address());
+        let version = Version::new(PROTOCOL_VERSION, 1u32, random_socket_address(), random_socket_address());
 
         let message = Verack::new(version.nonce, version.address_sender, version.address_receiver);
 
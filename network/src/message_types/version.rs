@@ -14,19 +14,29 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::message::{Message, MessageName};
+use crate::{
+    local_time,
+    message::{Message, MessageName},
+    Services,
+    LOCAL_SERVICES,
+};
 use snarkos_errors::network::message::MessageError;
 
-use chrono::Utc;
 use rand::Rng;
 use std::net::SocketAddr;
 
+/// The first protocol version whose Version messages carry the services of the sender.
+const SERVICES_VERSION: u64 = 2;
+
 #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/version.md"))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Version {
     /// The network version number
     pub version: u64,
 
+    /// Services supported by the node sending this message
+    pub services: Services,
+
     /// Latest block number of node sending this message
     pub height: u32,
 
@@ -49,9 +59,10 @@ impl Version {
 
         Self {
             version,
+            services: LOCAL_SERVICES,
             height,
             nonce: rng.gen::<u64>(),
-            timestamp: Utc::now().timestamp(),
+            timestamp: local_time().timestamp(),
             address_receiver,
             address_sender,
         }
@@ -66,9 +77,10 @@ impl Version {
     ) -> Self {
         Self {
             version,
+            services: LOCAL_SERVICES,
             height,
             nonce,
-            timestamp: Utc::now().timestamp(),
+            timestamp: local_time().timestamp(),
             address_receiver,
             address_sender,
         }
@@ -81,23 +93,43 @@ impl Message for Version {
     }
 
     fn deserialize(vec: Vec<u8>) -> Result<Self, MessageError> {
-        if vec.len() != 48 {
-            return Err(MessageError::InvalidLength(vec.len(), 48));
+        if vec.len() < 8 {
+            return Err(MessageError::InvalidLength(vec.len(), 8));
         }
 
+        // The layout of the remaining fields depends on the version of the sender,
+        // so that outdated peers can still be rejected with their version.
+        let version: u64 = bincode::deserialize(&vec[..8])?;
+
+        let (services, fields) = if version < SERVICES_VERSION {
+            if vec.len() != 48 {
+                return Err(MessageError::InvalidLength(vec.len(), 48));
+            }
+
+            (Services::NONE, &vec[8..])
+        } else {
+            if vec.len() != 56 {
+                return Err(MessageError::InvalidLength(vec.len(), 56));
+            }
+
+            (bincode::deserialize(&vec[8..16])?, &vec[16..])
+        };
+
         Ok(Version {
-            version: bincode::deserialize(&vec[..8])?,
-            height: bincode::deserialize(&vec[8..12])?,
-            nonce: bincode::deserialize(&vec[12..20])?,
-            timestamp: bincode::deserialize(&vec[20..28])?,
-            address_receiver: bincode::deserialize(&vec[28..38])?,
-            address_sender: bincode::deserialize(&vec[38..48])?,
+            version,
+            services,
+            height: bincode::deserialize(&fields[..4])?,
+            nonce: bincode::deserialize(&fields[4..12])?,
+            timestamp: bincode::deserialize(&fields[12..20])?,
+            address_receiver: bincode::deserialize(&fields[20..30])?,
+            address_sender: bincode::deserialize(&fields[30..40])?,
         })
     }
 
     fn serialize(&self) -> Result<Vec<u8>, MessageError> {
         let mut writer = vec![];
         writer.extend_from_slice(&bincode::serialize(&self.version)?);
+        writer.extend_from_slice(&bincode::serialize(&self.services)?);
         writer.extend_from_slice(&bincode::serialize(&self.height)?);
         writer.extend_from_slice(&bincode::serialize(&self.nonce)?);
         writer.extend_from_slice(&bincode::serialize(&self.timestamp)?);
@@ -110,11 +142,12 @@ impl Message for Version {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::PROTOCOL_VERSION;
 
     #[test]
     fn test_version() {
         let version = Version::new(
-            1u64,
+            PROTOCOL_VERSION,
             1u32,
             "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
             "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
@@ -126,4 +159,37 @@ mod tests {
 
         assert_eq!(version, deserialized);
     }
+    #[test]
+    fn test_version_length() {
+        let mut version = Version::new(
+            PROTOCOL_VERSION,
+            1u32,
+            "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
+            "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
+        )
+        .serialize()
+        .unwrap();
+
+        // Version messages without services are not accepted
+        version.drain(8..16);
+
+        assert!(Version::deserialize(version).is_err());
+    }
+
+    #[test]
+    fn test_legacy_version() {
+        let mut version = Version::new(
+            SERVICES_VERSION - 1,
+            1u32,
+            "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
+            "127.0.0.1:4130".parse::<SocketAddr>().unwrap(),
+        );
+        version.services = Services::NONE;
+
+        // Version messages before the services field are read with their version, so the peer can be rejected
+        let mut serialized = version.serialize().unwrap();
+        serialized.drain(8..16);
+
+        assert_eq!(Version::deserialize(serialized).unwrap(), version);
+    }
 }
@@ -15,8 +15,10 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    message::Channel,
+    message::{Channel, Identity, Transport},
     message_types::{Verack, Version},
+    Services,
+    MIN_PROTOCOL_VERSION,
 };
 use snarkos_errors::network::HandshakeError;
 
@@ -30,12 +32,14 @@ pub enum HandshakeState {
 }
 
 /// Establishes a connection between this node and a peer to send messages.
-/// 1. The server sends a Version message to a peer.
-/// 2. The peer responds with a Verack message followed by a Version message.
+/// 1. The server connects to a peer and sends a Version message.
+/// 2. The peer responds with a Verack message followed by a Version message over the same connection.
 /// 3. The server verifies the Verack and adds the peer to its peer list.
 /// 4. The server sees the Version message and responds with a Verack.
 /// 5. The peer verifies the Verack and adds the server to its peer list.
 ///
+/// Version messages from peers older than `MIN_PROTOCOL_VERSION` are rejected.
+///
 /// Receiving a Version message means you should send a Verack message.
 /// If you receive a Verack message from a peer and accept it, then the handshake is complete.
 /// Peers with completed handshakes are added to your connections and your connected peer list.
@@ -46,18 +50,25 @@ pub struct Handshake {
     pub version: u64,
     pub height: u32,
     pub nonce: u64,
+    /// The protocol version of the peer, or 0 if the peer has not sent a Version message yet
+    pub peer_version: u64,
+    /// The services advertised by the peer
+    pub peer_services: Services,
 }
 
 impl Handshake {
-    /// Send the initial Version message to a peer
+    /// Send the initial Version message to a peer over the given transport
+    /// The channel is encrypted if an identity is given.
     pub async fn send_new(
         version: u64,
         height: u32,
         address_sender: SocketAddr,
         address_receiver: SocketAddr,
+        identity: Option<&Identity>,
+        transport: &dyn Transport,
     ) -> Result<Self, HandshakeError> {
-        // Create temporary write only channel
-        let channel = Arc::new(Channel::new_write_only(address_receiver).await?);
+        // Connect to the peer
+        let channel = Arc::new(Channel::connect_with(transport, address_receiver, identity).await?);
 
         // Write Version request
         let message = Version::new(version, height, address_receiver, address_sender);
@@ -70,11 +81,13 @@ impl Handshake {
             version,
             height,
             nonce: message.nonce,
+            peer_version: 0,
+            peer_services: Services::NONE,
         })
     }
 
     /// Receive the initial Version message from a new peer.
-    /// Send a Verack message + Version message
+    /// Send a Verack message + Version message back over the channel the peer connected with.
     pub async fn receive_new(
         version: u64,
         height: u32,
@@ -83,8 +96,10 @@ impl Handshake {
         local_address: SocketAddr,
         peer_address: SocketAddr,
     ) -> Result<Handshake, HandshakeError> {
-        // Connect to the address specified in the peer_message
-        let channel = channel.update_writer(peer_address).await?;
+        Self::check_version(&peer_message, peer_address)?;
+
+        // Identify the channel by the listening address specified in the peer_message
+        let channel = channel.update_address(peer_address);
 
         // Write Verack response
 
@@ -113,6 +128,8 @@ impl Handshake {
             version,
             height,
             nonce: peer_message.nonce,
+            peer_version: peer_message.version,
+            peer_services: peer_message.services,
         })
     }
 
@@ -121,6 +138,11 @@ impl Handshake {
     pub async fn receive(&mut self, message: Version) -> Result<(), HandshakeError> {
         // You are the new sender and your peer is the receiver
         let address_receiver = self.channel.address;
+
+        Self::check_version(&message, address_receiver)?;
+        self.peer_version = message.version;
+        self.peer_services = message.services;
+
         let address_sender = message.address_receiver;
 
         self.channel
@@ -129,6 +151,19 @@ impl Handshake {
         Ok(())
     }
 
+    /// Reject a peer that speaks a protocol version older than `MIN_PROTOCOL_VERSION`.
+    fn check_version(message: &Version, peer_address: SocketAddr) -> Result<(), HandshakeError> {
+        if message.version < MIN_PROTOCOL_VERSION {
+            return Err(HandshakeError::IncompatibleVersion(
+                peer_address,
+                message.version,
+                MIN_PROTOCOL_VERSION,
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Accept the Verack from a peer.
     pub async fn accept(&mut self, message: Verack) -> Result<(), HandshakeError> {
         if self.nonce != message.nonce {
@@ -149,11 +184,6 @@ impl Handshake {
         }
     }
 
-    /// Updates the stored reader stream for an existing peer handshake.
-    pub fn update_reader(&mut self, read_channel: Channel) {
-        self.channel = Arc::new(self.channel.update_reader(read_channel.reader))
-    }
-
     /// Returns current handshake state.
     pub fn get_state(&self) -> HandshakeState {
         self.state.clone()
@@ -163,7 +193,10 @@ impl Handshake {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::message::Message;
+    use crate::{
+        message::{Message, TcpTransport},
+        PROTOCOL_VERSION,
+    };
     use snarkos_testing::network::random_socket_address;
 
     use serial_test::serial;
@@ -180,20 +213,20 @@ mod tests {
         let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
 
         tokio::spawn(async move {
-            let mut server_listener = TcpListener::bind(server_address).await.unwrap();
-
             // 2. Server connects to peer, server sends server_hand Version
 
-            let mut server_hand = Handshake::send_new(1u64, 0u32, server_address, peer_address)
-                .await
-                .unwrap();
-
-            let (reader, _socket) = server_listener.accept().await.unwrap();
-            let read_channel = Channel::new_read_only(reader).unwrap();
-
-            server_hand.update_reader(read_channel);
+            let mut server_hand = Handshake::send_new(
+                PROTOCOL_VERSION,
+                0u32,
+                server_address,
+                peer_address,
+                None,
+                &TcpTransport,
+            )
+            .await
+            .unwrap();
 
-            // 5. Server accepts server_hand Verack
+            // 5. Server accepts server_hand Verack from the same connection
 
             let (_name, bytes) = server_hand.channel.read().await.unwrap();
             let message = Verack::deserialize(bytes).unwrap();
@@ -212,17 +245,17 @@ mod tests {
 
         // 3. Peer accepts Server connection
 
-        let (reader, _socket) = peer_listener.accept().await.unwrap();
-        let read_channel = Channel::new_read_only(reader).unwrap();
-        let (_name, bytes) = read_channel.read().await.unwrap();
+        let (stream, _socket) = peer_listener.accept().await.unwrap();
+        let channel = Channel::accept(stream, None).await.unwrap();
+        let (_name, bytes) = channel.read().await.unwrap();
 
         // 4. Peer receives server_handshake Version.
         // Peer sends server_handshake Verack, peer_handshake Version
 
         let mut peer_hand = Handshake::receive_new(
-            1u64,
+            PROTOCOL_VERSION,
             0u32,
-            read_channel,
+            channel,
             Version::deserialize(bytes).unwrap(),
             peer_address,
             server_address,
@@ -230,11 +263,25 @@ mod tests {
         .await
         .unwrap();
 
+        assert_eq!(server_address, peer_hand.channel.address);
+
         // 8. Peer accepts peer_handshake Verack
 
         let (_name, bytes) = peer_hand.channel.read().await.unwrap();
         let message = Verack::deserialize(bytes).unwrap();
 
         peer_hand.accept(message).await.unwrap();
+        assert_eq!(HandshakeState::Accepted, peer_hand.get_state());
+    }
+
+    #[test]
+    fn test_check_version() {
+        let peer_address = random_socket_address();
+
+        let outdated = Version::new(MIN_PROTOCOL_VERSION - 1, 0u32, peer_address, random_socket_address());
+        assert!(Handshake::check_version(&outdated, peer_address).is_err());
+
+        let current = Version::new(PROTOCOL_VERSION, 0u32, peer_address, random_socket_address());
+        assert!(Handshake::check_version(&current, peer_address).is_ok());
     }
 }
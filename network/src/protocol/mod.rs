@@ -20,5 +20,8 @@ pub use handshake::*;
 pub mod ping_protocol;
 pub use ping_protocol::*;
 
+pub mod services;
+pub use services::*;
+
 pub mod sync;
 pub use sync::*;
@@ -0,0 +1,68 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use std::ops::BitOr;
+
+/// The version of the peer to peer protocol spoken by this node.
+/// Version 2 introduced headers-first sync and the services field of the Version message.
+pub const PROTOCOL_VERSION: u64 = 2;
+
+/// The oldest protocol version of a peer this node will connect to.
+pub const MIN_PROTOCOL_VERSION: u64 = 2;
+
+/// The services this node advertises to its peers.
+pub const LOCAL_SERVICES: Services = Services::NETWORK;
+
+/// A bitfield of the services a node advertises in its Version message.
+/// Optional message types should only be sent to peers that advertise the matching service.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Services(pub u64);
+
+impl Services {
+    /// The node stores the full chain and serves blocks and block headers.
+    pub const NETWORK: Services = Services(1);
+    /// The node does not advertise any services.
+    pub const NONE: Services = Services(0);
+
+    /// Returns true if all of the given services are advertised.
+    pub fn contains(&self, services: Services) -> bool {
+        self.0 & services.0 == services.0
+    }
+}
+
+impl BitOr for Services {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Services(self.0 | other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_services() {
+        let services = Services::NONE | Services::NETWORK;
+
+        assert!(services.contains(Services::NETWORK));
+        assert!(services.contains(Services::NONE));
+        assert!(!Services::NONE.contains(Services::NETWORK));
+        assert!(!services.contains(Services(1 << 1)));
+    }
+}
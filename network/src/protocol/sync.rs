@@ -15,18 +15,34 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+    local_time,
     message::Channel,
-    message_types::{GetBlock, GetSync},
+    message_types::{GetBlock, GetHeaders},
 };
-use snarkos_errors::network::SendError;
+use snarkos_consensus::ConsensusParameters;
+use snarkos_errors::{consensus::ConsensusError, network::SendError};
 use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
-use snarkos_objects::BlockHeaderHash;
+use snarkos_objects::{BlockHeader, BlockHeaderHash};
 use snarkos_storage::Ledger;
 
 use chrono::{DateTime, Duration as ChronoDuration, Utc};
-use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::time::delay_for;
 
+/// The maximum number of block requests in flight to a single peer.
+pub const MAX_BLOCK_REQUESTS_PER_PEER: usize = 8;
+
+/// The number of seconds to wait for a requested block before asking another peer for it.
+pub const BLOCK_REQUEST_TIMEOUT_SECS: i64 = 5;
+
+/// The maximum number of block headers sent in a single Headers message.
+pub const MAX_HEADERS_PER_MESSAGE: u32 = 2000;
+
 #[derive(Clone, PartialEq)]
 pub enum SyncState {
     Idle,
@@ -34,27 +50,43 @@ pub enum SyncState {
     Syncing(DateTime<Utc>, u32),
 }
 
-/// Manages syncing chain state with a sync node.
-/// 1. The server_node sends a GetSync message to a sync_node.
-/// 2. The sync_node responds with a Sync message with block_headers the server_node is missing.
-/// 3. The server_node sends a GetBlock message for each BlockHeaderHash in the message.
+/// A block that has been requested from a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockRequest {
+    /// The address of the peer the block was requested from
+    pub peer: SocketAddr,
+    /// The time of the request
+    pub time: DateTime<Utc>,
+}
+
+/// Manages syncing chain state with connected peers.
+/// 1. The server_node sends a GetHeaders message to a sync_node.
+/// 2. The sync_node responds with a Headers message with the block headers the server_node is missing.
+/// 3. The server_node validates the header chain and queues the hash of each valid header.
+/// 4. The server_node sends GetBlock messages for the queued hashes to all connected peers in parallel.
 pub struct SyncHandler {
     /// The address of the sync node
     pub sync_node: SocketAddr,
     /// Current state of the sync handler
     pub sync_state: SyncState,
     /// Block headers of blocks that need to be downloaded
-    block_headers: Vec<BlockHeaderHash>,
-    /// Pending blocks - Blocks that have been requested and the time of the request
-    pub pending_blocks: HashMap<BlockHeaderHash, DateTime<Utc>>,
+    block_headers: VecDeque<BlockHeaderHash>,
+    /// Validated headers of blocks that have not been stored yet, with their block heights
+    validated_headers: HashMap<BlockHeaderHash, (BlockHeader, u32)>,
+    /// Pending blocks - Blocks that have been requested, the peer and the time of the request
+    pub pending_blocks: HashMap<BlockHeaderHash, BlockRequest>,
+    /// Peers that let a block request time out. They are only used when no other peer is available
+    stalled_peers: HashSet<SocketAddr>,
 }
 
 impl SyncHandler {
     /// Construct a new `SyncHandler`.
     pub fn new(sync_node: SocketAddr) -> Self {
         Self {
-            block_headers: vec![],
+            block_headers: VecDeque::new(),
+            validated_headers: HashMap::new(),
             pending_blocks: HashMap::new(),
+            stalled_peers: HashSet::new(),
             sync_node,
             sync_state: SyncState::Idle,
         }
@@ -70,16 +102,44 @@ impl SyncHandler {
 
     /// Returns if the time of the block request, or None if the block was not requested.
     pub fn is_pending(&self, block_header_hash: &BlockHeaderHash) -> Option<DateTime<Utc>> {
-        self.pending_blocks.get(block_header_hash).map(|time| time.clone())
+        self.pending_blocks.get(block_header_hash).map(|request| request.time)
+    }
+
+    /// Returns the number of blocks that are currently requested from a peer.
+    pub fn requests_in_flight(&self, peer: &SocketAddr) -> usize {
+        self.pending_blocks
+            .values()
+            .filter(|request| &request.peer == peer)
+            .count()
     }
 
     /// Remove the blocks that are now included in the chain.
     pub fn clear_pending<T: Transaction, P: LoadableMerkleParameters>(&mut self, storage: Arc<Ledger<T, P>>) {
-        for (block_hash, _time_sent) in &self.pending_blocks.clone() {
-            if !storage.block_hash_exists(&block_hash) {
+        for (block_hash, _request) in &self.pending_blocks.clone() {
+            if storage.block_hash_exists(&block_hash) {
+                self.pending_blocks.remove(block_hash);
+            }
+        }
+
+        self.validated_headers
+            .retain(|block_hash, _validated| !storage.block_hash_exists(block_hash));
+    }
+
+    /// A peer has delivered a block, so it may be assigned requests again.
+    pub fn peer_responded(&mut self, peer: &SocketAddr) {
+        self.stalled_peers.remove(peer);
+    }
+
+    /// Requeue the blocks requested from peers we are no longer connected to.
+    pub fn release_disconnected(&mut self, connected_peers: &HashMap<SocketAddr, DateTime<Utc>>) {
+        for (block_hash, request) in &self.pending_blocks.clone() {
+            if !connected_peers.contains_key(&request.peer) {
                 self.pending_blocks.remove(block_hash);
+                self.block_headers.push_front(block_hash.clone());
             }
         }
+
+        self.stalled_peers.retain(|peer| connected_peers.contains_key(peer));
     }
 
     /// Set the SyncState to syncing and update the latest block height.
@@ -87,10 +147,10 @@ impl SyncHandler {
         match self.sync_state {
             SyncState::Idle => {
                 info!("Syncing blocks");
-                self.sync_state = SyncState::Syncing(Utc::now(), block_height)
+                self.sync_state = SyncState::Syncing(local_time(), block_height)
             }
             SyncState::Syncing(_date_time, _old_height) => {
-                self.sync_state = SyncState::Syncing(Utc::now(), block_height)
+                self.sync_state = SyncState::Syncing(local_time(), block_height)
             }
         }
     }
@@ -101,7 +161,7 @@ impl SyncHandler {
         if hashes.len() > 0 {
             for block_hash in hashes {
                 if !self.block_headers.contains(&block_hash) && self.pending_blocks.get(&block_hash).is_none() {
-                    self.block_headers.push(block_hash.clone());
+                    self.block_headers.push_back(block_hash.clone());
                 }
                 self.update_syncing(height);
             }
@@ -111,65 +171,185 @@ impl SyncHandler {
         }
     }
 
-    /// Finish syncing or ask for the next block from the sync node.
+    /// Process a vector of block headers in chain order.
+    /// Each header is verified against its parent, which is either stored or was validated earlier.
+    /// The hashes of valid headers are queued for download. Returns an error at the first invalid header.
+    pub fn receive_headers<T: Transaction, P: LoadableMerkleParameters>(
+        &mut self,
+        consensus: &ConsensusParameters,
+        storage: &Ledger<T, P>,
+        headers: Vec<BlockHeader>,
+        height: u32,
+    ) -> Result<(), ConsensusError> {
+        if headers.is_empty() {
+            if self.pending_blocks.is_empty() && self.block_headers.is_empty() {
+                info!("Sync state is set to Idle");
+                self.sync_state = SyncState::Idle;
+            }
+            return Ok(());
+        }
+
+        for header in headers {
+            let block_hash = header.get_hash();
+
+            if storage.block_hash_exists(&block_hash) || self.validated_headers.contains_key(&block_hash) {
+                continue;
+            }
+
+            let (parent_header, parent_height) = match self.validated_headers.get(&header.previous_block_hash) {
+                Some(validated) => validated.clone(),
+                None => (
+                    storage.get_block_header(&header.previous_block_hash)?,
+                    storage.get_block_number(&header.previous_block_hash)?,
+                ),
+            };
+
+            let ancestor_headers = self.get_ancestor_headers(
+                storage,
+                &header.previous_block_hash,
+                consensus.ancestor_span(parent_height + 1),
+            )?;
+
+            // The transactions are not known yet, so the merkle roots are checked once the block arrives.
+            consensus.verify_header(
+                &header,
+                &parent_header,
+                &header.merkle_root_hash,
+                &header.pedersen_merkle_root_hash,
+                parent_height + 1,
+                &ancestor_headers,
+            )?;
+
+            self.block_headers.push_back(block_hash.clone());
+            self.validated_headers.insert(block_hash, (header, parent_height + 1));
+            self.update_syncing(height);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the headers of the given block and its ancestors, up to `count` headers.
+    /// Headers that were validated but not stored yet are taken from the validated headers.
+    fn get_ancestor_headers<T: Transaction, P: LoadableMerkleParameters>(
+        &self,
+        storage: &Ledger<T, P>,
+        block_hash: &BlockHeaderHash,
+        count: usize,
+    ) -> Result<Vec<BlockHeader>, ConsensusError> {
+        let mut headers = Vec::with_capacity(count);
+        let mut block_hash = block_hash.clone();
+
+        while let Some((header, _height)) = self.validated_headers.get(&block_hash) {
+            if headers.len() == count {
+                return Ok(headers);
+            }
+
+            block_hash = header.previous_block_hash.clone();
+            headers.push(header.clone());
+        }
+
+        let stored_headers = storage.get_ancestor_headers(&block_hash, count - headers.len())?;
+        headers.extend(stored_headers);
+
+        Ok(headers)
+    }
+
+    /// Select the connected peer with the fewest requests in flight that can take another request.
+    /// Stalled peers are only selected if no other peer is available.
+    fn select_peer(&self, channels: &[Arc<Channel>], failed_peers: &HashSet<SocketAddr>) -> Option<Arc<Channel>> {
+        let available = |stalled: bool| {
+            channels
+                .iter()
+                .filter(|channel| !failed_peers.contains(&channel.address))
+                .filter(|channel| self.stalled_peers.contains(&channel.address) == stalled)
+                .map(|channel| (self.requests_in_flight(&channel.address), channel))
+                .filter(|(in_flight, _channel)| *in_flight < MAX_BLOCK_REQUESTS_PER_PEER)
+                .min_by_key(|(in_flight, _channel)| *in_flight)
+                .map(|(_in_flight, channel)| Arc::clone(channel))
+        };
+
+        available(false).or_else(|| available(true))
+    }
+
+    /// Finish syncing or ask the connected peers for the next blocks.
+    /// Blocks are requested from all given peers in parallel, with at most
+    /// `MAX_BLOCK_REQUESTS_PER_PEER` requests in flight per peer.
+    /// Requests that are not answered within `BLOCK_REQUEST_TIMEOUT_SECS` are sent to another peer.
     pub async fn increment<T: Transaction, P: LoadableMerkleParameters>(
         &mut self,
-        channel: Arc<Channel>,
+        channels: Vec<Arc<Channel>>,
         storage: Arc<Ledger<T, P>>,
     ) -> Result<(), SendError> {
+        self.clear_pending(Arc::clone(&storage));
+
         if let SyncState::Syncing(date_time, height) = self.sync_state {
             if storage.get_latest_block_height() > height {
                 debug!(
                     "Synced {} Block(s) in {:.2} seconds",
                     storage.get_latest_block_height() - height,
-                    (Utc::now() - date_time).num_milliseconds() as f64 / 1000.
+                    (local_time() - date_time).num_milliseconds() as f64 / 1000.
                 );
                 self.update_syncing(storage.get_latest_block_height());
             }
 
-            // Sync up to 3 blocks at once
-            for _ in 0..3 {
-                if self.block_headers.is_empty() {
-                    break;
+            // Requeue the blocks that were not downloaded in time
+            for (block_header_hash, request) in &self.pending_blocks.clone() {
+                if local_time() - request.time > ChronoDuration::seconds(BLOCK_REQUEST_TIMEOUT_SECS) {
+                    debug!("Block request to {} timed out", request.peer);
+                    self.stalled_peers.insert(request.peer);
+                    self.pending_blocks.remove(block_header_hash);
+                    self.block_headers.push_front(block_header_hash.clone());
                 }
+            }
 
-                let block_header_hash = self.block_headers.remove(0);
+            // Spread the queued block requests over the peers
+            let mut failed_peers = HashSet::new();
 
-                // If block is not pending, then ask the sync node for the block.
-                let should_request = match self.pending_blocks.get(&block_header_hash) {
-                    Some(request_time) => {
-                        // Request the block again if the block was not downloaded in 5 seconds
-                        Utc::now() - request_time.clone() > ChronoDuration::seconds(5)
+            while let Some(block_header_hash) = self.block_headers.pop_front() {
+                let channel = match self.select_peer(&channels, &failed_peers) {
+                    Some(channel) => channel,
+                    None => {
+                        self.block_headers.push_front(block_header_hash);
+                        break;
                     }
-                    None => !storage.block_hash_exists(&block_header_hash),
                 };
 
-                if should_request {
-                    channel.write(&GetBlock::new(block_header_hash.clone())).await?;
-                    self.pending_blocks.insert(block_header_hash, Utc::now());
+                if storage.block_hash_exists(&block_header_hash) || self.pending_blocks.contains_key(&block_header_hash)
+                {
+                    continue;
+                }
+
+                match channel.write(&GetBlock::new(block_header_hash.clone())).await {
+                    Ok(_) => {
+                        self.pending_blocks.insert(block_header_hash, BlockRequest {
+                            peer: channel.address,
+                            time: local_time(),
+                        });
+                    }
+                    Err(error) => {
+                        debug!("Failed to request a block from {}: {}", channel.address, error);
+                        failed_peers.insert(channel.address);
+                        self.block_headers.push_front(block_header_hash);
+                    }
                 }
             }
 
             // Request more block headers
 
-            if self.pending_blocks.is_empty() {
+            if self.pending_blocks.is_empty() && self.block_headers.is_empty() {
                 delay_for(Duration::from_millis(500)).await;
 
-                if let Ok(block_locator_hashes) = storage.get_block_locator_hashes() {
-                    channel.write(&GetSync::new(block_locator_hashes)).await?;
-                }
-            } else {
-                for (block_header_hash, request_time) in &self.pending_blocks.clone() {
-                    if Utc::now() - request_time.clone() > ChronoDuration::seconds(5) {
-                        channel.write(&GetBlock::new(block_header_hash.clone())).await?;
-                        self.pending_blocks.insert(block_header_hash.clone(), Utc::now());
+                let sync_channel = channels
+                    .iter()
+                    .find(|channel| channel.address == self.sync_node)
+                    .or_else(|| channels.first());
+
+                if let Some(channel) = sync_channel {
+                    if let Ok(block_locator_hashes) = storage.get_block_locator_hashes() {
+                        channel.write(&GetHeaders::new(block_locator_hashes)).await?;
                     }
                 }
             }
-
-            self.clear_pending(Arc::clone(&storage));
-        } else {
-            self.clear_pending(Arc::clone(&storage));
         }
 
         Ok(())
@@ -15,11 +15,13 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+    local_time,
     message_types::{GetMemoryPool, GetPeers, Version},
     Server,
+    PROTOCOL_VERSION,
 };
 
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::Duration as ChronoDuration;
 use std::time::Duration;
 use tokio::{task, time::delay_for};
 
@@ -30,7 +32,7 @@ impl Server {
     ///     1.2 Ask our gossiped peers to handshake and become connected.
     /// 2. Maintain connected peers by sending ping messages.
     /// 3. Purge peers that have not responded in connection_frequency x 5 seconds.
-    /// 4. Reselect a sync node if we purged it.
+    /// 4. Reselect a sync node if we purged it and requeue the blocks requested from purged peers.
     /// 5. Update our memory pool every connection_frequency x memory_pool_interval seconds.
     /// All errors encountered by the connection handler will be logged to the console but will not stop the thread.
     pub(in crate::server) async fn connection_handler(&self) {
@@ -39,6 +41,7 @@ impl Server {
         let sync_handler_lock = self.sync_handler_lock.clone();
         let storage = self.storage.clone();
         let connection_frequency = self.connection_frequency;
+        let sender = self.sender.clone();
 
         // Start a separate thread for the handler.
         task::spawn(async move {
@@ -58,6 +61,9 @@ impl Server {
                 let local_address = *context.local_address.read().await;
                 peer_book.forget_peer(local_address);
 
+                // Allow peers whose ban has expired to reconnect.
+                peer_book.remove_expired_bans();
+
                 // We have less peers than our minimum peer requirement. Look for more peers.
                 if peer_book.connected_total() < context.min_peers {
                     // Ask our connected peers.
@@ -79,19 +85,24 @@ impl Server {
                     // Try and connect to our gossiped peers.
                     for (address, _last_seen) in peer_book.get_gossiped() {
                         if address != *context.local_address.read().await {
-                            if let Err(_) = context
+                            let request = context
                                 .handshakes
                                 .write()
                                 .await
                                 .send_request(
-                                    1u64, // TODO (raychu86) Establish a formal node version
+                                    PROTOCOL_VERSION,
                                     storage.get_latest_block_height(),
                                     *context.local_address.read().await,
                                     address,
                                 )
-                                .await
-                            {
-                                peer_book.disconnect_peer(address);
+                                .await;
+
+                            match request {
+                                // Read the handshake response from the connection the request was sent on
+                                Ok(channel) => Self::spawn_connection_thread(context.clone(), channel, sender.clone()),
+                                Err(_) => {
+                                    peer_book.disconnect_peer(address);
+                                }
                             }
                         }
                     }
@@ -99,7 +110,7 @@ impl Server {
 
                 // Send a ping protocol request to each of our connected peers to maintain the connection.
                 for (address, last_seen) in peer_book.get_connected() {
-                    let time_since_last_seen = (Utc::now() - last_seen).num_milliseconds();
+                    let time_since_last_seen = (local_time() - last_seen).num_milliseconds();
                     if address != *context.local_address.read().await
                         && time_since_last_seen.is_positive()
                         && time_since_last_seen as u64 > (connection_frequency * 3)
@@ -123,7 +134,7 @@ impl Server {
                 let response_timeout = ChronoDuration::milliseconds((connection_frequency * 5) as i64);
 
                 for (address, last_seen) in peer_book.get_connected() {
-                    if Utc::now() - last_seen.clone() > response_timeout {
+                    if local_time() - last_seen.clone() > response_timeout {
                         peer_book.disconnect_peer(address);
                     }
                 }
@@ -137,6 +148,9 @@ impl Server {
                     };
                 }
 
+                // Request the blocks pending from disconnected peers from other peers.
+                sync_handler.release_disconnected(&peer_book.get_connected());
+
                 // Store connected peers in database.
                 peer_book
                     .store(&storage)
@@ -155,9 +169,8 @@ impl Server {
                                 // If they are behind, they will attempt to sync.
                                 if let Some(handshake) = context.handshakes.read().await.get(&address) {
                                     let nonce = handshake.nonce;
-                                    let version = 1u64; // TODO (raychu86) Establish a formal node version
                                     let message = Version::from(
-                                        version,
+                                        PROTOCOL_VERSION,
                                         storage.get_latest_block_height(),
                                         address,
                                         *context.local_address.read().await,
@@ -15,24 +15,27 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+    local_time,
     message::{Channel, Message, MessageName},
     message_types::*,
     process_transaction_internal,
     propagate_block,
+    BanScore,
     Pings,
     Server,
+    Services,
     SyncState,
+    MAX_HEADERS_PER_MESSAGE,
 };
 use snarkos_consensus::memory_pool::Entry;
 use snarkos_dpc::base_dpc::instantiated::Tx;
-use snarkos_errors::network::ServerError;
+use snarkos_errors::network::{HandshakeError, ServerError};
 use snarkos_objects::{Block as BlockStruct, BlockHeaderHash};
 use snarkos_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
 };
 
-use chrono::Utc;
 use std::{net::SocketAddr, sync::Arc};
 
 impl Server {
@@ -42,53 +45,18 @@ impl Server {
     /// the message name, bytes, associated channel, and a tokio oneshot sender.
     ///
     /// The oneshot sender lets the connection thread know when the message is handled.
+    ///
+    /// A peer that sends a message we cannot process has its ban score raised.
     pub(in crate::server) async fn message_handler(&mut self) -> Result<(), ServerError> {
-        while let Some((tx, name, bytes, mut channel)) = self.receiver.recv().await {
-            if name == Block::name() {
-                self.receive_block_message(Block::deserialize(bytes)?, channel.clone(), true)
-                    .await?;
-            } else if name == GetBlock::name() {
-                self.receive_get_block(GetBlock::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == GetMemoryPool::name() {
-                self.receive_get_memory_pool(GetMemoryPool::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == GetPeers::name() {
-                self.receive_get_peers(GetPeers::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == GetSync::name() {
-                self.receive_get_sync(GetSync::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == MemoryPool::name() {
-                self.receive_memory_pool(MemoryPool::deserialize(bytes)?).await?;
-            } else if name == Peers::name() {
-                self.receive_peers(Peers::deserialize(bytes)?, channel.clone()).await?;
-            } else if name == Ping::name() {
-                self.receive_ping(Ping::deserialize(bytes)?, channel.clone()).await?;
-            } else if name == Pong::name() {
-                self.receive_pong(Pong::deserialize(bytes)?, channel.clone()).await?;
-            } else if name == Sync::name() {
-                self.receive_sync(Sync::deserialize(bytes)?).await?;
-            } else if name == SyncBlock::name() {
-                self.receive_block_message(Block::deserialize(bytes)?, channel.clone(), false)
-                    .await?;
-            } else if name == Transaction::name() {
-                self.receive_transaction(Transaction::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == Version::name() {
-                channel = self
-                    .receive_version(Version::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == Verack::name() {
-                self.receive_verack(Verack::deserialize(bytes)?, channel.clone())
-                    .await?;
-            } else if name == MessageName::from("disconnect") {
-                info!("Disconnected from peer: {:?}", channel.address);
-                let mut peer_book = self.context.peer_book.write().await;
-                peer_book.disconnect_peer(channel.address);
-            } else {
-                debug!("Message name not recognized {:?}", name.to_string());
-            }
+        while let Some((tx, name, bytes, channel)) = self.receiver.recv().await {
+            let channel = match self.handle_message(name, bytes, channel.clone()).await {
+                Ok(channel) => channel,
+                Err(error) => {
+                    debug!("Failed to handle message from {:?}: {}", channel.address, error);
+                    self.misbehaving_peer(channel.address, error.ban_score()).await;
+                    channel
+                }
+            };
 
             if let Err(_) = tx.send(channel) {
                 warn!("error resetting connection thread");
@@ -97,6 +65,75 @@ impl Server {
         Ok(())
     }
 
+    /// Handle a single message and return the channel of the peer that sent it.
+    async fn handle_message(
+        &mut self,
+        name: MessageName,
+        bytes: Vec<u8>,
+        mut channel: Arc<Channel>,
+    ) -> Result<Arc<Channel>, ServerError> {
+        if name == Block::name() {
+            self.receive_block_message(Block::deserialize(bytes)?, channel.clone(), true)
+                .await?;
+        } else if name == GetBlock::name() {
+            self.receive_get_block(GetBlock::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == GetHeaders::name() {
+            self.receive_get_headers(GetHeaders::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == GetMemoryPool::name() {
+            self.receive_get_memory_pool(GetMemoryPool::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == GetPeers::name() {
+            self.receive_get_peers(GetPeers::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == GetSync::name() {
+            self.receive_get_sync(GetSync::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == Headers::name() {
+            self.receive_headers(Headers::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == MemoryPool::name() {
+            self.receive_memory_pool(MemoryPool::deserialize(bytes)?).await?;
+        } else if name == Peers::name() {
+            self.receive_peers(Peers::deserialize(bytes)?, channel.clone()).await?;
+        } else if name == Ping::name() {
+            self.receive_ping(Ping::deserialize(bytes)?, channel.clone()).await?;
+        } else if name == Pong::name() {
+            self.receive_pong(Pong::deserialize(bytes)?, channel.clone()).await?;
+        } else if name == Sync::name() {
+            self.receive_sync(Sync::deserialize(bytes)?).await?;
+        } else if name == SyncBlock::name() {
+            self.receive_block_message(Block::deserialize(bytes)?, channel.clone(), false)
+                .await?;
+        } else if name == Transaction::name() {
+            self.receive_transaction(Transaction::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == Version::name() {
+            channel = self
+                .receive_version(Version::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == Verack::name() {
+            self.receive_verack(Verack::deserialize(bytes)?, channel.clone())
+                .await?;
+        } else if name == MessageName::from("disconnect") {
+            info!("Disconnected from peer: {:?}", channel.address);
+            let mut peer_book = self.context.peer_book.write().await;
+            peer_book.disconnect_peer(channel.address);
+        } else {
+            debug!("Message name not recognized {:?}", name.to_string());
+        }
+
+        Ok(channel)
+    }
+
+    /// Raise the ban score of a peer that sent us an invalid message.
+    async fn misbehaving_peer(&self, address: SocketAddr, score: u32) {
+        if score > 0 && self.context.peer_book.write().await.misbehave(address, score) {
+            info!("Banned misbehaving peer: {:?}", address);
+        }
+    }
+
     /// A peer has sent us a new block to process.
     async fn receive_block_message(
         &mut self,
@@ -115,13 +152,22 @@ impl Server {
         // Verify the block and insert it into the storage.
         if !self.storage.block_hash_exists(&block.header.get_hash()) {
             let mut memory_pool = self.memory_pool_lock.lock().await;
-            let inserted = self
+            let inserted = match self
                 .consensus
                 .receive_block(&self.parameters, &self.storage, &mut memory_pool, &block)
-                .is_ok();
+            {
+                Ok(_) => true,
+                Err(error) => {
+                    debug!("Received an invalid block from {:?}: {}", channel.address, error);
+                    self.misbehaving_peer(channel.address, error.ban_score()).await;
+                    false
+                }
+            };
             drop(memory_pool);
 
+            let mut channels = self.connected_channels().await;
             let mut sync_handler = self.sync_handler_lock.lock().await;
+            sync_handler.peer_responded(&channel.address);
             sync_handler.clear_pending(Arc::clone(&self.storage));
 
             if inserted && propagate {
@@ -129,11 +175,12 @@ impl Server {
 
                 propagate_block(self.context.clone(), message.data, channel.address).await?;
             } else if !propagate && sync_handler.sync_state != SyncState::Idle {
-                // We are syncing with another node, ask for the next block.
+                // We are syncing with other nodes, ask for the next blocks.
 
                 if let Some(channel) = self.context.connections.read().await.get(&sync_handler.sync_node) {
-                    sync_handler.increment(channel, Arc::clone(&self.storage)).await?;
+                    push_channel(&mut channels, channel);
                 }
+                sync_handler.increment(channels, Arc::clone(&self.storage)).await?;
             }
         }
 
@@ -149,6 +196,57 @@ impl Server {
         Ok(())
     }
 
+    /// A peer has requested the block headers following their chain state.
+    async fn receive_get_headers(&mut self, message: GetHeaders, channel: Arc<Channel>) -> Result<(), ServerError> {
+        let latest_shared_hash = self.storage.get_latest_shared_hash(message.block_locator_hashes)?;
+        let current_height = self.storage.get_latest_block_height();
+
+        let mut headers = vec![];
+
+        if let Ok(height) = self.storage.get_block_number(&latest_shared_hash) {
+            // send at most MAX_HEADERS_PER_MESSAGE headers
+            let max_height = current_height.min(height.saturating_add(MAX_HEADERS_PER_MESSAGE));
+
+            for block_num in height + 1..=max_height {
+                let block_hash = self.storage.get_block_hash(block_num)?;
+                headers.push(self.storage.get_block_header(&block_hash)?);
+            }
+        }
+
+        channel.write(&Headers::new(headers)).await?;
+
+        Ok(())
+    }
+
+    /// A peer has sent us block headers following our chain state.
+    /// Validate the header chain and download the blocks from our connected peers.
+    /// A peer that sent headers failing verification has its ban score raised.
+    async fn receive_headers(&mut self, message: Headers, channel: Arc<Channel>) -> Result<(), ServerError> {
+        let height = self.storage.get_latest_block_height();
+        let mut channels = self.connected_channels().await;
+        let mut sync_handler = self.sync_handler_lock.lock().await;
+
+        match sync_handler.receive_headers(&self.consensus, &self.storage, message.headers, height) {
+            Ok(()) => push_channel(&mut channels, channel),
+            Err(error) => {
+                debug!("Received invalid block headers from {:?}: {}", channel.address, error);
+
+                // Blocks are not requested from a peer that sent headers failing verification.
+                let score = error.ban_score();
+                self.misbehaving_peer(channel.address, score).await;
+                if score > 0 {
+                    channels.retain(|connected| connected.address != channel.address);
+                } else {
+                    push_channel(&mut channels, channel);
+                }
+            }
+        }
+
+        sync_handler.increment(channels, Arc::clone(&self.storage)).await?;
+
+        Ok(())
+    }
+
     /// A peer has requested our memory pool transactions.
     async fn receive_get_memory_pool(
         &mut self,
@@ -222,7 +320,7 @@ impl Server {
             }
         }
 
-        peer_book.update_connected(channel.address, Utc::now());
+        peer_book.update_connected_peer(channel.peer_id(), channel.address, local_time());
 
         Ok(())
     }
@@ -233,7 +331,7 @@ impl Server {
         let mut peer_book = self.context.peer_book.write().await;
 
         if peer_book.connected_contains(&channel.address) {
-            peer_book.update_connected(channel.address, Utc::now());
+            peer_book.update_connected_peer(channel.peer_id(), channel.address, local_time());
         }
 
         Pings::send_pong(message, channel).await?;
@@ -253,11 +351,11 @@ impl Server {
             .await
         {
             Ok(()) => {
-                self.context
-                    .peer_book
-                    .write()
-                    .await
-                    .update_connected(channel.address, Utc::now());
+                self.context.peer_book.write().await.update_connected_peer(
+                    channel.peer_id(),
+                    channel.address,
+                    local_time(),
+                );
             }
             Err(error) => debug!(
                 "Invalid Pong message from: {:?}, Full error: {:?}",
@@ -304,18 +402,32 @@ impl Server {
     /// A peer has sent us their chain state.
     async fn receive_sync(&mut self, message: Sync) -> Result<(), ServerError> {
         let height = self.storage.get_latest_block_height();
+        let mut channels = self.connected_channels().await;
         let mut sync_handler = self.sync_handler_lock.lock().await;
 
         sync_handler.receive_hashes(message.block_hashes, height);
 
         // Received block headers
         if let Some(channel) = self.context.connections.read().await.get(&sync_handler.sync_node) {
-            sync_handler.increment(channel, Arc::clone(&self.storage)).await?;
+            push_channel(&mut channels, channel);
         }
+        sync_handler.increment(channels, Arc::clone(&self.storage)).await?;
 
         Ok(())
     }
 
+    /// Returns the channels of our connected peers.
+    /// The peer book is read before the sync handler is locked, as the connection handler locks them in that order.
+    async fn connected_channels(&self) -> Vec<Arc<Channel>> {
+        let connected_peers = self.context.peer_book.read().await.get_connected();
+        let connections = self.context.connections.read().await;
+
+        connected_peers
+            .keys()
+            .filter_map(|address| connections.get(address))
+            .collect()
+    }
+
     /// A peer has sent us a transaction.
     async fn receive_transaction(&mut self, message: Transaction, channel: Arc<Channel>) -> Result<(), ServerError> {
         process_transaction_internal(
@@ -345,12 +457,15 @@ impl Server {
             .await
         {
             Ok(()) => {
-                // Add connected peer.
-                self.context
-                    .peer_book
-                    .write()
-                    .await
-                    .update_connected(channel.address, Utc::now());
+                // Store the channel of peers that accepted our handshake request.
+                self.context.connections.write().await.store_channel(&channel);
+
+                // Add connected peer, identified by the key it authenticated with.
+                self.context.peer_book.write().await.update_connected_peer(
+                    channel.peer_id(),
+                    channel.address,
+                    local_time(),
+                );
 
                 // Ask connected peer for more peers.
                 channel.write(&GetPeers).await?;
@@ -368,34 +483,48 @@ impl Server {
 
     /// A connected peer has sent handshake request.
     /// Update peer's channel.
-    /// If peer's block height is greater than ours, send a sync request.
+    /// If peer's block height is greater than ours, send a block headers request.
     ///
     /// This method may seem redundant to handshake protocol functions but a peer can send additional
     /// Version messages if they want to update their ip address/port or want to share their chain height.
     async fn receive_version(&mut self, message: Version, channel: Arc<Channel>) -> Result<Arc<Channel>, ServerError> {
         let peer_address = SocketAddr::new(channel.address.ip(), message.address_sender.port());
 
-        let peer_book = &mut self.context.peer_book.read().await;
+        let accept_peer = {
+            let peer_book = self.context.peer_book.read().await;
 
-        if (peer_book.connected_total() < self.context.max_peers || peer_book.connected_contains(&peer_address))
-            && *self.context.local_address.read().await != peer_address
-        {
-            self.context
+            (peer_book.connected_total() < self.context.max_peers || peer_book.connected_contains(&peer_address))
+                && *self.context.local_address.read().await != peer_address
+        };
+
+        if accept_peer {
+            let handshake_result = self
+                .context
                 .handshakes
                 .write()
                 .await
                 .receive_request(message.clone(), peer_address)
-                .await?;
+                .await;
+
+            // Disconnect from peers that speak an incompatible protocol version
+            if let Err(HandshakeError::IncompatibleVersion(..)) = handshake_result {
+                self.context.peer_book.write().await.disconnect_peer(peer_address);
+            }
+            handshake_result?;
 
-            // If our peer has a longer chain, send a sync message
-            if message.height > self.storage.get_latest_block_height() {
+            // Adjust the network time by the clock of the peer
+            self.consensus.network_time.add_sample(peer_address.ip(), message.timestamp);
+
+            // If our peer has a longer chain, send a block headers request
+            // Only peers that serve blocks are chosen as sync nodes
+            if message.height > self.storage.get_latest_block_height() && message.services.contains(Services::NETWORK) {
                 // Update the sync node if the sync_handler is Idle
                 if let Ok(mut sync_handler) = self.sync_handler_lock.try_lock() {
                     if !sync_handler.is_syncing() {
                         sync_handler.sync_node = peer_address;
 
                         if let Ok(block_locator_hashes) = self.storage.get_block_locator_hashes() {
-                            channel.write(&GetSync::new(block_locator_hashes)).await?;
+                            channel.write(&GetHeaders::new(block_locator_hashes)).await?;
                         }
                     }
                 }
@@ -404,3 +533,10 @@ impl Server {
         Ok(channel)
     }
 }
+
+/// Adds the channel to the given channels, unless they already hold a channel to the same peer.
+fn push_channel(channels: &mut Vec<Arc<Channel>>, channel: Arc<Channel>) {
+    if channels.iter().all(|existing| existing.address != channel.address) {
+        channels.push(channel);
+    }
+}
@@ -15,30 +15,31 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    context::Context,
-    message::{Channel, MessageName},
-    message_types::GetSync,
+    context::{local_time, Context, Handshakes},
+    message::{Channel, MessageName, PeerStream},
+    message_types::GetHeaders,
     protocol::*,
+    BanScore,
+    StoredPeers,
 };
-use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger, NetworkTime};
 use snarkos_dpc::base_dpc::{
     instantiated::{Components, Tx},
     parameters::PublicParameters,
 };
-use snarkos_errors::network::ServerError;
+use snarkos_errors::network::{message::MessageHeaderError, ConnectError, NoiseError, ServerError};
 
-use chrono::{DateTime, Utc};
-use std::{
-    collections::HashMap,
-    net::{Shutdown, SocketAddr},
-    sync::Arc,
-};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tokio::{
-    net::TcpListener,
+    io::AsyncWriteExt,
     sync::{mpsc, oneshot, Mutex},
     task,
+    time::timeout,
 };
 
+/// The number of seconds a peer that opened a connection has to complete the handshake.
+pub const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+
 /// The main networking component of a node.
 pub struct Server {
     pub consensus: ConsensusParameters,
@@ -78,26 +79,29 @@ impl Server {
     }
 
     /// Send a handshake request to a node at address without blocking the server listener.
+    /// The handshake response is read from the same connection as the request.
     fn send_handshake_non_blocking(&self, address: SocketAddr) {
         let context = self.context.clone();
         let storage = self.storage.clone();
+        let sender = self.sender.clone();
 
         task::spawn(async move {
-            context
+            let request = context
                 .handshakes
                 .write()
                 .await
                 .send_request(
-                    1u64,
+                    PROTOCOL_VERSION,
                     storage.get_latest_block_height(),
                     *context.local_address.read().await,
                     address,
                 )
-                .await
-                .unwrap_or_else(|error| {
-                    info!("Failed to connect to address: {:?}", error);
-                    ()
-                });
+                .await;
+
+            match request {
+                Ok(channel) => Self::spawn_connection_thread(context, channel, sender),
+                Err(error) => info!("Failed to connect to address: {:?}", error),
+            }
         });
     }
 
@@ -110,14 +114,32 @@ impl Server {
             let bootnode_address = bootnode.parse::<SocketAddr>()?;
 
             if i == 0 {
-                // This node should not attempt to connect to itself.
-                if local_address != bootnode_address {
+                // This node should not attempt to connect to itself or a banned bootnode.
+                if local_address != bootnode_address && !peer_book.is_banned(&bootnode_address) {
                     info!("Connecting to bootnode: {:?}", bootnode_address);
 
                     self.send_handshake_non_blocking(bootnode_address);
                 }
             } else {
-                peer_book.update_gossiped(bootnode_address, Utc::now());
+                peer_book.update_gossiped(bootnode_address, local_time());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restore the peers this server banned before it was restarted.
+    async fn ban_peers_from_storage(&mut self) -> Result<(), ServerError> {
+        if let Ok(serialized_peers) = self.storage.get_peer_book() {
+            let stored_peers = StoredPeers::deserialize(&serialized_peers)?;
+            let mut peer_book = self.context.peer_book.write().await;
+
+            for (banned_ip, expiry) in stored_peers.banned {
+                peer_book.ban_ip(banned_ip, expiry);
+            }
+
+            for (banned_identity, expiry) in stored_peers.banned_identities {
+                peer_book.ban_identity(banned_identity, expiry);
             }
         }
 
@@ -127,9 +149,14 @@ impl Server {
     /// Send a handshake request to every peer this server previously connected to.
     async fn connect_peers_from_storage(&mut self) -> Result<(), ServerError> {
         if let Ok(serialized_peers) = self.storage.get_peer_book() {
-            let stored_connected_peers: HashMap<SocketAddr, DateTime<Utc>> = bincode::deserialize(&serialized_peers)?;
+            let stored_peers = StoredPeers::deserialize(&serialized_peers)?;
+            let peer_book = self.context.peer_book.read().await;
+
+            for (stored_peer, _old_time) in stored_peers.connected {
+                if peer_book.is_banned(&stored_peer) {
+                    continue;
+                }
 
-            for (stored_peer, _old_time) in stored_connected_peers {
                 info!("Attempting to connect to stored peer: {:?}", stored_peer);
 
                 self.send_handshake_non_blocking(stored_peer);
@@ -143,7 +170,9 @@ impl Server {
     /// Each thread is given a handle to the channel and a handle to the server mpsc sender.
     /// To ensure concurrency, each connection thread sends a tokio oneshot sender handle with every message to the server mpsc receiver.
     /// The thread then waits for the oneshot receiver to receive a signal from the server before reading again.
-    fn spawn_connection_thread(
+    /// The thread stops reading once the peer is banned.
+    pub(in crate::server) fn spawn_connection_thread(
+        context: Arc<Context>,
         mut channel: Arc<Channel>,
         mut message_handler_sender: mpsc::Sender<(oneshot::Sender<Arc<Channel>>, MessageName, Vec<u8>, Arc<Channel>)>,
     ) {
@@ -189,7 +218,24 @@ impl Server {
                 let (message_name, message_bytes) = match channel.read().await {
                     Ok((message_name, message_bytes)) => (message_name, message_bytes),
                     Err(error) => {
+                        let score = error.ban_score();
+                        if score > 0 {
+                            context.peer_book.write().await.misbehave(channel.address, score);
+                        }
+
+                        // The stream cannot be framed again after a header with an unknown magic, an unread
+                        // oversized payload, or encrypted frames that overrun the message, and the cipher nonces
+                        // are out of step after a failed decryption, so drop the peer straight away.
+                        let unframed = match &error {
+                            ConnectError::MessageHeaderError(MessageHeaderError::InvalidMagic(_, _))
+                            | ConnectError::MessageHeaderError(MessageHeaderError::MessageTooLarge(_, _, _))
+                            | ConnectError::NoiseError(NoiseError::DecryptionFailed)
+                            | ConnectError::NoiseError(NoiseError::InvalidFrame(_, _)) => true,
+                            _ => false,
+                        };
+
                         handle_failure(&mut failure, &mut failure_count, &mut disconnect_from_peer, error);
+                        disconnect_from_peer |= unframed;
 
                         // Determine if we should send a disconnect message.
                         match disconnect_from_peer {
@@ -222,24 +268,108 @@ impl Server {
                     warn!("Disconnecting from an unreliable peer");
                     break;
                 }
+
+                // Break out of the loop if the peer is banned.
+                if context.peer_book.read().await.is_banned(&channel.address) {
+                    warn!("Disconnecting from a banned peer");
+                    break;
+                }
             }
         });
     }
 
+    /// Follows the handshake protocol with a peer that opened a connection, and drops the connection if
+    /// it is unsuccessful or does not finish within `HANDSHAKE_TIMEOUT_SECS`.
+    /// The handshakes are only locked to store the handshake once it succeeds.
+    async fn accept_connection(
+        context: Arc<Context>,
+        storage: Arc<MerkleTreeLedger>,
+        sync_handler_lock: Arc<Mutex<SyncHandler>>,
+        network_time: NetworkTime,
+        sender: mpsc::Sender<(oneshot::Sender<Arc<Channel>>, MessageName, Vec<u8>, Arc<Channel>)>,
+        stream: Box<dyn PeerStream>,
+        peer_address: SocketAddr,
+    ) {
+        let local_address = context.local_address.read().await.clone();
+        let peer_book = context.peer_book.read().await.clone();
+        let identity = context.handshakes.read().await.identity().cloned();
+
+        let handshake_result = timeout(
+            Duration::from_secs(HANDSHAKE_TIMEOUT_SECS),
+            Handshakes::accept_any(
+                identity.as_ref(),
+                PROTOCOL_VERSION,
+                storage.get_latest_block_height(),
+                local_address,
+                peer_address,
+                &peer_book,
+                stream,
+            ),
+        )
+        .await;
+
+        let (handshake, receiver_address, version) = match handshake_result {
+            Ok(Ok(handshake)) => handshake,
+            Ok(Err(error)) => {
+                debug!("Handshake with {} failed: {}", peer_address, error);
+                return;
+            }
+            Err(_) => {
+                debug!("Handshake with {} timed out", peer_address);
+                return;
+            }
+        };
+
+        context.handshakes.write().await.insert(handshake.clone());
+
+        // Bootstrap discovery of local node IP via VERSION requests
+        let mut local_address = context.local_address.write().await;
+        if *local_address != receiver_address {
+            *local_address = receiver_address;
+            info!("Discovered local address: {:?}", *local_address);
+            context.peer_book.write().await.forget_peer(receiver_address);
+        }
+        drop(local_address);
+
+        context.connections.write().await.store_channel(&handshake.channel);
+
+        // Adjust the network time by the clock of the peer
+        network_time.add_sample(handshake.channel.address.ip(), version.timestamp);
+
+        // If our peer has a longer chain and serves blocks, send a block headers request
+        if version.height > storage.get_latest_block_height() && version.services.contains(Services::NETWORK) {
+            // Update the sync node if the sync_handler is Idle
+            if let Ok(mut sync_handler) = sync_handler_lock.try_lock() {
+                if !sync_handler.is_syncing() {
+                    sync_handler.sync_node = handshake.channel.address;
+
+                    if let Ok(block_locator_hashes) = storage.get_block_locator_hashes() {
+                        if let Err(error) = handshake.channel.write(&GetHeaders::new(block_locator_hashes)).await {
+                            debug!("Failed to request block headers from {}: {}", peer_address, error);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Inner loop spawns one thread per connection to read messages
+        Self::spawn_connection_thread(context, handshake.channel.clone(), sender);
+    }
+
     /// Starts the server event loop.
     ///
-    /// 1. Initialize TCP listener at `local_address` and accept new TCP connections.
+    /// 1. Initialize a listener at `local_address` over the context transport and accept new connections.
     /// 2. Spawn a new thread to handle new connections.
     /// 3. Start the connection handler.
-    /// 4. Send a handshake request to all bootnodes.
+    /// 4. Restore banned peers and send a handshake request to all bootnodes.
     /// 5. Send a handshake request to all stored peers.
     /// 6. Start the message handler.
     pub async fn listen(mut self) -> Result<(), ServerError> {
-        // 1. Initialize TCP listener at `local_address` and accept new TCP connections.
+        // 1. Initialize a listener at `local_address` over the context transport and accept new connections.
         let local_address = self.context.local_address.read().await.clone();
         let address = format!("0.0.0.0:{}", local_address.port());
         let listening_address = address.parse::<SocketAddr>()?;
-        let mut listener = TcpListener::bind(&listening_address).await?;
+        let mut listener = self.context.transport.bind(listening_address).await?;
         info!("listening at {:?}", listening_address);
 
         // Prepare to spawn the main loop.
@@ -247,6 +377,7 @@ impl Server {
         let storage = self.storage.clone();
         let context = self.context.clone();
         let sync_handler_lock = self.sync_handler_lock.clone();
+        let network_time = self.consensus.network_time.clone();
 
         // 2. Spawn a new thread to handle new connections.
         task::spawn(async move {
@@ -254,7 +385,7 @@ impl Server {
 
             loop {
                 // Listen for new peers.
-                let (stream, peer_address) = match listener.accept().await {
+                let (mut stream, peer_address) = match listener.accept().await {
                     Ok((stream, peer_address)) => {
                         info!("Listener received a new connection request from {}", peer_address);
                         (stream, peer_address)
@@ -270,59 +401,19 @@ impl Server {
                     warn!(
                         "Listener will exceed maximum number of allowed peers and is rejecting this connection request."
                     );
-                    stream
-                        .shutdown(Shutdown::Write)
-                        .expect("Failed to shutdown peer stream");
+                    stream.shutdown().await.expect("Failed to shutdown peer stream");
                 } else {
-                    let local_address = context.local_address.read().await.clone();
-
-                    // Follow handshake protocol and drop peer connection if unsuccessful.
-                    if let Ok((handshake, receiver_address, version_message)) = context
-                        .handshakes
-                        .write()
-                        .await
-                        .receive_any(
-                            1u64,
-                            storage.get_latest_block_height(),
-                            local_address,
-                            peer_address,
-                            stream,
-                        )
-                        .await
-                    {
-                        // Bootstrap discovery of local node IP via VERACK responses
-                        let mut local_address = context.local_address.write().await;
-                        if *local_address != receiver_address {
-                            *local_address = receiver_address;
-                            info!("Discovered local address: {:?}", *local_address);
-                            context.peer_book.write().await.forget_peer(receiver_address);
-                        }
-
-                        context.connections.write().await.store_channel(&handshake.channel);
-
-                        if let Some(version) = version_message {
-                            // If our peer has a longer chain, send a sync message
-                            if version.height > storage.get_latest_block_height() {
-                                // Update the sync node if the sync_handler is Idle
-                                if let Ok(mut sync_handler) = sync_handler_lock.try_lock() {
-                                    if !sync_handler.is_syncing() {
-                                        sync_handler.sync_node = handshake.channel.address;
-
-                                        if let Ok(block_locator_hashes) = storage.get_block_locator_hashes() {
-                                            handshake
-                                                .channel
-                                                .write(&GetSync::new(block_locator_hashes))
-                                                .await
-                                                .unwrap();
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        // Inner loop spawns one thread per connection to read messages
-                        Self::spawn_connection_thread(handshake.channel.clone(), sender.clone());
-                    }
+                    // Perform the handshake in its own task, so a slow or silent peer
+                    // does not hold up the listener or the handshakes of other peers.
+                    task::spawn(Self::accept_connection(
+                        context.clone(),
+                        storage.clone(),
+                        sync_handler_lock.clone(),
+                        network_time.clone(),
+                        sender.clone(),
+                        stream,
+                        peer_address,
+                    ));
                 }
             }
         });
@@ -331,7 +422,9 @@ impl Server {
         debug!("Starting connection handler");
         self.connection_handler().await;
 
-        // 4. Send handshake request to bootnodes.
+        // 4. Restore banned peers and send handshake request to bootnodes.
+        self.ban_peers_from_storage().await?;
+
         debug!("Sending handshake request to bootnodes");
         self.connect_bootnodes().await?;
 
@@ -40,11 +40,9 @@ mod ping_protocol {
 
         let (tx, rx) = tokio::sync::oneshot::channel();
         tokio::spawn(async move {
-            let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
-
             // 2. Peer connects to server address
 
-            let channel = Arc::new(connect_channel(&mut peer_listener, server_address).await);
+            let channel = Arc::new(connect_channel(server_address).await);
 
             // 4. Peer sends ping request
 
@@ -92,11 +90,9 @@ mod ping_protocol {
 
         let (tx, rx) = tokio::sync::oneshot::channel();
         tokio::spawn(async move {
-            let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
-
             // 2. Peer connects to server address
 
-            let channel = Arc::new(connect_channel(&mut peer_listener, server_address).await);
+            let channel = Arc::new(connect_channel(server_address).await);
 
             // 4. Peer send ping request
 
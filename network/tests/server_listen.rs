@@ -21,12 +21,14 @@ mod server_listen {
         parameters::PublicParameters,
     };
     use snarkos_network::{
-        context::Context,
+        context::{Context, DEFAULT_BAN_DURATION},
         message::Message,
-        message_types::{GetPeers, GetSync, Verack},
+        message_types::{GetHeaders, GetPeers, Verack},
         protocol::SyncHandler,
         server::Server,
         Handshakes,
+        PeerBook,
+        PROTOCOL_VERSION,
     };
     use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, network::*, storage::*};
 
@@ -57,9 +59,16 @@ mod server_listen {
         let sync_handler_lock = Arc::new(Mutex::new(sync_handler));
 
         let server = Server::new(
-            Context::new(server_address, 5, 0, 10, is_bootnode, vec![
-                bootnode_address.to_string(),
-            ]),
+            Context::new(
+                server_address,
+                5,
+                0,
+                10,
+                is_bootnode,
+                vec![bootnode_address.to_string()],
+                DEFAULT_BAN_DURATION,
+                None,
+            ),
             consensus,
             storage,
             parameters,
@@ -138,13 +147,20 @@ mod server_listen {
 
             // 3. Check that bootnode received Version message
 
-            let (reader, _peer) = bootnode_listener.accept().await.unwrap();
+            let (stream, _peer) = bootnode_listener.accept().await.unwrap();
 
             // 4. Send handshake response from bootnode to server
 
             let mut bootnode_handshakes = Handshakes::new();
             let (mut bootnode_hand, _, _) = bootnode_handshakes
-                .receive_any(1u64, 1u32, bootnode_address, server_address, reader)
+                .receive_any(
+                    PROTOCOL_VERSION,
+                    1u32,
+                    bootnode_address,
+                    server_address,
+                    &PeerBook::new(),
+                    stream,
+                )
                 .await
                 .unwrap();
 
@@ -162,10 +178,10 @@ mod server_listen {
             let verack_message = Verack::deserialize(bytes).unwrap();
             bootnode_hand.accept(verack_message).await.unwrap();
 
-            // 7. Check that bootnode received GetSync message
+            // 7. Check that bootnode received GetHeaders message
 
             let (name, _bytes) = bootnode_hand.channel.read().await.unwrap();
-            assert_eq!(GetSync::name(), name);
+            assert_eq!(GetHeaders::name(), name);
         });
 
         drop(rt);
@@ -210,14 +226,21 @@ mod server_listen {
 
             // 4. Check that peer received Version message
 
-            let (reader, _peer) = peer_listener.accept().await.unwrap();
+            let (stream, _peer) = peer_listener.accept().await.unwrap();
             sleep(1000).await;
 
             // 5. Send handshake response from peer to server
 
             let mut peer_handshakes = Handshakes::new();
             peer_handshakes
-                .receive_any(1u64, 1u32, peer_address, server_address, reader)
+                .receive_any(
+                    PROTOCOL_VERSION,
+                    1u32,
+                    peer_address,
+                    server_address,
+                    &PeerBook::new(),
+                    stream,
+                )
                 .await
                 .unwrap();
         });
@@ -75,7 +75,7 @@ mod server_message_handler {
                         tx,
                         Block::name(),
                         Block::new(BLOCK_1.to_vec()).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap();
@@ -135,7 +135,7 @@ mod server_message_handler {
                         GetBlock::new(BlockHeaderHash::new(GENESIS_BLOCK_HEADER_HASH.to_vec()))
                             .serialize()
                             .unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap();
@@ -187,7 +187,7 @@ mod server_message_handler {
             start_test_server(server);
             sleep(WAIT_PERIOD).await; // Sleep to give testing server time to spin up on a new thread
 
-            let channel_server_side = Arc::new(Channel::new_write_only(bootnode_address).await.unwrap());
+            let channel_server_side = Arc::new(Channel::connect(bootnode_address, None).await.unwrap());
             accept_channel(&mut bootnode_listener, server_address).await;
 
             // 2. Send SyncBlock message to server
@@ -258,7 +258,7 @@ mod server_message_handler {
                         tx,
                         Block::name(),
                         Block::new(BLOCK_1.to_vec()).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(bootnode_address).await.unwrap()),
+                        Arc::new(Channel::connect(bootnode_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -276,7 +276,7 @@ mod server_message_handler {
                         GetSync::new(vec![BlockHeaderHash::new(GENESIS_BLOCK_HEADER_HASH.to_vec())])
                             .serialize()
                             .unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -301,6 +301,143 @@ mod server_message_handler {
         kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
     }
 
+    #[test]
+    #[serial]
+    fn receive_get_headers() {
+        let mut rt = Runtime::new().unwrap();
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let path = storage.storage.db.path().to_owned();
+        let parameters = load_verifying_parameters();
+
+        rt.block_on(async move {
+            let bootnode_address = random_socket_address();
+            let server_address = random_socket_address();
+            let peer_address = random_socket_address();
+
+            let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
+
+            let server = initialize_test_server(
+                server_address,
+                bootnode_address,
+                storage,
+                parameters,
+                CONNECTION_FREQUENCY_LONG,
+            );
+            let mut server_sender_ref_1 = server.sender.clone();
+            let mut server_sender_ref_2 = server.sender.clone();
+
+            // 1. Start server
+
+            simulate_active_node(bootnode_address).await;
+            start_test_server(server);
+            sleep(WAIT_PERIOD).await; // Sleep to give testing server time to spin up on a new thread
+
+            // 2. Send Block 1 to server from bootnode
+
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                server_sender_ref_1
+                    .send((
+                        tx,
+                        Block::name(),
+                        Block::new(BLOCK_1.to_vec()).serialize().unwrap(),
+                        Arc::new(Channel::connect(bootnode_address, None).await.unwrap()),
+                    ))
+                    .await
+                    .unwrap()
+            });
+            rx.await.unwrap();
+
+            // 3. Send GetHeaders to server from peer
+
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                server_sender_ref_2
+                    .send((
+                        tx,
+                        GetHeaders::name(),
+                        GetHeaders::new(vec![BlockHeaderHash::new(GENESIS_BLOCK_HEADER_HASH.to_vec())])
+                            .serialize()
+                            .unwrap(),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
+                    ))
+                    .await
+                    .unwrap()
+            });
+            rx.await.unwrap();
+
+            // 4. Check that server correctly sent Headers message
+
+            let channel = accept_channel(&mut peer_listener, server_address).await;
+            let (name, bytes) = channel.read().await.unwrap();
+
+            let block_1 = BlockStruct::<Tx>::deserialize(&BLOCK_1).unwrap();
+
+            assert_eq!(Headers::name(), name);
+            assert_eq!(Headers::new(vec![block_1.header]).serialize().unwrap(), bytes);
+        });
+
+        drop(rt);
+        kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
+    }
+
+    #[test]
+    #[serial]
+    fn receive_invalid_headers() {
+        let mut rt = Runtime::new().unwrap();
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let path = storage.storage.db.path().to_owned();
+        let parameters = load_verifying_parameters();
+
+        rt.block_on(async move {
+            let bootnode_address = random_socket_address();
+            let server_address = random_socket_address();
+            let peer_address = random_socket_address();
+
+            let server = initialize_test_server(
+                server_address,
+                bootnode_address,
+                storage,
+                parameters,
+                CONNECTION_FREQUENCY_LONG,
+            );
+            let mut server_sender = server.sender.clone();
+            let context = server.context.clone();
+
+            let mut header = BlockStruct::<Tx>::deserialize(&BLOCK_1.to_vec()).unwrap().header;
+            header.nonce = header.nonce.wrapping_add(1);
+
+            // 1. Start peer and server
+
+            simulate_active_node(peer_address).await;
+            start_test_server(server);
+            sleep(WAIT_PERIOD).await; // Sleep to give testing server time to spin up on a new thread
+
+            // 2. Send Headers message with a header failing verification to server from peer
+
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                server_sender
+                    .send((
+                        tx,
+                        Headers::name(),
+                        Headers::new(vec![header]).serialize().unwrap(),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
+                    ))
+                    .await
+                    .unwrap();
+            });
+            rx.await.unwrap();
+
+            // 3. Check that server banned the peer
+
+            assert!(context.peer_book.read().await.is_banned(&peer_address));
+        });
+
+        drop(rt);
+        kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
+    }
+
     #[test]
     #[serial]
     fn receive_sync() {
@@ -329,7 +466,7 @@ mod server_message_handler {
                 .connections
                 .write()
                 .await
-                .store_channel(&Arc::new(Channel::new_write_only(bootnode_address).await.unwrap()));
+                .store_channel(&Arc::new(Channel::connect(bootnode_address, None).await.unwrap()));
 
             let block_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
             let block_hash_clone = block_hash.clone();
@@ -349,7 +486,7 @@ mod server_message_handler {
                         tx,
                         Sync::name(),
                         Sync::new(vec![block_hash_clone]).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap();
@@ -410,7 +547,7 @@ mod server_message_handler {
                         tx,
                         Transaction::name(),
                         Transaction::new(transaction_bytes_clone).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -468,7 +605,7 @@ mod server_message_handler {
                         tx,
                         GetMemoryPool::name(),
                         GetMemoryPool.serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap();
@@ -532,7 +669,7 @@ mod server_message_handler {
                         tx,
                         GetMemoryPool::name(),
                         GetMemoryPool.serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -592,7 +729,7 @@ mod server_message_handler {
                         tx,
                         MemoryPool::name(),
                         MemoryPool::new(vec![TRANSACTION_2.to_vec()]).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -653,7 +790,7 @@ mod server_message_handler {
                         tx,
                         GetPeers::name(),
                         GetPeers.serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap();
@@ -718,7 +855,7 @@ mod server_message_handler {
                         tx,
                         Peers::name(),
                         Peers::new(addresses).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(bootnode_address).await.unwrap()),
+                        Arc::new(Channel::connect(bootnode_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -774,7 +911,7 @@ mod server_message_handler {
                         tx,
                         Ping::name(),
                         ping_bytes,
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -831,7 +968,7 @@ mod server_message_handler {
                         tx,
                         Pong::name(),
                         Pong::new(Ping::new()).serialize().unwrap(),
-                        Arc::new(Channel::new_write_only(peer_address).await.unwrap()),
+                        Arc::new(Channel::connect(peer_address, None).await.unwrap()),
                     ))
                     .await
                     .unwrap()
@@ -878,7 +1015,7 @@ mod server_message_handler {
             start_test_server(server);
             sleep(WAIT_PERIOD).await; // Sleep to give testing server time to spin up on a new thread
 
-            let channel_server_side = Arc::new(Channel::new_write_only(peer_address).await.unwrap());
+            let channel_server_side = Arc::new(Channel::connect(peer_address, None).await.unwrap());
             let channel_peer_side = accept_channel(&mut peer_listener, server_address).await;
 
             // 2. Add peer to pings
@@ -959,7 +1096,7 @@ mod server_message_handler {
             start_test_server(server);
             sleep(WAIT_PERIOD).await; // Sleep to give testing server time to spin up on a new thread
 
-            let channel_server_side = Arc::new(Channel::new_write_only(peer_address).await.unwrap());
+            let channel_server_side = Arc::new(Channel::connect(peer_address, None).await.unwrap());
             let channel_peer_side = accept_channel(&mut peer_listener, server_address).await;
 
             // 2. Add peer to pings
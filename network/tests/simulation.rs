@@ -0,0 +1,175 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod simulation {
+    use snarkos_dpc::base_dpc::instantiated::Tx;
+    use snarkos_objects::Block;
+    use snarkos_testing::{consensus::*, network::*};
+    use snarkos_utilities::bytes::FromBytes;
+
+    use std::time::Duration;
+
+    const SEED: u64 = 1231275789;
+
+    /// Returns true once every node has the given chain height.
+    fn synced(simulation: &Simulation, height: u32) -> bool {
+        simulation.nodes.iter().all(|node| node.height() == height)
+    }
+
+    #[test]
+    fn nodes_connect_through_bootnode() {
+        let mut simulation = Simulation::new(SEED);
+
+        let bootnode = simulation.add_node(&[]);
+        let node_1 = simulation.add_node(&[bootnode.address]);
+        let node_2 = simulation.add_node(&[bootnode.address]);
+
+        simulation.run_for(Duration::from_secs(5));
+
+        assert_eq!(2, simulation.block_on(bootnode.connected_peers()));
+        assert!(simulation.block_on(node_1.connected_peers()) >= 1);
+        assert!(simulation.block_on(node_2.connected_peers()) >= 1);
+    }
+
+    #[test]
+    fn new_node_syncs_with_bootnode() {
+        let mut simulation = Simulation::new(SEED);
+
+        // 1. The bootnode has a chain of two blocks
+
+        let bootnode = simulation.add_node(&[]);
+        simulation.block_on(bootnode.publish_block(&DATA.block_1));
+        simulation.block_on(bootnode.publish_block(&DATA.block_2));
+
+        // 2. A new node joins and downloads the chain
+
+        let node = simulation.add_node(&[bootnode.address]);
+
+        assert!(simulation.run_until(Duration::from_secs(30), |simulation| synced(simulation, 2)));
+        assert!(node.is_canon(&DATA.block_2));
+    }
+
+    #[test]
+    fn new_node_syncs_over_slow_link() {
+        let mut simulation = Simulation::new(SEED);
+
+        let bootnode = simulation.add_node(&[]);
+        simulation.block_on(bootnode.publish_block(&DATA.block_1));
+
+        let node = simulation.add_node(&[bootnode.address]);
+        simulation
+            .network
+            .set_link_latency(bootnode.address, node.address, Duration::from_secs(2));
+
+        // The handshake and block request each take a round trip over the slow link
+
+        simulation.run_for(Duration::from_secs(3));
+        assert_eq!(0, node.height());
+
+        assert!(simulation.run_until(Duration::from_secs(30), |simulation| synced(simulation, 1)));
+    }
+
+    #[test]
+    fn fork_reorganizes_to_heavier_chain() {
+        let mut simulation = Simulation::new(SEED);
+
+        let alternative_block_1 = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+
+        // 1. The bootnode mines blocks 1 and 2 while the other node mines a competing block 1
+
+        let bootnode = simulation.add_node(&[]);
+        simulation.block_on(bootnode.publish_block(&DATA.block_1));
+        simulation.block_on(bootnode.publish_block(&DATA.block_2));
+
+        let node = simulation.add_node(&[bootnode.address]);
+        simulation.block_on(node.publish_block(&alternative_block_1));
+
+        assert!(node.is_canon(&alternative_block_1));
+
+        // 2. The node connects and reorganizes onto the heavier chain
+
+        assert!(simulation.run_until(Duration::from_secs(30), |simulation| synced(simulation, 2)));
+        assert!(node.is_canon(&DATA.block_1));
+        assert!(node.is_canon(&DATA.block_2));
+        assert!(!node.is_canon(&alternative_block_1));
+    }
+
+    #[test]
+    fn block_propagates_to_all_peers() {
+        let mut simulation = Simulation::new(SEED);
+
+        let bootnode = simulation.add_node(&[]);
+        let node_1 = simulation.add_node(&[bootnode.address]);
+        simulation.add_node(&[bootnode.address]);
+
+        simulation.run_for(Duration::from_secs(5));
+
+        // A block mined by one node reaches the other through the bootnode
+
+        simulation.block_on(node_1.publish_block(&DATA.block_1));
+
+        assert!(simulation.run_until(Duration::from_secs(5), |simulation| synced(simulation, 1)));
+    }
+
+    #[test]
+    fn transaction_gossips_to_all_memory_pools() {
+        let mut simulation = Simulation::new(SEED);
+
+        let bootnode = simulation.add_node(&[]);
+        let node_1 = simulation.add_node(&[bootnode.address]);
+        let node_2 = simulation.add_node(&[bootnode.address]);
+
+        simulation.run_for(Duration::from_secs(5));
+
+        // A transaction sent to one node reaches the memory pool of the other through the bootnode
+
+        simulation.block_on(node_1.submit_transaction(TRANSACTION_2.to_vec()));
+        simulation.run_for(Duration::from_secs(5));
+
+        for node in &[bootnode, node_1, node_2] {
+            assert_eq!(1, simulation.block_on(node.memory_pool_size()));
+        }
+    }
+
+    #[test]
+    fn partitioned_node_catches_up_after_heal() {
+        let mut simulation = Simulation::new(SEED);
+
+        let bootnode = simulation.add_node(&[]);
+        let node_1 = simulation.add_node(&[bootnode.address]);
+        let node_2 = simulation.add_node(&[bootnode.address]);
+
+        simulation.run_for(Duration::from_secs(5));
+
+        // 1. Cut node 2 off and mine a block on the rest of the network
+
+        simulation
+            .network
+            .partition(&[node_2.address], &[bootnode.address, node_1.address]);
+
+        simulation.block_on(bootnode.publish_block(&DATA.block_1));
+        simulation.run_for(Duration::from_secs(2));
+
+        assert_eq!(1, node_1.height());
+        assert_eq!(0, node_2.height());
+
+        // 2. Once the partition heals, node 2 learns of the longer chain and syncs
+
+        simulation.network.heal();
+
+        assert!(simulation.run_until(Duration::from_secs(30), |simulation| synced(simulation, 1)));
+    }
+}
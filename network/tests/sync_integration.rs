@@ -21,9 +21,11 @@ mod sync_integration {
         message_types::*,
         protocol::sync::*,
     };
-    use snarkos_objects::BlockHeaderHash;
+    use snarkos_objects::{Block, BlockHeaderHash};
     use snarkos_testing::{consensus::*, network::*, storage::*};
+    use snarkos_utilities::bytes::FromBytes;
 
+    use chrono::{Duration, Utc};
     use serial_test::serial;
     use std::sync::Arc;
     use tokio::{net::TcpListener, sync::oneshot};
@@ -53,7 +55,7 @@ mod sync_integration {
             tokio::spawn(async move {
                 sync_handler
                     .increment(
-                        Arc::new(Channel::new_write_only(bootnode_address).await.unwrap()),
+                        vec![Arc::new(Channel::connect(bootnode_address, None).await.unwrap())],
                         storage,
                     )
                     .await
@@ -76,9 +78,10 @@ mod sync_integration {
 
         #[tokio::test]
         #[serial]
-        async fn sends_get_sync() {
+        async fn sends_get_headers() {
             let storage = Arc::new(FIXTURE_VK.ledger());
             let path = storage.storage.db.path().to_owned();
+            let block_locator_hashes = storage.get_block_locator_hashes().unwrap();
 
             let bootnode_address = random_socket_address();
 
@@ -94,7 +97,7 @@ mod sync_integration {
             tokio::spawn(async move {
                 sync_handler
                     .increment(
-                        Arc::new(Channel::new_write_only(bootnode_address).await.unwrap()),
+                        vec![Arc::new(Channel::connect(bootnode_address, None).await.unwrap())],
                         storage,
                     )
                     .await
@@ -103,15 +106,147 @@ mod sync_integration {
             });
             rx.await.unwrap();
 
-            // 3. Check that bootnode received GetSync message
+            // 3. Check that bootnode received GetHeaders message
 
             let channel = accept_channel(&mut bootnode_listener, bootnode_address).await;
             let (name, bytes) = channel.read().await.unwrap();
 
-            assert_eq!(GetSync::name(), name);
-            assert_eq!(GetSync::new(vec![]).serialize().unwrap(), bytes);
+            assert_eq!(GetHeaders::name(), name);
+            assert_eq!(GetHeaders::new(block_locator_hashes).serialize().unwrap(), bytes);
 
             kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
         }
+
+        #[tokio::test]
+        #[serial]
+        async fn sends_get_block_to_multiple_peers() {
+            let storage = Arc::new(FIXTURE_VK.ledger());
+            let path = storage.storage.db.path().to_owned();
+            let peer_1_address = random_socket_address();
+            let peer_2_address = random_socket_address();
+
+            let mut peer_1_listener = TcpListener::bind(peer_1_address).await.unwrap();
+            let mut peer_2_listener = TcpListener::bind(peer_2_address).await.unwrap();
+
+            let block_hash_1 = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+            let block_hash_2 = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+
+            // 1. Push hashes to sync handler, set syncing to true
+
+            let mut sync_handler = SyncHandler::new(peer_1_address);
+            sync_handler.receive_hashes(vec![block_hash_1.clone(), block_hash_2.clone()], 1);
+
+            // 2. Call increment_sync_handler with both peers
+
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                sync_handler
+                    .increment(
+                        vec![
+                            Arc::new(Channel::connect(peer_1_address, None).await.unwrap()),
+                            Arc::new(Channel::connect(peer_2_address, None).await.unwrap()),
+                        ],
+                        storage,
+                    )
+                    .await
+                    .unwrap();
+
+                assert_eq!(1, sync_handler.requests_in_flight(&peer_1_address));
+                assert_eq!(1, sync_handler.requests_in_flight(&peer_2_address));
+
+                tx.send(()).unwrap();
+            });
+            rx.await.unwrap();
+
+            // 3. Check that each peer received one of the GetBlock messages
+
+            let channel_1 = accept_channel(&mut peer_1_listener, peer_1_address).await;
+            let channel_2 = accept_channel(&mut peer_2_listener, peer_2_address).await;
+            let (name_1, bytes_1) = channel_1.read().await.unwrap();
+            let (name_2, bytes_2) = channel_2.read().await.unwrap();
+
+            assert_eq!(GetBlock::name(), name_1);
+            assert_eq!(GetBlock::name(), name_2);
+
+            let mut requested = vec![
+                GetBlock::deserialize(bytes_1).unwrap().block_hash,
+                GetBlock::deserialize(bytes_2).unwrap().block_hash,
+            ];
+            requested.sort_by_key(|block_hash| block_hash.0);
+
+            let mut expected = vec![block_hash_1, block_hash_2];
+            expected.sort_by_key(|block_hash| block_hash.0);
+
+            assert_eq!(expected, requested);
+
+            kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn fails_over_timed_out_request() {
+            let storage = Arc::new(FIXTURE_VK.ledger());
+            let path = storage.storage.db.path().to_owned();
+            let stalled_address = random_socket_address();
+            let peer_address = random_socket_address();
+
+            let mut peer_listener = TcpListener::bind(peer_address).await.unwrap();
+
+            let block_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+
+            // 1. Set syncing to true with a block request that timed out
+
+            let mut sync_handler = SyncHandler::new(stalled_address);
+            sync_handler.update_syncing(0);
+            sync_handler.pending_blocks.insert(block_hash.clone(), BlockRequest {
+                peer: stalled_address,
+                time: Utc::now() - Duration::seconds(BLOCK_REQUEST_TIMEOUT_SECS + 1),
+            });
+
+            // 2. Call increment_sync_handler with the other peer
+
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(async move {
+                sync_handler
+                    .increment(
+                        vec![Arc::new(Channel::connect(peer_address, None).await.unwrap())],
+                        storage,
+                    )
+                    .await
+                    .unwrap();
+
+                assert_eq!(0, sync_handler.requests_in_flight(&stalled_address));
+                assert_eq!(1, sync_handler.requests_in_flight(&peer_address));
+
+                tx.send(()).unwrap();
+            });
+            rx.await.unwrap();
+
+            // 3. Check that the other peer received the GetBlock message
+
+            let channel = accept_channel(&mut peer_listener, peer_address).await;
+            let (name, bytes) = channel.read().await.unwrap();
+
+            assert_eq!(GetBlock::name(), name);
+            assert_eq!(GetBlock::new(block_hash).serialize().unwrap(), bytes);
+
+            kill_storage_async::<Tx, CommitmentMerkleParameters>(path);
+        }
+    }
+
+    #[test]
+    fn rejects_headers_without_parent() {
+        let storage = FIXTURE_VK.ledger();
+
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        let mut sync_handler = SyncHandler::new(random_socket_address());
+
+        assert!(sync_handler
+            .receive_headers(&TEST_CONSENSUS, &storage, vec![block_2.header], 0)
+            .is_err());
+        assert!(!sync_handler.is_syncing());
+
+        kill_storage(storage);
     }
 }
@@ -158,6 +158,11 @@ impl BlockHeader {
     pub fn to_difficulty_hash(&self) -> u64 {
         sha256d_to_u64(&self.proof.0[..])
     }
+
+    /// Returns the expected number of hashes required to find a header that meets the difficulty target.
+    pub fn to_work(&self) -> u128 {
+        (u64::max_value() as u128 + 1) / (self.difficulty_target as u128 + 1)
+    }
 }
 
 impl ToBytes for BlockHeader {
@@ -226,4 +231,27 @@ mod tests {
         assert_eq!(block_header, result);
         assert_eq!(block_header, de);
     }
+
+    #[test]
+    fn work() {
+        let mut block_header = BlockHeader {
+            previous_block_hash: BlockHeaderHash([0u8; 32]),
+            merkle_root_hash: MerkleRootHash([0u8; 32]),
+            time: Utc::now().timestamp(),
+            difficulty_target: u64::max_value(),
+            nonce: 0u32,
+            pedersen_merkle_root_hash: PedersenMerkleRootHash([0u8; 32]),
+            proof: ProofOfSuccinctWork([0u8; ProofOfSuccinctWork::size()]),
+        };
+
+        // Any hash meets the easiest target
+        assert_eq!(block_header.to_work(), 1);
+
+        // Halving the target doubles the work
+        block_header.difficulty_target = u64::max_value() / 2;
+        assert_eq!(block_header.to_work(), 2);
+
+        block_header.difficulty_target = 0;
+        assert_eq!(block_header.to_work(), u64::max_value() as u128 + 1);
+    }
 }
@@ -15,8 +15,14 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use snarkos_algorithms::merkle_tree::MerkleTree;
-use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger};
-use snarkos_dpc::base_dpc::{instantiated::*, record_payload::RecordPayload, BaseDPCComponents, DPC};
+use snarkos_consensus::{ConsensusProfile, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::*,
+    program::ProgramRegistry,
+    record_payload::RecordPayload,
+    BaseDPCComponents,
+    DPC,
+};
 use snarkos_errors::dpc::{DPCError, LedgerError};
 use snarkos_models::{
     algorithms::{LoadableMerkleParameters, MerkleParameters, CRH},
@@ -27,7 +33,7 @@ use snarkos_models::{
 use snarkos_objects::{Account, AccountAddress, Network};
 use snarkos_parameters::LedgerMerkleTreeParameters;
 use snarkos_posw::PoswMarlin;
-use snarkos_storage::{key_value::NUM_COLS, storage::Storage, Ledger};
+use snarkos_storage::{key_value::NUM_COLS, KeyValueStore, Ledger, RocksDb};
 use snarkos_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
@@ -51,7 +57,7 @@ fn empty_ledger<T: Transaction, P: LoadableMerkleParameters>(
     path: &PathBuf,
 ) -> Result<Ledger<T, P>, LedgerError> {
     fs::create_dir_all(&path).map_err(|err| LedgerError::Message(err.to_string()))?;
-    let storage = match Storage::open_cf(path, NUM_COLS) {
+    let storage = match RocksDb::open_cf(path, NUM_COLS) {
         Ok(storage) => storage,
         Err(err) => return Err(LedgerError::StorageError(err)),
     };
@@ -71,14 +77,10 @@ fn empty_ledger<T: Transaction, P: LoadableMerkleParameters>(
 pub fn generate(recipient: &String, value: u64, network_id: u8, file_name: &String) -> Result<Vec<u8>, DPCError> {
     let rng = &mut thread_rng();
 
-    let consensus = ConsensusParameters {
-        max_block_size: 1_000_000_000usize,
-        max_nonce: u32::max_value(),
-        target_block_time: 10i64,
-        network: Network::from_network_id(network_id),
-        verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
-        authorized_inner_snark_ids: vec![],
-    };
+    let consensus = ConsensusProfile::from_network(Network::from_network_id(network_id)).consensus_parameters(
+        PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
+        vec![],
+    );
 
     let recipient = AccountAddress::<Components>::from_str(&recipient)?;
 
@@ -88,6 +90,7 @@ pub fn generate(recipient: &String, value: u64, network_id: u8, file_name: &Stri
     let ledger_parameters = From::from(merkle_tree_hash_parameters);
 
     let parameters = <InstantiatedDPC as DPCScheme<MerkleTreeLedger>>::Parameters::load(false)?;
+    let program_registry = ProgramRegistry::new(&parameters)?;
 
     let noop_program_vk_hash = parameters
         .system_parameters
@@ -155,6 +158,7 @@ pub fn generate(recipient: &String, value: u64, network_id: u8, file_name: &Stri
     let (records, transaction) = consensus
         .create_transaction(
             &parameters,
+            &program_registry,
             old_records,
             old_account_private_keys,
             new_record_owners,
@@ -22,6 +22,9 @@
 // #![cfg_attr(nightly, warn(missing_docs))]
 #![cfg_attr(nightly, doc(include = "../documentation/concepts/rpc_server.md"))]
 
+#[macro_use]
+extern crate log;
+
 pub mod rpc_impl;
 #[doc(inline)]
 pub use rpc_impl::*;
@@ -41,3 +44,7 @@ pub use rpc_trait::*;
 pub mod rpc_types;
 #[doc(inline)]
 pub use rpc_types::*;
+
+pub mod ws_server;
+#[doc(inline)]
+pub use ws_server::*;
@@ -19,10 +19,11 @@
 //! See [RpcFunctions](../trait.RpcFunctions.html) for documentation of public endpoints.
 
 use crate::{rpc_trait::RpcFunctions, rpc_types::*};
-use snarkos_consensus::{get_block_reward, ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger, Miner};
 use snarkos_dpc::base_dpc::{
     instantiated::{Components, Tx},
     parameters::PublicParameters,
+    program::ProgramRegistry,
 };
 use snarkos_errors::rpc::RpcError;
 use snarkos_models::objects::Transaction;
@@ -31,13 +32,17 @@ use snarkos_objects::BlockHeaderHash;
 use snarkos_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
+    CanonicalDeserialize,
     CanonicalSerialize,
 };
+use snarkos_wallet::Wallet;
 
-use chrono::Utc;
 use std::sync::Arc;
 use tokio::{runtime::Runtime, sync::Mutex};
 
+/// The maximum number of blocks returned by a `getblocktransactions` call.
+pub const MAX_BLOCK_RANGE: u32 = 100;
+
 /// Implements JSON-RPC HTTP endpoint functions for a node.
 /// The constructor is given Arc::clone() copies of all needed node components.
 #[derive(Clone)]
@@ -48,6 +53,9 @@ pub struct RpcImpl {
     /// Public Parameters
     pub(crate) parameters: PublicParameters<Components>,
 
+    /// Programs that records can be created and spent with.
+    pub(crate) program_registry: Arc<ProgramRegistry<Components>>,
+
     /// Network context held by the server.
     pub(crate) server_context: Arc<Context>,
 
@@ -59,6 +67,12 @@ pub struct RpcImpl {
 
     /// RPC credentials for accessing guarded endpoints
     pub(crate) credentials: Option<RpcCredentials>,
+
+    /// Handle to the node wallet, which is opened by the first wallet call.
+    pub(crate) wallet_lock: Arc<Mutex<Option<Wallet>>>,
+
+    /// Mines blocks on demand for the `generate` endpoint of a development chain.
+    pub(crate) miner: Option<Miner>,
 }
 
 impl RpcImpl {
@@ -66,6 +80,7 @@ impl RpcImpl {
     pub fn new(
         storage: Arc<MerkleTreeLedger>,
         parameters: PublicParameters<Components>,
+        program_registry: Arc<ProgramRegistry<Components>>,
         server_context: Arc<Context>,
         consensus: ConsensusParameters,
         memory_pool_lock: Arc<Mutex<MemoryPool<Tx>>>,
@@ -74,12 +89,21 @@ impl RpcImpl {
         Self {
             storage,
             parameters,
+            program_registry,
             server_context,
             consensus,
             memory_pool_lock,
             credentials,
+            wallet_lock: Arc::new(Mutex::new(None)),
+            miner: None,
         }
     }
+
+    /// Enables the `generate` endpoint, which mines blocks on demand with the given miner.
+    pub fn with_miner(mut self, miner: Miner) -> Self {
+        self.miner = Some(miner);
+        self
+    }
 }
 
 impl RpcFunctions for RpcImpl {
@@ -147,6 +171,63 @@ impl RpcFunctions for RpcImpl {
         Ok(hex::encode(&block_hash.0))
     }
 
+    /// Returns the transaction ids of the canon blocks from the start height to the end height, inclusive.
+    fn get_block_transactions(&self, start_height: u32, end_height: u32) -> Result<Vec<BlockTransactions>, RpcError> {
+        if start_height > end_height
+            || end_height > self.storage.get_latest_block_height()
+            || end_height - start_height >= MAX_BLOCK_RANGE
+        {
+            return Err(RpcError::InvalidBlockRange(MAX_BLOCK_RANGE, start_height, end_height));
+        }
+
+        let mut blocks = vec![];
+
+        for height in start_height..=end_height {
+            let block_hash = self.storage.get_block_hash(height)?;
+
+            let mut transactions = vec![];
+            for transaction in self.storage.get_block_transactions(&block_hash)?.iter() {
+                transactions.push(hex::encode(&transaction.transaction_id()?));
+            }
+
+            blocks.push(BlockTransactions {
+                height,
+                hash: hex::encode(&block_hash.0),
+                transactions,
+            });
+        }
+
+        Ok(blocks)
+    }
+
+    /// Returns the id of the canon transaction that created a record commitment.
+    fn get_commitment_transaction(&self, commitment: String) -> Result<Option<String>, RpcError> {
+        let transaction_id = self.storage.get_commitment_transaction_id(&hex::decode(commitment)?)?;
+
+        Ok(transaction_id.map(hex::encode))
+    }
+
+    /// Returns the id of the canon transaction that spent a record with the serial number.
+    fn get_serial_number_transaction(&self, serial_number: String) -> Result<Option<String>, RpcError> {
+        // Serial numbers are given in the compressed encoding returned by `gettransactioninfo`
+        let serial_number_bytes = hex::decode(serial_number)?;
+        let serial_number: <Tx as Transaction>::SerialNumber =
+            CanonicalDeserialize::deserialize(&mut &serial_number_bytes[..])?;
+
+        let transaction_id = self
+            .storage
+            .get_serial_number_transaction_id(&to_bytes![serial_number]?)?;
+
+        Ok(transaction_id.map(hex::encode))
+    }
+
+    /// Returns the id of the canon transaction with the memo.
+    fn get_memo_transaction(&self, memo: String) -> Result<Option<String>, RpcError> {
+        let transaction_id = self.storage.get_memo_transaction_id(&hex::decode(memo)?)?;
+
+        Ok(transaction_id.map(hex::encode))
+    }
+
     /// Returns the hex encoded bytes of a transaction from its transaction id.
     fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError> {
         Ok(hex::encode(
@@ -254,6 +335,11 @@ impl RpcFunctions for RpcImpl {
         }
     }
 
+    /// Returns the ids of the programs known to this node.
+    fn get_programs(&self) -> Result<Vec<String>, RpcError> {
+        Ok(self.program_registry.program_ids().iter().map(hex::encode).collect())
+    }
+
     /// Validate and return if the transaction is valid.
     fn validate_raw_transaction(&self, transaction_bytes: String) -> Result<bool, RpcError> {
         let transaction_bytes = hex::decode(transaction_bytes)?;
@@ -290,15 +376,18 @@ impl RpcFunctions for RpcImpl {
     fn get_block_template(&self) -> Result<BlockTemplate, RpcError> {
         let block_height = self.storage.get_latest_block_height();
         let block = self.storage.get_block_from_block_number(block_height)?;
+        let ancestor_headers = self
+            .storage
+            .get_ancestor_headers(&block.header.get_hash(), self.consensus.ancestor_span(block_height + 1))?;
 
-        let time = Utc::now().timestamp();
+        let time = self.consensus.network_time.now();
 
         let memory_pool = Runtime::new()?.block_on(self.memory_pool_lock.lock());
         let full_transactions = memory_pool.get_candidates(&self.storage, self.consensus.max_block_size)?;
 
         let transaction_strings = full_transactions.serialize_as_str()?;
 
-        let mut coinbase_value = get_block_reward(block_height + 1);
+        let mut coinbase_value = self.consensus.get_block_reward(block_height + 1);
         for transaction in full_transactions.iter() {
             coinbase_value = coinbase_value.add(transaction.value_balance())
         }
@@ -307,7 +396,9 @@ impl RpcFunctions for RpcImpl {
             previous_block_hash: hex::encode(&block.header.get_hash().0),
             block_height: block_height + 1,
             time,
-            difficulty_target: self.consensus.get_block_difficulty(&block.header, time),
+            difficulty_target: self
+                .consensus
+                .get_block_difficulty(&ancestor_headers, time, block_height + 1),
             transactions: transaction_strings,
             coinbase_value: coinbase_value.0 as u64,
         })
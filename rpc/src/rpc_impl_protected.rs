@@ -19,32 +19,46 @@
 //! See [ProtectedRpcFunctions](../trait.ProtectedRpcFunctions.html) for documentation of private endpoints.
 
 use crate::{rpc_trait::ProtectedRpcFunctions, rpc_types::*, RpcImpl};
+use snarkos_consensus::memory_pool::Entry;
 use snarkos_dpc::base_dpc::{
     encrypted_record::EncryptedRecord,
-    instantiated::{Components, InstantiatedDPC},
+    instantiated::{Components, Tx},
     record::DPCRecord,
     record_encryption::RecordEncryption,
     record_payload::RecordPayload,
 };
 use snarkos_errors::rpc::RpcError;
 use snarkos_models::{
-    algorithms::CRH,
     dpc::{DPCComponents, Record},
-    objects::AccountScheme,
+    objects::{AccountScheme, Transaction},
 };
-use snarkos_objects::{Account, AccountAddress, AccountPrivateKey, AccountViewKey};
+use snarkos_network::{process_transaction_internal, propagate_block};
+use snarkos_objects::{Account, AccountAddress, AccountPrivateKey, AccountViewKey, Block};
 use snarkos_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
 };
+use snarkos_wallet::{create_transaction, PlanExecution, TransactionOutput, TransactionPlan, Wallet};
 
 use base64;
 use jsonrpc_http_server::jsonrpc_core::{IoDelegate, MetaIoHandler, Params, Value};
 use rand::{thread_rng, Rng};
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
 
 type JsonRPCError = jsonrpc_core::Error;
 
+/// The interval at which the remaining transactions of a `sendmany` plan are checked for submission.
+const PLANNED_TRANSACTION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The time after which the remaining transactions of a `sendmany` plan are no longer submitted.
+const PLANNED_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
 /// The following `*_protected` functions wrap an authentication check around sensitive functions
 /// before being exposed as an RPC endpoint
 impl RpcImpl {
@@ -83,6 +97,24 @@ impl RpcImpl {
         }
     }
 
+    /// Wrap authentication around `create_transaction`
+    pub fn create_transaction_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let val: CreateTransactionInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.create_transaction(val) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("transaction output serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
     /// Wrap authentication around `get_record_commitment_count`
     pub fn get_record_commitment_count_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
         self.validate_auth(meta)?;
@@ -187,22 +219,342 @@ impl RpcImpl {
         }
     }
 
+    /// Wrap authentication around `send_many`
+    pub fn send_many_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let send_input: SendManyInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.send_many(send_input) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("send output serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `import_account`
+    pub fn import_account_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let import_account_input: ImportAccountInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.import_account(import_account_input) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("importaccount serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `get_balance`
+    pub fn get_balance_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let wallet_input: WalletInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.get_balance(wallet_input) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("balance serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `list_unspent`
+    pub fn list_unspent_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let wallet_input: WalletInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.list_unspent(wallet_input) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("records serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `get_wallet_history`
+    pub fn get_wallet_history_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let wallet_input: WalletInput = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.get_wallet_history(wallet_input) {
+            Ok(result) => Ok(serde_json::to_value(result).expect("history serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `generate`
+    pub fn generate_protected(&self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let num_blocks: u32 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.generate(num_blocks) {
+            Ok(block_hashes) => Ok(Value::from(block_hashes)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
     /// Expose the protected functions as RPC enpoints
     pub fn add_protected(&self, io: &mut MetaIoHandler<Meta>) {
         let mut d = IoDelegate::<Self, Meta>::new(Arc::new(self.clone()));
 
         d.add_method_with_meta("createrawtransaction", Self::create_raw_transaction_protected);
+        d.add_method_with_meta("createtransaction", Self::create_transaction_protected);
         d.add_method_with_meta("decoderecord", Self::decode_record_protected);
         d.add_method_with_meta("decryptrecord", Self::decrypt_record_protected);
         d.add_method_with_meta("getrecordcommitmentcount", Self::get_record_commitment_count_protected);
         d.add_method_with_meta("getrecordcommitments", Self::get_record_commitments_protected);
         d.add_method_with_meta("getrawrecord", Self::get_raw_record_protected);
         d.add_method_with_meta("createaccount", Self::create_account_protected);
+        d.add_method_with_meta("importaccount", Self::import_account_protected);
+        d.add_method_with_meta("sendmany", Self::send_many_protected);
+        d.add_method_with_meta("getbalance", Self::get_balance_protected);
+        d.add_method_with_meta("listunspent", Self::list_unspent_protected);
+        d.add_method_with_meta("getwallethistory", Self::get_wallet_history_protected);
+        d.add_method_with_meta("generate", Self::generate_protected);
 
         io.extend_with(d)
     }
 }
 
+impl RpcImpl {
+    /// Create a new transaction that spends the records and creates the outputs,
+    /// returning the encoded transaction and the new records.
+    /// Any value of the records that is not paid to the outputs is the transaction fee.
+    fn build_transaction(
+        &self,
+        old_records: Vec<DPCRecord<Components>>,
+        old_account_private_keys: Vec<AccountPrivateKey<Components>>,
+        outputs: Vec<TransactionOutput>,
+        memo_string: Option<String>,
+    ) -> Result<CreateRawTransactionOuput, RpcError> {
+        let rng = &mut thread_rng();
+
+        if old_records.is_empty() || old_records.len() > Components::NUM_INPUT_RECORDS {
+            return Err(RpcError::InvalidInputCount(
+                Components::NUM_INPUT_RECORDS,
+                old_records.len(),
+            ));
+        }
+        if old_account_private_keys.len() != old_records.len() {
+            return Err(RpcError::InvalidPrivateKeyCount(
+                old_records.len(),
+                old_account_private_keys.len(),
+            ));
+        }
+        if outputs.is_empty() || outputs.len() > Components::NUM_OUTPUT_RECORDS {
+            return Err(RpcError::InvalidOutputCount(
+                Components::NUM_OUTPUT_RECORDS,
+                outputs.len(),
+            ));
+        }
+
+        // Check that the programs of the records are known to the node
+        let mut program_ids: Vec<&[u8]> = old_records.iter().map(|record| record.death_program_id()).collect();
+        for output in &outputs {
+            program_ids.extend(output.birth_program_id.as_deref());
+            program_ids.extend(output.death_program_id.as_deref());
+        }
+        for program_id in program_ids {
+            if !self.program_registry.contains(program_id) {
+                return Err(RpcError::UnknownProgram(hex::encode(program_id)));
+            }
+        }
+
+        // Check that the records cover the output values
+        let input_value = old_records
+            .iter()
+            .try_fold(0u64, |total, record| total.checked_add(record.value()))
+            .ok_or(RpcError::ValueOverflow)?;
+        let output_value = outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.value))
+            .ok_or(RpcError::ValueOverflow)?;
+        if input_value < output_value {
+            return Err(RpcError::InsufficientInputs(input_value, output_value));
+        }
+
+        // Decode memo
+        let mut memo = [0u8; 32];
+        if let Some(memo_string) = memo_string {
+            if let Ok(bytes) = hex::decode(memo_string) {
+                bytes.write(&mut memo[..])?;
+            }
+        }
+
+        // If the request did not specify a valid memo, generate one from random
+        if memo == [0u8; 32] {
+            memo = rng.gen();
+        }
+
+        // Generate transaction
+        let (records, transaction) = create_transaction(
+            &self.consensus,
+            &self.parameters,
+            &self.program_registry,
+            &self.storage,
+            old_records,
+            old_account_private_keys,
+            outputs,
+            memo,
+            rng,
+        )?;
+
+        let encoded_transaction = hex::encode(to_bytes![transaction]?);
+        let mut encoded_records = vec![];
+        for record in records {
+            encoded_records.push(hex::encode(to_bytes![record]?));
+        }
+
+        Ok(CreateRawTransactionOuput {
+            encoded_transaction,
+            encoded_records,
+        })
+    }
+
+    /// Create and submit the planned transactions whose records are in the ledger, checking that
+    /// each transaction is accepted into the memory pool. Returns the ids of the submitted transactions.
+    /// Fails if a submitted transaction that the remaining transactions depend on has left the memory pool
+    /// without being added to the ledger, as its outputs will never be spendable.
+    fn submit_planned_transactions(
+        &self,
+        runtime: &mut Runtime,
+        execution: &mut PlanExecution,
+    ) -> Result<Vec<String>, RpcError> {
+        for transaction_id in execution.pending_dependencies() {
+            let in_memory_pool = runtime
+                .block_on(self.memory_pool_lock.lock())
+                .transactions
+                .contains_key(&transaction_id);
+
+            if !in_memory_pool && self.storage.get_transaction_location(&transaction_id)?.is_none() {
+                return Err(RpcError::Message(format!(
+                    "planned transaction {} left the memory pool without being added to the ledger",
+                    hex::encode(&transaction_id)
+                )));
+            }
+        }
+
+        let transactions = execution.next_transactions(
+            &self.consensus,
+            &self.parameters,
+            &self.program_registry,
+            &self.storage,
+            &mut thread_rng(),
+        )?;
+
+        let local_address = *runtime.block_on(self.server_context.local_address.read());
+
+        let mut transaction_ids = vec![];
+        for transaction in transactions {
+            let transaction_bytes = to_bytes![transaction]?.to_vec();
+            let transaction_id = hex::encode(transaction.transaction_id()?);
+            let entry = Entry::<Tx> {
+                size: transaction_bytes.len(),
+                transaction,
+            };
+
+            runtime.block_on(process_transaction_internal(
+                self.server_context.clone(),
+                &self.consensus,
+                &self.parameters,
+                self.storage.clone(),
+                self.memory_pool_lock.clone(),
+                transaction_bytes,
+                local_address,
+            ))?;
+
+            if !runtime.block_on(self.memory_pool_lock.lock()).contains(&entry) {
+                return Err(RpcError::Message(format!(
+                    "transaction {} was not accepted into the memory pool",
+                    transaction_id
+                )));
+            }
+
+            transaction_ids.push(transaction_id);
+        }
+
+        Ok(transaction_ids)
+    }
+
+    /// Lock the node wallet, scan the blocks added since the last call and call the function with it.
+    /// The first call opens the wallet stored in the ledger with the password, and later calls
+    /// must give the same password. Functions that change the wallet must save it.
+    fn with_wallet<T, F>(&self, password: &str, function: F) -> Result<T, RpcError>
+    where
+        F: FnOnce(&mut Wallet) -> Result<T, RpcError>,
+    {
+        let mut wallet_lock = Runtime::new()?.block_on(self.wallet_lock.lock());
+
+        let wallet = match &mut *wallet_lock {
+            Some(wallet) => {
+                wallet.check_password(password)?;
+                wallet
+            }
+            empty => empty.get_or_insert(Wallet::open(
+                self.storage.clone(),
+                self.parameters.system_parameters.clone(),
+                password,
+            )?),
+        };
+        wallet.scan()?;
+
+        function(wallet)
+    }
+}
+
+/// Check that an optional list of record attributes is empty or holds an attribute for each recipient.
+fn check_record_attribute_count(name: &'static str, attributes: &[String], recipients: usize) -> Result<(), RpcError> {
+    if !attributes.is_empty() && attributes.len() != recipients {
+        return Err(RpcError::InvalidRecordAttributeCount(
+            name,
+            recipients,
+            attributes.len(),
+        ));
+    }
+    Ok(())
+}
+
 /// Functions that are sensitive and need to be protected with authentication.
 /// The authentication logic is defined in `validate_auth`
 impl ProtectedRpcFunctions for RpcImpl {
@@ -235,29 +587,6 @@ impl ProtectedRpcFunctions for RpcImpl {
         &self,
         transaction_input: TransactionInputs,
     ) -> Result<CreateRawTransactionOuput, RpcError> {
-        let rng = &mut thread_rng();
-
-        assert!(transaction_input.old_records.len() > 0);
-        assert!(transaction_input.old_records.len() <= Components::NUM_OUTPUT_RECORDS);
-        assert!(transaction_input.old_account_private_keys.len() > 0);
-        assert!(transaction_input.old_account_private_keys.len() <= Components::NUM_OUTPUT_RECORDS);
-        assert!(transaction_input.recipients.len() > 0);
-        assert!(transaction_input.recipients.len() <= Components::NUM_OUTPUT_RECORDS);
-
-        // Fetch birth/death programs
-        let program_vk_hash = self
-            .parameters
-            .system_parameters
-            .program_verification_key_crh
-            .hash(&to_bytes![
-                self.parameters.noop_program_snark_parameters.verification_key
-            ]?)?;
-        let program_vk_hash_bytes = to_bytes![program_vk_hash]?;
-
-        let program_id = program_vk_hash_bytes;
-        let new_birth_program_ids = vec![program_id.clone(); Components::NUM_OUTPUT_RECORDS];
-        let new_death_program_ids = vec![program_id.clone(); Components::NUM_OUTPUT_RECORDS];
-
         // Decode old records
         let mut old_records = vec![];
         for record_string in transaction_input.old_records {
@@ -270,104 +599,181 @@ impl ProtectedRpcFunctions for RpcImpl {
             old_account_private_keys.push(AccountPrivateKey::<Components>::from_str(&private_key_string)?);
         }
 
-        let sn_randomness: [u8; 32] = rng.gen();
-        // Fill any unused old_record indices with dummy records
-        while old_records.len() < Components::NUM_OUTPUT_RECORDS {
-            let old_sn_nonce = self
-                .parameters
-                .system_parameters
-                .serial_number_nonce
-                .hash(&sn_randomness)?;
-
-            let private_key = old_account_private_keys[0].clone();
-            let address = AccountAddress::<Components>::from_private_key(
-                self.parameters.account_signature_parameters(),
-                self.parameters.account_commitment_parameters(),
-                self.parameters.account_encryption_parameters(),
-                &private_key,
-            )?;
-
-            let dummy_record = InstantiatedDPC::generate_record(
-                &self.parameters.system_parameters,
-                &old_sn_nonce,
-                &address,
-                true, // The input record is dummy
-                0,
-                &RecordPayload::default(),
-                &program_id,
-                &program_id,
-                rng,
-            )?;
-
-            old_records.push(dummy_record);
-            old_account_private_keys.push(private_key);
-        }
-
-        assert_eq!(old_records.len(), Components::NUM_INPUT_RECORDS);
-        assert_eq!(old_account_private_keys.len(), Components::NUM_INPUT_RECORDS);
-
         // Decode new recipient data
-        let mut new_record_owners = vec![];
-        let mut new_is_dummy_flags = vec![];
-        let mut new_values = vec![];
+        let mut outputs = vec![];
         for recipient in transaction_input.recipients {
-            new_record_owners.push(AccountAddress::<Components>::from_str(&recipient.address)?);
-            new_is_dummy_flags.push(false);
-            new_values.push(recipient.amount);
+            outputs.push(TransactionOutput::new(
+                AccountAddress::<Components>::from_str(&recipient.address)?,
+                recipient.amount,
+            ));
         }
 
-        // Fill any unused new_record indices with dummy output values
-        while new_record_owners.len() < Components::NUM_OUTPUT_RECORDS {
-            new_record_owners.push(new_record_owners[0].clone());
-            new_is_dummy_flags.push(true);
-            new_values.push(0);
+        // Decode the optional payloads and programs of the new records
+        check_record_attribute_count("new_payloads", &transaction_input.new_payloads, outputs.len())?;
+        check_record_attribute_count(
+            "new_birth_programs",
+            &transaction_input.new_birth_programs,
+            outputs.len(),
+        )?;
+        check_record_attribute_count(
+            "new_death_programs",
+            &transaction_input.new_death_programs,
+            outputs.len(),
+        )?;
+
+        for (output, payload_string) in outputs.iter_mut().zip(transaction_input.new_payloads) {
+            let payload_bytes = hex::decode(payload_string)?;
+            if payload_bytes.len() != RecordPayload::default().size() {
+                return Err(RpcError::InvalidPayloadLength(
+                    RecordPayload::default().size(),
+                    payload_bytes.len(),
+                ));
+            }
+            output.payload = RecordPayload::from_bytes(&payload_bytes);
+        }
+        for (output, program_id) in outputs.iter_mut().zip(transaction_input.new_birth_programs) {
+            output.birth_program_id = Some(hex::decode(program_id)?);
+        }
+        for (output, program_id) in outputs.iter_mut().zip(transaction_input.new_death_programs) {
+            output.death_program_id = Some(hex::decode(program_id)?);
         }
 
-        assert_eq!(new_record_owners.len(), Components::NUM_OUTPUT_RECORDS);
-        assert_eq!(new_is_dummy_flags.len(), Components::NUM_OUTPUT_RECORDS);
-        assert_eq!(new_values.len(), Components::NUM_OUTPUT_RECORDS);
+        self.build_transaction(old_records, old_account_private_keys, outputs, transaction_input.memo)
+    }
 
-        // Default record payload
-        let new_payloads = vec![RecordPayload::default(); Components::NUM_OUTPUT_RECORDS];
+    /// Create a new transaction that spends records selected from a wallet account and
+    /// returns the change to the account, returning the encoded transaction and the new records.
+    fn create_transaction(
+        &self,
+        transaction_input: CreateTransactionInput,
+    ) -> Result<CreateRawTransactionOuput, RpcError> {
+        let from = AccountAddress::<Components>::from_str(&transaction_input.from)?;
 
-        // Decode memo
-        let mut memo = [0u8; 32];
-        if let Some(memo_string) = transaction_input.memo {
-            if let Ok(bytes) = hex::decode(memo_string) {
-                bytes.write(&mut memo[..])?;
-            }
+        let mut recipients = vec![];
+        for recipient in transaction_input.recipients {
+            recipients.push((
+                AccountAddress::<Components>::from_str(&recipient.address)?,
+                recipient.amount,
+            ));
         }
 
-        // If the request did not specify a valid memo, generate one from random
-        if memo == [0u8; 32] {
-            memo = rng.gen();
+        let amount = recipients
+            .iter()
+            .try_fold(transaction_input.fee, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or(RpcError::ValueOverflow)?;
+
+        // Select the records to spend, skipping those spent by transactions in the memory pool
+        let (private_key, old_records) = self.with_wallet(&transaction_input.password, |wallet| {
+            let memory_pool = Runtime::new()?.block_on(self.memory_pool_lock.lock());
+
+            let private_key = wallet.account(&from)?.private_key.clone();
+            let old_records: Vec<_> = wallet
+                .select_records(&from, amount, Components::NUM_INPUT_RECORDS, &memory_pool)?
+                .into_iter()
+                .map(|record| record.record.clone())
+                .collect();
+
+            Ok((private_key, old_records))
+        })?;
+        let old_account_private_keys = vec![private_key; old_records.len()];
+
+        // Return the change to the sender
+        let change = old_records.iter().map(|record| record.value()).sum::<u64>() - amount;
+        if change > 0 {
+            recipients.push((from, change));
         }
 
-        // Generate transaction
-        let (records, transaction) = self.consensus.create_transaction(
-            &self.parameters,
+        self.build_transaction(
             old_records,
             old_account_private_keys,
-            new_record_owners,
-            new_birth_program_ids,
-            new_death_program_ids,
-            new_is_dummy_flags,
-            new_values,
-            new_payloads,
-            memo,
-            &self.storage,
-            rng,
-        )?;
+            recipients
+                .into_iter()
+                .map(|(address, value)| TransactionOutput::new(address, value))
+                .collect(),
+            transaction_input.memo,
+        )
+    }
 
-        let encoded_transaction = hex::encode(to_bytes![transaction]?);
-        let mut encoded_records = vec![];
-        for record in records {
-            encoded_records.push(hex::encode(to_bytes![record]?));
+    /// Pays any number of recipients from a wallet account with a sequence of transactions.
+    /// The transactions whose records are in the ledger are submitted immediately, and the rest
+    /// are submitted in the background once the transactions they depend on are in the ledger.
+    /// The background submission gives up after `PLANNED_TRANSACTION_TIMEOUT`, or once a transaction
+    /// it depends on is dropped from the memory pool.
+    fn send_many(&self, send_input: SendManyInput) -> Result<SendManyOutput, RpcError> {
+        let from = AccountAddress::<Components>::from_str(&send_input.from)?;
+
+        let mut recipients = vec![];
+        for recipient in send_input.recipients {
+            recipients.push((
+                AccountAddress::<Components>::from_str(&recipient.address)?,
+                recipient.amount,
+            ));
         }
 
-        Ok(CreateRawTransactionOuput {
-            encoded_transaction,
-            encoded_records,
+        let amount = recipients
+            .iter()
+            .try_fold(send_input.fee, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or(RpcError::ValueOverflow)?;
+
+        // Select the records to spend, plan the transactions and submit the first of them.
+        // The wallet stays locked until the records are spent in the memory pool, so that
+        // a concurrent call does not select them again.
+        let mut runtime = Runtime::new()?;
+        let (transaction_count, transaction_ids, mut execution) = self.with_wallet(&send_input.password, |wallet| {
+            let private_key = wallet.account(&from)?.private_key.clone();
+            let records: Vec<_> = {
+                let memory_pool = runtime.block_on(self.memory_pool_lock.lock());
+
+                wallet
+                    .select_records(&from, amount, usize::MAX, &memory_pool)?
+                    .into_iter()
+                    .map(|record| record.record.clone())
+                    .collect()
+            };
+            let input_values: Vec<_> = records.iter().map(|record| record.value()).collect();
+
+            let plan = TransactionPlan::new(&input_values, recipients, from, send_input.fee)?;
+            let transaction_count = plan.transactions().len();
+
+            let mut execution = PlanExecution::new(plan, records, private_key);
+            let transaction_ids = self.submit_planned_transactions(&mut runtime, &mut execution)?;
+
+            Ok((transaction_count, transaction_ids, execution))
+        })?;
+
+        if !execution.is_complete() {
+            let rpc = self.clone();
+            let started = Instant::now();
+            thread::spawn(move || {
+                while !execution.is_complete() {
+                    if started.elapsed() >= PLANNED_TRANSACTION_TIMEOUT {
+                        error!(
+                            "Stopped submitting planned transactions: their dependencies were not added to the ledger within {} seconds",
+                            PLANNED_TRANSACTION_TIMEOUT.as_secs()
+                        );
+                        break;
+                    }
+
+                    thread::sleep(PLANNED_TRANSACTION_POLL_INTERVAL);
+
+                    match rpc.submit_planned_transactions(&mut runtime, &mut execution) {
+                        Ok(transaction_ids) => {
+                            for transaction_id in transaction_ids {
+                                info!("Submitted planned transaction {}", transaction_id);
+                            }
+                        }
+                        Err(error) => {
+                            error!("Stopped submitting planned transactions: {}", error);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(SendManyOutput {
+            transaction_count,
+            transaction_ids,
         })
     }
 
@@ -444,4 +850,87 @@ impl ProtectedRpcFunctions for RpcImpl {
             commitment_randomness,
         })
     }
+
+    /// Adds an account to the node wallet and returns its address.
+    fn import_account(&self, import_account_input: ImportAccountInput) -> Result<String, RpcError> {
+        let private_key = AccountPrivateKey::<Components>::from_str(&import_account_input.private_key)?;
+
+        let address = self.with_wallet(&import_account_input.password, |wallet| {
+            let address = wallet.import_account(private_key)?;
+            wallet.save()?;
+
+            Ok(address)
+        })?;
+
+        Ok(address.to_string())
+    }
+
+    /// Returns the total value of the unspent records owned by a wallet account.
+    fn get_balance(&self, wallet_input: WalletInput) -> Result<u64, RpcError> {
+        let address = AccountAddress::<Components>::from_str(&wallet_input.address)?;
+        self.with_wallet(&wallet_input.password, |wallet| {
+            let memory_pool = Runtime::new()?.block_on(self.memory_pool_lock.lock());
+
+            Ok(wallet.balance(&address, &memory_pool)?)
+        })
+    }
+
+    /// Returns the unspent records owned by a wallet account.
+    fn list_unspent(&self, wallet_input: WalletInput) -> Result<Vec<WalletRecordInfo>, RpcError> {
+        let address = AccountAddress::<Components>::from_str(&wallet_input.address)?;
+        self.with_wallet(&wallet_input.password, |wallet| {
+            let memory_pool = Runtime::new()?.block_on(self.memory_pool_lock.lock());
+
+            let mut records = vec![];
+            for record in wallet.unspent_records(&address, &memory_pool)? {
+                records.push(WalletRecordInfo {
+                    record: hex::encode(to_bytes![record.record]?),
+                    commitment: hex::encode(to_bytes![record.record.commitment()]?),
+                    value: record.record.value(),
+                    transaction_id: hex::encode(record.transaction_id),
+                    block_height: record.block_height,
+                });
+            }
+
+            Ok(records)
+        })
+    }
+
+    /// Returns the transactions that created or spent records owned by a wallet account.
+    fn get_wallet_history(&self, wallet_input: WalletInput) -> Result<Vec<WalletTransactionInfo>, RpcError> {
+        let address = AccountAddress::<Components>::from_str(&wallet_input.address)?;
+        self.with_wallet(&wallet_input.password, |wallet| {
+            Ok(wallet
+                .history(&address)?
+                .into_iter()
+                .map(|transaction| WalletTransactionInfo {
+                    transaction_id: hex::encode(transaction.transaction_id),
+                    block_height: transaction.block_height,
+                    received: transaction.received,
+                    spent: transaction.spent,
+                })
+                .collect())
+        })
+    }
+
+    /// Mines blocks on the development chain and returns their block hashes.
+    fn generate(&self, num_blocks: u32) -> Result<Vec<String>, RpcError> {
+        let miner = self.miner.as_ref().ok_or(RpcError::DevChainRequired)?;
+
+        let mut runtime = Runtime::new()?;
+        let local_address = *runtime.block_on(self.server_context.local_address.read());
+
+        let mut block_hashes = vec![];
+        for _ in 0..num_blocks {
+            let (block_bytes, _coinbase_records) =
+                runtime.block_on(miner.mine_block(&self.parameters, &self.storage, &self.memory_pool_lock))?;
+            let block = Block::<Tx>::deserialize(&block_bytes)?;
+
+            runtime.block_on(propagate_block(self.server_context.clone(), block_bytes, local_address))?;
+
+            block_hashes.push(hex::encode(block.header.get_hash().0));
+        }
+
+        Ok(block_hashes)
+    }
 }
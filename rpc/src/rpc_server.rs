@@ -21,10 +21,11 @@ use crate::{
     rpc_types::{Meta, RpcCredentials},
     RpcImpl,
 };
-use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger, Miner};
 use snarkos_dpc::base_dpc::{
     instantiated::{Components, Tx},
     parameters::PublicParameters,
+    program::ProgramRegistry,
 };
 use snarkos_network::context::Context;
 
@@ -35,15 +36,18 @@ use tokio::sync::Mutex;
 /// Starts a local JSON-RPC HTTP server at rpc_port in a new thread.
 /// Rpc failures will error on the thread level but not affect the main network server.
 /// This may be changed in the future to give the node more control of the rpc server.
+/// The `generate` endpoint is only enabled when a miner is given.
 pub async fn start_rpc_server(
     rpc_port: u16,
     storage: Arc<MerkleTreeLedger>,
     parameters: PublicParameters<Components>,
+    program_registry: Arc<ProgramRegistry<Components>>,
     server_context: Arc<Context>,
     consensus: ConsensusParameters,
     memory_pool_lock: Arc<Mutex<MemoryPool<Tx>>>,
     username: Option<String>,
     password: Option<String>,
+    miner: Option<Miner>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let rpc_server: SocketAddr = format!("0.0.0.0:{}", rpc_port).parse()?;
 
@@ -52,14 +56,18 @@ pub async fn start_rpc_server(
         _ => None,
     };
 
-    let rpc_impl = RpcImpl::new(
+    let mut rpc_impl = RpcImpl::new(
         storage,
         parameters,
+        program_registry,
         server_context,
         consensus,
         memory_pool_lock,
         credentials,
     );
+    if let Some(miner) = miner {
+        rpc_impl = rpc_impl.with_miner(miner);
+    }
     let mut io = jsonrpc_core::MetaIoHandler::default();
 
     rpc_impl.add_protected(&mut io);
@@ -40,6 +40,32 @@ pub trait RpcFunctions {
     #[rpc(name = "getblockhash")]
     fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocktransactions.md"))]
+    #[rpc(name = "getblocktransactions")]
+    fn get_block_transactions(&self, start_height: u32, end_height: u32) -> Result<Vec<BlockTransactions>, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/getcommitmenttransaction.md")
+    )]
+    #[rpc(name = "getcommitmenttransaction")]
+    fn get_commitment_transaction(&self, commitment: String) -> Result<Option<String>, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/getserialnumbertransaction.md")
+    )]
+    #[rpc(name = "getserialnumbertransaction")]
+    fn get_serial_number_transaction(&self, serial_number: String) -> Result<Option<String>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmemotransaction.md"))]
+    #[rpc(name = "getmemotransaction")]
+    fn get_memo_transaction(&self, memo: String) -> Result<Option<String>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getprograms.md"))]
+    #[rpc(name = "getprograms")]
+    fn get_programs(&self) -> Result<Vec<String>, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getrawtransaction.md"))]
     #[rpc(name = "getrawtransaction")]
     fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError>;
@@ -87,6 +113,12 @@ pub trait ProtectedRpcFunctions {
         transaction_input: TransactionInputs,
     ) -> Result<CreateRawTransactionOuput, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/createtransaction.md"))]
+    fn create_transaction(
+        &self,
+        transaction_input: CreateTransactionInput,
+    ) -> Result<CreateRawTransactionOuput, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getrecordcommitments.md"))]
     fn get_record_commitments(&self) -> Result<Vec<String>, RpcError>;
 
@@ -104,4 +136,22 @@ pub trait ProtectedRpcFunctions {
 
     #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/decryptrecord.md"))]
     fn decrypt_record(&self, decryption_input: DecryptRecordInput) -> Result<String, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/sendmany.md"))]
+    fn send_many(&self, send_input: SendManyInput) -> Result<SendManyOutput, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/importaccount.md"))]
+    fn import_account(&self, import_account_input: ImportAccountInput) -> Result<String, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getbalance.md"))]
+    fn get_balance(&self, wallet_input: WalletInput) -> Result<u64, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/listunspent.md"))]
+    fn list_unspent(&self, wallet_input: WalletInput) -> Result<Vec<WalletRecordInfo>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getwallethistory.md"))]
+    fn get_wallet_history(&self, wallet_input: WalletInput) -> Result<Vec<WalletTransactionInfo>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/generate.md"))]
+    fn generate(&self, num_blocks: u32) -> Result<Vec<String>, RpcError>;
 }
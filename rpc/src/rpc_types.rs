@@ -78,6 +78,19 @@ pub struct BlockInfo {
     pub transactions: Vec<String>,
 }
 
+/// A canon block and its transaction ids returned by the `getblocktransactions` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlockTransactions {
+    /// Block height
+    pub height: u32,
+
+    /// Block hash
+    pub hash: String,
+
+    /// List of transaction ids
+    pub transactions: Vec<String>,
+}
+
 /// Returned value for the `getblocktemplate` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockTemplate {
@@ -109,6 +122,25 @@ pub struct CreateRawTransactionOuput {
     pub encoded_records: Vec<String>,
 }
 
+/// Input for the `createtransaction` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CreateTransactionInput {
+    /// The address of the wallet account that funds the transaction
+    pub from: String,
+
+    /// The wallet password
+    pub password: String,
+
+    /// Transaction recipent and amounts
+    pub recipients: Vec<TransactionRecipient>,
+
+    /// The fee paid to the miner of the transaction
+    pub fee: u64,
+
+    /// Transaction memo
+    pub memo: Option<String>,
+}
+
 /// Input for the `decryptrecord` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DecryptRecordInput {
@@ -175,6 +207,33 @@ pub struct RpcAccount {
     pub address: String,
 }
 
+/// Input for the `sendmany` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SendManyInput {
+    /// The address of the wallet account that funds the transactions
+    pub from: String,
+
+    /// The wallet password
+    pub password: String,
+
+    /// Transaction recipent and amounts
+    pub recipients: Vec<TransactionRecipient>,
+
+    /// The fee paid to the miner of the last transaction
+    pub fee: u64,
+}
+
+/// Output for the `sendmany` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SendManyOutput {
+    /// The number of transactions needed to pay the recipients
+    pub transaction_count: usize,
+
+    /// The ids of the transactions that were submitted to the memory pool.
+    /// The remaining transactions are submitted once the records they spend are in the ledger.
+    pub transaction_ids: Vec<String>,
+}
+
 /// Returned value for the `gettransaction` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TransactionInfo {
@@ -238,10 +297,18 @@ pub struct TransactionInputs {
 
     /// Network id of the transaction
     pub network_id: u8,
-    // Attributes that will be relevant for custom programs
-    //    pub new_birth_programs: Vec<String>,
-    //    pub new_death_programs: Vec<String>,
-    //    pub new_payloads: Vec<String>,
+
+    /// Birth program ids of the new records, defaulting to the noop program
+    #[serde(default)]
+    pub new_birth_programs: Vec<String>,
+
+    /// Death program ids of the new records, defaulting to the noop program
+    #[serde(default)]
+    pub new_death_programs: Vec<String>,
+
+    /// Payloads of the new records, defaulting to zero bytes
+    #[serde(default)]
+    pub new_payloads: Vec<String>,
 }
 
 /// Additional metadata included with a transaction response
@@ -259,3 +326,97 @@ pub struct TransactionRecipient {
     /// The amount being sent
     pub amount: u64,
 }
+
+/// Input for the `importaccount` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ImportAccountInput {
+    /// The account private key to add to the wallet
+    pub private_key: String,
+
+    /// The wallet password
+    pub password: String,
+}
+
+/// Input for the `getbalance`, `listunspent`, and `getwallethistory` rpc calls
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WalletInput {
+    /// The address of a wallet account
+    pub address: String,
+
+    /// The wallet password
+    pub password: String,
+}
+
+/// Returned value for the `listunspent` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WalletRecordInfo {
+    /// The hex encoded record
+    pub record: String,
+
+    /// The record commitment
+    pub commitment: String,
+
+    /// The record value
+    pub value: u64,
+
+    /// The id of the transaction that created the record
+    pub transaction_id: String,
+
+    /// The height of the block that created the record
+    pub block_height: u32,
+}
+
+/// Returned value for the `getwallethistory` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WalletTransactionInfo {
+    /// The transaction id
+    pub transaction_id: String,
+
+    /// The height of the block that included the transaction
+    pub block_height: u32,
+
+    /// The value the account received in the transaction
+    pub received: u64,
+
+    /// The value the account spent in the transaction
+    pub spent: u64,
+}
+
+/// Notification of the `subscribe_newblocks` subscription
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NewBlockNotification {
+    /// Block hash
+    pub hash: String,
+
+    /// Block height
+    pub height: u32,
+
+    /// Previous block hash
+    pub previous_block_hash: String,
+
+    /// Block time
+    pub time: i64,
+
+    /// Transaction ids
+    pub transactions: Vec<String>,
+}
+
+/// Notification of the `subscribe_reorgs` subscription
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReorgNotification {
+    /// The height of the last block shared by the old and the new canon chain
+    pub shared_block_height: u32,
+
+    /// The hashes of the blocks removed from the canon chain, newest first
+    pub reverted_blocks: Vec<String>,
+}
+
+/// Notification of the `subscribe_mempool` subscription
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolNotification {
+    /// The transaction id
+    pub transaction_id: String,
+
+    /// The transaction size in bytes
+    pub size: usize,
+}
@@ -0,0 +1,382 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! WebSocket server for subscriptions to consensus events.
+//!
+//! Clients send JSON-RPC requests to subscribe to new blocks, chain reorganizations,
+//! memory pool transactions or the records of a view key, and receive a `subscription`
+//! notification holding the subscription id for every matching event.
+//!
+//! When RPC credentials are configured, clients authenticate the WebSocket handshake with them,
+//! and the server listens on all interfaces. Otherwise, the server only listens on localhost.
+
+use crate::rpc_types::{
+    MempoolNotification,
+    NewBlockNotification,
+    ReorgNotification,
+    RpcCredentials,
+    WalletRecordInfo,
+};
+use snarkos_consensus::{ConsensusEvent, ConsensusEvents};
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, Tx},
+    parameters::SystemParameters,
+};
+use snarkos_errors::rpc::RpcError;
+use snarkos_models::{dpc::Record, objects::Transaction};
+use snarkos_objects::AccountViewKey;
+use snarkos_utilities::{bytes::ToBytes, to_bytes};
+use snarkos_wallet::decrypt_records;
+
+use futures_util::{SinkExt, StreamExt};
+use jsonrpc_core::{Call, Error as JsonRPCError, Failure, Id, Notification, Output, Params, Success, Value, Version};
+use std::{collections::BTreeMap, net::SocketAddr, str::FromStr};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{Receiver, RecvError},
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::{header::AUTHORIZATION, StatusCode},
+    Error as WebSocketError,
+    Message,
+};
+
+/// The method name of subscription notifications.
+pub const SUBSCRIPTION_NOTIFICATION: &str = "subscription";
+
+/// The maximum number of subscriptions a client can hold at once.
+pub const MAX_SUBSCRIPTIONS: usize = 16;
+
+/// The events a client subscribed to.
+#[derive(Clone, Debug)]
+pub enum Subscription {
+    /// Blocks committed to the canon chain
+    NewBlocks,
+    /// Forks to a heavier side chain
+    Reorgs,
+    /// Transactions added to the memory pool
+    Mempool,
+    /// Records of new canon blocks that the view key can decrypt
+    Records(AccountViewKey<Components>),
+}
+
+/// The subscriptions of a WebSocket client.
+pub struct SubscriptionSession {
+    system_parameters: SystemParameters<Components>,
+    subscriptions: BTreeMap<u64, Subscription>,
+    next_subscription_id: u64,
+}
+
+impl SubscriptionSession {
+    /// Creates a session with no subscriptions.
+    pub fn new(system_parameters: SystemParameters<Components>) -> Self {
+        Self {
+            system_parameters,
+            subscriptions: BTreeMap::new(),
+            next_subscription_id: 1,
+        }
+    }
+
+    /// Handles a JSON-RPC request, returning the response.
+    /// JSON-RPC notifications from the client are ignored and have no response.
+    pub fn handle_request(&mut self, request: &str) -> Option<String> {
+        let output = match serde_json::from_str::<Call>(request) {
+            Ok(Call::MethodCall(call)) => match self.call(&call.method, call.params) {
+                Ok(result) => Output::Success(Success {
+                    jsonrpc: Some(Version::V2),
+                    result,
+                    id: call.id,
+                }),
+                Err(error) => Output::Failure(Failure {
+                    jsonrpc: Some(Version::V2),
+                    error,
+                    id: call.id,
+                }),
+            },
+            Ok(Call::Notification(_)) => return None,
+            Ok(Call::Invalid { id }) => Output::Failure(Failure {
+                jsonrpc: Some(Version::V2),
+                error: JsonRPCError::invalid_request(),
+                id,
+            }),
+            Err(_) => Output::Failure(Failure {
+                jsonrpc: Some(Version::V2),
+                error: JsonRPCError::parse_error(),
+                id: Id::Null,
+            }),
+        };
+
+        serde_json::to_string(&output).ok()
+    }
+
+    /// Returns the notifications of the event for every matching subscription.
+    pub fn notifications(&self, event: &ConsensusEvent<Tx>) -> Vec<String> {
+        let mut notifications = vec![];
+
+        for (subscription_id, subscription) in &self.subscriptions {
+            let results = match (subscription, event) {
+                (Subscription::NewBlocks, ConsensusEvent::NewBlock { .. })
+                | (Subscription::Reorgs, ConsensusEvent::Reorg { .. })
+                | (Subscription::Mempool, ConsensusEvent::NewTransaction { .. }) => {
+                    event_result(event).map(|result| vec![result])
+                }
+                (Subscription::Records(view_key), ConsensusEvent::NewBlock { height, block }) => {
+                    self.record_results(view_key, *height, &block.transactions)
+                }
+                _ => continue,
+            };
+
+            match results {
+                Ok(results) => {
+                    for result in results {
+                        let mut params = serde_json::Map::new();
+                        params.insert("subscription".into(), Value::from(*subscription_id));
+                        params.insert("result".into(), result);
+
+                        let notification = Notification {
+                            jsonrpc: Some(Version::V2),
+                            method: SUBSCRIPTION_NOTIFICATION.into(),
+                            params: Params::Map(params),
+                        };
+
+                        if let Ok(notification) = serde_json::to_string(&notification) {
+                            notifications.push(notification);
+                        }
+                    }
+                }
+                Err(error) => warn!("Failed to notify subscription {}: {}", subscription_id, error),
+            }
+        }
+
+        notifications
+    }
+
+    fn call(&mut self, method: &str, params: Params) -> Result<Value, JsonRPCError> {
+        let subscription = match method {
+            "subscribe_newblocks" => {
+                params.expect_no_params()?;
+                Subscription::NewBlocks
+            }
+            "subscribe_reorgs" => {
+                params.expect_no_params()?;
+                Subscription::Reorgs
+            }
+            "subscribe_mempool" => {
+                params.expect_no_params()?;
+                Subscription::Mempool
+            }
+            "subscribe_records" => {
+                let (view_key,): (String,) = params.parse()?;
+                let view_key = AccountViewKey::<Components>::from_str(&view_key)
+                    .map_err(|error| JsonRPCError::invalid_params(error.to_string()))?;
+                Subscription::Records(view_key)
+            }
+            "unsubscribe" => {
+                let (subscription_id,): (u64,) = params.parse()?;
+                return Ok(Value::Bool(self.subscriptions.remove(&subscription_id).is_some()));
+            }
+            _ => return Err(JsonRPCError::method_not_found()),
+        };
+
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(JsonRPCError::invalid_params(format!(
+                "A client can hold at most {} subscriptions",
+                MAX_SUBSCRIPTIONS
+            )));
+        }
+
+        let subscription_id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        self.subscriptions.insert(subscription_id, subscription);
+
+        Ok(Value::from(subscription_id))
+    }
+
+    fn record_results(
+        &self,
+        view_key: &AccountViewKey<Components>,
+        block_height: u32,
+        transactions: &[Tx],
+    ) -> Result<Vec<Value>, RpcError> {
+        let mut results = vec![];
+
+        for transaction in transactions {
+            for record in decrypt_records(&self.system_parameters, view_key, transaction) {
+                results.push(
+                    serde_json::to_value(WalletRecordInfo {
+                        record: hex::encode(to_bytes![record]?),
+                        commitment: hex::encode(to_bytes![record.commitment()]?),
+                        value: record.value(),
+                        transaction_id: hex::encode(transaction.transaction_id()?),
+                        block_height,
+                    })
+                    .expect("record serialization failed"),
+                );
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Returns the notification result of a block, reorg or memory pool event.
+fn event_result(event: &ConsensusEvent<Tx>) -> Result<Value, RpcError> {
+    let result = match event {
+        ConsensusEvent::NewBlock { height, block } => {
+            let mut transactions = vec![];
+            for transaction in block.transactions.iter() {
+                transactions.push(hex::encode(transaction.transaction_id()?));
+            }
+
+            serde_json::to_value(NewBlockNotification {
+                hash: hex::encode(block.header.get_hash().0),
+                height: *height,
+                previous_block_hash: hex::encode(block.header.previous_block_hash.0),
+                time: block.header.time,
+                transactions,
+            })
+            .expect("block notification serialization failed")
+        }
+        ConsensusEvent::Reorg {
+            shared_block_height,
+            reverted_blocks,
+        } => serde_json::to_value(ReorgNotification {
+            shared_block_height: *shared_block_height,
+            reverted_blocks: reverted_blocks
+                .iter()
+                .map(|block| hex::encode(block.header.get_hash().0))
+                .collect(),
+        })
+        .expect("reorg notification serialization failed"),
+        ConsensusEvent::NewTransaction { size, transaction } => serde_json::to_value(MempoolNotification {
+            transaction_id: hex::encode(transaction.transaction_id()?),
+            size: *size,
+        })
+        .expect("mempool notification serialization failed"),
+    };
+
+    Ok(result)
+}
+
+/// Returns true if the WebSocket handshake request holds the basic authentication of the credentials,
+/// or if no credentials are configured.
+pub fn is_authorized(credentials: Option<&RpcCredentials>, request: &Request) -> bool {
+    match credentials {
+        Some(credentials) => {
+            let basic_auth_encoding = format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", credentials.username, credentials.password))
+            );
+
+            request
+                .headers()
+                .get(AUTHORIZATION)
+                .map_or(false, |auth| auth.as_bytes() == basic_auth_encoding.as_bytes())
+        }
+        None => true,
+    }
+}
+
+/// Starts a WebSocket server that notifies clients of the consensus events they subscribe to.
+pub async fn start_ws_server(
+    ws_port: u16,
+    system_parameters: SystemParameters<Components>,
+    events: ConsensusEvents<Tx>,
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let credentials = match (username, password) {
+        (Some(username), Some(password)) => Some(RpcCredentials { username, password }),
+        _ => None,
+    };
+
+    // Without credentials, the subscriptions are only served to local clients.
+    let host = match credentials {
+        Some(_) => "0.0.0.0",
+        None => "127.0.0.1",
+    };
+    let ws_server: SocketAddr = format!("{}:{}", host, ws_port).parse()?;
+    let mut listener = TcpListener::bind(&ws_server).await?;
+
+    tokio::task::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, remote_address)) => {
+                    let session = SubscriptionSession::new(system_parameters.clone());
+                    let receiver = events.subscribe();
+                    let credentials = credentials.clone();
+
+                    tokio::task::spawn(async move {
+                        if let Err(error) = handle_connection(stream, credentials, session, receiver).await {
+                            debug!("WebSocket connection with {} failed: {}", remote_address, error);
+                        }
+                    });
+                }
+                Err(error) => error!("Failed to accept a WebSocket connection: {}", error),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Answers the requests of a WebSocket client and forwards the notifications of its subscriptions.
+async fn handle_connection(
+    stream: TcpStream,
+    credentials: Option<RpcCredentials>,
+    mut session: SubscriptionSession,
+    mut events: Receiver<ConsensusEvent<Tx>>,
+) -> Result<(), WebSocketError> {
+    let authenticate = |request: &Request, response: Response| {
+        if is_authorized(credentials.as_ref(), request) {
+            return Ok(response);
+        }
+
+        let mut error_response = ErrorResponse::new(Some("Authentication Error".into()));
+        *error_response.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(error_response)
+    };
+
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, authenticate).await?;
+    let (mut sink, mut source) = websocket.split();
+
+    loop {
+        tokio::select! {
+            message = source.next() => match message {
+                Some(Ok(Message::Text(request))) => {
+                    if let Some(response) = session.handle_request(&request) {
+                        sink.send(Message::Text(response)).await?;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    for notification in session.notifications(&event) {
+                        sink.send(Message::Text(notification)).await?;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => warn!("A WebSocket client missed {} consensus events", skipped),
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    Ok(())
+}
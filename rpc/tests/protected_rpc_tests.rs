@@ -20,10 +20,12 @@ mod protected_rpc_tests {
     use snarkos_dpc::base_dpc::{
         instantiated::{Components, Tx},
         parameters::PublicParameters,
+        program::ProgramRegistry,
         record::DPCRecord,
     };
-    use snarkos_models::dpc::Record;
-    use snarkos_network::Context;
+    use snarkos_errors::{rpc::RpcError, wallet::WalletError};
+    use snarkos_models::dpc::{DPCComponents, Record};
+    use snarkos_network::{Context, DEFAULT_BAN_DURATION};
     use snarkos_objects::{AccountAddress, AccountPrivateKey, AccountViewKey};
     use snarkos_rpc::*;
     use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, network::*, storage::*};
@@ -31,6 +33,7 @@ mod protected_rpc_tests {
         bytes::{FromBytes, ToBytes},
         to_bytes,
     };
+    use snarkos_wallet::Wallet;
 
     use jsonrpc_core::MetaIoHandler;
     use serde_json::Value;
@@ -77,11 +80,14 @@ mod protected_rpc_tests {
         let memory_pool = MemoryPool::new();
         let memory_pool_lock = Arc::new(Mutex::new(memory_pool));
 
-        let context = Context::new(server_address, 5, 1, 10, true, vec![]);
+        let context = Context::new(server_address, 5, 1, 10, true, vec![], DEFAULT_BAN_DURATION, None);
+
+        let program_registry = Arc::new(ProgramRegistry::new(&parameters).unwrap());
 
         let rpc_impl = RpcImpl::new(
             storage.clone(),
             parameters,
+            program_registry,
             Arc::new(context),
             consensus,
             memory_pool_lock,
@@ -314,6 +320,9 @@ mod protected_rpc_tests {
             recipients,
             memo: None,
             network_id,
+            new_birth_programs: vec![],
+            new_death_programs: vec![],
+            new_payloads: vec![],
         };
 
         let params = serde_json::to_value(params).unwrap();
@@ -340,6 +349,210 @@ mod protected_rpc_tests {
         kill_storage_sync(storage);
     }
 
+    #[test]
+    fn test_rpc_create_raw_transaction_invalid_inputs() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let meta = authentication();
+        let rpc = initialize_test_rpc(&storage, parameters);
+
+        let [sender, receiver, _] = &FIXTURE_VK.test_accounts;
+        let record = hex::encode(to_bytes![DATA.records_1[0]].unwrap());
+
+        let request = |old_records: Vec<String>, amount: u64| {
+            let params = TransactionInputs {
+                old_account_private_keys: vec![sender.private_key.to_string(); old_records.len()],
+                old_records,
+                recipients: vec![TransactionRecipient {
+                    address: receiver.address.to_string(),
+                    amount,
+                }],
+                memo: None,
+                network_id: 0,
+                new_birth_programs: vec![],
+                new_death_programs: vec![],
+                new_payloads: vec![],
+            };
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"createrawtransaction\", \"params\": [{}] }}",
+                serde_json::to_value(params).unwrap()
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            let extracted: Value = serde_json::from_str(&response).unwrap();
+            extracted["error"]["message"].clone()
+        };
+
+        let expected_error = RpcError::InvalidInputCount(Components::NUM_INPUT_RECORDS, 0).to_string();
+        assert_eq!(request(vec![], 100), Value::String(expected_error));
+
+        let expected_error = RpcError::InvalidInputCount(Components::NUM_INPUT_RECORDS, 3).to_string();
+        assert_eq!(request(vec![record.clone(); 3], 100), Value::String(expected_error));
+
+        let value = DATA.records_1[0].value();
+        let expected_error = RpcError::InsufficientInputs(value, value + 1).to_string();
+        assert_eq!(request(vec![record], value + 1), Value::String(expected_error));
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_create_raw_transaction_invalid_record_attributes() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let meta = authentication();
+        let rpc = initialize_test_rpc(&storage, parameters);
+
+        let [sender, receiver, _] = &FIXTURE_VK.test_accounts;
+        let record = hex::encode(to_bytes![DATA.records_1[0]].unwrap());
+
+        let request = |new_payloads: Vec<String>, new_birth_programs: Vec<String>| {
+            let params = TransactionInputs {
+                old_records: vec![record.clone()],
+                old_account_private_keys: vec![sender.private_key.to_string()],
+                recipients: vec![TransactionRecipient {
+                    address: receiver.address.to_string(),
+                    amount: 1,
+                }],
+                memo: None,
+                network_id: 0,
+                new_birth_programs,
+                new_death_programs: vec![],
+                new_payloads,
+            };
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"createrawtransaction\", \"params\": [{}] }}",
+                serde_json::to_value(params).unwrap()
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            let extracted: Value = serde_json::from_str(&response).unwrap();
+            extracted["error"]["message"].clone()
+        };
+
+        let payload = hex::encode([1u8; 32]);
+        let expected_error = RpcError::InvalidRecordAttributeCount("new_payloads", 1, 2).to_string();
+        assert_eq!(request(vec![payload.clone(); 2], vec![]), Value::String(expected_error));
+
+        let expected_error = RpcError::InvalidPayloadLength(32, 31).to_string();
+        assert_eq!(
+            request(vec![hex::encode([1u8; 31])], vec![]),
+            Value::String(expected_error)
+        );
+
+        let unknown_program = hex::encode([2u8; 48]);
+        let expected_error = RpcError::UnknownProgram(unknown_program.clone()).to_string();
+        assert_eq!(
+            request(vec![payload], vec![unknown_program]),
+            Value::String(expected_error)
+        );
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_create_transaction_insufficient_funds() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let meta = authentication();
+
+        TEST_CONSENSUS
+            .receive_block(&parameters, &storage, &mut MemoryPool::new(), &DATA.block_1)
+            .unwrap();
+
+        let rpc = initialize_test_rpc(&storage, parameters.clone());
+
+        let [sender, receiver, _] = &FIXTURE_VK.test_accounts;
+        let wallet_password = "wallet_password";
+
+        let mut wallet = Wallet::open(storage.clone(), parameters.system_parameters, wallet_password).unwrap();
+        wallet.import_account(sender.private_key.clone()).unwrap();
+        wallet.scan().unwrap();
+        wallet.save().unwrap();
+        let balance = wallet.balance(&sender.address, &MemoryPool::new()).unwrap();
+        drop(wallet);
+
+        let params = CreateTransactionInput {
+            from: sender.address.to_string(),
+            password: wallet_password.to_string(),
+            recipients: vec![TransactionRecipient {
+                address: receiver.address.to_string(),
+                amount: balance,
+            }],
+            fee: 1,
+            memo: None,
+        };
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"createtransaction\", \"params\": [{}] }}",
+            serde_json::to_value(params).unwrap()
+        );
+        let response = rpc.handle_request_sync(&request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        let expected_error = WalletError::InsufficientFunds(balance, balance + 1).to_string();
+        assert_eq!(extracted["error"]["message"], Value::String(expected_error));
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_send_many_twice() {
+        let storage = Arc::new(FIXTURE.ledger());
+        let parameters = FIXTURE.parameters.clone();
+        let meta = authentication();
+
+        TEST_CONSENSUS
+            .receive_block(&parameters, &storage, &mut MemoryPool::new(), &DATA.block_1)
+            .unwrap();
+
+        let rpc = initialize_test_rpc(&storage, parameters);
+
+        let [sender, receiver, _] = &FIXTURE_VK.test_accounts;
+        let wallet_password = "wallet_password";
+
+        let request = |method: &str, params: Value| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{}] }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        let import_account_input = ImportAccountInput {
+            private_key: sender.private_key.to_string(),
+            password: wallet_password.to_string(),
+        };
+        request("importaccount", serde_json::to_value(import_account_input).unwrap());
+
+        let send_many_input = serde_json::to_value(SendManyInput {
+            from: sender.address.to_string(),
+            password: wallet_password.to_string(),
+            recipients: vec![TransactionRecipient {
+                address: receiver.address.to_string(),
+                amount: 1,
+            }],
+            fee: 1,
+        })
+        .unwrap();
+
+        // The first send spends the only coinbase record of the sender in the memory pool
+        let output: SendManyOutput =
+            serde_json::from_value(request("sendmany", send_many_input.clone())["result"].clone()).unwrap();
+        assert_eq!(output.transaction_ids.len(), 1);
+
+        // The second send must not select the same record again
+        let expected_error = WalletError::InsufficientFunds(0, 2).to_string();
+        assert_eq!(
+            request("sendmany", send_many_input)["error"]["message"],
+            Value::String(expected_error)
+        );
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
     #[test]
     fn test_create_account() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -369,6 +582,93 @@ mod protected_rpc_tests {
         let _private_key = AccountPrivateKey::<Components>::from_str(&account.private_key).unwrap();
         let _address = AccountAddress::<Components>::from_str(&account.address).unwrap();
 
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+    #[test]
+    fn test_rpc_wallet() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let meta = authentication();
+
+        TEST_CONSENSUS
+            .receive_block(&parameters, &storage, &mut MemoryPool::new(), &DATA.block_1)
+            .unwrap();
+
+        let rpc = initialize_test_rpc(&storage, parameters);
+
+        let account = &FIXTURE_VK.test_accounts[0];
+        let wallet_password = "wallet_password";
+
+        let request = |method: &str, params: Value| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{}] }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            let extracted: Value = serde_json::from_str(&response).unwrap();
+            extracted["result"].clone()
+        };
+
+        let import_account_input = ImportAccountInput {
+            private_key: account.private_key.to_string(),
+            password: wallet_password.to_string(),
+        };
+        let address = request("importaccount", serde_json::to_value(import_account_input).unwrap());
+        assert_eq!(address, Value::String(account.address.to_string()));
+
+        let wallet_input = serde_json::to_value(WalletInput {
+            address: account.address.to_string(),
+            password: wallet_password.to_string(),
+        })
+        .unwrap();
+
+        let records = DATA.records_1.iter().filter(|record| !record.is_dummy());
+        let balance: u64 = records.clone().map(|record| record.value()).sum();
+        assert_eq!(request("getbalance", wallet_input.clone()), Value::from(balance));
+
+        let unspent: Vec<WalletRecordInfo> =
+            serde_json::from_value(request("listunspent", wallet_input.clone())).unwrap();
+        let expected_records: Vec<String> = records.map(|record| hex::encode(to_bytes![record].unwrap())).collect();
+        assert_eq!(
+            unspent.into_iter().map(|record| record.record).collect::<Vec<_>>(),
+            expected_records
+        );
+
+        let history: Vec<WalletTransactionInfo> =
+            serde_json::from_value(request("getwallethistory", wallet_input)).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].block_height, 1);
+        assert_eq!(history[0].received, balance);
+
+        // The open wallet is only used with the password it was opened with
+        let wrong_password_input = serde_json::to_value(WalletInput {
+            address: account.address.to_string(),
+            password: "wrong password".to_string(),
+        })
+        .unwrap();
+        assert_eq!(request("getbalance", wrong_password_input), Value::Null);
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_generate_requires_dev_chain() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let meta = authentication();
+        let rpc = initialize_test_rpc(&storage, parameters);
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"generate\", \"params\": [1] }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        let expected_result = Value::String(RpcError::DevChainRequired.to_string());
+        assert_eq!(extracted["error"]["message"], expected_result);
+        assert_eq!(storage.get_block_count(), 1);
+
         drop(rpc);
         kill_storage_sync(storage);
     }
@@ -17,7 +17,7 @@
 /// Tests for public RPC endpoints
 mod rpc_tests {
     use snarkos_consensus::{get_block_reward, MerkleTreeLedger};
-    use snarkos_dpc::base_dpc::instantiated::Tx;
+    use snarkos_dpc::base_dpc::{instantiated::Tx, program::ProgramRegistry};
     use snarkos_models::objects::Transaction;
     use snarkos_rpc::*;
     use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, network::*, storage::*};
@@ -46,10 +46,12 @@ mod rpc_tests {
         );
 
         let consensus = TEST_CONSENSUS.clone();
+        let program_registry = Arc::new(ProgramRegistry::new(&parameters).unwrap());
         Rpc::new(
             RpcImpl::new(
                 storage.clone(),
                 parameters,
+                program_registry,
                 server.context.clone(),
                 consensus,
                 server.memory_pool_lock,
@@ -306,6 +308,88 @@ mod rpc_tests {
         kill_storage_sync(storage);
     }
 
+    #[test]
+    fn test_rpc_get_programs() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(&storage);
+
+        let method = "getprograms".to_string();
+
+        let result = make_request_no_params(&rpc, method);
+
+        let program_registry = ProgramRegistry::new(&load_verifying_parameters()).unwrap();
+        let noop_program_id = Value::String(hex::encode(program_registry.noop_program_id()));
+
+        assert_eq!(result.as_array().unwrap(), &vec![noop_program_id]);
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_get_block_transactions() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        storage.insert_and_commit(&DATA.block_1).unwrap();
+        let rpc = initialize_test_rpc(&storage);
+
+        let response = rpc.request("getblocktransactions", &(0u32, 1u32));
+        let blocks: Value = serde_json::from_str(&response).unwrap();
+
+        for (height, block) in [genesis(), DATA.block_1.clone()].iter().enumerate() {
+            let transaction_ids: Vec<Value> = block
+                .transactions
+                .iter()
+                .map(|transaction| Value::String(hex::encode(transaction.transaction_id().unwrap())))
+                .collect();
+
+            assert_eq!(blocks[height]["height"], height);
+            assert_eq!(blocks[height]["hash"], hex::encode(block.header.get_hash().0));
+            assert_eq!(blocks[height]["transactions"], Value::Array(transaction_ids));
+        }
+
+        // The range must be within the canon chain
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getblocktransactions\", \"params\": [1, 2] }";
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(request).unwrap()).unwrap();
+        assert!(response["error"].is_object());
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_rpc_explorer_index() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        storage.insert_and_commit(&DATA.block_1).unwrap();
+        storage.enable_explorer_index().unwrap();
+        let rpc = initialize_test_rpc(&storage);
+
+        let transaction = &DATA.block_1.transactions.0[0];
+        let transaction_id = format![r#""{}""#, hex::encode(transaction.transaction_id().unwrap())];
+
+        for cm in transaction.new_commitments() {
+            let commitment = hex::encode(to_bytes![cm].unwrap());
+            assert_eq!(rpc.request("getcommitmenttransaction", &[commitment]), transaction_id);
+        }
+
+        for sn in transaction.old_serial_numbers() {
+            let mut serial_number: Vec<u8> = vec![];
+            CanonicalSerialize::serialize(sn, &mut serial_number).unwrap();
+            assert_eq!(
+                rpc.request("getserialnumbertransaction", &[hex::encode(serial_number)]),
+                transaction_id
+            );
+        }
+
+        let memo = hex::encode(transaction.memorandum());
+        assert_eq!(rpc.request("getmemotransaction", &[memo]), transaction_id);
+
+        // Unknown memos have no transaction
+        assert_eq!(rpc.request("getmemotransaction", &[hex::encode([0u8; 32])]), "null");
+
+        drop(rpc);
+        kill_storage_sync(storage);
+    }
+
     #[test]
     fn test_rpc_get_peer_info() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -0,0 +1,230 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+/// Tests for the WebSocket subscription protocol
+mod subscription_tests {
+    use snarkos_consensus::ConsensusEvent;
+    use snarkos_dpc::base_dpc::instantiated::Tx;
+    use snarkos_models::{dpc::Record, objects::Transaction};
+    use snarkos_objects::AccountViewKey;
+    use snarkos_rpc::*;
+    use snarkos_testing::consensus::*;
+    use snarkos_utilities::{bytes::ToBytes, to_bytes};
+
+    use serde_json::Value;
+    use tokio_tungstenite::tungstenite::http::Request;
+
+    fn request(session: &mut SubscriptionSession, method: &str, params: &str) -> Value {
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{}] }}",
+            method, params
+        );
+        let response = session.handle_request(&request).unwrap();
+
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn new_block_event() -> ConsensusEvent<Tx> {
+        ConsensusEvent::NewBlock {
+            height: 1,
+            block: DATA.block_1.clone(),
+        }
+    }
+
+    #[test]
+    fn test_subscribe_newblocks() {
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+
+        let response = request(&mut session, "subscribe_newblocks", "");
+        let subscription_id = response["result"].clone();
+        assert_eq!(subscription_id, Value::from(1));
+
+        let notifications = session.notifications(&new_block_event());
+        assert_eq!(notifications.len(), 1);
+
+        let notification: Value = serde_json::from_str(&notifications[0]).unwrap();
+        assert_eq!(notification["method"], SUBSCRIPTION_NOTIFICATION);
+        assert_eq!(notification["params"]["subscription"], subscription_id);
+        assert_eq!(
+            notification["params"]["result"]["hash"],
+            hex::encode(DATA.block_1.header.get_hash().0)
+        );
+        assert_eq!(notification["params"]["result"]["height"], 1);
+        assert_eq!(
+            notification["params"]["result"]["transactions"][0],
+            hex::encode(DATA.block_1.transactions.0[0].transaction_id().unwrap())
+        );
+
+        // Other events are not sent to block subscribers
+        let reorg = ConsensusEvent::Reorg {
+            shared_block_height: 0,
+            reverted_blocks: vec![DATA.block_1.clone()],
+        };
+        assert!(session.notifications(&reorg).is_empty());
+
+        let response = request(&mut session, "unsubscribe", "1");
+        assert_eq!(response["result"], true);
+        assert!(session.notifications(&new_block_event()).is_empty());
+
+        let response = request(&mut session, "unsubscribe", "1");
+        assert_eq!(response["result"], false);
+    }
+
+    #[test]
+    fn test_subscribe_reorgs_and_mempool() {
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+
+        assert_eq!(request(&mut session, "subscribe_reorgs", "")["result"], 1);
+        assert_eq!(request(&mut session, "subscribe_mempool", "")["result"], 2);
+
+        let reorg = ConsensusEvent::Reorg {
+            shared_block_height: 0,
+            reverted_blocks: vec![DATA.block_1.clone()],
+        };
+        let notifications = session.notifications(&reorg);
+        assert_eq!(notifications.len(), 1);
+
+        let notification: Value = serde_json::from_str(&notifications[0]).unwrap();
+        assert_eq!(notification["params"]["subscription"], 1);
+        assert_eq!(notification["params"]["result"]["shared_block_height"], 0);
+        assert_eq!(
+            notification["params"]["result"]["reverted_blocks"][0],
+            hex::encode(DATA.block_1.header.get_hash().0)
+        );
+
+        let new_transaction = ConsensusEvent::NewTransaction {
+            size: TRANSACTION_1.len(),
+            transaction: DATA.block_1.transactions.0[0].clone(),
+        };
+        let notifications = session.notifications(&new_transaction);
+        assert_eq!(notifications.len(), 1);
+
+        let notification: Value = serde_json::from_str(&notifications[0]).unwrap();
+        assert_eq!(notification["params"]["subscription"], 2);
+        assert_eq!(
+            notification["params"]["result"]["transaction_id"],
+            hex::encode(DATA.block_1.transactions.0[0].transaction_id().unwrap())
+        );
+        assert_eq!(notification["params"]["result"]["size"], TRANSACTION_1.len());
+    }
+
+    #[test]
+    fn test_subscribe_records() {
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+
+        let system_parameters = &FIXTURE_VK.parameters.system_parameters;
+        let [miner_acc, _, _] = FIXTURE_VK.test_accounts.clone();
+        let view_key = AccountViewKey::from_private_key(
+            &system_parameters.account_signature,
+            &system_parameters.account_commitment,
+            &miner_acc.private_key,
+        )
+        .unwrap();
+
+        let response = request(&mut session, "subscribe_records", &format!("\"{}\"", view_key));
+        assert_eq!(response["result"], 1);
+
+        let records: Vec<_> = DATA.records_1.iter().filter(|record| !record.is_dummy()).collect();
+        assert!(!records.is_empty());
+
+        let notifications = session.notifications(&new_block_event());
+        assert_eq!(notifications.len(), records.len());
+
+        for (notification, record) in notifications.iter().zip(records) {
+            let notification: Value = serde_json::from_str(notification).unwrap();
+            let result = &notification["params"]["result"];
+
+            assert_eq!(result["record"], hex::encode(to_bytes![record].unwrap()));
+            assert_eq!(
+                result["commitment"],
+                hex::encode(to_bytes![record.commitment()].unwrap())
+            );
+            assert_eq!(result["value"], record.value());
+            assert_eq!(result["block_height"], 1);
+        }
+
+        // Records of other accounts are not decrypted
+        let [_, receiver, _] = FIXTURE_VK.test_accounts.clone();
+        let view_key = AccountViewKey::from_private_key(
+            &system_parameters.account_signature,
+            &system_parameters.account_commitment,
+            &receiver.private_key,
+        )
+        .unwrap();
+
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+        request(&mut session, "subscribe_records", &format!("\"{}\"", view_key));
+        assert!(session.notifications(&new_block_event()).is_empty());
+    }
+
+    #[test]
+    fn test_subscription_errors() {
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+
+        let response = request(&mut session, "subscribe_blocks", "");
+        assert_eq!(response["error"]["code"], -32601);
+
+        let response = request(&mut session, "subscribe_records", "\"invalid view key\"");
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response: Value = serde_json::from_str(&session.handle_request("not json").unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], -32700);
+
+        // Notifications from the client are not answered
+        let notification = "{ \"jsonrpc\":\"2.0\", \"method\": \"subscribe_newblocks\", \"params\": [] }";
+        assert!(session.handle_request(notification).is_none());
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        let mut session = SubscriptionSession::new(FIXTURE_VK.parameters.system_parameters.clone());
+
+        for subscription_id in 1..=MAX_SUBSCRIPTIONS {
+            let response = request(&mut session, "subscribe_mempool", "");
+            assert_eq!(response["result"], Value::from(subscription_id));
+        }
+
+        let response = request(&mut session, "subscribe_newblocks", "");
+        assert_eq!(response["error"]["code"], -32602);
+
+        // Unsubscribing frees a subscription
+        request(&mut session, "unsubscribe", "1");
+        let response = request(&mut session, "subscribe_newblocks", "");
+        assert_eq!(response["result"], Value::from(MAX_SUBSCRIPTIONS + 1));
+    }
+
+    #[test]
+    fn test_handshake_authorization() {
+        let credentials = RpcCredentials {
+            username: "username".into(),
+            password: "password".into(),
+        };
+        let handshake = |authorization: &str| {
+            Request::builder()
+                .header("Authorization", authorization)
+                .body(())
+                .unwrap()
+        };
+
+        let valid = handshake(&format!("Basic {}", base64::encode("username:password")));
+        let invalid = handshake(&format!("Basic {}", base64::encode("username:wrong password")));
+
+        assert!(is_authorized(Some(&credentials), &valid));
+        assert!(!is_authorized(Some(&credentials), &invalid));
+        assert!(!is_authorized(Some(&credentials), &Request::new(())));
+        assert!(is_authorized(None, &Request::new(())));
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::Config;
+use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::{CommitmentMerkleParameters, Components, Tx},
+    parameters::PublicParameters,
+};
+use snarkos_errors::{consensus::ConsensusError, node::NodeError};
+use snarkos_objects::Block;
+use snarkos_storage::{KeyValueStore, Ledger};
+use snarkos_utilities::bytes::FromBytes;
+
+use clap::ArgMatches;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+/// Handles the `export-chain` and `import-chain` subcommands.
+///
+/// A chain snapshot is the sequence of canonical blocks, starting from genesis, each encoded with
+/// `Block::serialize`, the encoding of blocks in "block" messages.
+pub struct ChainCLI;
+
+impl ChainCLI {
+    /// Export the canonical chain of the configured ledger to the given snapshot file
+    pub fn export(arguments: &ArgMatches, config: &Config) -> Result<(), NodeError> {
+        let path = arguments.value_of("file").expect("file is a required argument");
+
+        let storage = MerkleTreeLedger::open_at_path(config.storage_path())?;
+        let num_blocks = export_chain(&storage, BufWriter::new(File::create(path)?))?;
+
+        println!("Exported {} blocks to {}", num_blocks, path);
+        Ok(())
+    }
+
+    /// Import the blocks of the given snapshot file into the configured ledger
+    pub fn import(arguments: &ArgMatches, config: &Config) -> Result<(), NodeError> {
+        let path = arguments.value_of("file").expect("file is a required argument");
+        let trusted = arguments.is_present("trusted");
+
+        let storage = MerkleTreeLedger::open_at_path(config.storage_path())?;
+        let parameters = PublicParameters::<Components>::load(true)?;
+        let consensus = config.consensus_parameters(&parameters)?;
+
+        let num_blocks = import_chain(
+            &consensus,
+            &parameters,
+            &storage,
+            BufReader::new(File::open(path)?),
+            trusted,
+        )?;
+
+        println!("Imported {} blocks from {}", num_blocks, path);
+        Ok(())
+    }
+}
+
+/// Writes every canonical block of the ledger to the writer and returns the number of blocks written.
+pub fn export_chain<S: KeyValueStore, W: Write>(
+    storage: &Ledger<Tx, CommitmentMerkleParameters, S>,
+    mut writer: W,
+) -> Result<u32, NodeError> {
+    let latest_block_height = storage.get_latest_block_height();
+
+    for block_number in 0..=latest_block_height {
+        let block = storage.get_block_from_block_number(block_number)?;
+        writer.write_all(&block.serialize()?)?;
+    }
+    writer.flush()?;
+
+    Ok(latest_block_height + 1)
+}
+
+/// Reads the blocks of a chain snapshot and adds them to the ledger, returning the number of new blocks.
+///
+/// Each block must extend the canon chain. Unless `trusted` is set, every block is received through
+/// `ConsensusParameters::receive_block`, exactly as a block from a peer. If `trusted` is set, the proof
+/// of succinct work and the transaction proofs are skipped, but the remaining header fields, the
+/// coinbase transaction and the block reward are still verified.
+/// Blocks that already exist in the ledger are skipped.
+pub fn import_chain<R: BufRead>(
+    consensus: &ConsensusParameters,
+    parameters: &PublicParameters<Components>,
+    storage: &MerkleTreeLedger,
+    mut reader: R,
+    trusted: bool,
+) -> Result<u32, NodeError> {
+    let mut memory_pool = MemoryPool::new();
+    let mut num_blocks = 0;
+
+    while !reader.fill_buf()?.is_empty() {
+        let block = Block::<Tx>::read(&mut reader)?;
+
+        let block_hash = block.header.get_hash();
+        if storage.block_hash_exists(&block_hash) {
+            continue;
+        }
+
+        let latest_block_hash = storage.get_latest_block()?.header.get_hash();
+        if block.header.previous_block_hash != latest_block_hash {
+            return Err(NodeError::Message(format!(
+                "block {} does not extend the canon chain",
+                block_hash
+            )));
+        }
+
+        if trusted {
+            let block_size = block.serialize()?.len();
+            if block_size > consensus.max_block_size {
+                return Err(ConsensusError::BlockTooLarge(block_size, consensus.max_block_size).into());
+            }
+
+            if !consensus.verify_block_without_proofs(&block, storage)? {
+                return Err(NodeError::Message(format!("block {} is invalid", block_hash)));
+            }
+
+            storage.insert_and_commit(&block)?;
+        } else {
+            consensus.receive_block(parameters, storage, &mut memory_pool, &block)?;
+        }
+
+        num_blocks += 1;
+    }
+
+    Ok(num_blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, storage::*};
+
+    fn empty_ledger() -> MerkleTreeLedger {
+        FIXTURE_VK.ledger()
+    }
+
+    /// Returns a ledger with the two test blocks on top of the genesis block, and its snapshot.
+    fn exported_ledger() -> (MerkleTreeLedger, Vec<u8>) {
+        let ledger = empty_ledger();
+        ledger
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+        ledger
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_2[..]).unwrap())
+            .unwrap();
+
+        let mut snapshot = vec![];
+        assert_eq!(export_chain(&ledger, &mut snapshot).unwrap(), 3);
+
+        (ledger, snapshot)
+    }
+
+    /// Returns the offset of each block of the ledger in its snapshot.
+    fn block_offsets(ledger: &MerkleTreeLedger) -> Vec<usize> {
+        let mut offsets = vec![];
+        let mut offset = 0;
+        for block_number in 0..=ledger.get_latest_block_height() {
+            offsets.push(offset);
+            offset += ledger
+                .get_block_from_block_number(block_number)
+                .unwrap()
+                .serialize()
+                .unwrap()
+                .len();
+        }
+        offsets
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let (source, snapshot) = exported_ledger();
+        let parameters = load_verifying_parameters();
+
+        for trusted in vec![false, true] {
+            let ledger = empty_ledger();
+
+            let num_blocks = import_chain(&TEST_CONSENSUS, &parameters, &ledger, &snapshot[..], trusted).unwrap();
+            assert_eq!(num_blocks, 2);
+
+            assert_eq!(ledger.get_latest_block_height(), source.get_latest_block_height());
+            for block_number in 0..=source.get_latest_block_height() {
+                assert_eq!(
+                    ledger.get_block_hash(block_number).unwrap(),
+                    source.get_block_hash(block_number).unwrap()
+                );
+            }
+
+            // Importing the snapshot again skips the known blocks
+            let num_blocks = import_chain(&TEST_CONSENSUS, &parameters, &ledger, &snapshot[..], trusted).unwrap();
+            assert_eq!(num_blocks, 0);
+
+            kill_storage(ledger);
+        }
+
+        kill_storage(source);
+    }
+
+    #[test]
+    fn test_import_truncated_snapshot() {
+        let (source, snapshot) = exported_ledger();
+        let parameters = load_verifying_parameters();
+        let last_block_offset = block_offsets(&source)[2];
+
+        // The snapshot ends within the last block, or within its header
+        for truncated_length in vec![snapshot.len() - 1, last_block_offset + 2] {
+            for trusted in vec![false, true] {
+                let ledger = empty_ledger();
+
+                let result = import_chain(
+                    &TEST_CONSENSUS,
+                    &parameters,
+                    &ledger,
+                    &snapshot[..truncated_length],
+                    trusted,
+                );
+                assert!(result.is_err());
+                assert_eq!(ledger.get_latest_block_height(), 1);
+
+                kill_storage(ledger);
+            }
+        }
+
+        kill_storage(source);
+    }
+
+    #[test]
+    fn test_import_corrupted_snapshot() {
+        let (source, snapshot) = exported_ledger();
+        let parameters = load_verifying_parameters();
+        let block_1_offset = block_offsets(&source)[1];
+
+        // A changed merkle root no longer matches the transactions of the block, even in trusted mode
+        let mut corrupted = snapshot;
+        corrupted[block_1_offset + 32] ^= 1;
+
+        for trusted in vec![false, true] {
+            let ledger = empty_ledger();
+
+            let result = import_chain(&TEST_CONSENSUS, &parameters, &ledger, &corrupted[..], trusted);
+            assert!(result.is_err());
+            assert_eq!(ledger.get_latest_block_height(), 0);
+
+            kill_storage(ledger);
+        }
+
+        kill_storage(source);
+    }
+}
@@ -19,7 +19,14 @@ use crate::{
     parameters::{flag, option, subcommand, types::*},
     update::UpdateCLI,
 };
-use snarkos_errors::node::CliError;
+use snarkos_consensus::{memory_pool::DEFAULT_MEMORY_POOL_SIZE, ConsensusParameters, ConsensusProfile, GenesisSource};
+use snarkos_dpc::base_dpc::{instantiated::Components, parameters::PublicParameters, BaseDPCComponents};
+use snarkos_errors::node::{CliError, NodeError};
+use snarkos_models::algorithms::{CRH, SNARK};
+use snarkos_network::DEFAULT_BAN_DURATION;
+use snarkos_objects::Network;
+use snarkos_posw::PoswMarlin;
+use snarkos_utilities::{to_bytes, ToBytes};
 
 use clap::ArgMatches;
 use dirs::home_dir;
@@ -30,10 +37,8 @@ use std::{
 };
 use toml;
 
-/// Hardcoded bootnodes maintained by Aleo.
-/// A node should try and connect to these first after coming online.
-pub const MAINNET_BOOTNODES: &'static [&str] = &[]; // "192.168.0.1:4130"
-pub const TESTNET_BOOTNODES: &'static [&str] = &["50.18.83.123:4131"]; // "192.168.0.1:4131"
+/// The database of a development chain, which is reset every time the node starts.
+pub const DEV_DB: &str = "snarkos_dev";
 
 /// Represents all configuration options for a node.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -54,6 +59,8 @@ pub struct Aleo {
 pub struct JsonRPC {
     pub json_rpc: bool,
     pub port: u16,
+    #[serde(default = "default_ws_port")]
+    pub ws_port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
 }
@@ -66,6 +73,10 @@ pub struct Node {
     pub ip: String,
     pub port: u16,
     pub verbose: u8,
+    #[serde(default)]
+    pub explorer_index: bool,
+    #[serde(default)]
+    pub dev: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -79,8 +90,34 @@ pub struct P2P {
     #[serde(skip_serializing, skip_deserializing)]
     pub bootnodes: Vec<String>,
     pub mempool_interval: u8,
+    #[serde(default = "default_mempool_size")]
+    pub mempool_size: usize,
     pub min_peers: u16,
     pub max_peers: u16,
+    #[serde(default = "default_ban_duration")]
+    pub ban_duration: u64,
+    #[serde(default = "default_encryption")]
+    pub encryption: bool,
+}
+
+/// The memory pool byte budget used when the config file does not specify one.
+fn default_mempool_size() -> usize {
+    DEFAULT_MEMORY_POOL_SIZE
+}
+
+/// The number of seconds misbehaving peers are banned for when the config file does not specify one.
+fn default_ban_duration() -> u64 {
+    DEFAULT_BAN_DURATION
+}
+
+/// The port of the WebSocket subscription server when the config file does not specify one.
+fn default_ws_port() -> u16 {
+    3031
+}
+
+/// Peer connections are encrypted when the config file does not specify otherwise.
+fn default_encryption() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -94,6 +131,8 @@ impl Default for Config {
                 ip: "0.0.0.0".into(),
                 port: 4131,
                 verbose: 1,
+                explorer_index: false,
+                dev: false,
             },
             miner: Miner {
                 is_miner: false,
@@ -102,24 +141,98 @@ impl Default for Config {
             rpc: JsonRPC {
                 json_rpc: true,
                 port: 3030,
+                ws_port: default_ws_port(),
                 // TODO (raychu86) Establish a random username and password for the node operator by default
                 username: Some("Username".into()),
                 password: Some("Password".into()),
             },
             p2p: P2P {
-                bootnodes: TESTNET_BOOTNODES
-                    .iter()
-                    .map(|node| (*node).to_string())
-                    .collect::<Vec<String>>(),
+                bootnodes: ConsensusProfile::testnet1().bootnodes,
                 mempool_interval: 5,
+                mempool_size: default_mempool_size(),
                 min_peers: 2,
                 max_peers: 20,
+                ban_duration: default_ban_duration(),
+                encryption: default_encryption(),
             },
         }
     }
 }
 
 impl Config {
+    /// Returns the consensus profile of the configured network
+    pub fn consensus_profile(&self) -> ConsensusProfile {
+        let mut profile = ConsensusProfile::from_network(Network::from_network_id(self.aleo.network_id));
+        if self.node.dev {
+            profile.genesis = GenesisSource::Generated;
+            profile.bootnodes = vec![];
+        }
+
+        profile
+    }
+
+    /// Returns the consensus parameters of the configured network
+    pub fn consensus_parameters(
+        &self,
+        parameters: &PublicParameters<Components>,
+    ) -> Result<ConsensusParameters, NodeError> {
+        // Fetch the valid inner snark ids
+        let inner_snark_vk: <<Components as BaseDPCComponents>::InnerSNARK as SNARK>::VerificationParameters =
+            parameters.inner_snark_parameters.1.clone().into();
+        let inner_snark_id = parameters
+            .system_parameters
+            .inner_snark_verification_key_crh
+            .hash(&to_bytes![inner_snark_vk]?)?;
+
+        let authorized_inner_snark_ids = vec![to_bytes![inner_snark_id]?];
+
+        Ok(self.consensus_profile().consensus_parameters(
+            PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
+            authorized_inner_snark_ids,
+        ))
+    }
+
+    /// The name of the node's ledger database.
+    /// A development chain always uses its own database, whether it is enabled from the CLI or the config file.
+    fn db(&self) -> &str {
+        match self.node.dev {
+            true => DEV_DB,
+            false => &self.node.db,
+        }
+    }
+
+    /// The path of the node's ledger database
+    pub fn storage_path(&self) -> PathBuf {
+        let mut path = self.node.dir.clone();
+        path.push(self.db());
+
+        path
+    }
+
+    /// The path of the development chain database, if the node runs a development chain
+    pub fn dev_storage_path(&self) -> Option<PathBuf> {
+        match self.node.dev {
+            true => Some(self.storage_path()),
+            false => None,
+        }
+    }
+
+    /// The path of the node's identity key file, which is kept next to the ledger database
+    pub fn identity_path(&self) -> PathBuf {
+        let mut path = self.node.dir.clone();
+        path.push(format!("{}.key", self.db()));
+
+        path
+    }
+
+    /// The path of the proving key file of the node's asset program, which is kept next to the ledger database
+    pub fn asset_program_path(&self) -> PathBuf {
+        let mut path = self.node.dir.clone();
+        path.push(format!("{}.asset_program", self.db()));
+
+        path
+    }
+
     /// The directory that snarkOS system files will be stored
     fn snarkos_dir() -> PathBuf {
         let mut path = home_dir().unwrap_or(std::env::current_dir().unwrap());
@@ -154,15 +267,7 @@ impl Config {
         // Parse the contents into the `Config` struct
         let mut config: Config = toml::from_str(&toml_string)?;
 
-        let bootnodes = match config.aleo.network_id {
-            0 => MAINNET_BOOTNODES,
-            _ => TESTNET_BOOTNODES,
-        };
-
-        config.p2p.bootnodes = bootnodes
-            .iter()
-            .map(|node| (*node).to_string())
-            .collect::<Vec<String>>();
+        config.p2p.bootnodes = config.consensus_profile().bootnodes;
 
         Ok(config)
     }
@@ -173,17 +278,23 @@ impl Config {
             "is-bootnode" => self.is_bootnode(arguments.is_present(option)),
             "is-miner" => self.is_miner(arguments.is_present(option)),
             "no-jsonrpc" => self.no_jsonrpc(arguments.is_present(option)),
+            "no-encryption" => self.no_encryption(arguments.is_present(option)),
+            "explorer-index" => self.explorer_index(arguments.is_present(option)),
+            "dev" => self.dev(arguments.is_present(option)),
             // Options
             "connect" => self.connect(arguments.value_of(option)),
             "ip" => self.ip(arguments.value_of(option)),
             "miner-address" => self.miner_address(arguments.value_of(option)),
             "mempool-interval" => self.mempool_interval(clap::value_t!(arguments.value_of(*option), u8).ok()),
+            "mempool-size" => self.mempool_size(clap::value_t!(arguments.value_of(*option), usize).ok()),
             "max-peers" => self.max_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
             "min-peers" => self.min_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
+            "ban-duration" => self.ban_duration(clap::value_t!(arguments.value_of(*option), u64).ok()),
             "network" => self.network(clap::value_t!(arguments.value_of(*option), u8).ok()),
             "path" => self.path(arguments.value_of(option)),
             "port" => self.port(clap::value_t!(arguments.value_of(*option), u16).ok()),
             "rpc-port" => self.rpc_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
+            "ws-port" => self.ws_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
             "rpc-username" => self.rpc_username(arguments.value_of(option)),
             "rpc-password" => self.rpc_password(arguments.value_of(option)),
             "verbose" => self.verbose(clap::value_t!(arguments.value_of(*option), u8).ok()),
@@ -192,28 +303,21 @@ impl Config {
     }
 
     /// Sets `network` to the specified network, overriding its previous state.
+    /// The bootnodes are those of the consensus profile of the network.
     fn network(&mut self, argument: Option<u8>) {
         if let Some(network_id) = argument {
             match network_id {
                 0 => {
                     self.node.db = "snarkos_mainnet".into();
                     self.node.port = 4130;
-                    self.p2p.bootnodes = MAINNET_BOOTNODES
-                        .iter()
-                        .map(|node| (*node).to_string())
-                        .collect::<Vec<String>>();
-                    self.aleo.network_id = network_id;
                 }
                 _ => {
                     self.node.db = format!("snarkos_testnet{}", network_id);
                     self.node.port = 4130 + (network_id as u16);
-                    self.p2p.bootnodes = TESTNET_BOOTNODES
-                        .iter()
-                        .map(|node| (*node).to_string())
-                        .collect::<Vec<String>>();
-                    self.aleo.network_id = network_id;
                 }
             }
+            self.aleo.network_id = network_id;
+            self.p2p.bootnodes = self.consensus_profile().bootnodes;
         }
     }
 
@@ -221,6 +325,26 @@ impl Config {
         self.rpc.json_rpc = !argument;
     }
 
+    fn no_encryption(&mut self, argument: bool) {
+        if argument {
+            self.p2p.encryption = false;
+        }
+    }
+
+    fn explorer_index(&mut self, argument: bool) {
+        if argument {
+            self.node.explorer_index = true;
+        }
+    }
+
+    /// Runs a local development chain in its own database, without connecting to the bootnodes.
+    fn dev(&mut self, argument: bool) {
+        if argument {
+            self.node.dev = true;
+            self.p2p.bootnodes = vec![];
+        }
+    }
+
     fn is_bootnode(&mut self, argument: bool) {
         self.node.is_bootnode = argument;
         if argument {
@@ -271,6 +395,12 @@ impl Config {
         }
     }
 
+    fn mempool_size(&mut self, argument: Option<usize>) {
+        if let Some(size) = argument {
+            self.p2p.mempool_size = size
+        }
+    }
+
     fn min_peers(&mut self, argument: Option<u16>) {
         if let Some(num_peers) = argument {
             self.p2p.min_peers = num_peers;
@@ -283,12 +413,24 @@ impl Config {
         }
     }
 
+    fn ban_duration(&mut self, argument: Option<u64>) {
+        if let Some(ban_duration) = argument {
+            self.p2p.ban_duration = ban_duration;
+        }
+    }
+
     fn rpc_port(&mut self, argument: Option<u16>) {
         if let Some(rpc_port) = argument {
             self.rpc.port = rpc_port;
         }
     }
 
+    fn ws_port(&mut self, argument: Option<u16>) {
+        if let Some(ws_port) = argument {
+            self.rpc.ws_port = ws_port;
+        }
+    }
+
     fn rpc_username(&mut self, argument: Option<&str>) {
         if let Some(username) = argument {
             self.rpc.username = Some(username.to_string());
@@ -311,11 +453,46 @@ impl Config {
 /// Parses command line arguments into node configuration parameters.
 pub struct ConfigCli;
 
+impl ConfigCli {
+    /// The flags and options that are parsed into the node configuration.
+    const ARGUMENTS: &'static [&'static str] = &[
+        "network",
+        "dev",
+        "no-jsonrpc",
+        "no-encryption",
+        "explorer-index",
+        "is-bootnode",
+        "is-miner",
+        "ip",
+        "port",
+        "path",
+        "connect",
+        "miner-address",
+        "mempool-interval",
+        "mempool-size",
+        "min-peers",
+        "max-peers",
+        "ban-duration",
+        "rpc-port",
+        "ws-port",
+        "rpc-username",
+        "rpc-password",
+        "verbose",
+    ];
+}
+
 impl CLI for ConfigCli {
     type Config = Config;
 
     const ABOUT: AboutType = "Run an Aleo node (include -h for more options)";
-    const FLAGS: &'static [FlagType] = &[flag::NO_JSONRPC, flag::IS_BOOTNODE, flag::IS_MINER];
+    const FLAGS: &'static [FlagType] = &[
+        flag::NO_JSONRPC,
+        flag::NO_ENCRYPTION,
+        flag::IS_BOOTNODE,
+        flag::IS_MINER,
+        flag::EXPLORER_INDEX,
+        flag::DEV,
+    ];
     const NAME: NameType = "snarkOS";
     const OPTIONS: &'static [OptionType] = &[
         option::IP,
@@ -324,37 +501,24 @@ impl CLI for ConfigCli {
         option::CONNECT,
         option::MINER_ADDRESS,
         option::MEMPOOL_INTERVAL,
+        option::MEMPOOL_SIZE,
         option::MIN_PEERS,
         option::MAX_PEERS,
+        option::BAN_DURATION,
         option::NETWORK,
         option::RPC_PORT,
+        option::WS_PORT,
         option::RPC_USERNAME,
         option::RPC_PASSWORD,
         option::VERBOSE,
     ];
-    const SUBCOMMANDS: &'static [SubCommandType] = &[subcommand::UPDATE];
+    const SUBCOMMANDS: &'static [SubCommandType] =
+        &[subcommand::UPDATE, subcommand::EXPORT_CHAIN, subcommand::IMPORT_CHAIN];
 
     /// Handle all CLI arguments and flags for skeleton node
     fn parse(arguments: &ArgMatches) -> Result<Self::Config, CliError> {
         let mut config = Config::read_config()?;
-        config.parse(arguments, &[
-            "network",
-            "no-jsonrpc",
-            "is-bootnode",
-            "is-miner",
-            "ip",
-            "port",
-            "path",
-            "connect",
-            "miner-address",
-            "mempool-interval",
-            "min-peers",
-            "max-peers",
-            "rpc-port",
-            "rpc-username",
-            "rpc-password",
-            "verbose",
-        ]);
+        config.parse(arguments, Self::ARGUMENTS);
 
         match arguments.subcommand() {
             ("update", Some(arguments)) => {
@@ -367,3 +531,51 @@ impl CLI for ConfigCli {
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::{App, Arg};
+
+    fn parse_arguments(arguments: &[&str]) -> Config {
+        let flags = ConfigCli::FLAGS
+            .iter()
+            .map(|flag| Arg::from_usage(flag))
+            .collect::<Vec<Arg<'static, 'static>>>();
+        let options = ConfigCli::OPTIONS
+            .iter()
+            .map(|option| Arg::from_usage(option.0))
+            .collect::<Vec<Arg<'static, 'static>>>();
+        let matches = App::new(ConfigCli::NAME)
+            .args(&flags)
+            .args(&options)
+            .get_matches_from(std::iter::once(ConfigCli::NAME).chain(arguments.iter().cloned()));
+
+        let mut config = Config::default();
+        config.parse(&matches, ConfigCli::ARGUMENTS);
+        config
+    }
+
+    #[test]
+    fn test_parse_ban_duration() {
+        assert_eq!(parse_arguments(&[]).p2p.ban_duration, DEFAULT_BAN_DURATION);
+        assert_eq!(parse_arguments(&["--ban-duration=60"]).p2p.ban_duration, 60);
+    }
+
+    #[test]
+    fn test_dev_storage_path() {
+        let config = Config::default();
+        assert_eq!(config.storage_path(), config.node.dir.join(&config.node.db));
+        assert_eq!(config.dev_storage_path(), None);
+
+        // A development chain enabled in the config file does not use the configured database
+        let mut config = Config::default();
+        config.node.dev = true;
+        assert_eq!(config.storage_path(), config.node.dir.join(DEV_DB));
+        assert_eq!(config.dev_storage_path(), Some(config.node.dir.join(DEV_DB)));
+
+        let config = parse_arguments(&["--dev"]);
+        assert_eq!(config.dev_storage_path(), Some(config.node.dir.join(DEV_DB)));
+    }
+}
@@ -18,29 +18,33 @@
 extern crate log;
 
 use snarkos::{
+    chain::ChainCLI,
     cli::CLI,
     config::{Config, ConfigCli},
     display::render_init,
 };
-use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
-use snarkos_dpc::base_dpc::{instantiated::Components, parameters::PublicParameters, BaseDPCComponents};
+use snarkos_consensus::{GenesisSource, MemoryPool, MerkleTreeLedger, Miner};
+use snarkos_dpc::base_dpc::{
+    instantiated::{AssetProgramSNARK, Components},
+    parameters::PublicParameters,
+    program::{AssetProgram, ProgramRegistry, NODE_ASSET_ID},
+};
 use snarkos_errors::node::NodeError;
-use snarkos_models::algorithms::{CRH, SNARK};
 use snarkos_network::{
     context::Context,
+    message::Identity,
     protocol::SyncHandler,
     server::{MinerInstance, Server},
 };
-use snarkos_objects::{AccountAddress, Network};
-use snarkos_posw::PoswMarlin;
-use snarkos_rpc::start_rpc_server;
-use snarkos_utilities::{to_bytes, ToBytes};
+use snarkos_objects::AccountAddress;
+use snarkos_rpc::{start_rpc_server, start_ws_server};
 
+use rand::thread_rng;
 use std::{net::SocketAddr, str::FromStr, sync::Arc};
 use tokio::{runtime::Runtime, sync::Mutex};
 
 /// Builds a node from configuration parameters.
-/// 1. Creates new storage database or uses existing.
+/// 1. Creates new storage database or uses existing, or starts a new development chain.
 /// 2. Creates new memory pool or uses existing from storage.
 /// 3. Creates consensus parameters.
 /// 4. Creates network server.
@@ -65,12 +69,39 @@ async fn start_server(config: Config) -> Result<(), NodeError> {
     let address = format! {"{}:{}", config.node.ip, config.node.port};
     let socket_address = address.parse::<SocketAddr>()?;
 
-    let mut path = config.node.dir;
-    path.push(&config.node.db);
-    let storage = Arc::new(MerkleTreeLedger::open_at_path(path)?);
+    // A development chain mines blocks on demand, so it always loads the proving parameters.
+    let is_miner = config.miner.is_miner || config.node.dev;
 
-    let memory_pool = MemoryPool::from_storage(&storage.clone())?;
-    let memory_pool_lock = Arc::new(Mutex::new(memory_pool.clone()));
+    info!("Loading Aleo parameters...");
+    let parameters = PublicParameters::<Components>::load(!is_miner)?;
+    info!("Loading complete.");
+
+    // Set the initial consensus parameters.
+    let consensus = config.consensus_parameters(&parameters)?;
+
+    let storage = match config.consensus_profile().genesis {
+        GenesisSource::Generated => {
+            // Start a new development chain whose genesis block funds the miner address.
+            let miner_address = AccountAddress::<Components>::from_str(&config.miner.miner_address)?;
+
+            // Only the development chain database is reset.
+            let path = config
+                .dev_storage_path()
+                .ok_or_else(|| NodeError::Message("a generated genesis block requires a development chain".into()))?;
+            if path.exists() {
+                MerkleTreeLedger::destroy_storage(path.clone())?;
+            }
+
+            info!("Creating a development chain...");
+            Arc::new(consensus.create_ledger_with_genesis(&path, &parameters, miner_address, &mut thread_rng())?)
+        }
+        GenesisSource::Hardcoded => Arc::new(MerkleTreeLedger::open_at_path(config.storage_path())?),
+    };
+
+    if config.node.explorer_index {
+        info!("Building the explorer index...");
+        storage.enable_explorer_index()?;
+    }
 
     let bootnode = match config.p2p.bootnodes.len() {
         0 => socket_address,
@@ -80,28 +111,15 @@ async fn start_server(config: Config) -> Result<(), NodeError> {
     let sync_handler = SyncHandler::new(bootnode);
     let sync_handler_lock = Arc::new(Mutex::new(sync_handler));
 
-    info!("Loading Aleo parameters...");
-    let parameters = PublicParameters::<Components>::load(!config.miner.is_miner)?;
-    info!("Loading complete.");
-
-    // Fetch the valid inner snark ids
-    let inner_snark_vk: <<Components as BaseDPCComponents>::InnerSNARK as SNARK>::VerificationParameters =
-        parameters.inner_snark_parameters.1.clone().into();
-    let inner_snark_id = parameters
-        .system_parameters
-        .inner_snark_verification_key_crh
-        .hash(&to_bytes![inner_snark_vk]?)?;
-
-    let authorized_inner_snark_ids = vec![to_bytes![inner_snark_id]?];
+    // The memory pool emits its events on the consensus event channel.
+    let mut memory_pool = MemoryPool::from_storage(&storage.clone(), config.p2p.mempool_size)?;
+    memory_pool.events = consensus.events.clone();
+    let memory_pool_lock = Arc::new(Mutex::new(memory_pool.clone()));
 
-    // Set the initial consensus parameters.
-    let consensus = ConsensusParameters {
-        max_block_size: 1_000_000_000usize,
-        max_nonce: u32::max_value(),
-        target_block_time: 10i64,
-        network: Network::from_network_id(config.aleo.network_id),
-        verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
-        authorized_inner_snark_ids,
+    // Load the static identity that encrypts peer connections from its key file.
+    let identity = match config.p2p.encryption {
+        true => Some(Identity::load_or_generate(&config.identity_path())?),
+        false => None,
     };
 
     // Construct the server instance. Note this does not start the server.
@@ -113,6 +131,8 @@ async fn start_server(config: Config) -> Result<(), NodeError> {
             config.p2p.max_peers,
             config.node.is_bootnode,
             config.p2p.bootnodes.clone(),
+            config.p2p.ban_duration,
+            identity,
         ),
         consensus.clone(),
         storage.clone(),
@@ -125,18 +145,57 @@ async fn start_server(config: Config) -> Result<(), NodeError> {
     // Start RPC thread, if the RPC configuration is enabled.
     if config.rpc.json_rpc {
         info!("Loading Aleo parameters for RPC...");
-        let proving_parameters = PublicParameters::<Components>::load(!config.miner.is_miner)?;
+        let proving_parameters = PublicParameters::<Components>::load(!is_miner)?;
+        let mut program_registry = ProgramRegistry::new(&proving_parameters)?;
+
+        // Register the asset program, so that the wallet can create records holding its asset id.
+        let (asset_proving_key, asset_verification_key) = AssetProgram::<_, AssetProgramSNARK<_>>::load_or_setup(
+            &config.asset_program_path(),
+            &proving_parameters.system_parameters,
+            NODE_ASSET_ID,
+            &mut thread_rng(),
+        )?;
+        let asset_program_id = AssetProgram::<_, AssetProgramSNARK<_>>::register(
+            &mut program_registry,
+            &proving_parameters.system_parameters,
+            NODE_ASSET_ID,
+            asset_proving_key,
+            asset_verification_key,
+        )?;
+        info!("Registered the asset program {}", hex::encode(asset_program_id));
+
+        let program_registry = Arc::new(program_registry);
         info!("Loading complete.");
 
+        // The `generate` endpoint mines the blocks of a development chain.
+        let dev_miner = match config.node.dev {
+            true => Some(Miner::new(
+                AccountAddress::<Components>::from_str(&config.miner.miner_address)?,
+                consensus.clone(),
+            )),
+            false => None,
+        };
+
+        start_ws_server(
+            config.rpc.ws_port,
+            proving_parameters.system_parameters.clone(),
+            consensus.events.clone(),
+            config.rpc.username.clone(),
+            config.rpc.password.clone(),
+        )
+        .await?;
+
         start_rpc_server(
             config.rpc.port,
             storage.clone(),
             proving_parameters,
+            program_registry,
             server.context.clone(),
             consensus.clone(),
             memory_pool_lock.clone(),
             config.rpc.username,
             config.rpc.password,
+            dev_miner,
         )
         .await?;
     }
@@ -172,7 +231,13 @@ fn main() -> Result<(), NodeError> {
 
     let config: Config = ConfigCli::parse(&arguments)?;
 
-    Runtime::new()?.block_on(start_server(config))?;
+    match arguments.subcommand() {
+        ("export-chain", Some(arguments)) => ChainCLI::export(arguments, &config),
+        ("import-chain", Some(arguments)) => ChainCLI::import(arguments, &config),
+        _ => {
+            Runtime::new()?.block_on(start_server(config))?;
 
-    Ok(())
+            Ok(())
+        }
+    }
 }
@@ -18,9 +18,21 @@
 
 pub const NO_JSONRPC: &str = "[no-jsonrpc] --no-jsonrpc 'Run the node without running the json rpc server'";
 
+pub const NO_ENCRYPTION: &str =
+    "[no-encryption] --no-encryption 'Run the node without encrypting its peer connections'";
+
 pub const IS_BOOTNODE: &str =
     "[is-bootnode] --is-bootnode 'Run the node as a bootnode (IP is hard coded in the protocol)'";
 
+pub const EXPLORER_INDEX: &str =
+    "[explorer-index] --explorer-index 'Index the transactions that create each commitment, serial number and memo'";
+
+pub const DEV: &str =
+    "[dev] --dev 'Run a local development chain whose genesis block funds the miner address, mined on demand'";
+
 pub const IS_MINER: &str = "[is-miner] --is-miner 'Start mining blocks from this node'";
 
 pub const LIST: &str = "[list] -l --list 'List all available releases of snarkOS'";
+
+pub const TRUSTED: &str =
+    "[trusted] --trusted 'Skip proof verification for blocks from a snapshot you trust (e.g. your own export)'";
@@ -58,6 +58,13 @@ pub const MEMPOOL_INTERVAL: OptionType = (
     &[],
 );
 
+pub const MEMPOOL_SIZE: OptionType = (
+    "[mempool-size] --mempool-size=[mempool-size] 'Specify the maximum size in bytes of the transactions held in the node's memory pool'",
+    &[],
+    &[],
+    &[],
+);
+
 pub const MIN_PEERS: OptionType = (
     "[min-peers] --min-peers=[min-peers] 'Specify the minimum number of peers the node should connect to'",
     &[],
@@ -72,6 +79,13 @@ pub const MAX_PEERS: OptionType = (
     &[],
 );
 
+pub const BAN_DURATION: OptionType = (
+    "[ban-duration] --ban-duration=[ban-duration] 'Specify the number of seconds a misbehaving peer is banned for'",
+    &[],
+    &[],
+    &[],
+);
+
 pub const NETWORK: OptionType = (
     "[network] --network=[network-id] 'Specify the network id (default = 1) of the node'",
     &[],
@@ -86,6 +100,13 @@ pub const RPC_PORT: OptionType = (
     &[],
 );
 
+pub const WS_PORT: OptionType = (
+    "[ws-port] --ws-port=[ws-port] 'Specify the port the websocket subscription server is run on'",
+    &["no_jsonrpc"],
+    &[],
+    &[],
+);
+
 pub const RPC_USERNAME: OptionType = (
     "[rpc-username] --rpc-username=[rpc-username] 'Specify a username for rpc authentication'",
     &["no-jsonrpc"],
@@ -106,3 +127,7 @@ pub const VERBOSE: OptionType = (
     &["0", "1", "2"],
     &[],
 );
+
+// Subcommands
+
+pub const CHAIN_FILE: OptionType = ("<file> 'Specify the chain snapshot file'", &[], &[], &[]);
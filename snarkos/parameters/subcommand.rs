@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::parameters::{flag, types::*};
+use crate::parameters::{flag, option, types::*};
 
 use clap::AppSettings;
 // Format
@@ -31,3 +31,27 @@ pub const UPDATE: SubCommandType = (
         AppSettings::DisableVersion,
     ],
 );
+
+pub const EXPORT_CHAIN: SubCommandType = (
+    "export-chain",
+    "Export the canonical chain of the node to a snapshot file (include -h for more options)",
+    &[option::CHAIN_FILE],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
+
+pub const IMPORT_CHAIN: SubCommandType = (
+    "import-chain",
+    "Import blocks from a chain snapshot file into the node (include -h for more options)",
+    &[option::CHAIN_FILE],
+    &[flag::TRUSTED],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
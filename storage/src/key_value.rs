@@ -29,11 +29,19 @@ pub const COL_MEMO: u32 = 7; // Memo -> index
 pub const COL_DIGEST: u32 = 8; // Ledger digest -> index
 pub const COL_RECORDS: u32 = 9; // commitment -> record bytes
 pub const COL_CHILD_HASHES: u32 = 10; // block hash -> vector of potential child hashes
-pub const NUM_COLS: u32 = 11;
+pub const COL_BLOCK_WORK: u32 = 11; // block hash -> cumulative chain work
+pub const COL_COMMITMENT_TREE: u32 = 12; // (height, position) -> commitment merkle tree node
+pub const COL_COMMITMENT_TRANSACTION: u32 = 13; // Commitment -> transaction id, if the explorer index is enabled
+pub const COL_SERIAL_NUMBER_TRANSACTION: u32 = 14; // SN -> transaction id, if the explorer index is enabled
+pub const COL_MEMO_TRANSACTION: u32 = 15; // Memo -> transaction id, if the explorer index is enabled
+pub const NUM_COLS: u32 = 16;
 
 pub const KEY_BEST_BLOCK_NUMBER: &str = "BEST_BLOCK_NUMBER";
 pub const KEY_MEMORY_POOL: &str = "MEMORY_POOL";
 pub const KEY_PEER_BOOK: &str = "PEER_BOOK";
+pub const KEY_WALLET: &str = "WALLET";
+pub const KEY_SCHEMA_VERSION: &str = "SCHEMA_VERSION";
+pub const KEY_EXPLORER_INDEX: &str = "EXPLORER_INDEX";
 
 pub const KEY_CURR_CM_INDEX: &str = "CURRENT_CM_INDEX";
 pub const KEY_CURR_SN_INDEX: &str = "CURRENT_SN_INDEX";
@@ -136,3 +144,10 @@ pub fn bytes_to_u32(bytes: Vec<u8>) -> u32 {
 
     u32::from_le_bytes(num_bytes)
 }
+
+pub fn bytes_to_u128(bytes: Vec<u8>) -> u128 {
+    let mut num_bytes = [0u8; 16];
+    num_bytes.copy_from_slice(&bytes);
+
+    u128::from_le_bytes(num_bytes)
+}
@@ -29,21 +29,20 @@ use snarkos_utilities::bytes::FromBytes;
 
 use parking_lot::RwLock;
 use std::{
-    fs,
     marker::PhantomData,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
-pub struct Ledger<T: Transaction, P: LoadableMerkleParameters> {
+pub struct Ledger<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore = RocksDb> {
     pub latest_block_height: RwLock<u32>,
     pub ledger_parameters: P,
     pub cm_merkle_tree: RwLock<MerkleTree<P>>,
-    pub storage: Arc<Storage>,
+    pub storage: Arc<S>,
     pub _transaction: PhantomData<T>,
 }
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Instantiates a new ledger storage.
     pub fn open() -> Result<Self, StorageError> {
         let mut path = std::env::current_dir()?;
@@ -54,11 +53,42 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
 
     /// Open the blockchain storage at a particular path.
     pub fn open_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
-        fs::create_dir_all(path.as_ref()).map_err(|err| StorageError::Message(err.to_string()))?;
-
         Self::load_ledger_state(path)
     }
 
+    /// Open a new ledger without a genesis block at a particular path.
+    /// Used to create the transactions of a custom genesis block, which is then added with `insert_and_commit`.
+    pub fn open_empty<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
+        let storage = S::open_cf(path.as_ref(), NUM_COLS)?;
+
+        if storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?.is_some() {
+            return Err(StorageError::Message(format!(
+                "the ledger at {} is not empty",
+                path.as_ref().display()
+            )));
+        }
+
+        let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
+        let ledger_parameters = P::from(crh);
+
+        let leaves: Vec<[u8; 32]> = vec![];
+        let empty_cm_merkle_tree = MerkleTree::new(ledger_parameters.clone(), &leaves)?;
+
+        let ledger_storage = Self {
+            latest_block_height: RwLock::new(0),
+            storage: Arc::new(storage),
+            cm_merkle_tree: RwLock::new(empty_cm_merkle_tree),
+            ledger_parameters,
+            _transaction: PhantomData,
+        };
+
+        ledger_storage
+            .storage
+            .write(DatabaseTransaction(vec![Self::schema_version_op(SCHEMA_VERSION)]))?;
+
+        Ok(ledger_storage)
+    }
+
     /// Returns true if there are no blocks in the ledger.
     pub fn is_empty(&self) -> bool {
         self.get_latest_block().is_err()
@@ -89,15 +119,30 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         self.storage.write(DatabaseTransaction(vec![op]))
     }
 
+    /// Get the stored encrypted wallet of this node.
+    pub fn get_wallet(&self) -> Result<Vec<u8>, StorageError> {
+        Ok(self.get(COL_META, &KEY_WALLET.as_bytes().to_vec())?)
+    }
+
+    /// Store the encrypted wallet of this node.
+    pub fn store_wallet(&self, wallet_serialized: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_META,
+            key: KEY_WALLET.as_bytes().to_vec(),
+            value: wallet_serialized,
+        };
+        self.storage.write(DatabaseTransaction(vec![op]))
+    }
+
     /// Destroy the storage given a path.
     pub fn destroy_storage(path: PathBuf) -> Result<(), StorageError> {
-        Storage::destroy_storage(path)
+        S::destroy_storage(path)
     }
 
     /// Returns a `Ledger` with the latest state loaded from storage.
     fn load_ledger_state<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
         let latest_block_number = {
-            let storage = Storage::open_cf(path.as_ref(), NUM_COLS)?;
+            let storage = S::open_cf(path.as_ref(), NUM_COLS)?;
             storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?
         };
 
@@ -106,31 +151,28 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
 
         match latest_block_number {
             Some(val) => {
-                let storage = Storage::open_cf(path.as_ref(), NUM_COLS)?;
+                let storage = S::open_cf(path.as_ref(), NUM_COLS)?;
 
-                // Build commitment merkle tree
+                let leaves: Vec<[u8; 32]> = vec![];
+                let empty_cm_merkle_tree = MerkleTree::new(ledger_parameters.clone(), &leaves)?;
 
-                let mut cm_and_indices = vec![];
+                let ledger_storage = Self {
+                    latest_block_height: RwLock::new(bytes_to_u32(val)),
+                    storage: Arc::new(storage),
+                    cm_merkle_tree: RwLock::new(empty_cm_merkle_tree),
+                    ledger_parameters,
+                    _transaction: PhantomData,
+                };
 
-                for (commitment_key, index_value) in storage.get_iter(COL_COMMITMENT)? {
-                    let commitment: T::Commitment = FromBytes::read(&commitment_key[..])?;
-                    let index = bytes_to_u32(index_value.to_vec()) as usize;
+                // Upgrade databases written by older versions of the node
 
-                    cm_and_indices.push((commitment, index));
-                }
+                ledger_storage.migrate()?;
 
-                cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
-                let commitments = cm_and_indices.into_iter().map(|(cm, _)| cm).collect::<Vec<_>>();
+                // Load the commitment merkle tree from its stored nodes
 
-                let merkle_tree = MerkleTree::new(ledger_parameters.clone(), &commitments)?;
+                *ledger_storage.cm_merkle_tree.write() = ledger_storage.load_merkle_tree()?;
 
-                Ok(Self {
-                    latest_block_height: RwLock::new(bytes_to_u32(val)),
-                    storage: Arc::new(storage),
-                    cm_merkle_tree: RwLock::new(merkle_tree),
-                    ledger_parameters,
-                    _transaction: PhantomData,
-                })
+                Ok(ledger_storage)
             }
             None => {
                 // Add genesis block to database
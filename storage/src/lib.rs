@@ -20,8 +20,14 @@ pub use ledger::*;
 pub mod key_value;
 pub use key_value::*;
 
+pub mod mem_db;
+pub use mem_db::*;
+
 pub mod objects;
 pub use objects::*;
 
+pub mod rocks_db;
+pub use rocks_db::*;
+
 pub mod storage;
 pub use storage::*;
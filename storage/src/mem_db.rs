@@ -0,0 +1,136 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{DatabaseTransaction, KeyValueIter, KeyValueStore, Op};
+use snarkos_errors::storage::StorageError;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The key-value pairs of each column.
+type Columns = Vec<HashMap<Vec<u8>, Vec<u8>>>;
+
+/// The in-memory storages of this process, by the path they were opened at.
+static DATABASES: Lazy<Mutex<HashMap<PathBuf, Arc<RwLock<Columns>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// An in-memory storage backed by a `HashMap` per column.
+/// Nothing is persisted to disk, but storages opened at the same path within a process share their state.
+#[derive(Clone)]
+pub struct MemDb {
+    pub cols: Arc<RwLock<Columns>>,
+    path: PathBuf,
+}
+
+impl KeyValueStore for MemDb {
+    /// Returns the storage opened at the given path, or a new, empty storage with the given number of columns.
+    /// If a storage with a different number of columns is open at the path, returns
+    /// [StorageError](snarkos_errors::storage::StorageError).
+    fn open_cf<P: AsRef<Path>>(path: P, num_cfs: u32) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let cols = DATABASES
+            .lock()
+            .entry(path.clone())
+            .or_insert_with(|| Arc::new(RwLock::new(vec![HashMap::new(); num_cfs as usize])))
+            .clone();
+
+        let open_cfs = cols.read().len();
+        if open_cfs != num_cfs as usize {
+            return Err(StorageError::Message(format!(
+                "the storage at {} has {} columns, not {}",
+                path.display(),
+                open_cfs,
+                num_cfs
+            )));
+        }
+
+        Ok(Self { cols, path })
+    }
+
+    /// Returns the path the storage was opened at.
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    /// Returns the value from a given key and col.
+    /// If the given col does not exist, returns [StorageError](snarkos_errors::storage::StorageError).
+    fn get(&self, col: u32, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.cols.read().get(col as usize) {
+            Some(column) => Ok(column.get(key).cloned()),
+            None => Err(StorageError::InvalidColumnFamily(col)),
+        }
+    }
+
+    /// Returns an iterator over a snapshot of the given col, ordered by key.
+    /// If the given col does not exist, returns [StorageError](snarkos_errors::storage::StorageError).
+    fn get_iter(&self, col: u32) -> Result<KeyValueIter<'_>, StorageError> {
+        let mut key_values = match self.cols.read().get(col as usize) {
+            Some(column) => column
+                .iter()
+                .map(|(key, value)| (key.clone().into_boxed_slice(), value.clone().into_boxed_slice()))
+                .collect::<Vec<_>>(),
+            None => return Err(StorageError::InvalidColumnFamily(col)),
+        };
+
+        key_values.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(Box::new(key_values.into_iter()))
+    }
+
+    /// Returns `Ok(())` after executing a database transaction.
+    /// If any operation refers to a col that does not exist, returns
+    /// [StorageError](snarkos_errors::storage::StorageError) and applies none of the operations.
+    fn write(&self, transaction: DatabaseTransaction) -> Result<(), StorageError> {
+        let mut cols = self.cols.write();
+
+        if let Some(op) = transaction.0.iter().find(|op| op.col() as usize >= cols.len()) {
+            return Err(StorageError::InvalidColumnFamily(op.col()));
+        }
+
+        for operation in transaction.0 {
+            match operation {
+                Op::Insert { col, key, value } => {
+                    cols[col as usize].insert(key, value);
+                }
+                Op::Delete { col, key } => {
+                    cols[col as usize].remove(&key);
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Returns `Ok(())` after removing all values from the storage and closing its path.
+    fn destroy(self) -> Result<(), StorageError> {
+        for column in self.cols.write().iter_mut() {
+            column.clear();
+        }
+
+        Self::destroy_storage(self.path)
+    }
+
+    /// Returns `Ok(())` after closing the storage at the given path, so the next `open_cf` starts empty.
+    fn destroy_storage(path: PathBuf) -> Result<(), StorageError> {
+        DATABASES.lock().remove(&path);
+
+        Ok(())
+    }
+}
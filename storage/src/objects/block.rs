@@ -20,7 +20,7 @@ use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction}
 use snarkos_objects::{Block, BlockHeaderHash, DPCTransactions};
 use snarkos_utilities::{to_bytes, FromBytes, ToBytes};
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Get the latest block in the chain.
     pub fn get_latest_block(&self) -> Result<Block<T>, StorageError> {
         self.get_block_from_block_number(self.get_latest_block_height())
@@ -172,8 +172,13 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         let mut memo_index = self.current_memo_index()?;
 
         let mut database_transaction = DatabaseTransaction::new();
+        let explorer_index = self.is_explorer_index_enabled();
 
         for transaction in self.get_block_transactions(&block_hash)?.0 {
+            if explorer_index {
+                database_transaction.push_vec(self.explorer_unindex_ops(&transaction)?);
+            }
+
             for sn in transaction.old_serial_numbers() {
                 database_transaction.push(Op::Delete {
                     col: COL_SERIAL_NUMBER,
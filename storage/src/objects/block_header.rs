@@ -14,13 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Ledger, COL_BLOCK_HEADER};
+use crate::{bytes_to_u128, KeyValueStore, Ledger, COL_BLOCK_HEADER, COL_BLOCK_WORK};
 use snarkos_errors::storage::StorageError;
 use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
 use snarkos_objects::{Block, BlockHeader, BlockHeaderHash};
 use snarkos_utilities::FromBytes;
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Returns true if the block for the given block header hash exists.
     pub fn block_hash_exists(&self, block_hash: &BlockHeaderHash) -> bool {
         if self.is_empty() {
@@ -41,6 +41,44 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         }
     }
 
+    /// Get the headers of the given block and its ancestors, from the newest to the oldest.
+    /// Returns at most `count` headers, and fewer if the genesis block is reached first.
+    pub fn get_ancestor_headers(
+        &self,
+        block_hash: &BlockHeaderHash,
+        count: usize,
+    ) -> Result<Vec<BlockHeader>, StorageError> {
+        let mut headers = Vec::with_capacity(count);
+        let mut block_hash = block_hash.clone();
+
+        while headers.len() < count {
+            let header = self.get_block_header(&block_hash)?;
+            let is_genesis = header.previous_block_hash == BlockHeaderHash([0u8; 32]);
+
+            block_hash = header.previous_block_hash.clone();
+            headers.push(header);
+
+            if is_genesis {
+                break;
+            }
+        }
+
+        Ok(headers)
+    }
+
+    /// Get the cumulative chain work up to and including the given block hash.
+    pub fn get_cumulative_work(&self, block_hash: &BlockHeaderHash) -> Result<u128, StorageError> {
+        match self.storage.get(COL_BLOCK_WORK, &block_hash.0)? {
+            Some(work_bytes) => Ok(bytes_to_u128(work_bytes)),
+            None => Err(StorageError::MissingCumulativeWork(block_hash.to_string())),
+        }
+    }
+
+    /// Get the cumulative chain work of the canon chain.
+    pub fn get_latest_cumulative_work(&self) -> Result<u128, StorageError> {
+        self.get_cumulative_work(&self.get_block_hash(self.get_latest_block_height())?)
+    }
+
     /// Returns true if the block corresponding to this block's previous_block_hash exists.
     pub fn previous_block_hash_exists(&self, block: &Block<T>) -> bool {
         self.block_hash_exists(&block.header.previous_block_hash)
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::Ledger;
+use crate::{KeyValueStore, Ledger};
 use snarkos_errors::{objects::BlockError, storage::StorageError};
 use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
 use snarkos_objects::{BlockHeader, BlockHeaderHash};
@@ -36,11 +36,14 @@ pub struct SideChainPath {
     /// New block number
     pub new_block_number: u32,
 
+    /// Cumulative chain work of the side chain, including the blocks in the path
+    pub aggregate_work: u128,
+
     /// Path of block hashes from the shared block to the latest diverging block (oldest first).
     pub path: Vec<BlockHeaderHash>,
 }
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Get the block's path/origin.
     pub fn get_block_path(&self, block_header: &BlockHeader) -> Result<BlockPath, StorageError> {
         let block_hash = block_header.get_hash();
@@ -64,15 +67,27 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
             match &self.get_block_number(&parent_hash) {
                 // This is a canon parent
                 Ok(block_num) => {
+                    // Sum the work of the side chain up to and including the given block
+
+                    let mut aggregate_work = self.get_cumulative_work(&parent_hash)?;
+
+                    for side_chain_block_hash in &side_chain_path {
+                        aggregate_work =
+                            aggregate_work.saturating_add(self.get_block_header(side_chain_block_hash)?.to_work());
+                    }
+
+                    aggregate_work = aggregate_work.saturating_add(block_header.to_work());
+
                     // Add the children from the latest block
 
-                    let longest_path = self.longest_child_path(block_hash)?;
+                    let (descendant_work, heaviest_path) = self.heaviest_child_path(block_hash)?;
 
-                    side_chain_path.extend(longest_path.1);
+                    side_chain_path.extend(heaviest_path);
 
                     return Ok(BlockPath::SideChain(SideChainPath {
                         shared_block_number: *block_num,
                         new_block_number: block_num + side_chain_path.len() as u32,
+                        aggregate_work: aggregate_work.saturating_add(descendant_work),
                         path: side_chain_path,
                     }));
                 }
@@ -89,31 +104,34 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         )))
     }
 
-    /// Returns the path length and the longest path of children from the given block header
-    pub fn longest_child_path(
+    /// Returns the accumulated work and the heaviest path of children from the given block hash.
+    /// The path starts with the given block hash, whose own work is not included in the total.
+    /// Ties are resolved in favour of the child that was received first.
+    pub fn heaviest_child_path(
         &self,
         block_hash: BlockHeaderHash,
-    ) -> Result<(usize, Vec<BlockHeaderHash>), StorageError> {
-        let children = self.get_child_block_hashes(&block_hash)?;
-
-        let mut final_path = vec![block_hash];
-
-        if children.len() == 0 {
-            Ok((1, final_path))
-        } else {
-            let mut paths = vec![];
-            for child in children {
-                paths.push(self.longest_child_path(child)?);
+    ) -> Result<(u128, Vec<BlockHeaderHash>), StorageError> {
+        let mut final_path = vec![block_hash.clone()];
+        let mut heaviest_path: Option<(u128, Vec<BlockHeaderHash>)> = None;
+
+        for child in self.get_child_block_hashes(&block_hash)? {
+            let child_work = self.get_block_header(&child)?.to_work();
+            let (descendant_work, child_path) = self.heaviest_child_path(child)?;
+            let path_work = child_work.saturating_add(descendant_work);
+
+            match &heaviest_path {
+                Some((heaviest_work, _)) if path_work <= *heaviest_work => {}
+                _ => heaviest_path = Some((path_work, child_path)),
             }
+        }
 
-            match paths.iter().max_by_key(|x| x.0) {
-                Some((longest_child_path_length, longest_child_path)) => {
-                    final_path.extend(longest_child_path.clone());
+        match heaviest_path {
+            Some((path_work, child_path)) => {
+                final_path.extend(child_path);
 
-                    Ok((*longest_child_path_length + 1, final_path))
-                }
-                None => Ok((1, final_path)),
+                Ok((path_work, final_path))
             }
+            None => Ok((0, final_path)),
         }
     }
 }
@@ -23,9 +23,9 @@ use snarkos_utilities::{
     to_bytes,
 };
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Get the current commitment index
     pub fn current_cm_index(&self) -> Result<usize, StorageError> {
         match self.storage.get(COL_META, KEY_CURR_CM_INDEX.as_bytes())? {
@@ -127,17 +127,62 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         Ok(MerkleTree::new(self.ledger_parameters.clone(), &commitments)?)
     }
 
-    /// Rebuild the stored merkle tree with the current stored commitments
+    /// Load the commitment merkle tree from its stored nodes
+    pub fn load_merkle_tree(&self) -> Result<MerkleTree<P>, StorageError> {
+        let mut nodes = HashMap::new();
+        for (node_key, node_value) in self.storage.get_iter(COL_COMMITMENT_TREE)? {
+            let height = bytes_to_u32(node_key[0..4].to_vec()) as usize;
+            let position = bytes_to_u32(node_key[4..8].to_vec()) as usize;
+
+            nodes.insert((height, position), FromBytes::read(&node_value[..])?);
+        }
+
+        Ok(MerkleTree::from_nodes(
+            self.ledger_parameters.clone(),
+            self.current_cm_index()?,
+            &nodes,
+        )?)
+    }
+
+    /// Returns the operations that store the given nodes of the commitment merkle tree,
+    /// deleting the nodes that no longer cover any commitment.
+    pub(crate) fn merkle_tree_node_ops(
+        merkle_tree: &MerkleTree<P>,
+        nodes: &[(usize, usize)],
+    ) -> Result<Vec<Op>, StorageError> {
+        let mut ops = Vec::with_capacity(nodes.len());
+        for &(height, position) in nodes {
+            let mut key = (height as u32).to_le_bytes().to_vec();
+            key.extend_from_slice(&(position as u32).to_le_bytes());
+
+            match merkle_tree.node(height, position) {
+                Some(node) => ops.push(Op::Insert {
+                    col: COL_COMMITMENT_TREE,
+                    key,
+                    value: to_bytes![node]?.to_vec(),
+                }),
+                None => ops.push(Op::Delete {
+                    col: COL_COMMITMENT_TREE,
+                    key,
+                }),
+            }
+        }
+
+        Ok(ops)
+    }
+
+    /// Remove the commitments that are no longer stored from the merkle tree
     pub fn update_merkle_tree(&self) -> Result<(), StorageError> {
         let mut merkle_tree = self.cm_merkle_tree.write();
-        *merkle_tree = self.build_merkle_tree(vec![])?;
+        let changed_nodes = merkle_tree.truncate_leaves(self.current_cm_index()?)?;
 
-        let update_current_digest = DatabaseTransaction(vec![Op::Insert {
+        let mut database_transaction = DatabaseTransaction(Self::merkle_tree_node_ops(&merkle_tree, &changed_nodes)?);
+        database_transaction.push(Op::Insert {
             col: COL_META,
             key: KEY_CURR_DIGEST.as_bytes().to_vec(),
             value: to_bytes![merkle_tree.root()]?.to_vec(),
-        }]);
+        });
 
-        self.storage.write(update_current_digest)
+        self.storage.write(database_transaction)
     }
 }
@@ -0,0 +1,133 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::*;
+use snarkos_errors::storage::StorageError;
+use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
+use snarkos_utilities::{bytes::ToBytes, to_bytes};
+
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
+    /// Returns true if the ledger maps the commitments, serial numbers and memos
+    /// of canon transactions to their transaction ids.
+    pub fn is_explorer_index_enabled(&self) -> bool {
+        self.storage.exists(COL_META, KEY_EXPLORER_INDEX.as_bytes())
+    }
+
+    /// Build the explorer index for the current canon chain and maintain it for every block
+    /// committed afterwards. The index stays enabled when the ledger is reopened.
+    pub fn enable_explorer_index(&self) -> Result<(), StorageError> {
+        if self.is_explorer_index_enabled() {
+            return Ok(());
+        }
+
+        let mut database_transaction = DatabaseTransaction::new();
+
+        for block_height in 0..=self.get_latest_block_height() {
+            let block_hash = self.get_block_hash(block_height)?;
+
+            for transaction in self.get_block_transactions(&block_hash)?.0 {
+                database_transaction.push_vec(self.explorer_index_ops(&transaction)?);
+            }
+        }
+
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_EXPLORER_INDEX.as_bytes().to_vec(),
+            value: vec![1u8],
+        });
+
+        self.storage.write(database_transaction)
+    }
+
+    /// Returns the operations that index the commitments, serial numbers and memo of a transaction.
+    pub(crate) fn explorer_index_ops(&self, transaction: &T) -> Result<Vec<Op>, StorageError> {
+        let transaction_id = transaction.transaction_id()?.to_vec();
+        let mut ops = vec![];
+
+        for sn in transaction.old_serial_numbers() {
+            ops.push(Op::Insert {
+                col: COL_SERIAL_NUMBER_TRANSACTION,
+                key: to_bytes![sn]?,
+                value: transaction_id.clone(),
+            });
+        }
+
+        for cm in transaction.new_commitments() {
+            ops.push(Op::Insert {
+                col: COL_COMMITMENT_TRANSACTION,
+                key: to_bytes![cm]?,
+                value: transaction_id.clone(),
+            });
+        }
+
+        ops.push(Op::Insert {
+            col: COL_MEMO_TRANSACTION,
+            key: to_bytes![transaction.memorandum()]?,
+            value: transaction_id,
+        });
+
+        Ok(ops)
+    }
+
+    /// Returns the operations that remove a decommitted transaction from the explorer index.
+    pub(crate) fn explorer_unindex_ops(&self, transaction: &T) -> Result<Vec<Op>, StorageError> {
+        let mut ops = vec![];
+
+        for sn in transaction.old_serial_numbers() {
+            ops.push(Op::Delete {
+                col: COL_SERIAL_NUMBER_TRANSACTION,
+                key: to_bytes![sn]?,
+            });
+        }
+
+        for cm in transaction.new_commitments() {
+            ops.push(Op::Delete {
+                col: COL_COMMITMENT_TRANSACTION,
+                key: to_bytes![cm]?,
+            });
+        }
+
+        ops.push(Op::Delete {
+            col: COL_MEMO_TRANSACTION,
+            key: to_bytes![transaction.memorandum()]?,
+        });
+
+        Ok(ops)
+    }
+
+    /// Returns the id of the canon transaction that created the commitment, if there is one.
+    pub fn get_commitment_transaction_id(&self, cm_bytes: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.get_indexed_transaction_id(COL_COMMITMENT_TRANSACTION, cm_bytes)
+    }
+
+    /// Returns the id of the canon transaction that revealed the serial number, if there is one.
+    pub fn get_serial_number_transaction_id(&self, sn_bytes: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.get_indexed_transaction_id(COL_SERIAL_NUMBER_TRANSACTION, sn_bytes)
+    }
+
+    /// Returns the id of the canon transaction with the memo, if there is one.
+    pub fn get_memo_transaction_id(&self, memo_bytes: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.get_indexed_transaction_id(COL_MEMO_TRANSACTION, memo_bytes)
+    }
+
+    fn get_indexed_transaction_id(&self, col: u32, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        if !self.is_explorer_index_enabled() {
+            return Err(StorageError::ExplorerIndexDisabled);
+        }
+
+        self.storage.get(col, key)
+    }
+}
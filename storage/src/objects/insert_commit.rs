@@ -20,7 +20,7 @@ use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction}
 use snarkos_objects::{Block, BlockHeader, BlockHeaderHash};
 use snarkos_utilities::{bytes::ToBytes, has_duplicates, to_bytes};
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     pub(crate) fn process_transaction(
         &self,
         sn_index: &mut usize,
@@ -140,7 +140,7 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         let mut child_hashes = self.get_child_block_hashes(&block.header.previous_block_hash)?;
 
         if !child_hashes.contains(&block_hash) {
-            child_hashes.push(block_hash);
+            child_hashes.push(block_hash.clone());
 
             database_transaction.push(Op::Insert {
                 col: COL_CHILD_HASHES,
@@ -155,11 +155,56 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
             value: to_bytes![block.transactions]?.to_vec(),
         });
 
+        // Update the cumulative chain work if the parent's work is known
+
+        let parent_work = if block.header.previous_block_hash == BlockHeaderHash([0u8; 32]) {
+            Some(0)
+        } else {
+            self.get_cumulative_work(&block.header.previous_block_hash).ok()
+        };
+
+        if let Some(parent_work) = parent_work {
+            let cumulative_work = parent_work.saturating_add(block.header.to_work());
+
+            database_transaction.push(Op::Insert {
+                col: COL_BLOCK_WORK,
+                key: block_hash.0.to_vec(),
+                value: cumulative_work.to_le_bytes().to_vec(),
+            });
+            database_transaction.push_vec(self.descendant_work_ops(&block_hash, cumulative_work)?);
+        }
+
         self.storage.write(database_transaction)?;
 
         Ok(())
     }
 
+    /// Returns the operations that store the cumulative chain work of the stored descendants
+    /// of the given block. Descendants are stored without work when they arrive as orphans.
+    pub(crate) fn descendant_work_ops(
+        &self,
+        block_hash: &BlockHeaderHash,
+        cumulative_work: u128,
+    ) -> Result<Vec<Op>, StorageError> {
+        let mut ops = vec![];
+        let mut pending = vec![(block_hash.clone(), cumulative_work)];
+
+        while let Some((parent_hash, parent_work)) = pending.pop() {
+            for child_hash in self.get_child_block_hashes(&parent_hash)? {
+                let child_work = parent_work.saturating_add(self.get_block_header(&child_hash)?.to_work());
+
+                ops.push(Op::Insert {
+                    col: COL_BLOCK_WORK,
+                    key: child_hash.0.to_vec(),
+                    value: child_work.to_le_bytes().to_vec(),
+                });
+                pending.push((child_hash, child_work));
+            }
+        }
+
+        Ok(ops)
+    }
+
     /// Commit/canonize a particular block.
     pub fn commit(&self, block_header_hash: &BlockHeaderHash) -> Result<(), StorageError> {
         let block = self.get_block(block_header_hash)?;
@@ -205,11 +250,16 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         // Process the individual transactions
 
         let mut transaction_cms = vec![];
+        let explorer_index = self.is_explorer_index_enabled();
 
         for transaction in block.transactions.0.iter() {
             let (tx_ops, cms) = self.process_transaction(&mut sn_index, &mut cm_index, &mut memo_index, transaction)?;
             database_transaction.push_vec(tx_ops);
             transaction_cms.extend(cms);
+
+            if explorer_index {
+                database_transaction.push_vec(self.explorer_index_ops(transaction)?);
+            }
         }
 
         // Update the database state for current indexes
@@ -261,9 +311,15 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
             value: block.header.get_hash().0.to_vec(),
         });
 
-        // Rebuild the new commitment merkle tree
-        let new_merkle_tree = self.build_merkle_tree(transaction_cms)?;
-        let new_digest = new_merkle_tree.root();
+        // Append the new commitments to the commitment merkle tree
+
+        let mut cm_merkle_tree = self.cm_merkle_tree.write();
+        let new_cms = transaction_cms.into_iter().map(|(cm, _)| cm).collect::<Vec<_>>();
+
+        let changed_nodes = cm_merkle_tree.push_leaves(&new_cms)?;
+        database_transaction.push_vec(Self::merkle_tree_node_ops(&cm_merkle_tree, &changed_nodes)?);
+
+        let new_digest = cm_merkle_tree.root();
 
         database_transaction.push(Op::Insert {
             col: COL_DIGEST,
@@ -276,9 +332,6 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
             value: to_bytes![new_digest]?.to_vec(),
         });
 
-        let mut cm_merkle_tree = self.cm_merkle_tree.write();
-        *cm_merkle_tree = new_merkle_tree;
-
         self.storage.write(database_transaction)?;
 
         if !is_genesis {
@@ -311,17 +364,26 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         self.is_canon(&block_header.previous_block_hash)
     }
 
-    /// Revert the chain to the state before the fork.
-    pub fn revert_for_fork(&self, side_chain_path: &SideChainPath) -> Result<(), StorageError> {
+    /// Returns true if the side chain carries more cumulative work than the canon chain.
+    /// Ties are resolved in favour of the canon chain.
+    pub fn is_heavier_side_chain(&self, side_chain_path: &SideChainPath) -> Result<bool, StorageError> {
+        Ok(side_chain_path.aggregate_work > self.get_latest_cumulative_work()?)
+    }
+
+    /// Revert the chain to the state before the fork if the side chain carries more work.
+    /// Returns the reverted canon blocks, starting from the latest block.
+    pub fn revert_for_fork(&self, side_chain_path: &SideChainPath) -> Result<Vec<Block<T>>, StorageError> {
         let latest_block_height = self.get_latest_block_height();
+        let mut reverted_blocks = vec![];
 
-        if side_chain_path.new_block_number > latest_block_height {
+        if self.is_heavier_side_chain(side_chain_path)? {
             // Decommit all blocks on canon chain up to the shared block number with the side chain.
             for _ in (side_chain_path.shared_block_number)..latest_block_height {
-                self.decommit_latest_block()?;
+                let block_hash = self.decommit_latest_block()?;
+                reverted_blocks.push(self.get_block(&block_hash)?);
             }
         }
 
-        Ok(())
+        Ok(reverted_blocks)
     }
 }
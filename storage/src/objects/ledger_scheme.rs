@@ -28,9 +28,9 @@ use snarkos_utilities::{
 };
 
 use parking_lot::RwLock;
-use std::{fs, marker::PhantomData, path::PathBuf, sync::Arc};
+use std::{marker::PhantomData, path::PathBuf, sync::Arc};
 
-impl<T: Transaction, P: LoadableMerkleParameters> LedgerScheme for Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> LedgerScheme for Ledger<T, P, S> {
     type Block = Block<Self::Transaction>;
     type Commitment = T::Commitment;
     type MerkleParameters = P;
@@ -45,8 +45,7 @@ impl<T: Transaction, P: LoadableMerkleParameters> LedgerScheme for Ledger<T, P>
         parameters: Self::MerkleParameters,
         genesis_block: Self::Block,
     ) -> Result<Self, LedgerError> {
-        fs::create_dir_all(&path).map_err(|err| LedgerError::Message(err.to_string()))?;
-        let storage = match Storage::open_cf(path, NUM_COLS) {
+        let storage = match S::open_cf(path, NUM_COLS) {
             Ok(storage) => storage,
             Err(err) => return Err(LedgerError::StorageError(err)),
         };
@@ -69,6 +68,9 @@ impl<T: Transaction, P: LoadableMerkleParameters> LedgerScheme for Ledger<T, P>
         };
 
         ledger_storage.insert_and_commit(&genesis_block)?;
+        ledger_storage
+            .storage
+            .write(DatabaseTransaction(vec![Self::schema_version_op(SCHEMA_VERSION)]))?;
 
         Ok(ledger_storage)
     }
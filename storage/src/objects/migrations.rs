@@ -0,0 +1,100 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::*;
+use snarkos_errors::storage::StorageError;
+use snarkos_models::{algorithms::LoadableMerkleParameters, objects::Transaction};
+
+/// The version of the database schema written by this node.
+///
+/// Version 0 is any database created before schema versioning.
+/// Version 1 stores the cumulative chain work of every block in `COL_BLOCK_WORK`.
+/// Version 2 stores the nodes of the commitment merkle tree in `COL_COMMITMENT_TREE`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
+    /// Get the schema version of the database.
+    pub fn get_schema_version(&self) -> Result<u32, StorageError> {
+        match self.storage.get(COL_META, KEY_SCHEMA_VERSION.as_bytes())? {
+            Some(version_bytes) => Ok(bytes_to_u32(version_bytes)),
+            None => Ok(0),
+        }
+    }
+
+    /// Returns the operation that stores the given schema version.
+    pub(crate) fn schema_version_op(version: u32) -> Op {
+        Op::Insert {
+            col: COL_META,
+            key: KEY_SCHEMA_VERSION.as_bytes().to_vec(),
+            value: version.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Upgrade the database to the current schema version, one migration at a time.
+    /// Returns an error if the database was written by a newer version of the node.
+    pub fn migrate(&self) -> Result<(), StorageError> {
+        let mut version = self.get_schema_version()?;
+
+        if version > SCHEMA_VERSION {
+            return Err(StorageError::UnsupportedSchemaVersion(version, SCHEMA_VERSION));
+        }
+
+        while version < SCHEMA_VERSION {
+            let mut database_transaction = DatabaseTransaction::new();
+
+            database_transaction.push_vec(self.migration_ops(version)?);
+            database_transaction.push(Self::schema_version_op(version + 1));
+
+            self.storage.write(database_transaction)?;
+
+            version += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the operations that upgrade the database from the given schema version to the next.
+    fn migration_ops(&self, version: u32) -> Result<Vec<Op>, StorageError> {
+        match version {
+            0 => self.block_work_migration_ops(),
+            1 => self.commitment_tree_migration_ops(),
+            _ => Err(StorageError::UnsupportedSchemaVersion(version, SCHEMA_VERSION)),
+        }
+    }
+
+    /// Returns the operations that store the cumulative chain work of the genesis block
+    /// and every stored block that descends from it.
+    fn block_work_migration_ops(&self) -> Result<Vec<Op>, StorageError> {
+        let genesis_hash = self.get_block_hash(0)?;
+        let genesis_work = self.get_block_header(&genesis_hash)?.to_work();
+
+        let mut ops = vec![Op::Insert {
+            col: COL_BLOCK_WORK,
+            key: genesis_hash.0.to_vec(),
+            value: genesis_work.to_le_bytes().to_vec(),
+        }];
+        ops.extend(self.descendant_work_ops(&genesis_hash, genesis_work)?);
+
+        Ok(ops)
+    }
+
+    /// Returns the operations that store every node of the commitment merkle tree.
+    fn commitment_tree_migration_ops(&self) -> Result<Vec<Op>, StorageError> {
+        let merkle_tree = self.build_merkle_tree(vec![])?;
+
+        Self::merkle_tree_node_ops(&merkle_tree, &merkle_tree.non_empty_nodes())
+    }
+}
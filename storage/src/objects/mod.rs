@@ -27,6 +27,9 @@ pub use block_path::*;
 pub mod dpc_state;
 pub use dpc_state::*;
 
+pub mod explorer_index;
+pub use explorer_index::*;
+
 pub mod insert_commit;
 pub use insert_commit::*;
 
@@ -36,6 +39,9 @@ pub use ledger_scheme::*;
 pub mod memory_pool;
 pub use memory_pool::*;
 
+pub mod migrations;
+pub use migrations::*;
+
 pub mod records;
 pub use records::*;
 
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Ledger, TransactionLocation, COL_TRANSACTION_LOCATION};
+use crate::{KeyValueStore, Ledger, TransactionLocation, COL_DIGEST, COL_TRANSACTION_LOCATION};
 use snarkos_errors::storage::StorageError;
 use snarkos_models::{
     algorithms::LoadableMerkleParameters,
@@ -27,7 +27,7 @@ use snarkos_utilities::{
     to_bytes,
 };
 
-impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+impl<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore> Ledger<T, P, S> {
     /// Returns a transaction location given the transaction ID if it exists. Returns `None` otherwise.
     pub fn get_transaction_location(
         &self,
@@ -62,6 +62,14 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         }
     }
 
+    /// Returns true if the transaction ledger digest is the digest of a current or past canon ledger state.
+    pub fn transaction_digest_is_valid(&self, transaction: &T) -> bool {
+        match to_bytes![transaction.ledger_digest()] {
+            Ok(digest) => self.storage.exists(COL_DIGEST, &digest),
+            Err(_) => false,
+        }
+    }
+
     /// Returns true if the transaction has internal parameters that already exist in the ledger.
     pub fn transcation_conflicts(&self, transaction: &T) -> bool {
         let transaction_serial_numbers = transaction.old_serial_numbers();
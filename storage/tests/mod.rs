@@ -24,10 +24,26 @@ mod test_storage {
         PedersenMerkleRootHash,
         ProofOfSuccinctWork,
     };
-    use snarkos_testing::storage::*;
+    use snarkos_storage::{DatabaseTransaction, COL_META, NUM_COLS};
+    use snarkos_testing::{consensus::TestTx, storage::*};
 
     use std::sync::Arc;
 
+    pub fn create_block(previous_block_hash: BlockHeaderHash, difficulty_target: u64, nonce: u32) -> Block<TestTx> {
+        Block {
+            header: BlockHeader {
+                difficulty_target,
+                nonce,
+                merkle_root_hash: MerkleRootHash([0; 32]),
+                previous_block_hash,
+                time: 123,
+                proof: ProofOfSuccinctWork::default(),
+                pedersen_merkle_root_hash: PedersenMerkleRootHash([0; 32]),
+            },
+            transactions: DPCTransactions::new(),
+        }
+    }
+
     #[test]
     pub fn test_new_blockchain() {
         let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
@@ -46,18 +62,7 @@ mod test_storage {
         assert_eq!(blockchain.get_latest_block_height(), 0);
 
         // insert a block
-        let block = Block {
-            header: BlockHeader {
-                difficulty_target: 100,
-                nonce: 99,
-                merkle_root_hash: MerkleRootHash([0; 32]),
-                previous_block_hash: BlockHeaderHash([0; 32]),
-                time: 123,
-                proof: ProofOfSuccinctWork::default(),
-                pedersen_merkle_root_hash: PedersenMerkleRootHash([0; 32]),
-            },
-            transactions: DPCTransactions::new(),
-        };
+        let block = create_block(BlockHeaderHash([0; 32]), 100, 99);
 
         blockchain.insert_and_commit(&block).unwrap();
         assert_eq!(blockchain.get_latest_block_height(), 1);
@@ -70,22 +75,118 @@ mod test_storage {
     }
 
     #[test]
-    pub fn test_storage() {
+    pub fn test_ancestor_headers() {
         let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
 
-        blockchain.storage.db.put(b"my key", b"my value").unwrap();
+        let genesis_hash = blockchain.get_block_hash(0).unwrap();
 
-        match blockchain.storage.db.get(b"my key") {
-            Ok(Some(value)) => println!("retrieved value {}", String::from_utf8(value).unwrap()),
-            Ok(None) => println!("value not found"),
-            Err(e) => println!("operational problem encountered: {}", e),
+        let mut previous_block_hash = genesis_hash.clone();
+        for time in 1..4 {
+            let mut block = create_block(previous_block_hash, 100, 99);
+            block.header.time = time;
+            previous_block_hash = block.header.get_hash();
+            blockchain.insert_and_commit(&block).unwrap();
         }
 
-        assert!(blockchain.storage.db.get(b"my key").is_ok());
+        // The newest headers come first
+        let headers = blockchain.get_ancestor_headers(&previous_block_hash, 2).unwrap();
+        let times: Vec<i64> = headers.iter().map(|header| header.time).collect();
+        assert_eq!(times, vec![3, 2]);
+
+        // The headers end at the genesis block
+        let headers = blockchain.get_ancestor_headers(&previous_block_hash, 10).unwrap();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers[3].get_hash(), genesis_hash);
 
         kill_storage_sync(blockchain);
     }
 
+    #[test]
+    pub fn test_storage() {
+        let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+        let mut database_transaction = DatabaseTransaction::new();
+        database_transaction.add(COL_META, b"my key", b"my value");
+        blockchain.storage.write(database_transaction).unwrap();
+
+        assert_eq!(
+            blockchain.storage.get(COL_META, b"my key").unwrap(),
+            Some(b"my value".to_vec())
+        );
+        assert!(blockchain.storage.exists(COL_META, b"my key"));
+
+        let mut database_transaction = DatabaseTransaction::new();
+        database_transaction.delete(COL_META, b"my key");
+        blockchain.storage.write(database_transaction).unwrap();
+
+        assert_eq!(blockchain.storage.get(COL_META, b"my key").unwrap(), None);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    pub fn test_storage_invalid_column() {
+        let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+        let mut database_transaction = DatabaseTransaction::new();
+        database_transaction.add(COL_META, b"my key", b"my value");
+        database_transaction.add(NUM_COLS, b"my key", b"my value");
+
+        assert!(blockchain.storage.write(database_transaction).is_err());
+        assert!(!blockchain.storage.exists(COL_META, b"my key"));
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    pub fn test_rocksdb_reopen() {
+        let mut path = std::env::temp_dir();
+        path.push(random_storage_path());
+
+        let block = create_block(BlockHeaderHash([0; 32]), 100, 99);
+
+        {
+            let blockchain = PersistentStore::open_at_path(&path).unwrap();
+            blockchain.insert_and_commit(&block).unwrap();
+            assert_eq!(blockchain.get_latest_block_height(), 1);
+        }
+
+        let blockchain = PersistentStore::open_at_path(&path).unwrap();
+
+        assert_eq!(blockchain.get_latest_block_height(), 1);
+        assert_eq!(blockchain.get_latest_block().unwrap(), block);
+
+        kill_storage(blockchain);
+    }
+
+    #[test]
+    pub fn test_mem_db_reopen() {
+        let mut path = std::env::temp_dir();
+        path.push(random_storage_path());
+
+        let block = create_block(BlockHeaderHash([0; 32]), 100, 99);
+
+        {
+            let blockchain = Store::open_at_path(&path).unwrap();
+            blockchain.insert_and_commit(&block).unwrap();
+            assert_eq!(blockchain.get_latest_block_height(), 1);
+        }
+
+        // Reopening the path shares the stored blocks instead of starting from a new genesis block
+        let blockchain = Store::open_at_path(&path).unwrap();
+
+        assert_eq!(blockchain.get_latest_block_height(), 1);
+        assert_eq!(blockchain.get_latest_block().unwrap(), block);
+
+        kill_storage(blockchain);
+
+        // Destroyed storage starts empty
+        let blockchain = Store::open_at_path(&path).unwrap();
+        assert_eq!(blockchain.get_latest_block_height(), 0);
+
+        kill_storage(blockchain);
+    }
+
     #[test]
     pub fn test_storage_memory_pool() {
         let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
@@ -110,6 +211,18 @@ mod test_storage {
         kill_storage_sync(blockchain);
     }
 
+    #[test]
+    pub fn test_storage_wallet() {
+        let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+        let wallet_serialized = vec![0u8];
+
+        assert!(blockchain.store_wallet(wallet_serialized.clone()).is_ok());
+        assert!(blockchain.get_wallet().is_ok());
+        assert_eq!(wallet_serialized, blockchain.get_wallet().unwrap());
+
+        kill_storage_sync(blockchain);
+    }
+
     #[test]
     pub fn test_destroy_storage() {
         let mut path = std::env::temp_dir();
@@ -118,6 +231,375 @@ mod test_storage {
         Store::destroy_storage(path).unwrap();
     }
 
+    mod test_fork_choice {
+        use super::*;
+        use snarkos_storage::BlockPath;
+
+        #[test]
+        pub fn test_cumulative_work() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            let genesis_hash = blockchain.get_block_hash(0).unwrap();
+            let genesis_work = blockchain.get_cumulative_work(&genesis_hash).unwrap();
+            assert_eq!(genesis_work, blockchain.get_latest_block().unwrap().header.to_work());
+
+            let block_1 = create_block(genesis_hash, u64::max_value() / 2, 1);
+            let block_2 = create_block(block_1.header.get_hash(), u64::max_value() / 4, 2);
+
+            // Receive block 2 as an orphan without a known chain work
+            blockchain.insert_only(&block_2).unwrap();
+            assert!(blockchain.get_cumulative_work(&block_2.header.get_hash()).is_err());
+
+            // Receiving block 1 fills in the work of its orphaned descendants
+            blockchain.insert_and_commit(&block_1).unwrap();
+
+            assert_eq!(
+                blockchain.get_cumulative_work(&block_1.header.get_hash()).unwrap(),
+                genesis_work + 2
+            );
+            assert_eq!(
+                blockchain.get_cumulative_work(&block_2.header.get_hash()).unwrap(),
+                genesis_work + 2 + 4
+            );
+
+            kill_storage_sync(blockchain);
+        }
+
+        #[test]
+        pub fn test_heavier_shorter_side_chain() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            let genesis_hash = blockchain.get_block_hash(0).unwrap();
+
+            // Canon chain of two low difficulty blocks
+            let canon_block_1 = create_block(genesis_hash.clone(), u64::max_value() / 2, 1);
+            let canon_block_2 = create_block(canon_block_1.header.get_hash(), u64::max_value() / 2, 2);
+
+            blockchain.insert_and_commit(&canon_block_1).unwrap();
+            blockchain.insert_and_commit(&canon_block_2).unwrap();
+
+            // Side chain of a single high difficulty block
+            let side_block_1 = create_block(genesis_hash, u64::max_value() / 8, 3);
+
+            let side_chain_path = match blockchain.get_block_path(&side_block_1.header).unwrap() {
+                BlockPath::SideChain(side_chain_path) => side_chain_path,
+                block_path => panic!("expected a side chain path, found {:?}", block_path),
+            };
+
+            assert_eq!(side_chain_path.shared_block_number, 0);
+            assert_eq!(side_chain_path.new_block_number, 1);
+            assert!(blockchain.is_heavier_side_chain(&side_chain_path).unwrap());
+
+            let reverted_blocks = blockchain.revert_for_fork(&side_chain_path).unwrap();
+            assert_eq!(reverted_blocks, vec![canon_block_2, canon_block_1]);
+            assert_eq!(blockchain.get_latest_block_height(), 0);
+
+            blockchain.insert_and_commit(&side_block_1).unwrap();
+            assert_eq!(blockchain.get_latest_block_height(), 1);
+            assert_eq!(blockchain.get_latest_block().unwrap(), side_block_1);
+
+            kill_storage_sync(blockchain);
+        }
+
+        #[test]
+        pub fn test_equal_work_tie() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            let genesis_hash = blockchain.get_block_hash(0).unwrap();
+
+            let canon_block_1 = create_block(genesis_hash.clone(), u64::max_value() / 2, 1);
+            let side_block_1 = create_block(genesis_hash, u64::max_value() / 2, 2);
+
+            blockchain.insert_and_commit(&canon_block_1).unwrap();
+
+            let side_chain_path = match blockchain.get_block_path(&side_block_1.header).unwrap() {
+                BlockPath::SideChain(side_chain_path) => side_chain_path,
+                block_path => panic!("expected a side chain path, found {:?}", block_path),
+            };
+
+            // The first chain received wins a tie
+            assert!(!blockchain.is_heavier_side_chain(&side_chain_path).unwrap());
+
+            assert!(blockchain.revert_for_fork(&side_chain_path).unwrap().is_empty());
+            assert_eq!(blockchain.get_latest_block_height(), 1);
+            assert_eq!(blockchain.get_latest_block().unwrap(), canon_block_1);
+
+            kill_storage_sync(blockchain);
+        }
+
+        #[test]
+        pub fn test_deep_reorg() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            let genesis_hash = blockchain.get_block_hash(0).unwrap();
+            let num_canon_blocks = 20;
+
+            let mut previous_block_hash = genesis_hash.clone();
+            for nonce in 0..num_canon_blocks {
+                let block = create_block(previous_block_hash, u64::max_value() / 2, nonce);
+                blockchain.insert_and_commit(&block).unwrap();
+                previous_block_hash = block.header.get_hash();
+            }
+
+            // Store a side chain of the same length, which ties with the canon chain
+            let mut side_blocks = vec![];
+            let mut previous_block_hash = genesis_hash;
+            for nonce in 0..num_canon_blocks {
+                let block = create_block(previous_block_hash, u64::max_value() / 2, num_canon_blocks + nonce);
+                previous_block_hash = block.header.get_hash();
+                side_blocks.push(block);
+            }
+
+            for block in &side_blocks[1..] {
+                blockchain.insert_only(block).unwrap();
+            }
+
+            // Receiving the missing first side chain block reveals the full side chain
+            let side_chain_path = match blockchain.get_block_path(&side_blocks[0].header).unwrap() {
+                BlockPath::SideChain(side_chain_path) => side_chain_path,
+                block_path => panic!("expected a side chain path, found {:?}", block_path),
+            };
+
+            assert_eq!(side_chain_path.new_block_number, num_canon_blocks);
+            assert!(!blockchain.is_heavier_side_chain(&side_chain_path).unwrap());
+
+            blockchain.insert_only(&side_blocks[0]).unwrap();
+
+            // One more side chain block makes it heavier than the canon chain
+            let side_tip = create_block(previous_block_hash, u64::max_value() / 2, 2 * num_canon_blocks);
+
+            let side_chain_path = match blockchain.get_block_path(&side_tip.header).unwrap() {
+                BlockPath::SideChain(side_chain_path) => side_chain_path,
+                block_path => panic!("expected a side chain path, found {:?}", block_path),
+            };
+
+            assert_eq!(side_chain_path.shared_block_number, 0);
+            assert_eq!(side_chain_path.new_block_number, num_canon_blocks + 1);
+            assert_eq!(side_chain_path.path.len(), num_canon_blocks as usize + 1);
+            assert!(blockchain.is_heavier_side_chain(&side_chain_path).unwrap());
+
+            blockchain.revert_for_fork(&side_chain_path).unwrap();
+            assert_eq!(blockchain.get_latest_block_height(), 0);
+
+            for block in &side_blocks {
+                blockchain.commit(&block.header.get_hash()).unwrap();
+            }
+            blockchain.insert_and_commit(&side_tip).unwrap();
+
+            assert_eq!(blockchain.get_latest_block_height(), num_canon_blocks + 1);
+            assert_eq!(blockchain.get_latest_block().unwrap(), side_tip);
+            assert_eq!(
+                blockchain.get_latest_cumulative_work().unwrap(),
+                side_chain_path.aggregate_work
+            );
+
+            kill_storage_sync(blockchain);
+        }
+    }
+
+    mod test_commitment_tree {
+        use super::*;
+        use snarkos_consensus::MerkleTreeLedger;
+        use snarkos_testing::consensus::{DATA, FIXTURE_VK};
+        use snarkos_utilities::{to_bytes, ToBytes};
+
+        fn assert_matches_full_rebuild(blockchain: &MerkleTreeLedger) {
+            let expected_root = blockchain.build_merkle_tree(vec![]).unwrap().root();
+
+            assert_eq!(blockchain.cm_merkle_tree.read().root(), expected_root);
+            assert_eq!(blockchain.load_merkle_tree().unwrap().root(), expected_root);
+            assert_eq!(blockchain.current_digest().unwrap(), to_bytes![expected_root].unwrap());
+        }
+
+        #[test]
+        pub fn test_incremental_commitment_tree() {
+            let blockchain = FIXTURE_VK.ledger();
+            assert_matches_full_rebuild(&blockchain);
+
+            blockchain.insert_and_commit(&DATA.block_1).unwrap();
+            assert_matches_full_rebuild(&blockchain);
+
+            blockchain.insert_and_commit(&DATA.block_2).unwrap();
+            assert_matches_full_rebuild(&blockchain);
+
+            blockchain.remove_latest_block().unwrap();
+            assert_matches_full_rebuild(&blockchain);
+
+            blockchain.remove_latest_block().unwrap();
+            assert_matches_full_rebuild(&blockchain);
+
+            kill_storage(blockchain);
+        }
+    }
+
+    mod test_explorer_index {
+        use super::*;
+        use snarkos_consensus::MerkleTreeLedger;
+        use snarkos_errors::storage::StorageError;
+        use snarkos_models::objects::Transaction;
+        use snarkos_testing::consensus::{DATA, FIXTURE_VK};
+        use snarkos_utilities::{to_bytes, ToBytes};
+
+        fn assert_indexed<T: Transaction>(blockchain: &MerkleTreeLedger, transaction: &T, indexed: bool) {
+            let transaction_id = match indexed {
+                true => Some(transaction.transaction_id().unwrap().to_vec()),
+                false => None,
+            };
+
+            for sn in transaction.old_serial_numbers() {
+                let sn_bytes = to_bytes![sn].unwrap();
+                assert_eq!(
+                    blockchain.get_serial_number_transaction_id(&sn_bytes).unwrap(),
+                    transaction_id
+                );
+            }
+
+            for cm in transaction.new_commitments() {
+                let cm_bytes = to_bytes![cm].unwrap();
+                assert_eq!(
+                    blockchain.get_commitment_transaction_id(&cm_bytes).unwrap(),
+                    transaction_id
+                );
+            }
+
+            let memo_bytes = to_bytes![transaction.memorandum()].unwrap();
+            assert_eq!(blockchain.get_memo_transaction_id(&memo_bytes).unwrap(), transaction_id);
+        }
+
+        #[test]
+        pub fn test_explorer_index() {
+            let blockchain = FIXTURE_VK.ledger();
+            let transaction_1 = &DATA.block_1.transactions.0[0];
+            let transaction_2 = &DATA.block_2.transactions.0[0];
+
+            blockchain.insert_and_commit(&DATA.block_1).unwrap();
+
+            // Lookups fail until the index is enabled
+
+            let memo_bytes = to_bytes![transaction_1.memorandum()].unwrap();
+            match blockchain.get_memo_transaction_id(&memo_bytes) {
+                Err(StorageError::ExplorerIndexDisabled) => {}
+                result => panic!("expected a disabled explorer index error, found {:?}", result),
+            }
+
+            // Enabling the index covers the blocks that are already canon
+
+            blockchain.enable_explorer_index().unwrap();
+            assert!(blockchain.is_explorer_index_enabled());
+            assert_indexed(&blockchain, transaction_1, true);
+
+            blockchain.insert_and_commit(&DATA.block_2).unwrap();
+            assert_indexed(&blockchain, transaction_2, true);
+
+            // Decommitted transactions are removed from the index
+
+            blockchain.remove_latest_block().unwrap();
+            assert_indexed(&blockchain, transaction_2, false);
+            assert_indexed(&blockchain, transaction_1, true);
+
+            kill_storage(blockchain);
+        }
+    }
+
+    mod test_migrations {
+        use super::*;
+        use snarkos_errors::storage::StorageError;
+        use snarkos_storage::{COL_BLOCK_WORK, COL_COMMITMENT_TREE, KEY_SCHEMA_VERSION, SCHEMA_VERSION};
+
+        #[test]
+        pub fn test_new_ledger_schema_version() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            assert_eq!(blockchain.get_schema_version().unwrap(), SCHEMA_VERSION);
+
+            kill_storage_sync(blockchain);
+        }
+
+        #[test]
+        pub fn test_migrate_unversioned_ledger() {
+            let (blockchain, _): (Arc<Store>, _) = open_test_blockchain();
+
+            let genesis_hash = blockchain.get_block_hash(0).unwrap();
+
+            let canon_block_1 = create_block(genesis_hash.clone(), u64::max_value() / 2, 1);
+            let canon_block_2 = create_block(canon_block_1.header.get_hash(), u64::max_value() / 4, 2);
+            let side_block_1 = create_block(genesis_hash, u64::max_value() / 8, 3);
+
+            blockchain.insert_and_commit(&canon_block_1).unwrap();
+            blockchain.insert_and_commit(&canon_block_2).unwrap();
+            blockchain.insert_only(&side_block_1).unwrap();
+
+            let block_hashes = [
+                blockchain.get_block_hash(0).unwrap(),
+                canon_block_1.header.get_hash(),
+                canon_block_2.header.get_hash(),
+                side_block_1.header.get_hash(),
+            ];
+            let expected_work: Vec<u128> = block_hashes
+                .iter()
+                .map(|block_hash| blockchain.get_cumulative_work(block_hash).unwrap())
+                .collect();
+
+            // Strip the ledger back to the layout written before schema versioning
+
+            let mut database_transaction = DatabaseTransaction::new();
+            database_transaction.delete(COL_META, KEY_SCHEMA_VERSION.as_bytes());
+            for (block_hash, _) in blockchain.storage.get_iter(COL_BLOCK_WORK).unwrap() {
+                database_transaction.delete(COL_BLOCK_WORK, &block_hash);
+            }
+            for (node_key, _) in blockchain.storage.get_iter(COL_COMMITMENT_TREE).unwrap() {
+                database_transaction.delete(COL_COMMITMENT_TREE, &node_key);
+            }
+            blockchain.storage.write(database_transaction).unwrap();
+
+            assert_eq!(blockchain.get_schema_version().unwrap(), 0);
+            assert!(blockchain.get_cumulative_work(&block_hashes[0]).is_err());
+            assert!(blockchain.load_merkle_tree().is_err());
+
+            blockchain.migrate().unwrap();
+
+            assert_eq!(blockchain.get_schema_version().unwrap(), SCHEMA_VERSION);
+            for (block_hash, work) in block_hashes.iter().zip(expected_work) {
+                assert_eq!(blockchain.get_cumulative_work(block_hash).unwrap(), work);
+            }
+            assert_eq!(
+                blockchain.load_merkle_tree().unwrap().root(),
+                blockchain.cm_merkle_tree.read().root()
+            );
+
+            kill_storage_sync(blockchain);
+        }
+
+        #[test]
+        pub fn test_newer_schema_version() {
+            let mut path = std::env::temp_dir();
+            path.push(random_storage_path());
+
+            {
+                let blockchain = PersistentStore::open_at_path(&path).unwrap();
+
+                let mut database_transaction = DatabaseTransaction::new();
+                database_transaction.add(
+                    COL_META,
+                    KEY_SCHEMA_VERSION.as_bytes(),
+                    &(SCHEMA_VERSION + 1).to_le_bytes(),
+                );
+                blockchain.storage.write(database_transaction).unwrap();
+            }
+
+            match PersistentStore::open_at_path(&path) {
+                Err(StorageError::UnsupportedSchemaVersion(version, supported)) => {
+                    assert_eq!(version, SCHEMA_VERSION + 1);
+                    assert_eq!(supported, SCHEMA_VERSION);
+                }
+                Err(error) => panic!("expected an unsupported schema version error, found {}", error),
+                Ok(_) => panic!("expected an unsupported schema version error"),
+            }
+
+            PersistentStore::destroy_storage(path).unwrap();
+        }
+    }
+
     mod test_invalid {
         use super::*;
 
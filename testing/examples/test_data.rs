@@ -15,7 +15,12 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger, Miner};
-use snarkos_dpc::base_dpc::{instantiated::*, record::DPCRecord, record_payload::RecordPayload};
+use snarkos_dpc::base_dpc::{
+    instantiated::*,
+    program::ProgramRegistry,
+    record::DPCRecord,
+    record_payload::RecordPayload,
+};
 use snarkos_errors::consensus::ConsensusError;
 use snarkos_models::dpc::{DPCScheme, Program, Record};
 use snarkos_objects::{dpc::DPCTransactions, Account, AccountAddress, Block};
@@ -58,11 +63,13 @@ fn setup_test_data() -> Result<TestData, ConsensusError> {
 
     // Find alternative conflicting/late blocks
 
-    let alternative_block_1_header = miner.find_block(
-        &block_1.transactions,
-        &ledger.get_block_header(&block_1.header.previous_block_hash)?,
+    let genesis_header = ledger.get_block_header(&block_1.header.previous_block_hash)?;
+    let alternative_block_1_header = miner.find_block(&block_1.transactions, &[genesis_header.clone()], 1)?;
+    let alternative_block_2_header = miner.find_block(
+        &block_2.transactions,
+        &[alternative_block_1_header.clone(), genesis_header],
+        2,
     )?;
-    let alternative_block_2_header = miner.find_block(&block_2.transactions, &alternative_block_1_header)?;
 
     let test_data = TestData {
         block_1,
@@ -86,10 +93,10 @@ fn mine_block(
 ) -> Result<(Block<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
     let transactions = DPCTransactions(txs);
 
-    let (previous_block_header, transactions, coinbase_records) =
+    let (ancestor_headers, transactions, coinbase_records) =
         miner.establish_block(&parameters, ledger, &transactions)?;
 
-    let header = miner.find_block(&transactions, &previous_block_header)?;
+    let header = miner.find_block(&transactions, &ancestor_headers, ledger.get_latest_block_height() + 1)?;
 
     let block = Block { header, transactions };
 
@@ -140,6 +147,7 @@ fn send<R: Rng>(
     let from = vec![from.private_key.clone(); NUM_INPUT_RECORDS];
     consensus.create_transaction(
         parameters,
+        &ProgramRegistry::new(parameters)?,
         inputs,
         from,
         to,
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_consensus::ConsensusParameters;
+use snarkos_consensus::{ConsensusEvents, ConsensusParameters, NetworkTime, RetargetAlgorithm, RewardSchedule};
 use snarkos_dpc::instantiated::Components;
 use snarkos_errors::objects::TransactionError;
 use snarkos_models::{algorithms::CRH, dpc::DPCComponents, objects::Transaction, parameters::Parameters};
@@ -50,8 +50,14 @@ pub static TEST_CONSENSUS: Lazy<ConsensusParameters> = Lazy::new(|| {
         max_nonce: u32::max_value(),
         target_block_time: 2i64, //unix seconds
         network: Network::Mainnet,
+        retarget_algorithm: RetargetAlgorithm::Bitcoin,
+        reward_schedule: RewardSchedule::ALEO,
+        upgrades: vec![],
         verifier: PoswMarlin::verify_only().unwrap(),
         authorized_inner_snark_ids: vec![inner_snark_id],
+        events: ConsensusEvents::new(),
+        network_time: NetworkTime::new(),
+        dev_mode: false,
     }
 });
 
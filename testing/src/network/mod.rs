@@ -17,12 +17,15 @@
 use crate::consensus::*;
 use snarkos_consensus::{MemoryPool, MerkleTreeLedger};
 use snarkos_dpc::base_dpc::{instantiated::Components, parameters::PublicParameters};
-use snarkos_network::{Channel, Context, Server, SyncHandler};
+use snarkos_network::{Channel, Context, Server, SyncHandler, DEFAULT_BAN_DURATION};
 
 use rand::Rng;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::{net::TcpListener, sync::Mutex};
 
+mod simulator;
+pub use simulator::*;
+
 pub const LOCALHOST: &'static str = "0.0.0.0:";
 pub const CONNECTION_FREQUENCY_LONG: u64 = 100000; // 100 seconds
 pub const CONNECTION_FREQUENCY_SHORT: u64 = 100; // .1 seconds
@@ -56,7 +59,7 @@ pub fn initialize_test_server(
     let sync_handler_lock = Arc::new(Mutex::new(sync_handler));
 
     Server::new(
-        Context::new(server_address, 5, 1, 10, true, vec![]),
+        Context::new(server_address, 5, 1, 10, true, vec![], DEFAULT_BAN_DURATION, None),
         consensus,
         storage,
         parameters,
@@ -72,19 +75,16 @@ pub fn start_test_server(server: Server) {
 }
 
 /// Returns a tcp channel connected to the address
-pub async fn connect_channel(listener: &mut TcpListener, address: SocketAddr) -> Channel {
-    let channel = Channel::new_write_only(address).await.unwrap();
-    let (reader, _socket) = listener.accept().await.unwrap();
-
-    channel.update_reader(Arc::new(Mutex::new(reader)))
+pub async fn connect_channel(address: SocketAddr) -> Channel {
+    Channel::connect(address, None).await.unwrap()
 }
 
-/// Returns the next tcp channel connected to the listener
+/// Returns the next tcp channel accepted by the listener, identified by the given peer address
 pub async fn accept_channel(listener: &mut TcpListener, address: SocketAddr) -> Channel {
-    let (reader, _peer) = listener.accept().await.unwrap();
-    let channel = Channel::new_read_only(reader).unwrap();
+    let (stream, _peer) = listener.accept().await.unwrap();
+    let channel = Channel::accept(stream, None).await.unwrap();
 
-    channel.update_writer(address).await.unwrap()
+    channel.update_address(address)
 }
 
 /// Starts a fake node that accepts all tcp connections at the given socket address
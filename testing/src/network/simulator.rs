@@ -0,0 +1,652 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{consensus::*, dpc::load_verifying_parameters};
+use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, Tx},
+    parameters::PublicParameters,
+};
+use snarkos_network::{
+    process_transaction_internal,
+    propagate_block,
+    Context,
+    Listener,
+    PeerStream,
+    Server,
+    SyncHandler,
+    Transport,
+    TransportFuture,
+    DEFAULT_BAN_DURATION,
+};
+use snarkos_objects::Block;
+use snarkos_utilities::{bytes::ToBytes, to_bytes};
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::{
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context as TaskContext, Poll, Waker},
+    time::Duration,
+};
+use tokio::{
+    future::poll_fn,
+    io::{AsyncRead, AsyncWrite},
+    runtime::{Builder, Runtime},
+    sync::{mpsc, Mutex},
+    time::{delay_for, delay_until, Delay, Instant},
+};
+
+/// The port every simulated node listens on.
+pub const SIMULATED_PORT: u16 = 4131;
+
+/// The latency of every link in a new simulated network.
+pub const SIMULATED_LATENCY: Duration = Duration::from_millis(50);
+
+/// The connection frequency of simulated nodes, in milliseconds.
+pub const SIMULATED_CONNECTION_FREQUENCY: u64 = 1000;
+
+/// A virtual network that carries streams between nodes in the same process.
+/// Each host is identified by its ip address.
+/// Bytes are delivered after the latency of the link they are written on,
+/// unless they are dropped at random or the link is partitioned.
+/// Delivery is timed by the runtime clock, so a network run on a paused clock is deterministic.
+#[derive(Clone, Debug)]
+pub struct SimulatedNetwork {
+    state: Arc<StdMutex<NetworkState>>,
+}
+
+#[derive(Debug)]
+struct NetworkState {
+    rng: XorShiftRng,
+    latency: Duration,
+    link_latencies: HashMap<(IpAddr, IpAddr), Duration>,
+    drop_rate: f64,
+    partitions: HashSet<(IpAddr, IpAddr)>,
+    listeners: HashMap<SocketAddr, mpsc::UnboundedSender<SimulatedStream>>,
+    next_port: u16,
+}
+
+/// Returns the key of the link between two hosts, which is the same in both directions.
+fn link(a: IpAddr, b: IpAddr) -> (IpAddr, IpAddr) {
+    (cmp::min(a, b), cmp::max(a, b))
+}
+
+impl SimulatedNetwork {
+    /// Returns a new network whose dropped writes are chosen by an rng with the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(StdMutex::new(NetworkState {
+                rng: XorShiftRng::seed_from_u64(seed),
+                latency: SIMULATED_LATENCY,
+                link_latencies: HashMap::new(),
+                drop_rate: 0.0,
+                partitions: HashSet::new(),
+                listeners: HashMap::new(),
+                next_port: 49152,
+            })),
+        }
+    }
+
+    /// Returns a transport that connects from the given host.
+    pub fn transport(&self, host: IpAddr) -> SimulatedTransport {
+        SimulatedTransport {
+            network: self.clone(),
+            host,
+        }
+    }
+
+    /// Sets the latency of every link without a latency of its own.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// Sets the latency of the link between two hosts.
+    pub fn set_link_latency(&self, a: SocketAddr, b: SocketAddr, latency: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.link_latencies.insert(link(a.ip(), b.ip()), latency);
+    }
+
+    /// Sets the probability that a write is dropped instead of delivered.
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        self.state.lock().unwrap().drop_rate = drop_rate;
+    }
+
+    /// Drops every write between the two groups of hosts and refuses new connections between them.
+    pub fn partition(&self, group_a: &[SocketAddr], group_b: &[SocketAddr]) {
+        let mut state = self.state.lock().unwrap();
+        for a in group_a {
+            for b in group_b {
+                state.partitions.insert(link(a.ip(), b.ip()));
+            }
+        }
+    }
+
+    /// Removes every partition.
+    pub fn heal(&self) {
+        self.state.lock().unwrap().partitions.clear();
+    }
+
+    /// Returns true if the link between two hosts is partitioned.
+    pub fn is_partitioned(&self, a: SocketAddr, b: SocketAddr) -> bool {
+        self.state.lock().unwrap().partitions.contains(&link(a.ip(), b.ip()))
+    }
+
+    /// Returns the time a write from one host takes to reach another, or `None` if the write is dropped.
+    fn transit(&self, from: IpAddr, to: IpAddr) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.partitions.contains(&link(from, to)) {
+            return None;
+        }
+
+        let drop_rate = state.drop_rate;
+        if drop_rate > 0.0 && state.rng.gen_bool(drop_rate.min(1.0)) {
+            return None;
+        }
+
+        Some(*state.link_latencies.get(&link(from, to)).unwrap_or(&state.latency))
+    }
+
+    /// Opens a stream from the host to a listening address.
+    fn connect(&self, host: IpAddr, address: SocketAddr) -> io::Result<SimulatedStream> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.partitions.contains(&link(host, address.ip())) {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        let local_address = SocketAddr::new(host, state.next_port);
+        state.next_port = state.next_port.checked_add(1).unwrap_or(49152);
+
+        let listener = state
+            .listeners
+            .get(&address)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionRefused))?;
+
+        let (local, remote) = SimulatedStream::pair(self.clone(), local_address, address);
+        listener
+            .send(remote)
+            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionRefused))?;
+
+        Ok(local)
+    }
+
+    /// Listens for streams opened to the address.
+    fn bind(&self, address: SocketAddr) -> io::Result<SimulatedListener> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.listeners.contains_key(&address) {
+            return Err(io::ErrorKind::AddrInUse.into());
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        state.listeners.insert(address, sender);
+
+        Ok(SimulatedListener {
+            network: self.clone(),
+            address,
+            receiver,
+        })
+    }
+}
+
+/// A transport that opens streams from one host of a simulated network.
+#[derive(Clone, Debug)]
+pub struct SimulatedTransport {
+    network: SimulatedNetwork,
+    host: IpAddr,
+}
+
+impl Transport for SimulatedTransport {
+    fn connect(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn PeerStream>> {
+        let stream = self.network.connect(self.host, address);
+        Box::pin(async move { Ok(Box::new(stream?) as Box<dyn PeerStream>) })
+    }
+
+    fn bind(&self, address: SocketAddr) -> TransportFuture<'_, Box<dyn Listener>> {
+        // Listening on all interfaces listens on the host
+        let address = match address.ip().is_unspecified() {
+            true => SocketAddr::new(self.host, address.port()),
+            false => address,
+        };
+
+        let listener = self.network.bind(address);
+        Box::pin(async move { Ok(Box::new(listener?) as Box<dyn Listener>) })
+    }
+}
+
+/// Accepts the streams opened to an address of a simulated network.
+#[derive(Debug)]
+pub struct SimulatedListener {
+    network: SimulatedNetwork,
+    address: SocketAddr,
+    receiver: mpsc::UnboundedReceiver<SimulatedStream>,
+}
+
+impl Listener for SimulatedListener {
+    fn accept(&mut self) -> TransportFuture<'_, (Box<dyn PeerStream>, SocketAddr)> {
+        Box::pin(async move {
+            match self.receiver.recv().await {
+                Some(stream) => {
+                    let peer_address = stream.peer_address;
+                    Ok((Box::new(stream) as Box<dyn PeerStream>, peer_address))
+                }
+                None => Err(io::ErrorKind::NotConnected.into()),
+            }
+        })
+    }
+}
+
+impl Drop for SimulatedListener {
+    fn drop(&mut self) {
+        self.network.state.lock().unwrap().listeners.remove(&self.address);
+    }
+}
+
+/// The bytes in flight in one direction of a simulated stream.
+#[derive(Debug, Default)]
+struct Pipe {
+    /// Written chunks and the time they arrive at the reader
+    chunks: VecDeque<(Instant, Vec<u8>)>,
+    /// Set once the writer shuts down
+    closed: bool,
+    /// Set once the reader is dropped
+    abandoned: bool,
+    /// Woken when a chunk is written or the pipe is closed
+    reader: Option<Waker>,
+}
+
+impl Pipe {
+    fn wake_reader(&mut self) {
+        if let Some(waker) = self.reader.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// One end of a stream between two hosts of a simulated network.
+/// Chunks are read in the order they were written, once they have arrived.
+#[derive(Debug)]
+pub struct SimulatedStream {
+    network: SimulatedNetwork,
+    local_address: SocketAddr,
+    peer_address: SocketAddr,
+    incoming: Arc<StdMutex<Pipe>>,
+    outgoing: Arc<StdMutex<Pipe>>,
+    arrival: Option<Delay>,
+}
+
+impl SimulatedStream {
+    /// Returns both ends of a new stream.
+    fn pair(network: SimulatedNetwork, a: SocketAddr, b: SocketAddr) -> (Self, Self) {
+        let a_to_b = Arc::new(StdMutex::new(Pipe::default()));
+        let b_to_a = Arc::new(StdMutex::new(Pipe::default()));
+
+        let a_end = Self {
+            network: network.clone(),
+            local_address: a,
+            peer_address: b,
+            incoming: b_to_a.clone(),
+            outgoing: a_to_b.clone(),
+            arrival: None,
+        };
+        let b_end = Self {
+            network,
+            local_address: b,
+            peer_address: a,
+            incoming: a_to_b,
+            outgoing: b_to_a,
+            arrival: None,
+        };
+
+        (a_end, b_end)
+    }
+
+    /// Waits until the next incoming chunk has arrived or the peer has closed the stream.
+    fn poll_arrival(&mut self, cx: &mut TaskContext<'_>) -> Poll<()> {
+        loop {
+            let arrival = {
+                let mut incoming = self.incoming.lock().unwrap();
+                match incoming.chunks.front() {
+                    Some((arrival, _)) => *arrival,
+                    None if incoming.closed => return Poll::Ready(()),
+                    None => {
+                        incoming.reader = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            };
+
+            if arrival <= Instant::now() {
+                return Poll::Ready(());
+            }
+
+            let delay = self.arrival.get_or_insert_with(|| delay_until(arrival));
+            if delay.deadline() != arrival {
+                delay.reset(arrival);
+            }
+            if Pin::new(delay).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Copies the arrived incoming bytes into the buffer, removing them from the stream if `consume` is set.
+    fn poll_copy(&mut self, cx: &mut TaskContext<'_>, buffer: &mut [u8], consume: bool) -> Poll<io::Result<usize>> {
+        if self.poll_arrival(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let now = Instant::now();
+        let mut incoming = self.incoming.lock().unwrap();
+        let mut length = 0;
+
+        for (arrival, chunk) in incoming.chunks.iter_mut() {
+            if *arrival > now || length == buffer.len() {
+                break;
+            }
+
+            let count = cmp::min(buffer.len() - length, chunk.len());
+            buffer[length..length + count].copy_from_slice(&chunk[..count]);
+            length += count;
+
+            if consume {
+                chunk.drain(..count);
+                // Partial reads leave the rest of the chunk at the front of the pipe
+                break;
+            }
+        }
+
+        if consume {
+            while incoming
+                .chunks
+                .front()
+                .map(|(_, chunk)| chunk.is_empty())
+                .unwrap_or(false)
+            {
+                incoming.chunks.pop_front();
+            }
+        }
+
+        Poll::Ready(Ok(length))
+    }
+}
+
+impl AsyncRead for SimulatedStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buffer: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.poll_copy(cx, buffer, true)
+    }
+}
+
+impl AsyncWrite for SimulatedStream {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>, buffer: &[u8]) -> Poll<io::Result<usize>> {
+        let transit = self.network.transit(self.local_address.ip(), self.peer_address.ip());
+        let mut outgoing = self.outgoing.lock().unwrap();
+
+        if outgoing.closed || outgoing.abandoned {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        if let (Some(latency), false) = (transit, buffer.is_empty()) {
+            // Chunks never overtake the chunks written before them
+            let mut arrival = Instant::now() + latency;
+            if let Some((last_arrival, _)) = outgoing.chunks.back() {
+                arrival = cmp::max(arrival, *last_arrival);
+            }
+
+            outgoing.chunks.push_back((arrival, buffer.to_vec()));
+            outgoing.wake_reader();
+        }
+
+        Poll::Ready(Ok(buffer.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        let mut outgoing = self.outgoing.lock().unwrap();
+        outgoing.closed = true;
+        outgoing.wake_reader();
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl PeerStream for SimulatedStream {
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.peer_address)
+    }
+
+    fn peek<'a>(&'a mut self, buffer: &'a mut [u8]) -> TransportFuture<'a, usize> {
+        Box::pin(poll_fn(move |cx| self.poll_copy(cx, buffer, false)))
+    }
+}
+
+impl Drop for SimulatedStream {
+    fn drop(&mut self) {
+        let mut outgoing = self.outgoing.lock().unwrap();
+        outgoing.closed = true;
+        outgoing.wake_reader();
+        drop(outgoing);
+
+        self.incoming.lock().unwrap().abandoned = true;
+    }
+}
+
+/// A node of a simulation, with handles to the state shared with its server.
+#[derive(Clone)]
+pub struct SimulatedNode {
+    pub address: SocketAddr,
+    pub consensus: ConsensusParameters,
+    pub context: Arc<Context>,
+    pub storage: Arc<MerkleTreeLedger>,
+    pub parameters: PublicParameters<Components>,
+    pub memory_pool_lock: Arc<Mutex<MemoryPool<Tx>>>,
+    pub sync_handler_lock: Arc<Mutex<SyncHandler>>,
+}
+
+impl SimulatedNode {
+    /// Returns the height of the node's canon chain.
+    pub fn height(&self) -> u32 {
+        self.storage.get_latest_block_height()
+    }
+
+    /// Returns true if the block is on the node's canon chain.
+    pub fn is_canon(&self, block: &Block<Tx>) -> bool {
+        self.storage.is_canon(&block.header.get_hash())
+    }
+
+    /// Returns the number of peers the node is connected to.
+    pub async fn connected_peers(&self) -> u16 {
+        self.context.peer_book.read().await.connected_total()
+    }
+
+    /// Returns the number of transactions in the node's memory pool.
+    pub async fn memory_pool_size(&self) -> usize {
+        self.memory_pool_lock.lock().await.transactions.len()
+    }
+
+    /// Adds the block to the node's chain and propagates it to its peers, as if the node had mined it.
+    pub async fn publish_block(&self, block: &Block<Tx>) {
+        let mut memory_pool = self.memory_pool_lock.lock().await;
+        self.consensus
+            .receive_block(&self.parameters, &self.storage, &mut memory_pool, block)
+            .unwrap();
+        drop(memory_pool);
+
+        propagate_block(self.context.clone(), to_bytes![block].unwrap(), self.address)
+            .await
+            .unwrap();
+    }
+
+    /// Adds the transaction to the node's memory pool and propagates it to its peers,
+    /// as if the transaction had been sent to the node over rpc.
+    pub async fn submit_transaction(&self, transaction_bytes: Vec<u8>) {
+        process_transaction_internal(
+            self.context.clone(),
+            &self.consensus,
+            &self.parameters,
+            self.storage.clone(),
+            self.memory_pool_lock.clone(),
+            transaction_bytes,
+            self.address,
+        )
+        .await
+        .unwrap();
+    }
+}
+
+/// Runs nodes on a simulated network, on a runtime with a paused clock.
+/// Time only passes while the simulation is run, and it passes as fast as the nodes can process their messages.
+pub struct Simulation {
+    pub network: SimulatedNetwork,
+    pub nodes: Vec<SimulatedNode>,
+    runtime: Option<Runtime>,
+    storage_paths: Vec<PathBuf>,
+}
+
+impl Simulation {
+    /// Returns a new simulation whose network drops writes with an rng seeded by the given seed.
+    pub fn new(seed: u64) -> Self {
+        let mut runtime = Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()
+            .expect("failed to build the simulation runtime");
+
+        runtime.block_on(async { tokio::time::pause() });
+
+        Self {
+            network: SimulatedNetwork::new(seed),
+            nodes: vec![],
+            runtime: Some(runtime),
+            storage_paths: vec![],
+        }
+    }
+
+    /// Starts a node with a new chain that connects to the given bootnodes.
+    /// A node without bootnodes is a bootnode itself.
+    /// The node handshakes with its first bootnode once the simulation is run.
+    pub fn add_node(&mut self, bootnodes: &[SocketAddr]) -> SimulatedNode {
+        let host = IpAddr::V4(Ipv4Addr::new(10, 0, 0, self.nodes.len() as u8 + 1));
+        let address = SocketAddr::new(host, SIMULATED_PORT);
+
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        self.storage_paths.push(storage.storage.db.path().to_owned());
+
+        let bootnodes: Vec<String> = bootnodes.iter().map(|bootnode| bootnode.to_string()).collect();
+        let sync_node = bootnodes
+            .first()
+            .and_then(|bootnode| bootnode.parse().ok())
+            .unwrap_or(address);
+
+        let context = Context::new(
+            address,
+            5,
+            1,
+            10,
+            bootnodes.is_empty(),
+            bootnodes,
+            DEFAULT_BAN_DURATION,
+            None,
+        )
+        .with_transport(Arc::new(self.network.transport(host)));
+
+        let server = Server::new(
+            context,
+            TEST_CONSENSUS.clone(),
+            storage.clone(),
+            load_verifying_parameters(),
+            Arc::new(Mutex::new(MemoryPool::new())),
+            Arc::new(Mutex::new(SyncHandler::new(sync_node))),
+            SIMULATED_CONNECTION_FREQUENCY,
+        );
+
+        let node = SimulatedNode {
+            address,
+            consensus: server.consensus.clone(),
+            context: server.context.clone(),
+            storage,
+            parameters: server.parameters.clone(),
+            memory_pool_lock: server.memory_pool_lock.clone(),
+            sync_handler_lock: server.sync_handler_lock.clone(),
+        };
+
+        self.runtime().spawn(async move {
+            if let Err(error) = server.listen().await {
+                panic!("simulated node {} stopped: {}", address, error);
+            }
+        });
+        self.nodes.push(node.clone());
+
+        node
+    }
+
+    /// Runs the simulation until the future completes.
+    pub fn block_on<F: Future>(&mut self, future: F) -> F::Output {
+        self.runtime().block_on(future)
+    }
+
+    /// Runs the simulation for the given duration.
+    pub fn run_for(&mut self, duration: Duration) {
+        self.block_on(async move { delay_for(duration).await });
+    }
+
+    /// Runs the simulation until the condition holds, checking it every 100 milliseconds.
+    /// Returns false if the condition does not hold before the timeout.
+    pub fn run_until<F: Fn(&Simulation) -> bool>(&mut self, timeout: Duration, condition: F) -> bool {
+        let step = Duration::from_millis(100);
+        let mut elapsed = Duration::from_secs(0);
+
+        while !condition(self) {
+            if elapsed >= timeout {
+                return false;
+            }
+
+            self.run_for(step);
+            elapsed += step;
+        }
+
+        true
+    }
+
+    fn runtime(&mut self) -> &mut Runtime {
+        self.runtime.as_mut().expect("the simulation runtime is running")
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        // Stop the nodes before their storage is destroyed
+        drop(self.runtime.take());
+
+        for path in self.storage_paths.drain(..) {
+            MerkleTreeLedger::destroy_storage(path).unwrap_or_else(|error| {
+                eprintln!("failed to destroy simulated node storage: {}", error);
+            });
+        }
+    }
+}
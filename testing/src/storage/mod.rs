@@ -21,12 +21,13 @@ use snarkos_models::{
     objects::{LedgerScheme, Transaction},
 };
 use snarkos_objects::Block;
-pub use snarkos_storage::Ledger;
+pub use snarkos_storage::{KeyValueStore, Ledger, MemDb, RocksDb};
 
 use rand::{thread_rng, Rng};
 use std::{path::PathBuf, sync::Arc};
 
-pub type Store = Ledger<TestTx, CommitmentMerkleParameters>;
+pub type Store = Ledger<TestTx, CommitmentMerkleParameters, MemDb>;
+pub type PersistentStore = Ledger<TestTx, CommitmentMerkleParameters, RocksDb>;
 
 pub fn random_storage_path() -> String {
     let random_path: usize = thread_rng().gen();
@@ -34,46 +35,47 @@ pub fn random_storage_path() -> String {
 }
 
 // Initialize a test blockchain given genesis attributes
-pub fn initialize_test_blockchain<T: Transaction, P: LoadableMerkleParameters>(
+pub fn initialize_test_blockchain<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore>(
     parameters: P,
     genesis_block: Block<T>,
-) -> Ledger<T, P> {
+) -> Ledger<T, P, S> {
     let mut path = std::env::temp_dir();
     path.push(random_storage_path());
 
-    Ledger::<T, P>::destroy_storage(path.clone()).unwrap();
+    Ledger::<T, P, S>::destroy_storage(path.clone()).unwrap();
 
-    let storage = Ledger::<T, P>::new(&path, parameters, genesis_block).unwrap();
+    let storage = Ledger::<T, P, S>::new(&path, parameters, genesis_block).unwrap();
 
     storage
 }
 
 // Open a test blockchain from stored genesis attributes
-pub fn open_test_blockchain<T: Transaction, P: LoadableMerkleParameters>() -> (Arc<Ledger<T, P>>, PathBuf) {
+pub fn open_test_blockchain<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore>(
+) -> (Arc<Ledger<T, P, S>>, PathBuf) {
     let mut path = std::env::temp_dir();
     path.push(random_storage_path());
 
-    Ledger::<T, P>::destroy_storage(path.clone()).unwrap();
+    Ledger::<T, P, S>::destroy_storage(path.clone()).unwrap();
 
-    let storage = Arc::new(Ledger::<T, P>::open_at_path(path.clone()).unwrap());
+    let storage = Arc::new(Ledger::<T, P, S>::open_at_path(path.clone()).unwrap());
 
     (storage, path)
 }
 
-pub fn kill_storage<T: Transaction, P: LoadableMerkleParameters>(ledger: Ledger<T, P>) {
-    let path = ledger.storage.db.path().to_owned();
+pub fn kill_storage<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore>(ledger: Ledger<T, P, S>) {
+    let path = ledger.storage.path();
 
     drop(ledger);
-    Ledger::<T, P>::destroy_storage(path).unwrap();
+    Ledger::<T, P, S>::destroy_storage(path).unwrap();
 }
 
 pub fn kill_storage_async<T: Transaction, P: LoadableMerkleParameters>(path: PathBuf) {
     Ledger::<T, P>::destroy_storage(path).unwrap();
 }
 
-pub fn kill_storage_sync<T: Transaction, P: LoadableMerkleParameters>(ledger: Arc<Ledger<T, P>>) {
-    let path = ledger.storage.db.path().to_owned();
+pub fn kill_storage_sync<T: Transaction, P: LoadableMerkleParameters, S: KeyValueStore>(ledger: Arc<Ledger<T, P, S>>) {
+    let path = ledger.storage.path();
 
     drop(ledger);
-    Ledger::<T, P>::destroy_storage(path).unwrap();
+    Ledger::<T, P, S>::destroy_storage(path).unwrap();
 }
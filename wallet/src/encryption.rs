@@ -0,0 +1,141 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Password based encryption of the wallet at rest.
+//!
+//! The stored wallet is laid out as `salt || nonce || ciphertext || tag`. The key is derived from
+//! the password and a random salt with scrypt, and the plaintext is encrypted and authenticated
+//! with XChaCha20-Poly1305 under a random nonce.
+
+use snarkos_errors::wallet::WalletError;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    Key,
+    XChaCha20Poly1305,
+    XNonce,
+};
+use rand::Rng;
+use scrypt::{scrypt, ScryptParams};
+
+/// The size of the random salt prepended to the stored wallet.
+pub const SALT_SIZE: usize = 32;
+
+/// The size of the random nonce following the salt.
+pub const NONCE_SIZE: usize = 24;
+
+/// The size of the authentication tag appended to the stored wallet.
+pub const TAG_SIZE: usize = 16;
+
+/// The base 2 logarithm of the scrypt cost parameter `N`.
+pub const SCRYPT_LOG_N: u8 = 15;
+
+/// The scrypt block size parameter `r`.
+pub const SCRYPT_R: u32 = 8;
+
+/// The scrypt parallelization parameter `p`.
+pub const SCRYPT_P: u32 = 1;
+
+/// Returns the cipher keyed by the scrypt hash of the password and salt.
+fn cipher(password: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, WalletError> {
+    let params = ScryptParams::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)
+        .map_err(|error| WalletError::Crate("scrypt", format!("{:?}", error)))?;
+
+    let mut key = [0u8; 32];
+    scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|error| WalletError::Crate("scrypt", format!("{:?}", error)))?;
+
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Encrypt the plaintext under the password with a fresh salt and nonce.
+pub fn encrypt<R: Rng>(password: &str, plaintext: &[u8], rng: &mut R) -> Result<Vec<u8>, WalletError> {
+    let salt: [u8; SALT_SIZE] = rng.gen();
+    let nonce: [u8; NONCE_SIZE] = rng.gen();
+
+    let ciphertext = cipher(password, &salt)?
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|error| WalletError::Crate("chacha20poly1305", format!("{:?}", error)))?;
+
+    let mut result = Vec::with_capacity(SALT_SIZE + NONCE_SIZE + ciphertext.len());
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+/// Decrypt bytes produced by `encrypt`, failing if the password is wrong or the bytes were modified.
+pub fn decrypt(password: &str, bytes: &[u8]) -> Result<Vec<u8>, WalletError> {
+    let min_length = SALT_SIZE + NONCE_SIZE + TAG_SIZE;
+    if bytes.len() < min_length {
+        return Err(WalletError::InvalidLength(bytes.len(), min_length));
+    }
+
+    let (salt, rest) = bytes.split_at(SALT_SIZE);
+    let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+    cipher(password, salt)?
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| WalletError::InvalidPassword)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let rng = &mut XorShiftRng::seed_from_u64(1231275789u64);
+        let plaintext = b"wallet state";
+
+        let ciphertext = encrypt("password", plaintext, rng).unwrap();
+        let offset = SALT_SIZE + NONCE_SIZE;
+        assert_eq!(ciphertext.len(), offset + plaintext.len() + TAG_SIZE);
+        assert_ne!(&ciphertext[offset..offset + plaintext.len()], &plaintext[..]);
+
+        assert_eq!(decrypt("password", &ciphertext).unwrap(), plaintext.to_vec());
+
+        // The same plaintext encrypts to a different salt and nonce every time
+        let other_ciphertext = encrypt("password", plaintext, rng).unwrap();
+        assert_ne!(&ciphertext[..offset], &other_ciphertext[..offset]);
+        assert_eq!(decrypt("password", &other_ciphertext).unwrap(), plaintext.to_vec());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_password_and_tampering() {
+        let rng = &mut XorShiftRng::seed_from_u64(1231275789u64);
+        let mut ciphertext = encrypt("password", b"wallet state", rng).unwrap();
+
+        assert!(matches!(
+            decrypt("wrong password", &ciphertext),
+            Err(WalletError::InvalidPassword)
+        ));
+
+        ciphertext[SALT_SIZE + NONCE_SIZE] ^= 1;
+        assert!(matches!(
+            decrypt("password", &ciphertext),
+            Err(WalletError::InvalidPassword)
+        ));
+
+        assert!(matches!(
+            decrypt("password", &ciphertext[..SALT_SIZE]),
+            Err(WalletError::InvalidLength(_, _))
+        ));
+    }
+}
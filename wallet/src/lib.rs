@@ -0,0 +1,49 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#![deny(unused_import_braces, unused_qualifications, trivial_casts, trivial_numeric_casts)]
+#![deny(unused_qualifications, variant_size_differences, stable_features, unreachable_pub)]
+#![deny(non_shorthand_field_patterns, unused_attributes, unused_extern_crates)]
+#![deny(
+    renamed_and_removed_lints,
+    stable_features,
+    unused_allocation,
+    unused_comparisons,
+    bare_trait_objects
+)]
+#![deny(
+    const_err,
+    unused_must_use,
+    unused_mut,
+    unused_unsafe,
+    private_in_public,
+    unsafe_code
+)]
+#![forbid(unsafe_code)]
+
+pub mod encryption;
+
+pub mod planner;
+pub use planner::*;
+
+pub mod record;
+pub use record::*;
+
+pub mod transaction;
+pub use transaction::*;
+
+pub mod wallet;
+pub use wallet::*;
@@ -0,0 +1,410 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{create_transaction, TransactionOutput};
+use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, Tx},
+    parameters::PublicParameters,
+    program::ProgramRegistry,
+    record::DPCRecord,
+};
+use snarkos_errors::wallet::WalletError;
+use snarkos_models::{
+    dpc::{DPCComponents, Record},
+    objects::Transaction,
+};
+use snarkos_objects::{AccountAddress, AccountPrivateKey};
+use snarkos_utilities::{bytes::ToBytes, to_bytes};
+
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// A record spent by a planned transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlannedInput {
+    /// The record at this index of the records the plan spends
+    Record(usize),
+    /// The output at the second index of the planned transaction at the first index
+    Output(usize, usize),
+}
+
+/// A transaction of a plan and the owners and values of the records it creates.
+#[derive(Clone, Debug)]
+pub struct PlannedTransaction {
+    pub inputs: Vec<PlannedInput>,
+    pub outputs: Vec<(AccountAddress<Components>, u64)>,
+}
+
+impl PlannedTransaction {
+    /// Returns the planned transactions whose outputs this transaction spends.
+    pub fn dependencies(&self) -> Vec<usize> {
+        self.inputs
+            .iter()
+            .filter_map(|input| match input {
+                PlannedInput::Output(transaction, _) => Some(*transaction),
+                PlannedInput::Record(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// A sequence of transactions that spends any number of records to pay any number of recipients,
+/// while each transaction spends at most `NUM_INPUT_RECORDS` records and creates at most
+/// `NUM_OUTPUT_RECORDS` records. Transactions only spend the given records or the outputs of
+/// earlier transactions in the plan.
+///
+/// The records are first merged into change records until they fit in a single transaction.
+/// The recipients are then paid one transaction at a time, each transaction passing its change
+/// on to the next. The fee is paid by the last transaction.
+#[derive(Clone, Debug)]
+pub struct TransactionPlan {
+    transactions: Vec<PlannedTransaction>,
+}
+
+impl TransactionPlan {
+    /// Plan the transactions that spend records with the given values to pay the recipients and the fee,
+    /// returning the remaining value to the change address.
+    pub fn new(
+        input_values: &[u64],
+        recipients: Vec<(AccountAddress<Components>, u64)>,
+        change_address: AccountAddress<Components>,
+        fee: u64,
+    ) -> Result<Self, WalletError> {
+        if recipients.is_empty() {
+            return Err(WalletError::Message(
+                "a transaction plan needs at least one recipient".into(),
+            ));
+        }
+
+        let balance = input_values
+            .iter()
+            .try_fold(0u64, |total, value| total.checked_add(*value))
+            .ok_or(WalletError::ValueOverflow)?;
+        let amount = recipients
+            .iter()
+            .try_fold(fee, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or(WalletError::ValueOverflow)?;
+        if input_values.is_empty() || balance < amount {
+            return Err(WalletError::InsufficientFunds(balance, amount));
+        }
+
+        let mut transactions = vec![];
+
+        // Merge the records until they fit in a single transaction
+        let mut inputs: Vec<_> = input_values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (PlannedInput::Record(index), *value))
+            .collect();
+
+        while inputs.len() > Components::NUM_INPUT_RECORDS {
+            let mut merged_inputs = vec![];
+            for chunk in inputs.chunks(Components::NUM_INPUT_RECORDS) {
+                if chunk.len() == 1 {
+                    merged_inputs.push(chunk[0]);
+                    continue;
+                }
+
+                let value = chunk.iter().map(|(_, value)| value).sum();
+                transactions.push(PlannedTransaction {
+                    inputs: chunk.iter().map(|(input, _)| *input).collect(),
+                    outputs: vec![(change_address.clone(), value)],
+                });
+                merged_inputs.push((PlannedInput::Output(transactions.len() - 1, 0), value));
+            }
+            inputs = merged_inputs;
+        }
+
+        // Pay the recipients, passing the change from one transaction to the next
+        let mut recipients: VecDeque<_> = recipients.into_iter().collect();
+        loop {
+            let value: u64 = inputs.iter().map(|(_, value)| value).sum();
+            let planned_inputs = inputs.iter().map(|(input, _)| *input).collect();
+
+            let remaining_amount = recipients.iter().map(|(_, amount)| amount).sum::<u64>() + fee;
+            let change = value - remaining_amount;
+            if recipients.len() + (change > 0) as usize <= Components::NUM_OUTPUT_RECORDS {
+                let mut outputs: Vec<_> = recipients.into_iter().collect();
+                if change > 0 {
+                    outputs.push((change_address, change));
+                }
+
+                transactions.push(PlannedTransaction {
+                    inputs: planned_inputs,
+                    outputs,
+                });
+                break;
+            }
+
+            let mut outputs: Vec<_> = recipients.drain(..Components::NUM_OUTPUT_RECORDS - 1).collect();
+            let change = value - outputs.iter().map(|(_, amount)| amount).sum::<u64>();
+            outputs.push((change_address.clone(), change));
+
+            let change_index = outputs.len() - 1;
+            transactions.push(PlannedTransaction {
+                inputs: planned_inputs,
+                outputs,
+            });
+            inputs = vec![(PlannedInput::Output(transactions.len() - 1, change_index), change)];
+        }
+
+        Ok(Self { transactions })
+    }
+
+    /// Returns the planned transactions in an order where every transaction comes after its dependencies.
+    pub fn transactions(&self) -> &[PlannedTransaction] {
+        &self.transactions
+    }
+}
+
+/// Creates the transactions of a plan once the records they spend are in the ledger.
+/// A transaction proves that the records it spends are in the ledger, so a transaction that
+/// spends the outputs of other planned transactions is created once those are in a block.
+pub struct PlanExecution {
+    plan: TransactionPlan,
+    records: Vec<DPCRecord<Components>>,
+    private_key: AccountPrivateKey<Components>,
+    /// The records created by each planned transaction that has been created
+    outputs: Vec<Option<Vec<DPCRecord<Components>>>>,
+    /// The id of each planned transaction that has been created
+    transaction_ids: Vec<Option<Vec<u8>>>,
+}
+
+impl PlanExecution {
+    /// Execute a plan that was created from the values of the records,
+    /// spending the records with the private key that owns them.
+    pub fn new(
+        plan: TransactionPlan,
+        records: Vec<DPCRecord<Components>>,
+        private_key: AccountPrivateKey<Components>,
+    ) -> Self {
+        let outputs = vec![None; plan.transactions.len()];
+        let transaction_ids = vec![None; plan.transactions.len()];
+
+        Self {
+            plan,
+            records,
+            private_key,
+            outputs,
+            transaction_ids,
+        }
+    }
+
+    /// Returns the plan being executed.
+    pub fn plan(&self) -> &TransactionPlan {
+        &self.plan
+    }
+
+    /// Returns true if every planned transaction has been created.
+    pub fn is_complete(&self) -> bool {
+        self.outputs.iter().all(Option::is_some)
+    }
+
+    /// Returns the ids of the created transactions whose outputs are spent by planned transactions
+    /// that have not been created yet.
+    pub fn pending_dependencies(&self) -> Vec<Vec<u8>> {
+        let mut dependencies: Vec<usize> = self
+            .plan
+            .transactions
+            .iter()
+            .enumerate()
+            .filter(|(index, _planned)| self.outputs[*index].is_none())
+            .flat_map(|(_index, planned)| planned.dependencies())
+            .collect();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+
+        dependencies
+            .into_iter()
+            .filter_map(|dependency| self.transaction_ids[dependency].clone())
+            .collect()
+    }
+
+    /// Create every planned transaction that has not been created yet and whose records are in the ledger,
+    /// returning the transactions in dependency order.
+    pub fn next_transactions<R: Rng>(
+        &mut self,
+        consensus: &ConsensusParameters,
+        parameters: &PublicParameters<Components>,
+        program_registry: &ProgramRegistry<Components>,
+        storage: &MerkleTreeLedger,
+        rng: &mut R,
+    ) -> Result<Vec<Tx>, WalletError> {
+        let mut transactions = vec![];
+
+        'planned: for (index, planned) in self.plan.transactions.iter().enumerate() {
+            if self.outputs[index].is_some() {
+                continue;
+            }
+
+            let mut old_records = vec![];
+            for input in &planned.inputs {
+                let record = match input {
+                    PlannedInput::Record(record) => self.records[*record].clone(),
+                    PlannedInput::Output(transaction, output) => match &self.outputs[*transaction] {
+                        Some(outputs) => outputs[*output].clone(),
+                        None => continue 'planned,
+                    },
+                };
+
+                if storage.get_cm_index(&to_bytes![record.commitment()]?)?.is_none() {
+                    continue 'planned;
+                }
+                old_records.push(record);
+            }
+
+            let (records, transaction) = create_transaction(
+                consensus,
+                parameters,
+                program_registry,
+                storage,
+                old_records,
+                vec![self.private_key.clone(); planned.inputs.len()],
+                planned
+                    .outputs
+                    .iter()
+                    .map(|(address, value)| TransactionOutput::new(address.clone(), *value))
+                    .collect(),
+                rng.gen(),
+                rng,
+            )?;
+
+            self.outputs[index] = Some(records);
+            self.transaction_ids[index] = Some(transaction.transaction_id()?.to_vec());
+            transactions.push(transaction);
+        }
+
+        Ok(transactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_plan(input_values: &[u64], recipient_values: &[u64], fee: u64) -> TransactionPlan {
+        let recipients = recipient_values
+            .iter()
+            .map(|value| (AccountAddress::default(), *value))
+            .collect();
+
+        TransactionPlan::new(input_values, recipients, AccountAddress::default(), fee).unwrap()
+    }
+
+    /// Checks that every planned transaction fits in a DPC transaction, only spends
+    /// earlier outputs once, and that the value of the plan adds up.
+    fn check_plan(plan: &TransactionPlan, input_values: &[u64], recipient_values: &[u64], fee: u64) {
+        let mut spent_records = vec![false; input_values.len()];
+        let mut spent_outputs: Vec<Vec<bool>> = vec![];
+        let mut total_fee = 0;
+
+        for (index, transaction) in plan.transactions().iter().enumerate() {
+            assert!(!transaction.inputs.is_empty() && transaction.inputs.len() <= Components::NUM_INPUT_RECORDS);
+            assert!(!transaction.outputs.is_empty() && transaction.outputs.len() <= Components::NUM_OUTPUT_RECORDS);
+            assert!(transaction.dependencies().iter().all(|dependency| *dependency < index));
+
+            let mut input_value = 0;
+            for input in &transaction.inputs {
+                input_value += match *input {
+                    PlannedInput::Record(record) => {
+                        assert!(!spent_records[record]);
+                        spent_records[record] = true;
+                        input_values[record]
+                    }
+                    PlannedInput::Output(transaction, output) => {
+                        assert!(!spent_outputs[transaction][output]);
+                        spent_outputs[transaction][output] = true;
+                        plan.transactions()[transaction].outputs[output].1
+                    }
+                };
+            }
+
+            let output_value: u64 = transaction.outputs.iter().map(|(_, value)| value).sum();
+            total_fee += input_value - output_value;
+            spent_outputs.push(vec![false; transaction.outputs.len()]);
+        }
+
+        assert!(spent_records.iter().all(|spent| *spent));
+        assert_eq!(total_fee, fee);
+
+        // Every recipient is paid, and the change is the only other unspent output
+        let mut unspent_outputs: Vec<u64> = vec![];
+        for (transaction, spent) in plan.transactions().iter().zip(&spent_outputs) {
+            for ((_, value), spent) in transaction.outputs.iter().zip(spent) {
+                if !spent {
+                    unspent_outputs.push(*value);
+                }
+            }
+        }
+
+        let change = input_values.iter().sum::<u64>() - recipient_values.iter().sum::<u64>() - fee;
+        let paid: u64 = unspent_outputs.iter().sum();
+        assert_eq!(paid, recipient_values.iter().sum::<u64>() + change);
+        assert!(unspent_outputs.len() <= recipient_values.len() + 1);
+    }
+
+    #[test]
+    fn test_single_transaction() {
+        let plan = new_plan(&[100, 50], &[120], 10);
+        assert_eq!(plan.transactions().len(), 1);
+        assert_eq!(plan.transactions()[0].outputs.len(), 2);
+        check_plan(&plan, &[100, 50], &[120], 10);
+
+        // Without change, two recipients share a transaction
+        let plan = new_plan(&[100], &[60, 30], 10);
+        assert_eq!(plan.transactions().len(), 1);
+        check_plan(&plan, &[100], &[60, 30], 10);
+    }
+
+    #[test]
+    fn test_many_inputs() {
+        let input_values = [10, 20, 30, 40, 50];
+        let plan = new_plan(&input_values, &[140], 5);
+
+        // Two merges of two records, a merge of the merged records, and the payment
+        assert_eq!(plan.transactions().len(), 4);
+        check_plan(&plan, &input_values, &[140], 5);
+    }
+
+    #[test]
+    fn test_many_recipients() {
+        let recipient_values = [10, 20, 30, 40];
+        let plan = new_plan(&[200], &recipient_values, 5);
+
+        assert_eq!(plan.transactions().len(), 4);
+        check_plan(&plan, &[200], &recipient_values, 5);
+
+        // Without change, the last two recipients share a transaction
+        let plan = new_plan(&[105], &recipient_values, 5);
+        assert_eq!(plan.transactions().len(), 3);
+        check_plan(&plan, &[105], &recipient_values, 5);
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let recipients = vec![(AccountAddress::default(), 100)];
+
+        assert!(matches!(
+            TransactionPlan::new(&[50, 40], recipients.clone(), AccountAddress::default(), 20),
+            Err(WalletError::InsufficientFunds(90, 120))
+        ));
+        assert!(matches!(
+            TransactionPlan::new(&[], recipients, AccountAddress::default(), 0),
+            Err(WalletError::InsufficientFunds(0, 100))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, Tx},
+    parameters::SystemParameters,
+    record::DPCRecord,
+    record_encryption::RecordEncryption,
+};
+use snarkos_models::{
+    algorithms::SignatureScheme,
+    dpc::{DPCComponents, Record},
+};
+use snarkos_objects::AccountViewKey;
+use snarkos_utilities::bytes::{FromBytes, ToBytes};
+
+use std::io::{Read, Result as IoResult, Write};
+
+/// The serial number revealed when a record is spent.
+pub type SerialNumber = <<Components as DPCComponents>::AccountSignature as SignatureScheme>::PublicKey;
+
+/// A record owned by one of the wallet accounts.
+#[derive(Clone, Debug)]
+pub struct WalletRecord {
+    /// The decrypted record
+    pub record: DPCRecord<Components>,
+    /// The serial number that spends the record
+    pub serial_number: SerialNumber,
+    /// The id of the transaction that created the record
+    pub transaction_id: [u8; 32],
+    /// The height of the block that created the record
+    pub block_height: u32,
+    /// The id and block height of the transaction that spent the record
+    pub spent: Option<([u8; 32], u32)>,
+}
+
+impl ToBytes for WalletRecord {
+    #[inline]
+    fn write<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        self.record.write(&mut writer)?;
+        self.serial_number.write(&mut writer)?;
+        self.transaction_id.write(&mut writer)?;
+        self.block_height.write(&mut writer)?;
+
+        match &self.spent {
+            Some((transaction_id, block_height)) => {
+                true.write(&mut writer)?;
+                transaction_id.write(&mut writer)?;
+                block_height.write(&mut writer)
+            }
+            None => false.write(&mut writer),
+        }
+    }
+}
+
+impl FromBytes for WalletRecord {
+    #[inline]
+    fn read<R: Read>(mut reader: R) -> IoResult<Self> {
+        let record = DPCRecord::<Components>::read(&mut reader)?;
+        let serial_number = SerialNumber::read(&mut reader)?;
+        let transaction_id = <[u8; 32]>::read(&mut reader)?;
+        let block_height = u32::read(&mut reader)?;
+
+        let spent = match bool::read(&mut reader)? {
+            true => Some((<[u8; 32]>::read(&mut reader)?, u32::read(&mut reader)?)),
+            false => None,
+        };
+
+        Ok(Self {
+            record,
+            serial_number,
+            transaction_id,
+            block_height,
+            spent,
+        })
+    }
+}
+
+/// The value an account received and spent in a single transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WalletTransaction {
+    pub transaction_id: [u8; 32],
+    pub block_height: u32,
+    pub received: u64,
+    pub spent: u64,
+}
+
+/// Returns the non-dummy records of a transaction that the view key can decrypt.
+/// Records encrypted to other accounts either fail to decrypt or decrypt to a record
+/// whose commitment does not match the one in the transaction.
+pub fn decrypt_records(
+    system_parameters: &SystemParameters<Components>,
+    view_key: &AccountViewKey<Components>,
+    transaction: &Tx,
+) -> Vec<DPCRecord<Components>> {
+    transaction
+        .encrypted_records
+        .iter()
+        .zip(&transaction.new_commitments)
+        .filter_map(|(encrypted_record, commitment)| {
+            RecordEncryption::decrypt_record(system_parameters, view_key, encrypted_record)
+                .ok()
+                .filter(|record| !record.is_dummy() && record.commitment() == *commitment)
+        })
+        .collect()
+}
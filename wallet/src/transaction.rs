@@ -0,0 +1,162 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, InstantiatedDPC, Tx},
+    parameters::PublicParameters,
+    program::ProgramRegistry,
+    record::DPCRecord,
+    record_payload::RecordPayload,
+};
+use snarkos_errors::wallet::WalletError;
+use snarkos_models::{algorithms::CRH, dpc::DPCComponents};
+use snarkos_objects::{AccountAddress, AccountPrivateKey};
+
+use rand::Rng;
+
+/// A new record paid to a recipient by a transaction.
+#[derive(Clone, Debug)]
+pub struct TransactionOutput {
+    /// The owner of the record
+    pub address: AccountAddress<Components>,
+
+    /// The value of the record
+    pub value: u64,
+
+    /// The payload of the record
+    pub payload: RecordPayload,
+
+    /// The id of the program that must be satisfied when the record is created,
+    /// or `None` for the noop program
+    pub birth_program_id: Option<Vec<u8>>,
+
+    /// The id of the program that must be satisfied when the record is spent,
+    /// or `None` for the noop program
+    pub death_program_id: Option<Vec<u8>>,
+}
+
+impl TransactionOutput {
+    /// Create an output paying the value to the address, with an empty payload and the noop programs.
+    pub fn new(address: AccountAddress<Components>, value: u64) -> Self {
+        Self {
+            address,
+            value,
+            payload: RecordPayload::default(),
+            birth_program_id: None,
+            death_program_id: None,
+        }
+    }
+}
+
+/// Create a transaction that spends the records and creates a new record for each output.
+/// Unused input and output slots are filled with dummy records, and any value of the
+/// records that is not paid to the outputs is the transaction fee.
+///
+/// The caller must pass 1 to `NUM_INPUT_RECORDS` records, a private key for each record,
+/// and 1 to `NUM_OUTPUT_RECORDS` outputs.
+pub fn create_transaction<R: Rng>(
+    consensus: &ConsensusParameters,
+    parameters: &PublicParameters<Components>,
+    program_registry: &ProgramRegistry<Components>,
+    storage: &MerkleTreeLedger,
+    mut old_records: Vec<DPCRecord<Components>>,
+    mut old_account_private_keys: Vec<AccountPrivateKey<Components>>,
+    outputs: Vec<TransactionOutput>,
+    memo: [u8; 32],
+    rng: &mut R,
+) -> Result<(Vec<DPCRecord<Components>>, Tx), WalletError> {
+    // Fetch birth/death programs
+    let program_id = program_registry.noop_program_id().to_vec();
+
+    let sn_randomness: [u8; 32] = rng.gen();
+    // Fill any unused old_record indices with dummy records
+    while old_records.len() < Components::NUM_INPUT_RECORDS {
+        let old_sn_nonce = parameters.system_parameters.serial_number_nonce.hash(&sn_randomness)?;
+
+        let private_key = old_account_private_keys[0].clone();
+        let address = AccountAddress::<Components>::from_private_key(
+            parameters.account_signature_parameters(),
+            parameters.account_commitment_parameters(),
+            parameters.account_encryption_parameters(),
+            &private_key,
+        )?;
+
+        let dummy_record = InstantiatedDPC::generate_record(
+            &parameters.system_parameters,
+            &old_sn_nonce,
+            &address,
+            true, // The input record is dummy
+            0,
+            &RecordPayload::default(),
+            &program_id,
+            &program_id,
+            rng,
+        )?;
+
+        old_records.push(dummy_record);
+        old_account_private_keys.push(private_key);
+    }
+
+    assert_eq!(old_records.len(), Components::NUM_INPUT_RECORDS);
+    assert_eq!(old_account_private_keys.len(), Components::NUM_INPUT_RECORDS);
+
+    let mut new_record_owners = vec![];
+    let mut new_is_dummy_flags = vec![];
+    let mut new_values = vec![];
+    let mut new_payloads = vec![];
+    let mut new_birth_program_ids = vec![];
+    let mut new_death_program_ids = vec![];
+    for output in outputs {
+        new_record_owners.push(output.address);
+        new_is_dummy_flags.push(false);
+        new_values.push(output.value);
+        new_payloads.push(output.payload);
+        new_birth_program_ids.push(output.birth_program_id.unwrap_or_else(|| program_id.clone()));
+        new_death_program_ids.push(output.death_program_id.unwrap_or_else(|| program_id.clone()));
+    }
+
+    // Fill any unused new_record indices with dummy output values
+    while new_record_owners.len() < Components::NUM_OUTPUT_RECORDS {
+        new_record_owners.push(new_record_owners[0].clone());
+        new_is_dummy_flags.push(true);
+        new_values.push(0);
+        new_payloads.push(RecordPayload::default());
+        new_birth_program_ids.push(program_id.clone());
+        new_death_program_ids.push(program_id.clone());
+    }
+
+    assert_eq!(new_record_owners.len(), Components::NUM_OUTPUT_RECORDS);
+    assert_eq!(new_is_dummy_flags.len(), Components::NUM_OUTPUT_RECORDS);
+    assert_eq!(new_values.len(), Components::NUM_OUTPUT_RECORDS);
+
+    // Generate transaction
+    Ok(consensus.create_transaction(
+        parameters,
+        program_registry,
+        old_records,
+        old_account_private_keys,
+        new_record_owners,
+        new_birth_program_ids,
+        new_death_program_ids,
+        new_is_dummy_flags,
+        new_values,
+        new_payloads,
+        memo,
+        storage,
+        rng,
+    )?)
+}
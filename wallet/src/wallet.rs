@@ -0,0 +1,360 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{decrypt_records, encryption, WalletRecord, WalletTransaction};
+use snarkos_consensus::{MemoryPool, MerkleTreeLedger};
+use snarkos_dpc::base_dpc::{
+    instantiated::{Components, InstantiatedDPC, Tx},
+    parameters::SystemParameters,
+};
+use snarkos_errors::{storage::StorageError, wallet::WalletError};
+use snarkos_models::{dpc::Record, objects::Transaction};
+use snarkos_objects::{AccountAddress, AccountPrivateKey, AccountViewKey, BlockHeaderHash};
+use snarkos_utilities::{
+    bytes::{FromBytes, ToBytes},
+    to_bytes,
+};
+
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+
+/// An account held by the wallet, with the keys derived from its private key.
+#[derive(Clone, Debug)]
+pub struct WalletAccount {
+    pub private_key: AccountPrivateKey<Components>,
+    pub view_key: AccountViewKey<Components>,
+    pub address: AccountAddress<Components>,
+}
+
+impl WalletAccount {
+    /// Derive the view key and address of a private key.
+    pub fn new(
+        system_parameters: &SystemParameters<Components>,
+        private_key: AccountPrivateKey<Components>,
+    ) -> Result<Self, WalletError> {
+        let view_key = AccountViewKey::from_private_key(
+            &system_parameters.account_signature,
+            &system_parameters.account_commitment,
+            &private_key,
+        )?;
+        let address = AccountAddress::from_private_key(
+            &system_parameters.account_signature,
+            &system_parameters.account_commitment,
+            &system_parameters.account_encryption,
+            &private_key,
+        )?;
+
+        Ok(Self {
+            private_key,
+            view_key,
+            address,
+        })
+    }
+}
+
+/// The wallet state that is encrypted and stored in the ledger.
+#[derive(Default, Serialize, Deserialize)]
+struct StoredWallet {
+    private_keys: Vec<String>,
+    records: Vec<Vec<u8>>,
+    scanned: Option<(u32, BlockHeaderHash)>,
+}
+
+/// Accounts and the records they own, scanned from the ledger with each account's view key.
+/// The wallet is stored in the ledger encrypted with a password.
+pub struct Wallet {
+    storage: Arc<MerkleTreeLedger>,
+    system_parameters: SystemParameters<Components>,
+    password: String,
+    accounts: Vec<WalletAccount>,
+    records: Vec<WalletRecord>,
+    /// The height and hash of the last scanned block
+    scanned: Option<(u32, BlockHeaderHash)>,
+}
+
+impl Wallet {
+    /// Open the wallet stored in the ledger, or an empty wallet if none has been stored.
+    pub fn open(
+        storage: Arc<MerkleTreeLedger>,
+        system_parameters: SystemParameters<Components>,
+        password: &str,
+    ) -> Result<Self, WalletError> {
+        let stored = match storage.get_wallet() {
+            Ok(bytes) => bincode::deserialize(&encryption::decrypt(password, &bytes)?)?,
+            Err(StorageError::MissingValue(_)) => StoredWallet::default(),
+            Err(error) => return Err(error.into()),
+        };
+
+        let accounts = stored
+            .private_keys
+            .iter()
+            .map(|private_key| WalletAccount::new(&system_parameters, AccountPrivateKey::from_str(private_key)?))
+            .collect::<Result<Vec<_>, WalletError>>()?;
+        let records = stored
+            .records
+            .iter()
+            .map(|record| WalletRecord::read(&record[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            storage,
+            system_parameters,
+            password: password.to_string(),
+            accounts,
+            records,
+            scanned: stored.scanned,
+        })
+    }
+
+    /// Encrypt the wallet with its password and store it in the ledger.
+    pub fn save(&self) -> Result<(), WalletError> {
+        let stored = StoredWallet {
+            private_keys: self
+                .accounts
+                .iter()
+                .map(|account| account.private_key.to_string())
+                .collect(),
+            records: self
+                .records
+                .iter()
+                .map(|record| to_bytes![record])
+                .collect::<Result<Vec<_>, _>>()?,
+            scanned: self.scanned.clone(),
+        };
+
+        let bytes = encryption::encrypt(&self.password, &bincode::serialize(&stored)?, &mut thread_rng())?;
+        Ok(self.storage.store_wallet(bytes)?)
+    }
+
+    /// Returns the accounts held by the wallet.
+    pub fn accounts(&self) -> &[WalletAccount] {
+        &self.accounts
+    }
+
+    /// Returns the wallet account with the given address.
+    pub fn account(&self, address: &AccountAddress<Components>) -> Result<&WalletAccount, WalletError> {
+        self.accounts
+            .iter()
+            .find(|account| account.address == *address)
+            .ok_or_else(|| WalletError::AccountNotFound(address.to_string()))
+    }
+
+    /// Returns an error if the password is not the one the wallet was opened with.
+    pub fn check_password(&self, password: &str) -> Result<(), WalletError> {
+        match self.password == password {
+            true => Ok(()),
+            false => Err(WalletError::InvalidPassword),
+        }
+    }
+
+    /// Add an account to the wallet and return its address.
+    /// The blocks scanned before the account was added are rescanned for the records of the account only.
+    pub fn import_account(
+        &mut self,
+        private_key: AccountPrivateKey<Components>,
+    ) -> Result<AccountAddress<Components>, WalletError> {
+        let account = WalletAccount::new(&self.system_parameters, private_key)?;
+        let address = account.address.clone();
+
+        if self.account(&address).is_err() {
+            self.accounts.push(account);
+
+            if let Some((scanned_height, _)) = self.scanned {
+                let new_account = self.accounts.len() - 1;
+
+                for height in 0..=scanned_height {
+                    let block = self.storage.get_block_from_block_number(height)?;
+
+                    for transaction in block.transactions.iter() {
+                        self.scan_transaction(transaction, height, new_account)?;
+                    }
+                }
+            }
+        }
+
+        Ok(address)
+    }
+
+    /// Scan the blocks added to the ledger since the last scan for records owned by
+    /// or spent from the wallet accounts. If the last scanned block is no longer in the
+    /// canon chain, the wallet is rescanned from the genesis block.
+    pub fn scan(&mut self) -> Result<(), WalletError> {
+        if let Some((height, hash)) = &self.scanned {
+            let is_canon = match self.storage.get_block_hash(*height) {
+                Ok(canon_hash) => canon_hash == *hash,
+                Err(_) => false,
+            };
+
+            if !is_canon {
+                self.records.clear();
+                self.scanned = None;
+            }
+        }
+
+        let start = match &self.scanned {
+            Some((height, _)) => height + 1,
+            None => 0,
+        };
+
+        for height in start..=self.storage.get_latest_block_height() {
+            let block = self.storage.get_block_from_block_number(height)?;
+
+            for transaction in block.transactions.iter() {
+                self.scan_transaction(transaction, height, 0)?;
+            }
+
+            self.scanned = Some((height, block.header.get_hash()));
+        }
+
+        Ok(())
+    }
+
+    /// Mark the wallet records spent by the transaction and add the records it creates for the wallet accounts,
+    /// starting from the account at the given index.
+    fn scan_transaction(
+        &mut self,
+        transaction: &Tx,
+        block_height: u32,
+        first_account: usize,
+    ) -> Result<(), WalletError> {
+        let transaction_id = transaction.transaction_id()?;
+
+        for serial_number in transaction.old_serial_numbers() {
+            for record in self.records.iter_mut() {
+                if record.serial_number == *serial_number {
+                    record.spent = Some((transaction_id, block_height));
+                }
+            }
+        }
+
+        for account in &self.accounts[first_account..] {
+            for record in decrypt_records(&self.system_parameters, &account.view_key, transaction) {
+                let (serial_number, _) =
+                    InstantiatedDPC::generate_sn(&self.system_parameters, &record, &account.private_key)?;
+
+                self.records.push(WalletRecord {
+                    record,
+                    serial_number,
+                    transaction_id,
+                    block_height,
+                    spent: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the records owned by the address that have not been spent in the ledger
+    /// or by a transaction in the memory pool.
+    pub fn unspent_records(
+        &self,
+        address: &AccountAddress<Components>,
+        memory_pool: &MemoryPool<Tx>,
+    ) -> Result<Vec<&WalletRecord>, WalletError> {
+        self.account(address)?;
+
+        let mut unspent_records = vec![];
+        for record in self.records.iter().filter(|record| record.record.owner() == address) {
+            let is_pending_spend = memory_pool
+                .transactions
+                .values()
+                .any(|entry| entry.transaction.old_serial_numbers().contains(&record.serial_number));
+
+            if !is_pending_spend && self.storage.get_sn_index(&to_bytes![record.serial_number]?)?.is_none() {
+                unspent_records.push(record);
+            }
+        }
+
+        Ok(unspent_records)
+    }
+
+    /// Returns the total value of the unspent records owned by the address.
+    pub fn balance(
+        &self,
+        address: &AccountAddress<Components>,
+        memory_pool: &MemoryPool<Tx>,
+    ) -> Result<u64, WalletError> {
+        Ok(self
+            .unspent_records(address, memory_pool)?
+            .iter()
+            .map(|record| record.record.value())
+            .sum())
+    }
+
+    /// Select at most `max_records` unspent records owned by the address that hold at least the amount.
+    /// The smallest record that covers the amount is preferred, otherwise the largest records are selected.
+    /// Records spent by a transaction in the memory pool are not selected.
+    pub fn select_records(
+        &self,
+        address: &AccountAddress<Components>,
+        amount: u64,
+        max_records: usize,
+        memory_pool: &MemoryPool<Tx>,
+    ) -> Result<Vec<&WalletRecord>, WalletError> {
+        let mut unspent_records = self.unspent_records(address, memory_pool)?;
+        unspent_records.sort_by_key(|record| record.record.value());
+
+        let balance = unspent_records.iter().map(|record| record.record.value()).sum();
+        if balance < amount {
+            return Err(WalletError::InsufficientFunds(balance, amount));
+        }
+
+        if let Some(record) = unspent_records.iter().find(|record| record.record.value() >= amount) {
+            return Ok(vec![*record]);
+        }
+
+        let selected_records: Vec<_> = unspent_records.into_iter().rev().take(max_records).collect();
+        if selected_records.iter().map(|record| record.record.value()).sum::<u64>() < amount {
+            return Err(WalletError::TooManyInputs(amount, max_records));
+        }
+
+        Ok(selected_records)
+    }
+
+    /// Returns the transactions that created or spent records owned by the address, oldest first.
+    pub fn history(&self, address: &AccountAddress<Components>) -> Result<Vec<WalletTransaction>, WalletError> {
+        self.account(address)?;
+
+        let mut history = BTreeMap::new();
+        for record in self.records.iter().filter(|record| record.record.owner() == address) {
+            history_entry(&mut history, record.transaction_id, record.block_height).received += record.record.value();
+
+            if let Some((transaction_id, block_height)) = record.spent {
+                history_entry(&mut history, transaction_id, block_height).spent += record.record.value();
+            }
+        }
+
+        Ok(history.into_iter().map(|(_, transaction)| transaction).collect())
+    }
+}
+
+/// Returns the history entry of a transaction, inserting an empty entry if there is none.
+fn history_entry(
+    history: &mut BTreeMap<(u32, [u8; 32]), WalletTransaction>,
+    transaction_id: [u8; 32],
+    block_height: u32,
+) -> &mut WalletTransaction {
+    history
+        .entry((block_height, transaction_id))
+        .or_insert_with(|| WalletTransaction {
+            transaction_id,
+            block_height,
+            received: 0,
+            spent: 0,
+        })
+}
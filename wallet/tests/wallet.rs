@@ -0,0 +1,233 @@
+// Copyright (C) 2019-2020 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod wallet {
+    use snarkos_consensus::{memory_pool::Entry, MemoryPool};
+    use snarkos_errors::wallet::WalletError;
+    use snarkos_models::{dpc::Record, objects::Transaction};
+    use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, storage::*};
+    use snarkos_utilities::{bytes::ToBytes, to_bytes};
+    use snarkos_wallet::{Wallet, WalletTransaction};
+
+    use std::sync::Arc;
+
+    const PASSWORD: &str = "password";
+
+    #[test]
+    fn test_wallet_scan() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let consensus = TEST_CONSENSUS.clone();
+        let mut memory_pool = MemoryPool::new();
+
+        let [miner, receiver, _] = &FIXTURE_VK.test_accounts;
+
+        // Block 1 pays the coinbase records to the miner
+
+        consensus
+            .receive_block(&parameters, &storage, &mut memory_pool, &DATA.block_1)
+            .unwrap();
+
+        let mut wallet = Wallet::open(storage.clone(), parameters.system_parameters.clone(), PASSWORD).unwrap();
+        assert_eq!(wallet.import_account(miner.private_key.clone()).unwrap(), miner.address);
+        assert_eq!(
+            wallet.import_account(receiver.private_key.clone()).unwrap(),
+            receiver.address
+        );
+        wallet.scan().unwrap();
+        wallet.save().unwrap();
+
+        let coinbase_value_1: u64 = DATA.records_1.iter().map(|record| record.value()).sum();
+        assert_eq!(wallet.balance(&miner.address, &memory_pool).unwrap(), coinbase_value_1);
+        assert_eq!(wallet.balance(&receiver.address, &memory_pool).unwrap(), 0);
+
+        // Block 2 spends the coinbase records, sending 10 to the receiver and the change back to the miner
+
+        consensus
+            .receive_block(&parameters, &storage, &mut memory_pool, &DATA.block_2)
+            .unwrap();
+
+        wallet = Wallet::open(storage.clone(), parameters.system_parameters.clone(), PASSWORD).unwrap();
+        assert_eq!(wallet.accounts().len(), 2);
+        wallet.scan().unwrap();
+
+        let coinbase_value_2: u64 = DATA.records_2.iter().map(|record| record.value()).sum();
+        assert_eq!(
+            wallet.balance(&miner.address, &memory_pool).unwrap(),
+            coinbase_value_1 - 10 + coinbase_value_2
+        );
+        assert_eq!(wallet.balance(&receiver.address, &memory_pool).unwrap(), 10);
+        assert!(wallet
+            .unspent_records(&miner.address, &memory_pool)
+            .unwrap()
+            .iter()
+            .all(|record| record.spent.is_none()));
+
+        let history = wallet.history(&miner.address).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0], WalletTransaction {
+            transaction_id: DATA.block_1.transactions[0].transaction_id().unwrap(),
+            block_height: 1,
+            received: coinbase_value_1,
+            spent: 0,
+        });
+        assert_eq!(history.iter().map(|entry| entry.spent).sum::<u64>(), coinbase_value_1);
+
+        // Coin selection prefers the smallest record that covers the amount
+
+        let balance = wallet.balance(&miner.address, &memory_pool).unwrap();
+        let smallest_value = wallet
+            .unspent_records(&miner.address, &memory_pool)
+            .unwrap()
+            .iter()
+            .map(|record| record.record.value())
+            .min()
+            .unwrap();
+
+        let selected = wallet.select_records(&miner.address, 1, 2, &memory_pool).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].record.value(), smallest_value);
+
+        let selected = wallet.select_records(&miner.address, balance, 2, &memory_pool).unwrap();
+        assert_eq!(
+            selected.iter().map(|record| record.record.value()).sum::<u64>(),
+            balance
+        );
+
+        assert!(matches!(
+            wallet.select_records(&miner.address, balance + 1, 2, &memory_pool),
+            Err(WalletError::InsufficientFunds(_, _))
+        ));
+
+        drop(wallet);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_wallet_import_after_scan() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let consensus = TEST_CONSENSUS.clone();
+        let mut memory_pool = MemoryPool::new();
+
+        let [miner, receiver, _] = &FIXTURE_VK.test_accounts;
+
+        consensus
+            .receive_block(&parameters, &storage, &mut memory_pool, &DATA.block_1)
+            .unwrap();
+        consensus
+            .receive_block(&parameters, &storage, &mut memory_pool, &DATA.block_2)
+            .unwrap();
+
+        let mut wallet = Wallet::open(storage.clone(), parameters.system_parameters.clone(), PASSWORD).unwrap();
+        wallet.import_account(miner.private_key.clone()).unwrap();
+        wallet.scan().unwrap();
+
+        let miner_balance = wallet.balance(&miner.address, &memory_pool).unwrap();
+        let miner_history = wallet.history(&miner.address).unwrap();
+
+        // An account imported after the scan finds its records without rescanning the other accounts
+        wallet.import_account(receiver.private_key.clone()).unwrap();
+
+        assert_eq!(wallet.balance(&receiver.address, &memory_pool).unwrap(), 10);
+        assert_eq!(wallet.balance(&miner.address, &memory_pool).unwrap(), miner_balance);
+        assert_eq!(wallet.history(&miner.address).unwrap(), miner_history);
+
+        drop(wallet);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_wallet_memory_pool_spends() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+        let consensus = TEST_CONSENSUS.clone();
+        let mut memory_pool = MemoryPool::new();
+
+        let [miner, _, _] = &FIXTURE_VK.test_accounts;
+
+        consensus
+            .receive_block(&parameters, &storage, &mut memory_pool, &DATA.block_1)
+            .unwrap();
+
+        let mut wallet = Wallet::open(storage.clone(), parameters.system_parameters.clone(), PASSWORD).unwrap();
+        wallet.import_account(miner.private_key.clone()).unwrap();
+        wallet.scan().unwrap();
+
+        let coinbase_value_1: u64 = DATA.records_1.iter().map(|record| record.value()).sum();
+        wallet
+            .select_records(&miner.address, coinbase_value_1, 2, &memory_pool)
+            .unwrap();
+
+        // The transaction of block 2 that spends the coinbase records waits in the memory pool
+
+        let spend = DATA
+            .block_2
+            .transactions
+            .iter()
+            .find(|transaction| !transaction.value_balance.is_negative())
+            .unwrap();
+        let entry = Entry {
+            size: to_bytes![spend].unwrap().len(),
+            transaction: spend.clone(),
+        };
+        assert!(memory_pool.insert(&storage, entry).unwrap().is_some());
+
+        // The records it spends are no longer selected
+
+        assert!(wallet
+            .unspent_records(&miner.address, &memory_pool)
+            .unwrap()
+            .iter()
+            .all(|record| !spend.old_serial_numbers().contains(&record.serial_number)));
+        assert!(matches!(
+            wallet.select_records(&miner.address, coinbase_value_1, 2, &memory_pool),
+            Err(WalletError::InsufficientFunds(_, _))
+        ));
+
+        drop(wallet);
+        kill_storage_sync(storage);
+    }
+
+    #[test]
+    fn test_wallet_wrong_password() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let parameters = load_verifying_parameters();
+
+        let [account, _, _] = &FIXTURE_VK.test_accounts;
+
+        let mut wallet = Wallet::open(storage.clone(), parameters.system_parameters.clone(), PASSWORD).unwrap();
+        wallet.import_account(account.private_key.clone()).unwrap();
+        wallet.save().unwrap();
+
+        assert!(matches!(
+            Wallet::open(storage.clone(), parameters.system_parameters.clone(), "wrong password"),
+            Err(WalletError::InvalidPassword)
+        ));
+
+        wallet = Wallet::open(storage.clone(), parameters.system_parameters, PASSWORD).unwrap();
+        assert_eq!(wallet.accounts()[0].address, account.address);
+
+        assert!(matches!(
+            wallet.check_password("wrong password"),
+            Err(WalletError::InvalidPassword)
+        ));
+        assert!(wallet.check_password(PASSWORD).is_ok());
+
+        drop(wallet);
+        kill_storage_sync(storage);
+    }
+}